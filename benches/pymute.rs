@@ -0,0 +1,73 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use pymute::cache::{read_csv_cache, write_csv_cache};
+use pymute::config::DEFAULT_MUTATION_TYPES;
+use pymute::mutants::find_mutants;
+use pymute::runner::{run_mutants, OutputLevel, Runner};
+use pymute::synthetic::{self, SyntheticProject, INSTANT_TEST_COMMAND};
+use pymute::RunConfig;
+use tempfile::tempdir;
+
+fn discovery(c: &mut Criterion) {
+    let temp_dir = tempdir().unwrap();
+    let project = SyntheticProject::new(200, 100).operator_density(0.5);
+    project.write(temp_dir.path()).unwrap();
+    let glob = format!("{}/**/*.py", temp_dir.path().to_str().unwrap());
+
+    let mut group = c.benchmark_group("discovery");
+    group.throughput(Throughput::Elements((project.files * project.lines) as u64));
+    group.bench_function("200 files x 100 lines", |b| {
+        b.iter(|| find_mutants(&glob, &DEFAULT_MUTATION_TYPES).unwrap())
+    });
+    group.finish();
+}
+
+fn per_mutant_overhead(c: &mut Criterion) {
+    let temp_dir = tempdir().unwrap();
+    SyntheticProject::new(20, 20)
+        .write(temp_dir.path())
+        .unwrap();
+    let glob = format!("{}/**/*.py", temp_dir.path().to_str().unwrap());
+    let mutants: Vec<_> = find_mutants(&glob, &DEFAULT_MUTATION_TYPES)
+        .unwrap()
+        .into_iter()
+        .take(50)
+        .collect();
+
+    // the test command returns instantly, so only pymute's own work is measured
+    let config = RunConfig::new(temp_dir.path())
+        .runner(Runner::Custom)
+        .test_command(INSTANT_TEST_COMMAND)
+        .output_level(OutputLevel::Quiet);
+
+    let mut group = c.benchmark_group("runner");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(mutants.len() as u64));
+    group.bench_function("per-mutant overhead", |b| {
+        b.iter(|| run_mutants(&config, &mutants).unwrap())
+    });
+    group.finish();
+}
+
+fn cache(c: &mut Criterion) {
+    let outcomes = synthetic::outcomes(100_000);
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().join("cache.csv");
+
+    let mut group = c.benchmark_group("cache");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(outcomes.len() as u64));
+    group.bench_function("write 100k entries", |b| {
+        b.iter(|| write_csv_cache(&path, &outcomes, temp_dir.path()).unwrap())
+    });
+    group.bench_function("read 100k entries", |b| {
+        b.iter_batched(
+            || write_csv_cache(&path, &outcomes, temp_dir.path()).unwrap(),
+            |_| read_csv_cache(&path, temp_dir.path()).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, discovery, per_mutant_overhead, cache);
+criterion_main!(benches);
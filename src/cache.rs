@@ -0,0 +1,1152 @@
+//! Cache of mutant results as a CSV file.
+//!
+//! After each run the result of every mutant is written to the cache file in the
+//! state directory (see `state::StateDir::cache_file`) or at `RunConfig::cache_path`,
+//! one row per mutant. The results of mutants that were not part of the run (e.g.
+//! because of a different `--modules` filter) are kept, unless they are stale, see
+//! `merge_csv_cache`.
+//!
+//! Files are written relative to the root of the project, so that the cache stays
+//! valid when the project is checked out at another path (e.g. on another machine
+//! or in a CI workspace). Caches of older versions with absolute paths can still be
+//! read, and their paths are written relative to the root once the cache is written
+//! again.
+//!
+//! During a run, the result of each mutant is also appended to the cache by a
+//! `CacheWriter`, so that the results of an interrupted run are not lost and the
+//! run can be resumed. A later row of the same mutant replaces an earlier one when
+//! the cache is read, and the cache is compacted at the end of the run. Apart
+//! from these single rows, the cache file is always replaced atomically, so it is
+//! never left half-written.
+//!
+//! A malformed row (e.g. a merge conflict marker or a manual edit gone wrong) is an
+//! error naming the row, unless the cache is read leniently, in which case such
+//! rows are skipped and counted, see `read_csv_cache_lenient`.
+//!
+//! Each row carries the id of its mutant (see `Mutant::id`), e.g. to pick a mutant
+//! to run again with `--mutant-id`, and a hash of the content of its file. Once the
+//! file is edited, line numbers may shift and the result may no longer hold, so
+//! its entries are stale: they are dropped by the next run instead of being reused,
+//! see `cached_outcomes`. Rows also record the mutation type of their mutant, so
+//! that `pymute report` can break the results down by mutation type, and how long
+//! the test suite ran against it.
+//!
+//! A mutant is identified in the cache by its file, line, column and replacement.
+//! Mutants sharing all of these insert the same code, so they cannot be told
+//! apart by their results either: `ambiguous_mutants` finds them at discovery, and
+//! they are only written to the cache if their results agree.
+
+use crate::error::PymuteError;
+use crate::events::{Event, Observer};
+use crate::mutants::{self, Mutant, MutationType};
+use crate::runner::{self, MutantOutcome, MutantResult};
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+/// A single row of the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// Id of the mutant, see `Mutant::id`. Caches written before ids were recorded
+    /// do not have it.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Path to the python file in which the mutant was inserted. It is absolute,
+    /// but relative to the root of the project in the file, see `read_csv_cache`.
+    pub file_path: PathBuf,
+    /// Line number on which the mutant was inserted.
+    pub line_number: usize,
+    /// Byte offset in the line at which `before` starts. Caches written before
+    /// columns were recorded do not have it, in which case the entry matches a
+    /// mutant at any column of the line.
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// The original string.
+    pub before: String,
+    /// The replacement string.
+    pub after: String,
+    /// The result of running the test suite against the mutant.
+    pub status: MutantResult,
+    /// Hash of the content of the file when the mutant was run, see `file_hash`.
+    /// Caches written before hashes were recorded do not have it, in which case the
+    /// entry is stale.
+    #[serde(default)]
+    pub file_hash: Option<String>,
+    /// The mutation type of the mutant, see `Mutant::mutation_type`. Caches written
+    /// before mutation types were recorded do not have it.
+    #[serde(default)]
+    pub mutation_type: Option<MutationType>,
+    /// How long the test suite ran against the mutant in milliseconds, see
+    /// `MutantOutcome::duration`. Caches written before durations were recorded do
+    /// not have it.
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// Why pymute failed to run the test suite against the mutant, see
+    /// `MutantOutcome::error`.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Identity of a mutant in the cache: file, line, column and replacement.
+type CacheKey<'a> = (&'a Path, usize, Option<usize>, &'a str, &'a str);
+
+/// `duration` in whole milliseconds, as recorded in the cache.
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+/// The current hash of each file read so far, or None if it cannot be read.
+pub type FileHashes = HashMap<PathBuf, Option<String>>;
+
+/// Hash of the content of the file at `path`, or None if it cannot be read.
+pub fn file_hash(path: &Path) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    Some(format!("{:016x}", mutants::stable_hash(&content)))
+}
+
+/// The current hash of the file at `path`, reading it only once.
+fn current_hash<'a>(path: &Path, hashes: &'a mut FileHashes) -> &'a Option<String> {
+    hashes
+        .entry(path.to_path_buf())
+        .or_insert_with(|| file_hash(path))
+}
+
+impl CacheEntry {
+    /// The entry of `outcome`, with the id of its mutant relative to `root` and the
+    /// current hash of its file.
+    pub fn new(outcome: &MutantOutcome, root: &Path, hashes: &mut FileHashes) -> Self {
+        let mutant = &outcome.mutant;
+        CacheEntry {
+            id: Some(mutant.id(root)),
+            file_path: mutant.file_path.clone(),
+            line_number: mutant.line_number,
+            column: Some(mutant.column),
+            before: mutant.before.clone(),
+            after: mutant.after.clone(),
+            status: outcome.result,
+            file_hash: current_hash(&mutant.file_path, hashes).clone(),
+            mutation_type: mutant.mutation_type,
+            duration_ms: outcome.duration.map(duration_ms),
+            error: outcome.error.clone(),
+        }
+    }
+
+    /// How long the test suite ran against the mutant.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration_ms.map(Duration::from_millis)
+    }
+
+    /// The mutant the entry is about, i.e. everything but the id and the status.
+    fn key(&self) -> CacheKey<'_> {
+        (
+            &self.file_path,
+            self.line_number,
+            self.column,
+            &self.before,
+            &self.after,
+        )
+    }
+
+    /// The entry with its file relative to `root`, as it is written to the file.
+    /// Files outside of the root keep their absolute path.
+    fn relative_to(&self, root: &Path) -> Self {
+        let file_path = self.file_path.strip_prefix(root).unwrap_or(&self.file_path);
+        CacheEntry {
+            file_path: file_path.to_path_buf(),
+            ..self.clone()
+        }
+    }
+
+    /// Whether the result may no longer hold, because the file of the mutant was
+    /// edited or removed since, or the entry has no hash.
+    fn is_stale(&self, hashes: &mut FileHashes) -> bool {
+        self.file_hash.is_none() || *current_hash(&self.file_path, hashes) != self.file_hash
+    }
+}
+
+/// Find the pairs of mutants that share their identity in the cache, i.e. insert
+/// the same replacement at the same position (e.g. found by different mutation
+/// types).
+pub fn ambiguous_mutants(mutants: &[Mutant]) -> Vec<(&Mutant, &Mutant)> {
+    let mut first: HashMap<CacheKey, &Mutant> = HashMap::new();
+    let mut pairs = Vec::new();
+    for mutant in mutants {
+        let key = (
+            mutant.file_path.as_path(),
+            mutant.line_number,
+            Some(mutant.column),
+            mutant.before.as_str(),
+            mutant.after.as_str(),
+        );
+        match first.get(&key) {
+            Some(other) => pairs.push((*other, mutant)),
+            None => {
+                first.insert(key, mutant);
+            }
+        }
+    }
+    pairs
+}
+
+/// The cache entries of `outcomes`, with a single entry for mutants sharing their
+/// identity.
+///
+/// Mutants sharing their identity but not their result are not written at all,
+/// since the cache cannot tell which result belongs to which. If `warn`, a warning
+/// is printed for each of them.
+fn unambiguous_entries(
+    outcomes: &[MutantOutcome],
+    root: &Path,
+    hashes: &mut FileHashes,
+    warn: bool,
+) -> Vec<CacheEntry> {
+    let entries: Vec<CacheEntry> = outcomes
+        .iter()
+        .map(|outcome| CacheEntry::new(outcome, root, hashes))
+        .collect();
+    let mut statuses: HashMap<CacheKey, Vec<MutantResult>> = HashMap::new();
+    for entry in &entries {
+        statuses.entry(entry.key()).or_default().push(entry.status);
+    }
+
+    let mut written = HashSet::new();
+    let mut unambiguous = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        if !written.insert(entry.key()) {
+            continue;
+        }
+        let results = &statuses[&entry.key()];
+        if results.iter().any(|status| *status != entry.status) {
+            if !warn {
+                continue;
+            }
+            eprintln!(
+                "{}: {} mutants replacing {:?} by {:?} at line {}, column {} of {} have different results and are not cached.",
+                "Warning".yellow(),
+                results.len(),
+                entry.before,
+                entry.after,
+                entry.line_number,
+                entry.column.unwrap_or_default(),
+                entry.file_path.display()
+            );
+            continue;
+        }
+        unambiguous.push(entry.clone());
+    }
+    unambiguous
+}
+
+/// Write the outcomes of a run to the cache file at `path`, replacing all entries
+/// the file had before.
+pub fn write_csv_cache(
+    path: &Path,
+    outcomes: &[MutantOutcome],
+    root: &Path,
+) -> Result<(), PymuteError> {
+    let entries = unambiguous_entries(outcomes, root, &mut FileHashes::new(), true);
+    write_entries(path, &entries, root)
+}
+
+/// Write `entries` of the project at `root` to the cache file at `path`, creating
+/// its directory if needed.
+///
+/// The entries are written to a temporary file next to it (e.g. `cache.csv.tmp`)
+/// first, which then replaces the cache, so that the cache is never left
+/// half-written.
+fn write_entries<'a>(
+    path: &Path,
+    entries: impl IntoIterator<Item = &'a CacheEntry>,
+    root: &Path,
+) -> Result<(), PymuteError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(PymuteError::io(dir))?;
+    }
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(".tmp");
+    let temporary = path.with_file_name(file_name);
+
+    let write = || -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(&temporary)?;
+        for entry in entries {
+            writer.serialize(entry.relative_to(root))?;
+        }
+        writer.flush()?;
+        Ok(())
+    };
+    write().map_err(|error| PymuteError::io(&temporary)(error.into()))?;
+    fs::rename(&temporary, path).map_err(PymuteError::io(path))
+}
+
+/// Append `entry` of the project at `root` to the cache file at `path` in a single
+/// write, with a header if the file is empty.
+fn append_entry(path: &Path, entry: &CacheEntry, root: &Path) -> Result<(), PymuteError> {
+    let empty = fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(empty)
+        .from_writer(Vec::new());
+    writer
+        .serialize(entry.relative_to(root))
+        .map_err(|error| PymuteError::io(path)(error.into()))?;
+    let row = writer
+        .into_inner()
+        .map_err(|error| PymuteError::io(path)(error.into_error()))?;
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(&row))
+        .map_err(PymuteError::io(path))
+}
+
+/// Write the outcomes of a run to the cache file at `path`, keeping the entries of
+/// mutants that were not run.
+///
+/// Entries of the existing cache (if there is one) are updated with the outcome
+/// of the same mutant, new mutants are appended and stale entries (their file was
+/// edited or removed) are dropped, see `CacheEntry::is_stale`. Fails if the
+/// existing cache has a malformed row.
+pub fn merge_csv_cache(
+    path: &Path,
+    outcomes: &[MutantOutcome],
+    root: &Path,
+) -> Result<(), PymuteError> {
+    let existing = match path.exists() {
+        true => read_csv_cache(path, root)?,
+        false => Vec::new(),
+    };
+    merge_entries(path, &existing, outcomes, root)
+}
+
+/// Like `merge_csv_cache`, with the `existing` entries of the cache already read.
+pub fn merge_entries(
+    path: &Path,
+    existing: &[CacheEntry],
+    outcomes: &[MutantOutcome],
+    root: &Path,
+) -> Result<(), PymuteError> {
+    merge(path, existing, outcomes, root, &mut FileHashes::new(), true)
+}
+
+/// Merge `outcomes` into the `existing` entries and write them to `path`, see
+/// `merge_csv_cache`. Ambiguous mutants are only warned about if `warn`.
+fn merge(
+    path: &Path,
+    existing: &[CacheEntry],
+    outcomes: &[MutantOutcome],
+    root: &Path,
+    hashes: &mut FileHashes,
+    warn: bool,
+) -> Result<(), PymuteError> {
+    let entries = unambiguous_entries(outcomes, root, hashes, warn);
+    let mut updated: HashMap<_, _> = entries.iter().map(|entry| (entry.key(), entry)).collect();
+
+    let mut merged = Vec::with_capacity(existing.len() + entries.len());
+    for entry in existing {
+        match updated.remove(&entry.key()) {
+            Some(update) => merged.push(update),
+            None if entry.is_stale(hashes) => {}
+            None => merged.push(entry),
+        }
+    }
+    // mutants that were not in the cache yet, in the order of the run
+    merged.extend(
+        entries
+            .iter()
+            .filter(|entry| updated.contains_key(&entry.key())),
+    );
+    write_entries(path, merged, root)
+}
+
+/// Identity of a mutant in the cache, owning its parts, see `CacheKey`.
+type OwnedCacheKey = (PathBuf, usize, usize, String, String);
+
+/// Writes the cache after each mutant of a run.
+///
+/// Before the first mutant finishes, the `existing` entries of the cache are
+/// written without the stale ones. Each finished mutant is then appended as a
+/// single row, so that the work per mutant does not grow with the size of the
+/// cache, and the run compacts the cache with `merge_entries` at its end. Mutants
+/// whose test run was interrupted by the user are not written, and of mutants
+/// sharing their identity only the first one is. The files of the project are only
+/// hashed once, since they do not change during the run.
+pub struct CacheWriter {
+    path: PathBuf,
+    root: PathBuf,
+    existing: Vec<CacheEntry>,
+    /// The mutants of the run, to find the mutant of a `Event::MutantFinished`.
+    mutants: HashMap<OwnedCacheKey, Mutant>,
+    written: Mutex<Written>,
+}
+
+/// What a `CacheWriter` has written so far.
+#[derive(Default)]
+struct Written {
+    /// Whether the existing entries were written.
+    existing: bool,
+    /// The mutants appended so far.
+    appended: HashSet<OwnedCacheKey>,
+    /// The hashes of the files read so far.
+    hashes: FileHashes,
+}
+
+impl CacheWriter {
+    /// Write the outcomes of `mutants` of the project at `root` to the cache file at
+    /// `path`, merged into its `existing` entries.
+    pub fn new(path: &Path, root: &Path, existing: Vec<CacheEntry>, mutants: &[Mutant]) -> Self {
+        let mutants = mutants
+            .iter()
+            .map(|mutant| {
+                let key = (
+                    mutant.file_path.clone(),
+                    mutant.line_number,
+                    mutant.column,
+                    mutant.before.clone(),
+                    mutant.after.clone(),
+                );
+                (key, mutant.clone())
+            })
+            .collect();
+        CacheWriter {
+            path: path.to_path_buf(),
+            root: root.to_path_buf(),
+            existing,
+            mutants,
+            written: Mutex::default(),
+        }
+    }
+
+    /// Append the entry of `outcome` to the cache, writing the existing entries
+    /// first if that did not happen yet.
+    fn append(&self, outcome: &MutantOutcome, written: &mut Written) -> Result<(), PymuteError> {
+        if !written.existing {
+            merge(
+                &self.path,
+                &self.existing,
+                &[],
+                &self.root,
+                &mut written.hashes,
+                false,
+            )?;
+            written.existing = true;
+        }
+        let entry = CacheEntry::new(outcome, &self.root, &mut written.hashes);
+        append_entry(&self.path, &entry, &self.root)
+    }
+}
+
+impl Observer for CacheWriter {
+    fn notify(&self, event: &Event) {
+        let Event::MutantFinished {
+            file_path,
+            line_number,
+            column,
+            before,
+            after,
+            result,
+            duration_ms,
+            error,
+        } = event
+        else {
+            return;
+        };
+        if *result
+            == (MutantResult::Error {
+                signal: Some(runner::SIGINT),
+                code: None,
+            })
+        {
+            return;
+        }
+        let key = (
+            file_path.clone(),
+            *line_number,
+            *column,
+            before.clone(),
+            after.clone(),
+        );
+        let Some(mutant) = self.mutants.get(&key) else {
+            return;
+        };
+
+        let mut written = self.written.lock().unwrap_or_else(PoisonError::into_inner);
+        if !written.appended.insert(key) {
+            return;
+        }
+        let outcome = MutantOutcome {
+            mutant: mutant.clone(),
+            result: *result,
+            duration: Some(Duration::from_millis(
+                (*duration_ms).try_into().unwrap_or(u64::MAX),
+            )),
+            error: error.clone(),
+        };
+        if let Err(error) = self.append(&outcome, &mut written) {
+            eprintln!(
+                "{}: Could not write the cache {}: {error}",
+                "Warning".yellow(),
+                self.path.display()
+            );
+        }
+    }
+}
+
+/// The outcomes of those `mutants` (of the project at `root`) that were caught or
+/// missed according to the `entries` of the cache.
+///
+/// Stale entries (see `CacheEntry::is_stale`) are never used, and an entry only
+/// applies to a mutant if the id of the mutant is the same.
+pub fn cached_outcomes(
+    entries: &[CacheEntry],
+    mutants: &[Mutant],
+    root: &Path,
+) -> Vec<MutantOutcome> {
+    let mut hashes = FileHashes::new();
+    let results: HashMap<_, _> = entries
+        .iter()
+        .filter(|entry| matches!(entry.status, MutantResult::Caught | MutantResult::Missed))
+        .filter(|entry| !entry.is_stale(&mut hashes))
+        .filter_map(|entry| Some((entry.key(), (entry.id.as_deref()?, entry))))
+        .collect();
+
+    mutants
+        .iter()
+        .filter_map(|mutant| {
+            let key = (
+                mutant.file_path.as_path(),
+                mutant.line_number,
+                Some(mutant.column),
+                mutant.before.as_str(),
+                mutant.after.as_str(),
+            );
+            let (id, entry) = results.get(&key)?;
+            (*id == mutant.id(root)).then(|| MutantOutcome {
+                mutant: mutant.clone(),
+                result: entry.status,
+                duration: entry.duration(),
+                error: entry.error.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Read all entries from the cache file at `path` of the project at `root`.
+///
+/// Relative files are resolved against `root`, while the absolute files of caches
+/// written by older versions are kept as they are. Fails on the first malformed
+/// row, naming its line and content.
+pub fn read_csv_cache(path: &Path, root: &Path) -> Result<Vec<CacheEntry>, PymuteError> {
+    let (entries, _) = read_entries(path, root, false)?;
+    Ok(entries)
+}
+
+/// Read the entries from the cache file at `path` like `read_csv_cache`, but skip
+/// malformed rows. Also returns the number of skipped rows.
+pub fn read_csv_cache_lenient(
+    path: &Path,
+    root: &Path,
+) -> Result<(Vec<CacheEntry>, usize), PymuteError> {
+    read_entries(path, root, true)
+}
+
+/// Read the entries from the cache file at `path`, skipping and counting malformed
+/// rows if `lenient`.
+fn read_entries(
+    path: &Path,
+    root: &Path,
+    lenient: bool,
+) -> Result<(Vec<CacheEntry>, usize), PymuteError> {
+    // a row that could not be parsed at all (e.g. because it is not UTF-8) has no
+    // content to show
+    let unparsable_row = |error: csv::Error| PymuteError::CacheParse {
+        path: path.to_path_buf(),
+        row: error.position().map(csv::Position::line),
+        content: None,
+        source: error,
+    };
+    // rows with missing or extra fields fail to deserialize below, with their content
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(|error| PymuteError::io(path)(error.into()))?;
+    let headers = reader.headers().map_err(unparsable_row)?.clone();
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for record in reader.records() {
+        let entry = record.map_err(unparsable_row).and_then(|record| {
+            record
+                .deserialize::<CacheEntry>(Some(&headers))
+                .map_err(|error| {
+                    let content = record.iter().collect::<Vec<_>>().join(",");
+                    PymuteError::CacheParse {
+                        path: path.to_path_buf(),
+                        row: record.position().map(csv::Position::line),
+                        content: Some(content),
+                        source: error,
+                    }
+                })
+        });
+        match (entry, lenient) {
+            (Ok(mut entry), _) => {
+                if entry.file_path.is_relative() {
+                    entry.file_path = root.join(&entry.file_path);
+                }
+                entries.push(entry);
+            }
+            (Err(_), true) => skipped += 1,
+            (Err(error), false) => return Err(error),
+        }
+    }
+
+    // rows appended by a `CacheWriter` replace earlier rows of the same mutant
+    let last: HashMap<CacheKey, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (entry.key(), index))
+        .collect();
+    let keep: HashSet<usize> = last.into_values().collect();
+    let entries = entries
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| keep.contains(index))
+        .map(|(_, entry)| entry)
+        .collect();
+    Ok((entries, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{
+        ambiguous_mutants, cached_outcomes, merge_csv_cache, read_csv_cache,
+        read_csv_cache_lenient, write_csv_cache, CacheEntry, CacheWriter,
+    };
+    use crate::error::PymuteError;
+    use crate::events::{Event, Observer};
+    use crate::mutants::{Mutant, MutationType};
+    use crate::runner::{MutantOutcome, MutantResult};
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn root() -> &'static Path {
+        Path::new("/projects/project")
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cache.csv");
+
+        let mut compared = Mutant::new(
+            PathBuf::from("/projects/project/script.py"),
+            6,
+            "==",
+            "!=",
+            9,
+            "    if a == b:",
+        );
+        compared.mutation_type = Some(MutationType::CompOps);
+        let outcomes = vec![
+            MutantOutcome {
+                mutant: Mutant::new(
+                    PathBuf::from("/projects/project/script.py"),
+                    2,
+                    " + ",
+                    " - ",
+                    12,
+                    "    return a + b",
+                ),
+                result: MutantResult::Caught,
+                duration: None,
+                error: None,
+            },
+            MutantOutcome {
+                mutant: compared,
+                result: MutantResult::Missed,
+                duration: None,
+                error: None,
+            },
+            MutantOutcome {
+                mutant: Mutant::new(
+                    PathBuf::from("/projects/project/script.py"),
+                    8,
+                    " * ",
+                    " / ",
+                    5,
+                    "x = a * b",
+                ),
+                result: MutantResult::Error {
+                    signal: Some(9),
+                    code: None,
+                },
+                duration: None,
+                error: None,
+            },
+            // a replaced return value containing commas and quotes
+            MutantOutcome {
+                mutant: Mutant::new(
+                    PathBuf::from("/projects/project/script.py"),
+                    11,
+                    "{\"a\": 1, 'b': 2}",
+                    "None",
+                    11,
+                    "    return {\"a\": 1, 'b': 2}",
+                ),
+                result: MutantResult::Missed,
+                duration: None,
+                error: None,
+            },
+        ];
+        write_csv_cache(&path, &outcomes, root()).unwrap();
+
+        let entries = read_csv_cache(&path, root()).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(
+            entries[3],
+            CacheEntry::new(&outcomes[3], root(), &mut HashMap::new())
+        );
+        assert_eq!(
+            entries[0],
+            CacheEntry::new(&outcomes[0], root(), &mut HashMap::new())
+        );
+        assert_eq!(entries[1].line_number, 6);
+        assert_eq!(entries[1].before, "==");
+        assert_eq!(entries[1].mutation_type, Some(MutationType::CompOps));
+        assert_eq!(entries[0].mutation_type, None);
+        assert_eq!(entries[1].status, MutantResult::Missed);
+        assert_eq!(
+            entries[2].status,
+            MutantResult::Error {
+                signal: Some(9),
+                code: None
+            }
+        );
+        assert!(std::fs::read_to_string(&path).unwrap().contains(",error:9"));
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_merge_csv_cache_keeps_other_modules() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cache.csv");
+        let pkg_a = temp_dir.path().join("pkg_a.py");
+        let pkg_b = temp_dir.path().join("pkg_b.py");
+        std::fs::write(&pkg_a, "def add(a, b):\n    return a + b\n").unwrap();
+        std::fs::write(&pkg_b, "def mul(a, b):\n    return a * b\n").unwrap();
+
+        let outcome = |file: &PathBuf, before: &str, after: &str, result| MutantOutcome {
+            mutant: Mutant::new(
+                file.clone(),
+                2,
+                before,
+                after,
+                12,
+                std::fs::read_to_string(file)
+                    .unwrap()
+                    .lines()
+                    .nth(1)
+                    .unwrap(),
+            ),
+            result,
+            duration: None,
+            error: None,
+        };
+        let first_run = vec![outcome(&pkg_a, " + ", " - ", MutantResult::Caught)];
+        let second_run = vec![outcome(&pkg_b, " * ", " / ", MutantResult::Missed)];
+
+        // runs with disjoint module filters
+        merge_csv_cache(&path, &first_run, root()).unwrap();
+        merge_csv_cache(&path, &second_run, root()).unwrap();
+        let entries = read_csv_cache(&path, root()).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                CacheEntry::new(&first_run[0], root(), &mut HashMap::new()),
+                CacheEntry::new(&second_run[0], root(), &mut HashMap::new())
+            ]
+        );
+
+        // the result of a mutant run again is updated in place
+        let rerun = vec![outcome(&pkg_a, " + ", " - ", MutantResult::Missed)];
+        merge_csv_cache(&path, &rerun, root()).unwrap();
+        let entries = read_csv_cache(&path, root()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].status, MutantResult::Missed);
+
+        // stale entries are dropped: the line of pkg_a changed, pkg_b was removed
+        std::fs::write(&pkg_a, "def add(a, b):\n    return sum((a, b))\n").unwrap();
+        std::fs::remove_file(&pkg_b).unwrap();
+        merge_csv_cache(&path, &[], root()).unwrap();
+        assert!(read_csv_cache(&path, root()).unwrap().is_empty());
+
+        // overwriting drops everything that was not run
+        merge_csv_cache(&path, &first_run, root()).unwrap();
+        write_csv_cache(&path, &second_run, root()).unwrap();
+        assert_eq!(
+            read_csv_cache(&path, root()).unwrap(),
+            vec![CacheEntry::new(&second_run[0], root(), &mut HashMap::new())]
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_cache_identity_of_colliding_mutants() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cache.csv");
+        let script = temp_dir.path().join("script.py");
+        let line = "    return a + b + c";
+        std::fs::write(&script, format!("def add(a, b, c):\n{line}\n")).unwrap();
+
+        let outcome = |column, result| MutantOutcome {
+            mutant: Mutant::new(script.clone(), 2, " + ", " - ", column, line),
+            result,
+            duration: None,
+            error: None,
+        };
+        // the same replacement at different columns of the same line
+        let outcomes = vec![
+            outcome(12, MutantResult::Caught),
+            outcome(16, MutantResult::Missed),
+        ];
+        let mutants: Vec<Mutant> = outcomes.iter().map(|o| o.mutant.clone()).collect();
+        assert!(ambiguous_mutants(&mutants).is_empty());
+
+        merge_csv_cache(&path, &outcomes, root()).unwrap();
+        let entries = read_csv_cache(&path, root()).unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.column, entry.status))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(12), MutantResult::Caught),
+                (Some(16), MutantResult::Missed)
+            ]
+        );
+
+        // the same replacement at the same column, e.g. from two mutation types
+        let colliding = vec![
+            outcome(12, MutantResult::Caught),
+            outcome(12, MutantResult::Caught),
+            outcome(16, MutantResult::Caught),
+            outcome(16, MutantResult::Missed),
+        ];
+        let mutants: Vec<Mutant> = colliding.iter().map(|o| o.mutant.clone()).collect();
+        let pairs = ambiguous_mutants(&mutants);
+        assert_eq!(pairs.len(), 2);
+        assert_eq!((pairs[0].0.column, pairs[1].0.column), (12, 16));
+
+        // agreeing results are written once, conflicting ones not at all
+        write_csv_cache(&path, &colliding, root()).unwrap();
+        let entries = read_csv_cache(&path, root()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            (entries[0].column, entries[0].status),
+            (Some(12), MutantResult::Caught)
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_merge_cache_without_column() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cache.csv");
+        let script = temp_dir.path().join("script.py");
+        let line = "    return a + b + c";
+        std::fs::write(&script, format!("def add(a, b, c):\n{line}\n")).unwrap();
+        std::fs::write(
+            &path,
+            format!(
+                "file_path,line_number,before,after,status\n{0},2, + , - ,caught\n{0},2, + , * ,missed\n",
+                script.display()
+            ),
+        )
+        .unwrap();
+
+        // entries of caches without columns have no hash either, so they are stale
+        let outcome = MutantOutcome {
+            mutant: Mutant::new(script.clone(), 2, " + ", " - ", 16, line),
+            result: MutantResult::Missed,
+            duration: None,
+            error: None,
+        };
+        merge_csv_cache(&path, std::slice::from_ref(&outcome), root()).unwrap();
+        let entries = read_csv_cache(&path, root()).unwrap();
+        assert_eq!(
+            entries,
+            vec![CacheEntry::new(&outcome, root(), &mut HashMap::new())]
+        );
+        assert!(entries[0].file_hash.is_some());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_cache_writer_appends_rows() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let path = root.join("cache.csv");
+        let code = "def add(a, b):\n    return a + b\n\nx = 1 * 2\n";
+        std::fs::write(root.join("calc.py"), code).unwrap();
+        let outcome = |line_number, before, after, column, result| MutantOutcome {
+            mutant: Mutant::new(
+                root.join("calc.py"),
+                line_number,
+                before,
+                after,
+                column,
+                code.lines().nth(line_number - 1).unwrap(),
+            ),
+            result,
+            duration: None,
+            error: None,
+        };
+        let first = outcome(2, " + ", " - ", 12, MutantResult::Missed);
+        let second = outcome(4, " * ", " / ", 5, MutantResult::Caught);
+        write_csv_cache(&path, std::slice::from_ref(&first), root).unwrap();
+        let existing = read_csv_cache(&path, root).unwrap();
+
+        let mutants = [first.mutant.clone(), second.mutant.clone()];
+        let writer = CacheWriter::new(&path, root, existing, &mutants);
+        let mut rerun = first.clone();
+        rerun.result = MutantResult::Caught;
+        writer.notify(&Event::mutant_finished(&rerun, Duration::ZERO));
+        writer.notify(&Event::mutant_finished(&second, Duration::ZERO));
+
+        // the rows are appended to the existing ones, and the last row of a mutant
+        // is the one that is read
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 4, "{content}");
+        let entries = read_csv_cache(&path, root).unwrap();
+        let statuses: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.line_number, entry.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [(2, MutantResult::Caught), (4, MutantResult::Caught)]
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_read_cache_without_column() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cache.csv");
+        std::fs::write(
+            &path,
+            "file_path,line_number,before,after,status\nscript.py,2, + , - ,caught\n",
+        )
+        .unwrap();
+
+        let entries = read_csv_cache(&path, root()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].column, None);
+        assert_eq!(entries[0].before, " + ");
+        assert_eq!(entries[0].status, MutantResult::Caught);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_read_malformed_cache() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cache.csv");
+        let header = "file_path,line_number,column,before,after,status\n";
+        let valid = "script.py,2,12, + , - ,caught\n";
+        let write = |rows: &[&str]| std::fs::write(&path, format!("{header}{}", rows.concat()));
+
+        // a line number that is not a number
+        let other = "script.py,3,12, + , - ,caught\n";
+        write(&[valid, "script.py,two,12, + , - ,missed\n", other]).unwrap();
+        let error = read_csv_cache(&path, root()).unwrap_err();
+        assert!(
+            matches!(error, PymuteError::CacheParse { row: Some(3), .. }),
+            "{error}"
+        );
+        let error = error.to_string();
+        assert!(error.contains("at line 3"), "{error}");
+        assert!(error.contains("script.py,two,12"), "{error}");
+        assert!(error.contains("--cache-lenient"), "{error}");
+        let (entries, skipped) = read_csv_cache_lenient(&path, root()).unwrap();
+        assert_eq!((entries.len(), skipped), (2, 1));
+
+        // a missing column and a merge conflict marker
+        write(&[
+            "<<<<<<< HEAD\n",
+            valid,
+            "script.py,2,12, + ,caught\n",
+            ">>>>>>> branch\n",
+        ])
+        .unwrap();
+        let error = read_csv_cache(&path, root()).unwrap_err().to_string();
+        assert!(error.contains("at line 2"), "{error}");
+        let (entries, skipped) = read_csv_cache_lenient(&path, root()).unwrap();
+        assert_eq!(skipped, 3);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].file_path, root().join("script.py"));
+
+        // merging into a malformed cache fails instead of dropping it
+        assert!(merge_csv_cache(&path, &[], root()).is_err());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_cached_outcomes() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let path = root.join("cache.csv");
+        let file = root.join("calc.py");
+        let code = "def add(a, b):\n    return a + b\n\ndef mul(a, b):\n    return a * b\n";
+        std::fs::write(&file, code).unwrap();
+
+        let mutant = |line_number, before: &str, after: &str| {
+            let line = code.lines().nth(line_number - 1).unwrap();
+            let column = line.find(before).unwrap();
+            Mutant::new(file.clone(), line_number, before, after, column, line)
+        };
+        let mutants = vec![
+            mutant(2, " + ", " - "),
+            mutant(2, " + ", " * "),
+            mutant(5, " * ", " / "),
+        ];
+        let results = [
+            MutantResult::Caught,
+            MutantResult::Missed,
+            MutantResult::Error {
+                signal: None,
+                code: None,
+            },
+        ];
+        let outcomes: Vec<_> = mutants
+            .iter()
+            .zip(results)
+            .map(|(mutant, result)| MutantOutcome {
+                mutant: mutant.clone(),
+                result,
+                duration: None,
+                error: None,
+            })
+            .collect();
+        assert!(cached_outcomes(&[], &mutants, root).is_empty());
+
+        // errors are run again
+        write_csv_cache(&path, &outcomes, root).unwrap();
+        assert_eq!(
+            cached_outcomes(&read_csv_cache(&path, root).unwrap(), &mutants, root),
+            outcomes[..2]
+        );
+
+        // editing the file makes all of its entries stale, even for unchanged lines
+        std::fs::write(&file, format!("{code}\n\ndef neg(a):\n    return -a\n")).unwrap();
+        assert!(cached_outcomes(&read_csv_cache(&path, root).unwrap(), &mutants, root).is_empty());
+        let rerun = vec![MutantOutcome {
+            mutant: mutants[0].clone(),
+            result: MutantResult::Missed,
+            duration: None,
+            error: None,
+        }];
+        merge_csv_cache(&path, &rerun, root).unwrap();
+        let entries = read_csv_cache(&path, root).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, MutantResult::Missed);
+        assert_eq!(
+            cached_outcomes(&read_csv_cache(&path, root).unwrap(), &mutants, root),
+            rerun
+        );
+
+        // entries of older versions of pymute have no hash
+        std::fs::write(
+            &path,
+            format!(
+                "id,file_path,line_number,column,before,after,status\n{},{},2,12, + , - ,caught\n",
+                mutants[0].id(root),
+                file.display()
+            ),
+        )
+        .unwrap();
+        assert_eq!(read_csv_cache(&path, root).unwrap()[0].file_hash, None);
+        assert!(cached_outcomes(&read_csv_cache(&path, root).unwrap(), &mutants, root).is_empty());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_cache_of_moved_project() {
+        let first = tempdir().unwrap();
+        let second = tempdir().unwrap();
+        let code = "def add(a, b):\n    return a + b\n";
+        let outcome = |root: &Path| MutantOutcome {
+            mutant: Mutant::new(
+                root.join("pkg/calc.py"),
+                2,
+                " + ",
+                " - ",
+                12,
+                "    return a + b",
+            ),
+            result: MutantResult::Caught,
+            duration: None,
+            error: None,
+        };
+        for root in [first.path(), second.path()] {
+            std::fs::create_dir(root.join("pkg")).unwrap();
+            std::fs::write(root.join("pkg/calc.py"), code).unwrap();
+        }
+
+        let path = first.path().join(".pymute/cache.csv");
+        write_csv_cache(&path, &[outcome(first.path())], first.path()).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            !content.contains(&*first.path().to_string_lossy()),
+            "{content}"
+        );
+
+        // the same mutant is found in the cache after moving the project
+        let moved = second.path().join("cache.csv");
+        std::fs::copy(&path, &moved).unwrap();
+        let mutants = [outcome(second.path()).mutant];
+        assert_eq!(
+            cached_outcomes(
+                &read_csv_cache(&moved, second.path()).unwrap(),
+                &mutants,
+                second.path()
+            ),
+            vec![outcome(second.path())]
+        );
+
+        // absolute paths of older caches are made relative when writing them again
+        let relative = Path::new("pkg").join("calc.py").display().to_string();
+        let absolute = content.replacen(
+            &relative,
+            &second.path().join(&relative).display().to_string(),
+            1,
+        );
+        std::fs::write(&moved, absolute).unwrap();
+        assert_eq!(
+            read_csv_cache(&moved, second.path()).unwrap()[0].file_path,
+            second.path().join("pkg/calc.py")
+        );
+        merge_csv_cache(&moved, &[], second.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(&moved).unwrap(), content);
+
+        first.close().unwrap();
+        second.close().unwrap();
+    }
+}
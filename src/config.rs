@@ -0,0 +1,990 @@
+//! Configuration of a pymute run.
+//!
+//! `RunConfig` collects all options of a run and is the single source of truth for
+//! their defaults, both for library users and for the command line interface.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use pymute::RunConfig;
+//!
+//! RunConfig::new("path/to/python/project")
+//!     .modules("src/**/*.py")
+//!     .max_mutants(50)
+//!     .seed(7)
+//!     .run()
+//!     .expect("Mutation testing failed");
+//! ```
+
+use crate::error::PymuteError;
+use crate::events::{Observer, Observers};
+use crate::mutants::MutationType;
+use crate::plan::{ListFormat, Order, Sampling};
+use crate::runner::{OutputLevel, PytestWorkers, Runner};
+use crate::shard::Shard;
+use crate::state::StateDir;
+use crate::summary::RunSummary;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+/// Default glob expression for modules to mutate.
+pub const DEFAULT_MODULES: &str = "**/*.py";
+/// Default path to the tests to run.
+pub const DEFAULT_TESTS: &str = ".";
+/// Default number of mutants to run in parallel.
+pub const DEFAULT_NUM_THREADS: usize = 1;
+/// Default seed for sampling mutants.
+pub const DEFAULT_SEED: u64 = 42;
+/// Mutation types used by default.
+pub const DEFAULT_MUTATION_TYPES: [MutationType; 9] = [
+    MutationType::MathOps,
+    MutationType::Conjunctions,
+    MutationType::Booleans,
+    MutationType::ControlFlow,
+    MutationType::CompOps,
+    MutationType::Numbers,
+    MutationType::AugAssign,
+    MutationType::Membership,
+    MutationType::Identity,
+];
+
+/// Files and directories that are never copied into the working copies of the project.
+///
+/// The names are excluded at any depth, so `.git` also excludes the repositories of
+/// nested repositories and submodules as well as the `.git` files of worktrees.
+pub const DEFAULT_COPY_EXCLUDES: [&str; 9] = [
+    ".git",
+    ".hg",
+    "__pycache__",
+    ".tox",
+    ".nox",
+    ".venv",
+    "venv",
+    ".mypy_cache",
+    ".pytest_cache",
+];
+
+/// Serde default of `RunConfig::skip_invalid` for plans written before it existed.
+fn default_skip_invalid() -> bool {
+    true
+}
+
+/// A named bundle of option defaults, see `Profile::settings`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Profile {
+    /// Run at most 100 random mutants on the lines changed since the last commit,
+    /// for fast feedback. There are no fail-fast or timeout options to set yet.
+    Quick,
+    /// Use the default options.
+    Standard,
+    /// Use all mutation types, including opt-in ones, and run every mutant.
+    /// Missed mutants are not retried, as there is no option for it yet.
+    Thorough,
+    /// Print nothing but the final summary, write JSON and JUnit reports to the
+    /// state directory and fail below a score of 80%, for use in CI. GitHub
+    /// annotations are not written, upload a `--report-sarif` report instead.
+    Ci,
+}
+
+/// Mutation score (in percent) below which the `ci` profile fails the run.
+pub const CI_FAIL_UNDER: f64 = 80.0;
+
+/// Options set by a profile. Options that are None keep their default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileSettings {
+    /// Output level to use.
+    pub output_level: Option<OutputLevel>,
+    /// Maximum number of mutants to run (None inside means no sampling).
+    pub max_mutants: Option<Option<usize>>,
+    /// Mutation types to look for.
+    pub mutation_types: Option<Vec<MutationType>>,
+    /// Only run the mutants on lines changed since this git ref.
+    pub diff_base: Option<String>,
+    /// Write a JSON report to this file.
+    pub report_json: Option<PathBuf>,
+    /// Write a JUnit XML report to this file.
+    pub report_junit: Option<PathBuf>,
+    /// Minimum mutation score (in percent) for the run to pass.
+    pub fail_under: Option<f64>,
+}
+
+impl Profile {
+    /// The options this profile sets, with reports written to the reports
+    /// directory of `state`.
+    ///
+    /// Profiles only change defaults, options set explicitly always take precedence.
+    pub fn settings(self, state: &StateDir) -> ProfileSettings {
+        match self {
+            Profile::Quick => ProfileSettings {
+                max_mutants: Some(Some(100)),
+                diff_base: Some("HEAD".to_string()),
+                ..Default::default()
+            },
+            Profile::Standard => ProfileSettings::default(),
+            Profile::Thorough => ProfileSettings {
+                max_mutants: Some(None),
+                mutation_types: Some(MutationType::value_variants().to_vec()),
+                ..Default::default()
+            },
+            Profile::Ci => ProfileSettings {
+                output_level: Some(OutputLevel::Quiet),
+                report_json: Some(state.reports_dir().join("mutants.json")),
+                report_junit: Some(state.reports_dir().join("junit.xml")),
+                fail_under: Some(CI_FAIL_UNDER),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// All options of a pymute run.
+///
+/// Construct it with `RunConfig::new` to get the same defaults as the command line
+/// interface and adjust individual options using the builder methods.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunConfig {
+    /// Path to the root of the python project.
+    pub root: PathBuf,
+    /// Glob expressions (relative to root) for modules to mutate.
+    pub modules: Vec<String>,
+    /// Path (relative to root) of the tests to run.
+    pub tests: String,
+    /// Number of mutants to run in parallel.
+    pub num_threads: usize,
+    /// How much to print while running the mutants.
+    pub output_level: OutputLevel,
+    /// Which runner to use to run the test suite.
+    pub runner: Runner,
+    /// Tox environment, or comma-separated nox sessions, to use. Ignored by the
+    /// pytest and unittest runners.
+    pub environment: Option<String>,
+    /// Test command used by the custom runner, split using shell-style quoting.
+    pub test_command: Option<String>,
+    /// Number of pytest-xdist workers of each test run of the pytest runner.
+    #[serde(default)]
+    pub pytest_workers: Option<PytestWorkers>,
+    /// Distribution mode of pytest-xdist (e.g. `loadfile`), if there are workers.
+    #[serde(default)]
+    pub pytest_dist: Option<String>,
+    /// Run the pytest runner with `--cache-clear`, so that no test run is affected
+    /// by the pytest cache of an earlier one.
+    #[serde(default)]
+    pub pytest_cache_clear: bool,
+    /// Extra arguments appended to the test command of every runner, split using
+    /// shell-style quoting. The tox runner passes them after `--`.
+    #[serde(default)]
+    pub runner_args: Option<String>,
+    /// Python interpreter that runs pytest and unittest and compiles the files,
+    /// instead of `python` on the PATH.
+    #[serde(default)]
+    pub python: Option<PathBuf>,
+    /// Executable of the tox or nox runner, instead of `tox` or `nox` on the PATH.
+    #[serde(default)]
+    pub runner_binary: Option<PathBuf>,
+    /// Environment variables set for each test run, on top of the inherited
+    /// environment and the `PYMUTE_MUTANT_*` variables of the mutant.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Directory to write the output of the test run of each mutant to, see
+    /// `runner::mutant_log_file`. Otherwise the output is discarded unless the
+    /// output level is `Process`.
+    #[serde(default)]
+    pub log_dir: Option<PathBuf>,
+    /// Directory to move the working copy of each surviving mutant to after its
+    /// test run, with the mutant still inserted, see `runner::mutant_kept_dir`.
+    /// Otherwise working copies are deleted.
+    #[serde(default)]
+    pub keep_missed_dirs: Option<PathBuf>,
+    /// Keep the working copies of all mutants in `keep_missed_dirs`, not only of
+    /// the surviving ones.
+    #[serde(default)]
+    pub keep_all_dirs: bool,
+    /// Stop the run once more than this many mutants could not be run at all
+    /// (see `MutantOutcome::error`). Unlimited by default.
+    #[serde(default)]
+    pub max_errors: Option<usize>,
+    /// Maximum number of mutants to run, sampled randomly.
+    pub max_mutants: Option<usize>,
+    /// Mutation types to look for.
+    pub mutation_types: Vec<MutationType>,
+    /// Only list the mutants instead of running them.
+    pub list: bool,
+    /// Format of the list of mutants.
+    #[serde(default)]
+    pub list_format: ListFormat,
+    /// Do not run mutants in files without any associated tests, see
+    /// `selection::TestMapping`.
+    pub skip_untested_files: bool,
+    /// Run each mutant against only the tests covering its line, see `coverage`.
+    /// The coverage is collected with pytest-cov unless `coverage_json` is set.
+    #[serde(default)]
+    pub coverage_guided: bool,
+    /// Coverage JSON report with contexts to select the tests from instead of
+    /// collecting it, implies `coverage_guided`.
+    #[serde(default)]
+    pub coverage_json: Option<PathBuf>,
+    /// Coverage.py JSON or XML report of the project. Mutants on lines it reports
+    /// as not executed are not run but reported as uncovered, see `coverage`.
+    #[serde(default)]
+    pub coverage_file: Option<PathBuf>,
+    /// Exclude files with syntax errors from discovery, see `syntax::check_syntax`.
+    pub check_syntax: bool,
+    /// Do not run the tests of mutants that are not valid python but report them
+    /// as invalid, see `MutantResult::Invalid`.
+    #[serde(default = "default_skip_invalid")]
+    pub skip_invalid: bool,
+    /// Seed for the random number generator used for sampling.
+    pub seed: u64,
+    /// How the `max_mutants` mutants are sampled.
+    #[serde(default)]
+    pub sampling: Sampling,
+    /// Order in which the mutants are started.
+    #[serde(default)]
+    pub order: Order,
+    /// Write aggregate numbers of the run to this file.
+    pub summary_file: Option<PathBuf>,
+    /// Write a JSON report of every mutant and its outcome to this file, see
+    /// `report`.
+    #[serde(default)]
+    pub report_json: Option<PathBuf>,
+    /// Write a JUnit XML report of every mutant to this file, see `report`.
+    #[serde(default)]
+    pub report_junit: Option<PathBuf>,
+    /// Write an HTML report to this directory, see `report`.
+    #[serde(default)]
+    pub report_html: Option<PathBuf>,
+    /// Write the surviving mutants as a SARIF log to this file, see `sarif`.
+    #[serde(default)]
+    pub report_sarif: Option<PathBuf>,
+    /// Include caught mutants as notes in the SARIF log.
+    #[serde(default)]
+    pub sarif_include_caught: bool,
+    /// Unix domain socket (or named pipe on Windows) to write the events of the
+    /// run to, see `events::SocketObserver`.
+    pub events_socket: Option<PathBuf>,
+    /// Further observers notified about the events of the run. Not serialized.
+    #[serde(skip)]
+    pub observers: Observers,
+    /// Additional glob patterns of files and directories not to copy into the
+    /// working copies of the project, on top of `DEFAULT_COPY_EXCLUDES`.
+    pub copy_exclude: Vec<String>,
+    /// Directory for all files pymute keeps for the project (relative to root).
+    /// Defaults to `.pymute/` under the root.
+    pub state_dir: Option<PathBuf>,
+    /// Path of the cache file (relative to root), see `cache_file`. Defaults to the
+    /// cache file in the state directory.
+    #[serde(default)]
+    pub cache_path: Option<PathBuf>,
+    /// Replace the whole cache with the results of this run instead of merging them
+    /// into it, see `cache::merge_csv_cache`.
+    #[serde(default)]
+    pub cache_overwrite: bool,
+    /// Skip malformed rows of the cache instead of failing, see
+    /// `cache::read_csv_cache_lenient`.
+    #[serde(default)]
+    pub cache_lenient: bool,
+    /// Prepared tree (e.g. with an installed virtual environment) copied into the
+    /// working copies instead of the root, see `runner::WorkdirPool::prepared`.
+    /// Mutants are still discovered in the root.
+    #[serde(default)]
+    pub prepared_root: Option<PathBuf>,
+    /// Path in the prepared root under which the files of the project are found.
+    /// Defaults to the prepared root itself.
+    #[serde(default)]
+    pub prepared_src_prefix: Option<PathBuf>,
+    /// Existing copies of the root (e.g. with a virtual environment built in place)
+    /// to insert the mutants into instead of temporary working copies, one per
+    /// worker, see `runner::WorkdirPool::workspaces`. The number of workspaces
+    /// replaces `num_threads`.
+    #[serde(default)]
+    pub workspaces: Vec<PathBuf>,
+    /// Insert the mutants into the files of the root itself, one at a time, see
+    /// `runner::WorkdirPool::in_place`. Requires a clean git working tree unless
+    /// `allow_dirty` is set.
+    #[serde(default)]
+    pub in_place: bool,
+    /// Run `in_place` even if the git working tree has uncommitted changes.
+    #[serde(default)]
+    pub allow_dirty: bool,
+    /// Do not run mutants nested in more blocks than this, see `scope::line_scopes`.
+    #[serde(default)]
+    pub max_nesting: Option<usize>,
+    /// Do not run mutants in functions longer than this many lines.
+    #[serde(default)]
+    pub max_function_lines: Option<usize>,
+    /// Count likely equivalent mutants in the mutation score, see
+    /// `equivalence::likely_equivalent`.
+    #[serde(default)]
+    pub include_likely_equivalent: bool,
+    /// Count uncovered mutants as survivors in the mutation score, see
+    /// `RunSummary::include_uncovered`.
+    #[serde(default)]
+    pub include_uncovered: bool,
+    /// Only run the mutants with these ids, see `Mutant::id`. All mutants are run
+    /// if empty.
+    #[serde(default)]
+    pub mutant_ids: Vec<String>,
+    /// Glob patterns (relative to root) of modules not to mutate, even if they
+    /// match `modules`.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Also mutate test files (test_*.py and *_test.py) matching `modules`.
+    #[serde(default)]
+    pub include_tests: bool,
+    /// Only run the mutants on lines changed since this git ref, see
+    /// `diff::changed_lines`.
+    #[serde(default)]
+    pub diff_base: Option<String>,
+    /// Only run the mutants of this shard, see `shard`.
+    #[serde(default)]
+    pub shard: Option<Shard>,
+    /// Run all mutants, even those whose result is in the cache, see
+    /// `cache::cached_outcomes`.
+    #[serde(default)]
+    pub rerun_all: bool,
+    /// Fail the run if the mutation score (in percent, see `RunSummary::score`) is
+    /// below this, see `RunSummary::check`.
+    #[serde(default)]
+    pub fail_under: Option<f64>,
+    /// Fail the run if any mutant survives, see `RunSummary::check`.
+    #[serde(default)]
+    pub fail_on_missed: bool,
+    /// Neither read nor write the cache, e.g. for a pristine run in CI.
+    #[serde(default)]
+    pub no_cache: bool,
+    /// Delete the cache before discovery, so that the cache only holds the results
+    /// of this run afterwards.
+    #[serde(default)]
+    pub clear_cache: bool,
+}
+
+impl RunConfig {
+    /// Create a configuration for the project at `root` with default options.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        RunConfig {
+            root: root.into(),
+            modules: vec![DEFAULT_MODULES.into()],
+            tests: DEFAULT_TESTS.into(),
+            num_threads: DEFAULT_NUM_THREADS,
+            output_level: OutputLevel::Missed,
+            runner: Runner::Pytest,
+            environment: None,
+            test_command: None,
+            pytest_workers: None,
+            pytest_cache_clear: false,
+            pytest_dist: None,
+            runner_args: None,
+            python: None,
+            runner_binary: None,
+            env: BTreeMap::new(),
+            log_dir: None,
+            keep_missed_dirs: None,
+            keep_all_dirs: false,
+            max_errors: None,
+            max_mutants: None,
+            mutation_types: DEFAULT_MUTATION_TYPES.to_vec(),
+            list: false,
+            list_format: ListFormat::Human,
+            skip_untested_files: false,
+            coverage_guided: false,
+            coverage_json: None,
+            coverage_file: None,
+            check_syntax: true,
+            skip_invalid: true,
+            seed: DEFAULT_SEED,
+            sampling: Sampling::Global,
+            order: Order::File,
+            summary_file: None,
+            report_json: None,
+            report_junit: None,
+            report_html: None,
+            report_sarif: None,
+            sarif_include_caught: false,
+            events_socket: None,
+            observers: Observers::default(),
+            copy_exclude: Vec::new(),
+            state_dir: None,
+            cache_path: None,
+            cache_overwrite: false,
+            cache_lenient: false,
+            prepared_root: None,
+            prepared_src_prefix: None,
+            workspaces: Vec::new(),
+            in_place: false,
+            allow_dirty: false,
+            max_nesting: None,
+            max_function_lines: None,
+            include_likely_equivalent: false,
+            include_uncovered: false,
+            mutant_ids: Vec::new(),
+            exclude: Vec::new(),
+            include_tests: false,
+            diff_base: None,
+            shard: None,
+            rerun_all: false,
+            fail_under: None,
+            fail_on_missed: false,
+            no_cache: false,
+            clear_cache: false,
+        }
+    }
+
+    /// Set the glob expression for modules to mutate.
+    pub fn modules(mut self, modules: impl Into<String>) -> Self {
+        self.modules = vec![modules.into()];
+        self
+    }
+
+    /// Also mutate the modules matching the glob expression `modules`.
+    pub fn add_modules(mut self, modules: impl Into<String>) -> Self {
+        self.modules.push(modules.into());
+        self
+    }
+
+    /// Do not mutate modules matching any of `patterns`.
+    pub fn exclude(mut self, patterns: &[&str]) -> Self {
+        self.exclude = patterns.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// Also mutate test files matching the glob expressions for modules.
+    pub fn include_tests(mut self, include_tests: bool) -> Self {
+        self.include_tests = include_tests;
+        self
+    }
+
+    /// Set the path of the tests to run.
+    pub fn tests(mut self, tests: impl Into<String>) -> Self {
+        self.tests = tests.into();
+        self
+    }
+
+    /// Set the number of mutants to run in parallel.
+    pub fn num_threads(mut self, num_threads: usize) -> Self {
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Set the output level.
+    pub fn output_level(mut self, output_level: OutputLevel) -> Self {
+        self.output_level = output_level;
+        self
+    }
+
+    /// Set the test runner.
+    pub fn runner(mut self, runner: Runner) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    /// Set the tox environment or the comma-separated nox sessions.
+    pub fn environment(mut self, environment: impl Into<String>) -> Self {
+        self.environment = Some(environment.into());
+        self
+    }
+
+    /// Set the test command for the custom runner.
+    pub fn test_command(mut self, test_command: impl Into<String>) -> Self {
+        self.test_command = Some(test_command.into());
+        self
+    }
+
+    /// Run each test run of the pytest runner with pytest-xdist using `workers`.
+    pub fn pytest_workers(mut self, workers: PytestWorkers) -> Self {
+        self.pytest_workers = Some(workers);
+        self
+    }
+
+    /// Set the distribution mode of pytest-xdist, e.g. `loadfile`.
+    pub fn pytest_dist(mut self, pytest_dist: impl Into<String>) -> Self {
+        self.pytest_dist = Some(pytest_dist.into());
+        self
+    }
+
+    /// Clear the pytest cache at the start of each test run of the pytest runner.
+    pub fn pytest_cache_clear(mut self, pytest_cache_clear: bool) -> Self {
+        self.pytest_cache_clear = pytest_cache_clear;
+        self
+    }
+
+    /// Append `runner_args`, split using shell-style quoting, to the test command.
+    pub fn runner_args(mut self, runner_args: impl Into<String>) -> Self {
+        self.runner_args = Some(runner_args.into());
+        self
+    }
+
+    /// Run the tests and compile the files with the python interpreter `python`.
+    pub fn python(mut self, python: impl Into<PathBuf>) -> Self {
+        self.python = Some(python.into());
+        self
+    }
+
+    /// Use `runner_binary` as the executable of the tox or nox runner.
+    pub fn runner_binary(mut self, runner_binary: impl Into<PathBuf>) -> Self {
+        self.runner_binary = Some(runner_binary.into());
+        self
+    }
+
+    /// Set the environment variable `key` to `value` for each test run.
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Write the output of the test run of each mutant to a file in `log_dir`.
+    pub fn log_dir(mut self, log_dir: impl Into<PathBuf>) -> Self {
+        self.log_dir = Some(log_dir.into());
+        self
+    }
+
+    /// Keep the working copy of each surviving mutant in a directory named after
+    /// its id in `keep_missed_dirs`.
+    pub fn keep_missed_dirs(mut self, keep_missed_dirs: impl Into<PathBuf>) -> Self {
+        self.keep_missed_dirs = Some(keep_missed_dirs.into());
+        self
+    }
+
+    /// Keep the working copies of all mutants, not only of the surviving ones.
+    pub fn keep_all_dirs(mut self, keep_all_dirs: bool) -> Self {
+        self.keep_all_dirs = keep_all_dirs;
+        self
+    }
+
+    /// Stop the run once more than `max_errors` mutants could not be run.
+    pub fn max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = Some(max_errors);
+        self
+    }
+
+    /// Run at most `max_mutants` randomly sampled mutants.
+    pub fn max_mutants(mut self, max_mutants: usize) -> Self {
+        self.max_mutants = Some(max_mutants);
+        self
+    }
+
+    /// Set the mutation types to look for.
+    pub fn mutation_types(mut self, mutation_types: &[MutationType]) -> Self {
+        self.mutation_types = mutation_types.to_vec();
+        self
+    }
+
+    /// Only list the mutants instead of running them.
+    pub fn list(mut self, list: bool) -> Self {
+        self.list = list;
+        self
+    }
+
+    /// Set the format of the list of mutants.
+    pub fn list_format(mut self, list_format: ListFormat) -> Self {
+        self.list_format = list_format;
+        self
+    }
+
+    /// Do not run mutants in files without any associated tests.
+    pub fn skip_untested_files(mut self, skip_untested_files: bool) -> Self {
+        self.skip_untested_files = skip_untested_files;
+        self
+    }
+
+    /// Run each mutant against only the tests covering its line.
+    pub fn coverage_guided(mut self, coverage_guided: bool) -> Self {
+        self.coverage_guided = coverage_guided;
+        self
+    }
+
+    /// Select the tests of each mutant from the coverage JSON report at `path`.
+    pub fn coverage_json(mut self, path: impl Into<PathBuf>) -> Self {
+        self.coverage_json = Some(path.into());
+        self
+    }
+
+    /// Report the mutants on lines not executed according to the coverage.py JSON
+    /// or XML report at `path` as uncovered instead of running them.
+    pub fn coverage_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.coverage_file = Some(path.into());
+        self
+    }
+
+    /// Check the python files for syntax errors and exclude those that fail.
+    pub fn check_syntax(mut self, check_syntax: bool) -> Self {
+        self.check_syntax = check_syntax;
+        self
+    }
+
+    /// Skip the tests of mutants that are not valid python.
+    pub fn skip_invalid(mut self, skip_invalid: bool) -> Self {
+        self.skip_invalid = skip_invalid;
+        self
+    }
+
+    /// Set the seed used for sampling mutants.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set how the `max_mutants` mutants are sampled.
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Set the order in which the mutants are started.
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Write aggregate numbers of the run to `summary_file`.
+    pub fn summary_file(mut self, summary_file: impl Into<PathBuf>) -> Self {
+        self.summary_file = Some(summary_file.into());
+        self
+    }
+
+    /// Write a JSON report of every mutant and its outcome to `report_json`.
+    pub fn report_json(mut self, report_json: impl Into<PathBuf>) -> Self {
+        self.report_json = Some(report_json.into());
+        self
+    }
+
+    /// Write a JUnit XML report of every mutant to `report_junit`.
+    pub fn report_junit(mut self, report_junit: impl Into<PathBuf>) -> Self {
+        self.report_junit = Some(report_junit.into());
+        self
+    }
+
+    /// Write an HTML report to the directory `report_html`.
+    pub fn report_html(mut self, report_html: impl Into<PathBuf>) -> Self {
+        self.report_html = Some(report_html.into());
+        self
+    }
+
+    /// Write the surviving mutants as a SARIF log to `report_sarif`.
+    pub fn report_sarif(mut self, report_sarif: impl Into<PathBuf>) -> Self {
+        self.report_sarif = Some(report_sarif.into());
+        self
+    }
+
+    /// Include caught mutants as notes in the SARIF log.
+    pub fn sarif_include_caught(mut self, sarif_include_caught: bool) -> Self {
+        self.sarif_include_caught = sarif_include_caught;
+        self
+    }
+
+    /// Write the events of the run as newline-delimited JSON to the socket at
+    /// `events_socket`.
+    pub fn events_socket(mut self, events_socket: impl Into<PathBuf>) -> Self {
+        self.events_socket = Some(events_socket.into());
+        self
+    }
+
+    /// Notify `observer` about the events of the run, e.g. to show the progress in a
+    /// custom way.
+    pub fn observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observers.0.push(observer);
+        self
+    }
+
+    /// Do not copy files or directories matching any of `patterns` into the working
+    /// copies of the project.
+    pub fn copy_exclude(mut self, patterns: &[&str]) -> Self {
+        self.copy_exclude = patterns.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// Keep all files pymute produces for the project in `state_dir`.
+    pub fn state_dir(mut self, state_dir: impl Into<PathBuf>) -> Self {
+        self.state_dir = Some(state_dir.into());
+        self
+    }
+
+    /// Keep the cache in the file at `cache_path` (relative to root) instead of the
+    /// state directory, e.g. to commit it to the repository.
+    pub fn cache_path(mut self, cache_path: impl Into<PathBuf>) -> Self {
+        self.cache_path = Some(cache_path.into());
+        self
+    }
+
+    /// Replace the whole cache with the results of this run, dropping the results
+    /// of all mutants that were not run.
+    pub fn cache_overwrite(mut self, cache_overwrite: bool) -> Self {
+        self.cache_overwrite = cache_overwrite;
+        self
+    }
+
+    /// Skip malformed rows of the cache instead of failing.
+    pub fn cache_lenient(mut self, cache_lenient: bool) -> Self {
+        self.cache_lenient = cache_lenient;
+        self
+    }
+
+    /// Copy the prepared tree at `prepared_root` into the working copies instead of
+    /// the root, e.g. to run the tests with an environment that takes long to build.
+    pub fn prepared_root(mut self, prepared_root: impl Into<PathBuf>) -> Self {
+        self.prepared_root = Some(prepared_root.into());
+        self
+    }
+
+    /// Find the files of the project under `prepared_src_prefix` in the prepared
+    /// root, e.g. `lib/python3.11/site-packages`.
+    pub fn prepared_src_prefix(mut self, prepared_src_prefix: impl Into<PathBuf>) -> Self {
+        self.prepared_src_prefix = Some(prepared_src_prefix.into());
+        self
+    }
+
+    /// Insert the mutants into the existing copy of the root at `workspace`
+    /// instead of a temporary copy. Each workspace is used by one worker.
+    pub fn workspace(mut self, workspace: impl Into<PathBuf>) -> Self {
+        self.workspaces.push(workspace.into());
+        self
+    }
+
+    /// Insert the mutants into the files of the root itself instead of working
+    /// copies, one mutant at a time.
+    pub fn in_place(mut self, in_place: bool) -> Self {
+        self.in_place = in_place;
+        self
+    }
+
+    /// Run in place even if the git working tree has uncommitted changes.
+    pub fn allow_dirty(mut self, allow_dirty: bool) -> Self {
+        self.allow_dirty = allow_dirty;
+        self
+    }
+
+    /// Do not run mutants on lines nested in more than `max_nesting` blocks
+    /// (functions, classes, loops, conditions, ...).
+    pub fn max_nesting(mut self, max_nesting: usize) -> Self {
+        self.max_nesting = Some(max_nesting);
+        self
+    }
+
+    /// Do not run mutants in functions longer than `max_function_lines` lines.
+    pub fn max_function_lines(mut self, max_function_lines: usize) -> Self {
+        self.max_function_lines = Some(max_function_lines);
+        self
+    }
+
+    /// Count likely equivalent mutants in the mutation score instead of excluding
+    /// them.
+    pub fn include_likely_equivalent(mut self, include_likely_equivalent: bool) -> Self {
+        self.include_likely_equivalent = include_likely_equivalent;
+        self
+    }
+
+    /// Count uncovered mutants as survivors in the mutation score instead of
+    /// excluding them.
+    pub fn include_uncovered(mut self, include_uncovered: bool) -> Self {
+        self.include_uncovered = include_uncovered;
+        self
+    }
+
+    /// Only run the mutant with id `mutant_id`, in addition to any other mutants
+    /// selected this way.
+    pub fn mutant_id(mut self, mutant_id: impl Into<String>) -> Self {
+        self.mutant_ids.push(mutant_id.into());
+        self
+    }
+
+    /// Only run the mutants on lines changed since the git ref `diff_base`.
+    pub fn diff_base(mut self, diff_base: impl Into<String>) -> Self {
+        self.diff_base = Some(diff_base.into());
+        self
+    }
+
+    /// Only run the mutants of `shard`.
+    pub fn shard(mut self, shard: Shard) -> Self {
+        self.shard = Some(shard);
+        self
+    }
+
+    /// Run all mutants, even those whose result is in the cache.
+    pub fn rerun_all(mut self, rerun_all: bool) -> Self {
+        self.rerun_all = rerun_all;
+        self
+    }
+
+    /// Fail the run if the mutation score is below `fail_under` percent.
+    pub fn fail_under(mut self, fail_under: f64) -> Self {
+        self.fail_under = Some(fail_under);
+        self
+    }
+
+    /// Fail the run if any mutant survives.
+    pub fn fail_on_missed(mut self, fail_on_missed: bool) -> Self {
+        self.fail_on_missed = fail_on_missed;
+        self
+    }
+
+    /// Neither read nor write the cache.
+    pub fn no_cache(mut self, no_cache: bool) -> Self {
+        self.no_cache = no_cache;
+        self
+    }
+
+    /// Delete the cache before discovery.
+    pub fn clear_cache(mut self, clear_cache: bool) -> Self {
+        self.clear_cache = clear_cache;
+        self
+    }
+
+    /// The state directory layout for this configuration.
+    pub fn state(&self) -> StateDir {
+        StateDir::new(&self.root, self.state_dir.as_deref())
+    }
+
+    /// Path to the cache of mutant results: `cache_path` relative to the root, or
+    /// the cache file in the state directory.
+    pub fn cache_file(&self) -> PathBuf {
+        match &self.cache_path {
+            Some(cache_path) => self.root.join(cache_path),
+            None => self.state().cache_file(),
+        }
+    }
+
+    /// Run pymute with this configuration.
+    pub fn run(&self) -> Result<RunSummary, PymuteError> {
+        crate::run(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{
+        Profile, ProfileSettings, RunConfig, CI_FAIL_UNDER, DEFAULT_MUTATION_TYPES,
+    };
+    use crate::mutants::MutationType;
+    use crate::plan::{ListFormat, Order, Sampling};
+    use crate::runner::{OutputLevel, Runner};
+    use crate::state::StateDir;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_run_config_defaults() {
+        let config = RunConfig::new("project");
+        assert_eq!(config.root, PathBuf::from("project"));
+        assert_eq!(config.modules, vec!["**/*.py"]);
+        assert_eq!(config.tests, ".");
+        assert_eq!(config.num_threads, 1);
+        assert_eq!(config.output_level, OutputLevel::Missed);
+        assert_eq!(config.runner, Runner::Pytest);
+        assert_eq!(config.environment, None);
+        assert_eq!(config.test_command, None);
+        assert_eq!(config.pytest_workers, None);
+        assert!(!config.pytest_cache_clear);
+        assert_eq!(config.pytest_dist, None);
+        assert_eq!(config.runner_args, None);
+        assert_eq!(config.python, None);
+        assert_eq!(config.runner_binary, None);
+        assert!(config.env.is_empty());
+        assert_eq!(config.log_dir, None);
+        assert_eq!(config.keep_missed_dirs, None);
+        assert!(!config.keep_all_dirs);
+        assert_eq!(config.max_errors, None);
+        assert_eq!(config.max_mutants, None);
+        assert_eq!(config.mutation_types, DEFAULT_MUTATION_TYPES.to_vec());
+        assert!(!config.list);
+        assert_eq!(config.list_format, ListFormat::Human);
+        assert!(!config.skip_untested_files);
+        assert!(!config.coverage_guided);
+        assert_eq!(config.coverage_json, None);
+        assert_eq!(config.coverage_file, None);
+        assert!(config.check_syntax);
+        assert!(config.skip_invalid);
+        assert_eq!(config.seed, 42);
+        assert_eq!(config.sampling, Sampling::Global);
+        assert_eq!(config.order, Order::File);
+        assert_eq!(config.summary_file, None);
+        assert_eq!(config.report_json, None);
+        assert_eq!(config.report_junit, None);
+        assert_eq!(config.report_html, None);
+        assert_eq!(config.report_sarif, None);
+        assert!(!config.sarif_include_caught);
+        assert_eq!(config.events_socket, None);
+        assert!(config.copy_exclude.is_empty());
+        assert_eq!(config.cache_path, None);
+        assert_eq!(config.cache_file(), config.state().cache_file());
+        assert!(!config.cache_overwrite);
+        assert!(!config.cache_lenient);
+        assert_eq!(config.prepared_root, None);
+        assert_eq!(config.prepared_src_prefix, None);
+        assert!(config.workspaces.is_empty());
+        assert!(!config.in_place);
+        assert!(!config.allow_dirty);
+        assert_eq!(config.max_nesting, None);
+        assert_eq!(config.max_function_lines, None);
+        assert!(!config.include_likely_equivalent);
+        assert!(!config.include_uncovered);
+        assert!(config.mutant_ids.is_empty());
+        assert!(config.exclude.is_empty());
+        assert!(!config.include_tests);
+        assert_eq!(config.diff_base, None);
+        assert_eq!(config.shard, None);
+        assert!(!config.rerun_all);
+        assert_eq!(config.fail_under, None);
+        assert!(!config.fail_on_missed);
+        assert!(!config.no_cache);
+        assert!(!config.clear_cache);
+        assert_eq!(config.state().path(), PathBuf::from("project/.pymute"));
+    }
+
+    #[test]
+    fn test_profile_settings() {
+        let state = StateDir::new(Path::new("project"), None);
+        assert_eq!(
+            Profile::Standard.settings(&state),
+            ProfileSettings::default()
+        );
+
+        let quick = Profile::Quick.settings(&state);
+        assert_eq!(quick.max_mutants, Some(Some(100)));
+        assert_eq!(quick.diff_base.as_deref(), Some("HEAD"));
+
+        let thorough = Profile::Thorough.settings(&state);
+        assert_eq!(thorough.max_mutants, Some(None));
+        assert!(thorough
+            .mutation_types
+            .unwrap()
+            .contains(&MutationType::Time));
+
+        let ci = Profile::Ci.settings(&state);
+        assert_eq!(ci.output_level, Some(OutputLevel::Quiet));
+        assert_eq!(
+            ci.report_json,
+            Some(PathBuf::from("project/.pymute/reports/mutants.json"))
+        );
+        assert_eq!(
+            ci.report_junit,
+            Some(PathBuf::from("project/.pymute/reports/junit.xml"))
+        );
+        assert_eq!(ci.fail_under, Some(CI_FAIL_UNDER));
+    }
+
+    #[test]
+    fn test_run_config_builder() {
+        let config = RunConfig::new("project")
+            .modules("src/**/*.py")
+            .add_modules("scripts/*.py")
+            .exclude(&["src/generated/**"])
+            .tests("tests")
+            .num_threads(4)
+            .runner(Runner::Tox)
+            .environment("py311")
+            .max_mutants(50)
+            .mutation_types(&[MutationType::Numbers])
+            .seed(7);
+
+        assert_eq!(config.modules, vec!["src/**/*.py", "scripts/*.py"]);
+        assert_eq!(config.exclude, vec!["src/generated/**"]);
+        assert_eq!(config.tests, "tests");
+        assert_eq!(config.num_threads, 4);
+        assert_eq!(config.runner, Runner::Tox);
+        assert_eq!(config.environment, Some("py311".into()));
+        assert_eq!(config.max_mutants, Some(50));
+        assert_eq!(config.mutation_types, vec![MutationType::Numbers]);
+        assert_eq!(config.seed, 7);
+    }
+}
@@ -0,0 +1,182 @@
+//! Options of a run read from a `pymute.toml` in the root of the project, with
+//! named profiles.
+//!
+//! The top-level table sets the same options as the `[tool.pymute]` table of
+//! `pyproject.toml` (see `pyproject`), and each `[profile.<name>]` table sets
+//! options for the profile selected with `--profile <name>`:
+//!
+//! ```toml
+//! modules = "src/**/*.py"
+//!
+//! [profile.quick]
+//! max_mutants = 50
+//!
+//! [profile.full]
+//! mutation_types = ["math-ops", "comp-ops", "numbers", "strings", "time"]
+//! ```
+//!
+//! The options of the selected profile take precedence over the top-level table,
+//! which takes precedence over `pyproject.toml`. Profiles can have the name of a
+//! built-in profile (see `config::Profile`), whose options they then override.
+//! Unknown keys are returned as warnings instead of failing, so that a file
+//! written for a newer version of pymute still works.
+//!
+//! A `pymute.toml` below the root marks a subproject instead, see `subprojects`.
+
+use crate::config::Profile;
+use crate::pyproject::{self, PyprojectSettings};
+
+use clap::ValueEnum;
+
+use std::{collections::BTreeMap, error::Error, fmt, path::Path};
+
+/// Name of the configuration file in the root of the project.
+pub const CONFIG_FILE: &str = "pymute.toml";
+
+/// The options set in a `CONFIG_FILE`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    /// Options of the top-level table.
+    pub settings: PyprojectSettings,
+    /// Options of each profile, by name.
+    pub profiles: BTreeMap<String, PyprojectSettings>,
+    /// Keys that are not options, e.g. `profile.quick.max_mutant`.
+    pub unknown_keys: Vec<String>,
+}
+
+impl ConfigFile {
+    /// Read the `CONFIG_FILE` of the project at `root`. A missing file sets no
+    /// options.
+    pub fn read(root: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let path = root.join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(ConfigFile::default());
+        }
+        let mut table = pyproject::read_table(&path)?;
+        let profiles = table.remove("profile");
+        let (settings, mut unknown_keys) = PyprojectSettings::from_table(&table, "", &path)?;
+
+        let mut config_file = ConfigFile {
+            settings,
+            ..Default::default()
+        };
+        if let Some(profiles) = &profiles {
+            for (name, profile) in pyproject::as_table(profiles, "profile", &path)? {
+                let key = format!("profile.{name}");
+                let profile = pyproject::as_table(profile, &key, &path)?;
+                let (settings, unknown) =
+                    PyprojectSettings::from_table(profile, &format!("{key}."), &path)?;
+                unknown_keys.extend(unknown);
+                config_file.profiles.insert(name.clone(), settings);
+            }
+        }
+        config_file.unknown_keys = unknown_keys;
+        Ok(config_file)
+    }
+
+    /// The options for the profile `name`: those of the profile over those of the
+    /// top-level table.
+    ///
+    /// Fails if `name` is neither a profile of the file nor a built-in profile.
+    pub fn settings(&self, name: &str) -> Result<PyprojectSettings, Box<dyn Error + Send + Sync>> {
+        match self.profiles.get(name) {
+            Some(profile) => Ok(self.settings.clone().merge(profile.clone())),
+            None if Profile::from_str(name, false).is_ok() => Ok(self.settings.clone()),
+            None => Err(Box::new(UnknownProfile {
+                name: name.into(),
+                profiles: self.profile_names(),
+            })),
+        }
+    }
+
+    /// Names of the built-in profiles followed by those only defined in the file.
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Profile::value_variants()
+            .iter()
+            .filter_map(|profile| profile.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        for name in self.profiles.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+}
+
+#[derive(Debug)]
+struct UnknownProfile {
+    name: String,
+    profiles: Vec<String>,
+}
+
+impl Error for UnknownProfile {}
+impl fmt::Display for UnknownProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown profile `{}`, expected one of {}",
+            self.name,
+            self.profiles.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config_file::ConfigFile;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_config_file() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(ConfigFile::read(root).unwrap(), ConfigFile::default());
+
+        fs::write(
+            root.join("pymute.toml"),
+            r#"modules = "src/**/*.py"
+max_mutants = 10
+colour = "always"
+
+[profile.quick]
+max_mutants = 50
+max_mutant = 5
+
+[profile.ci]
+output_level = "quiet"
+"#,
+        )
+        .unwrap();
+        let config_file = ConfigFile::read(root).unwrap();
+        assert_eq!(
+            config_file.unknown_keys,
+            vec!["colour", "profile.quick.max_mutant"]
+        );
+
+        let quick = config_file.settings("quick").unwrap();
+        assert_eq!(quick.modules, Some(vec!["src/**/*.py".to_string()]));
+        assert_eq!(quick.max_mutants, Some(50));
+        assert_eq!(
+            config_file.settings("standard").unwrap().max_mutants,
+            Some(10)
+        );
+
+        let error = config_file.settings("nightly").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown profile `nightly`, expected one of quick, standard, thorough, ci"
+        );
+
+        fs::write(root.join("pymute.toml"), "[profile.quick]\nseed = -1\n").unwrap();
+        let error = ConfigFile::read(root).unwrap_err();
+        assert!(
+            error.to_string().contains("`profile.quick.seed`"),
+            "{error}"
+        );
+
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,446 @@
+//! Coverage-guided selection of the tests run against each mutant.
+//!
+//! Running the whole test suite for every mutant is the largest cost of a run. With
+//! coverage-guided selection, the test suite is run once with
+//! [pytest-cov](https://pytest-cov.readthedocs.io) recording which test executed
+//! each line (`--cov-context=test`), and exported with
+//! `coverage json --show-contexts`:
+//!
+//! ```json
+//! {"files": {"pkg/calc.py": {
+//!     "missing_lines": [7],
+//!     "contexts": {"2": ["tests/test_calc.py::test_add|run"]}
+//! }}}
+//! ```
+//!
+//! Each mutant is then tested with only the tests covering its line. Mutants on
+//! lines that no test executed are reported as uncovered without running them, as
+//! they can not be caught. Mutants on lines the coverage data knows nothing about,
+//! e.g. continuation lines of a statement or files that were not measured, are
+//! tested with the whole test suite.
+//!
+//! Without contexts, e.g. from an existing coverage.py JSON or XML report of the
+//! project (`RunConfig::coverage_file`), the coverage only tells which mutants are
+//! uncovered, and all other mutants are tested with the whole test suite.
+
+use crate::error::PymuteError;
+use crate::mutants::Mutant;
+use crate::runner::{self, Runner};
+use crate::RunConfig;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Phases of a test that pytest-cov appends to the node id in the context.
+const TEST_PHASES: [&str; 3] = ["setup", "run", "teardown"];
+
+/// Which lines of the files of a project are covered by which tests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageMap {
+    /// Coverage of each measured file, by path relative to the root.
+    files: BTreeMap<PathBuf, FileCoverage>,
+    /// Whether the tests executing each line are known.
+    #[serde(default)]
+    contexts: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct FileCoverage {
+    /// Node ids of the tests executing each line.
+    tests: BTreeMap<usize, BTreeSet<String>>,
+    /// Lines with statements that were not executed.
+    missing: BTreeSet<usize>,
+}
+
+/// What the coverage data tells about the line of a mutant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage<'a> {
+    /// The line is executed by these tests.
+    Tests(&'a BTreeSet<String>),
+    /// The line is not executed by any test.
+    Uncovered,
+    /// The line was not measured, so all tests are run.
+    Unknown,
+}
+
+/// A coverage JSON report of coverage.py, only the parts pymute needs.
+#[derive(Debug, Deserialize)]
+struct CoverageReport {
+    files: BTreeMap<String, CoverageReportFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CoverageReportFile {
+    #[serde(default)]
+    missing_lines: Vec<usize>,
+    contexts: Option<BTreeMap<usize, Vec<String>>>,
+}
+
+impl CoverageMap {
+    /// Parse a coverage.py JSON report of the project at `root`, with or without
+    /// contexts.
+    ///
+    /// Paths in the report are relative to the directory coverage was run in, which
+    /// must be the root, or absolute.
+    pub fn from_json(json: &str, root: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let report: CoverageReport =
+            serde_json::from_str(json).map_err(|error| InvalidCoverage {
+                reason: error.to_string(),
+            })?;
+
+        let mut map = CoverageMap {
+            contexts: report.files.values().any(|file| file.contexts.is_some()),
+            ..Default::default()
+        };
+        for (path, file) in report.files {
+            // files outside of the project are never mutated
+            let Some(relative) = relative_path(PathBuf::from(path), root) else {
+                continue;
+            };
+            let tests = file
+                .contexts
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(line, contexts)| {
+                    let tests = contexts.iter().filter_map(|context| test_id(context));
+                    (line, tests.collect())
+                })
+                .collect();
+            map.files.insert(
+                relative,
+                FileCoverage {
+                    tests,
+                    missing: file.missing_lines.into_iter().collect(),
+                },
+            );
+        }
+        Ok(map)
+    }
+
+    /// Parse a coverage.py XML (Cobertura) report of the project at `root`. XML
+    /// reports have no contexts.
+    ///
+    /// File names are relative to the first source directory of the report inside
+    /// the root, or to the root if there is none.
+    pub fn from_xml(xml: &str, root: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let source = Regex::new(r"<source>([^<]*)</source>")?;
+        let class = Regex::new(r#"(?s)<class\b[^>]*\bfilename="([^"]*)"[^>]*>(.*?)</class>"#)?;
+        let line = Regex::new(r#"<line\b[^>]*\bnumber="(\d+)"[^>]*\bhits="(\d+)""#)?;
+
+        let sources: Vec<PathBuf> = source
+            .captures_iter(xml)
+            .map(|source| PathBuf::from(unescape_xml(&source[1])))
+            .collect();
+        let mut map = CoverageMap::default();
+        for class in class.captures_iter(xml) {
+            let filename = PathBuf::from(unescape_xml(&class[1]));
+            let relative = sources
+                .iter()
+                .find_map(|source| relative_path(source.join(&filename), root))
+                .or_else(|| relative_path(filename, root));
+            let Some(relative) = relative else {
+                continue;
+            };
+            let file = map.files.entry(relative).or_default();
+            for line in line.captures_iter(&class[2]) {
+                let number: usize = line[1].parse()?;
+                if &line[2] == "0" {
+                    file.missing.insert(number);
+                } else {
+                    file.tests.entry(number).or_default();
+                }
+            }
+        }
+        if map.files.is_empty() && !xml.contains("<coverage") {
+            return Err(Box::new(InvalidCoverage {
+                reason: "not a coverage XML report".into(),
+            }));
+        }
+        Ok(map)
+    }
+
+    /// Read the coverage.py report at `path` of the project at `root`, as XML if
+    /// its extension is `.xml` and as JSON otherwise.
+    pub fn read(path: &Path, root: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let content = fs::read_to_string(path).map_err(|error| InvalidCoverage {
+            reason: format!("{}: {error}", path.display()),
+        })?;
+        match path.extension().is_some_and(|extension| extension == "xml") {
+            true => Self::from_xml(&content, root),
+            false => Self::from_json(&content, root),
+        }
+    }
+
+    /// The coverage map for the run of `config`: read from its
+    /// `RunConfig::coverage_json` or collected if it is `RunConfig::coverage_guided`,
+    /// and otherwise read from its `RunConfig::coverage_file` to find the uncovered
+    /// mutants only. None if the run uses no coverage.
+    ///
+    /// Coverage-guided runs fail for runners other than pytest, since only pytest
+    /// can run single tests.
+    pub fn for_config(config: &RunConfig) -> Result<Option<Self>, Box<dyn Error + Send + Sync>> {
+        if !config.coverage_guided && config.coverage_json.is_none() {
+            return match &config.coverage_file {
+                Some(path) => Self::read(path, &config.root).map(Some),
+                None => Ok(None),
+            };
+        }
+        if config.runner != Runner::Pytest {
+            return Err(Box::new(InvalidCoverage {
+                reason: "coverage-guided runs require the pytest runner".into(),
+            }));
+        }
+        let map = match &config.coverage_json {
+            Some(path) => Self::read(path, &config.root)?,
+            None => Self::collect(config)?,
+        };
+        if !map.contexts && !map.files.is_empty() {
+            return Err(Box::new(InvalidCoverage {
+                reason: "the report has no contexts, run pytest with --cov-context=test and \
+                         export it with `coverage json --show-contexts`"
+                    .into(),
+            }));
+        }
+        Ok(Some(map))
+    }
+
+    /// Run the tests of `config` once with pytest-cov in the root of the project,
+    /// and read the coverage of each test.
+    ///
+    /// The coverage data and the JSON report are written to the state directory.
+    /// Failing tests do not prevent the collection.
+    pub fn collect(config: &RunConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let state = config.state();
+        state.create()?;
+        let data_file = state.coverage_data_file();
+        let report_file = state.coverage_file();
+        let spawn_failed = |source| PymuteError::RunnerSpawn {
+            program: runner::python(config),
+            source,
+        };
+
+        let status = Command::new(runner::python(config))
+            .args(["-B", "-m", "pytest", &config.tests])
+            .args(["--cov=.", "--cov-context=test", "--cov-report="])
+            .args(["-p", "no:cacheprovider"])
+            .envs(&config.env)
+            .env("COVERAGE_FILE", &data_file)
+            .current_dir(&config.root)
+            .status()
+            .map_err(spawn_failed)?;
+        // pytest exits with 1 if tests failed, which still records their coverage
+        if !matches!(status.code(), Some(0 | 1)) {
+            return Err(Box::new(InvalidCoverage {
+                reason: format!("pytest with pytest-cov failed ({status})"),
+            }));
+        }
+
+        let status = Command::new(runner::python(config))
+            .args(["-m", "coverage", "json", "--show-contexts", "-o"])
+            .arg(&report_file)
+            .env("COVERAGE_FILE", &data_file)
+            .current_dir(&config.root)
+            .status()
+            .map_err(spawn_failed)?;
+        if !status.success() {
+            return Err(Box::new(InvalidCoverage {
+                reason: format!("coverage json failed ({status})"),
+            }));
+        }
+        Self::read(&report_file, &config.root)
+    }
+
+    /// The coverage of the line of `mutant` in the project at `root`.
+    pub fn coverage(&self, mutant: &Mutant, root: &Path) -> Coverage<'_> {
+        let path = mutant
+            .file_path
+            .strip_prefix(root)
+            .unwrap_or(&mutant.file_path);
+        let Some(file) = self.files.get(path) else {
+            return Coverage::Unknown;
+        };
+        match file.tests.get(&mutant.line_number) {
+            Some(tests) if !tests.is_empty() => Coverage::Tests(tests),
+            // executed, but only outside of tests, e.g. on import during collection
+            Some(_) => Coverage::Unknown,
+            None if file.missing.contains(&mutant.line_number) => Coverage::Uncovered,
+            None => Coverage::Unknown,
+        }
+    }
+}
+
+/// `path` relative to `root`, or None if it is an absolute path outside of the
+/// root. Relative paths are kept as they are.
+fn relative_path(path: PathBuf, root: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return Some(path);
+    }
+    let relative = root
+        .canonicalize()
+        .ok()
+        .and_then(|canonical| path.strip_prefix(canonical).ok().map(Path::to_path_buf));
+    relative.or_else(|| path.strip_prefix(root).ok().map(Path::to_path_buf))
+}
+
+/// Replace the predefined entities of XML in `text`.
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The node id of the test in the context `context` recorded by pytest-cov, e.g.
+/// `tests/test_calc.py::test_add` for `tests/test_calc.py::test_add|run`. The empty
+/// context of code run outside of tests has no test.
+fn test_id(context: &str) -> Option<String> {
+    let test = match context.rsplit_once('|') {
+        Some((test, phase)) if TEST_PHASES.contains(&phase) => test,
+        _ => context,
+    };
+    (!test.is_empty()).then(|| test.to_string())
+}
+
+#[derive(Debug)]
+struct InvalidCoverage {
+    reason: String,
+}
+
+impl Error for InvalidCoverage {}
+impl fmt::Display for InvalidCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not read the coverage of the tests: {}",
+            self.reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coverage::{Coverage, CoverageMap};
+    use crate::mutants::Mutant;
+    use std::{collections::BTreeSet, path::Path};
+
+    #[test]
+    fn test_coverage_map() {
+        let root = Path::new("/projects/project");
+        let json = r#"{"meta": {"version": "7.4.0", "show_contexts": true}, "files": {
+            "pkg/calc.py": {
+                "executed_lines": [1, 2, 4],
+                "missing_lines": [7],
+                "contexts": {
+                    "1": [""],
+                    "2": ["tests/test_calc.py::test_add|run", "tests/test_calc.py::test_add|setup",
+                          "tests/test_calc.py::test_sum[1-2]|run"],
+                    "4": [""]
+                }
+            },
+            "/projects/project/pkg/util.py": {"missing_lines": [], "contexts": {}},
+            "/usr/lib/python3/site.py": {"missing_lines": [3], "contexts": {}}
+        }}"#;
+        let map = CoverageMap::from_json(json, root).unwrap();
+        let mutant =
+            |file: &str, line| Mutant::new(root.join(file), line, " + ", " - ", 12, "a + b");
+
+        let tests: BTreeSet<String> = [
+            "tests/test_calc.py::test_add",
+            "tests/test_calc.py::test_sum[1-2]",
+        ]
+        .map(String::from)
+        .into();
+        assert_eq!(
+            map.coverage(&mutant("pkg/calc.py", 2), root),
+            Coverage::Tests(&tests)
+        );
+        assert_eq!(
+            map.coverage(&mutant("pkg/calc.py", 7), root),
+            Coverage::Uncovered
+        );
+        // executed outside of tests, or not a statement
+        assert_eq!(
+            map.coverage(&mutant("pkg/calc.py", 4), root),
+            Coverage::Unknown
+        );
+        assert_eq!(
+            map.coverage(&mutant("pkg/calc.py", 3), root),
+            Coverage::Unknown
+        );
+        // absolute paths in the root are relative to it, files outside are dropped
+        assert_eq!(map.files.len(), 2);
+        assert_eq!(
+            map.coverage(&mutant("pkg/util.py", 1), root),
+            Coverage::Unknown
+        );
+        assert_eq!(
+            map.coverage(&mutant("pkg/other.py", 1), root),
+            Coverage::Unknown
+        );
+
+        // reports without contexts only tell the uncovered lines
+        let json = r#"{"files": {"pkg/calc.py": {"executed_lines": [2], "missing_lines": [7]}}}"#;
+        let map = CoverageMap::from_json(json, root).unwrap();
+        assert!(!map.contexts);
+        assert_eq!(
+            map.coverage(&mutant("pkg/calc.py", 2), root),
+            Coverage::Unknown
+        );
+        assert_eq!(
+            map.coverage(&mutant("pkg/calc.py", 7), root),
+            Coverage::Uncovered
+        );
+    }
+
+    #[test]
+    fn test_coverage_from_xml() {
+        let root = Path::new("/projects/project");
+        let xml = r#"<?xml version="1.0" ?>
+<coverage version="7.4.0" timestamp="1700000000000" line-rate="0.75">
+    <sources>
+        <source>/projects/project/src</source>
+    </sources>
+    <packages>
+        <package name="pkg" line-rate="0.75">
+            <classes>
+                <class name="calc.py" filename="pkg/calc.py" line-rate="0.75">
+                    <methods/>
+                    <lines>
+                        <line number="1" hits="1"/>
+                        <line number="2" hits="3"/>
+                        <line number="4" hits="1" branch="true" condition-coverage="50% (1/2)"/>
+                        <line number="5" hits="0"/>
+                    </lines>
+                </class>
+            </classes>
+        </package>
+    </packages>
+</coverage>"#;
+        let map = CoverageMap::from_xml(xml, root).unwrap();
+        let mutant =
+            |file: &str, line| Mutant::new(root.join(file), line, " + ", " - ", 12, "a + b");
+        assert_eq!(
+            map.coverage(&mutant("src/pkg/calc.py", 2), root),
+            Coverage::Unknown
+        );
+        assert_eq!(
+            map.coverage(&mutant("src/pkg/calc.py", 5), root),
+            Coverage::Uncovered
+        );
+        assert_eq!(
+            map.coverage(&mutant("pkg/calc.py", 5), root),
+            Coverage::Unknown
+        );
+
+        assert!(CoverageMap::from_xml("<html></html>", root).is_err());
+    }
+}
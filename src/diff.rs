@@ -0,0 +1,308 @@
+//! Restrict a run to the lines changed since a git ref.
+//!
+//! In CI, usually only the mutants on lines touched by the change under review are
+//! of interest. `changed_lines` asks git for the lines added or modified since a
+//! ref (including uncommitted changes in the working tree) and `filter_mutants`
+//! keeps the mutants on these lines. Files that were deleted or only renamed have
+//! no changed lines, and untracked files are not part of the diff.
+
+use crate::mutants::Mutant;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Line numbers (starting at 1) of the lines added or modified in each file,
+/// relative to the root of the project.
+pub type ChangedLines = BTreeMap<PathBuf, BTreeSet<usize>>;
+
+/// Find the lines changed since the git ref `base` in the project at `root`.
+///
+/// Only files below `root` are considered, even if `root` is a subdirectory of the
+/// git repository.
+pub fn changed_lines(
+    root: &Path,
+    base: &str,
+) -> Result<ChangedLines, Box<dyn Error + Send + Sync>> {
+    let failed = |reason| DiffFailed {
+        base: base.into(),
+        reason,
+    };
+    // outside of a repository, git diff would compare paths instead
+    git(root, &["rev-parse", "--is-inside-work-tree"]).map_err(|_| NotAGitRepository {
+        root: root.to_path_buf(),
+        option: "`--diff-base`",
+    })?;
+    let diff = git(
+        root,
+        &[
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--unified=0",
+            "--no-color",
+            "--no-ext-diff",
+            // `parse_diff` expects these prefixes, whatever diff.noprefix or
+            // diff.mnemonicPrefix are set to
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+            "--relative",
+            "--find-renames",
+            base,
+            "--",
+        ],
+    )
+    .map_err(failed)?;
+    Ok(parse_diff(&diff))
+}
+
+/// The uncommitted changes below `root`, as lines of `git status --porcelain`
+/// (e.g. ` M app.py`), including untracked files. Ignored files, like the state
+/// directory, are not changes.
+pub fn uncommitted_changes(root: &Path) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    git(root, &["rev-parse", "--is-inside-work-tree"]).map_err(|_| NotAGitRepository {
+        root: root.to_path_buf(),
+        option: "`--in-place` without `--allow-dirty`",
+    })?;
+    let status = git(
+        root,
+        &["status", "--porcelain", "--untracked-files=all", "--", "."],
+    )
+    .map_err(|reason| {
+        format!(
+            "Could not check the git working tree of {} ({reason})",
+            root.display()
+        )
+    })?;
+    Ok(status.lines().map(String::from).collect())
+}
+
+/// Run git with `args` in `dir` and return its output, or why it failed.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|error| error.to_string())?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).into()),
+        false => Err(String::from_utf8_lossy(&output.stderr).trim().into()),
+    }
+}
+
+/// Parse the output of `git diff --unified=0` into the added or modified lines of
+/// each file.
+///
+/// ```
+/// use pymute::diff::parse_diff;
+/// use std::path::Path;
+///
+/// let diff = "\
+/// --- a/src/app.py
+/// +++ b/src/app.py
+/// @@ -3 +3,2 @@ def main():
+/// -    return 1
+/// +    x = 2
+/// +    return x
+/// ";
+/// let lines = parse_diff(diff);
+/// assert_eq!(lines[Path::new("src/app.py")], [3, 4].into());
+/// ```
+pub fn parse_diff(diff: &str) -> ChangedLines {
+    let mut changed = ChangedLines::new();
+    let mut file: Option<PathBuf> = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            // deleted files have no new path
+            file = path.trim_matches('"').strip_prefix("b/").map(PathBuf::from);
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            let (Some(file), Some(lines)) = (&file, new_lines(hunk)) else {
+                continue;
+            };
+            changed.entry(file.clone()).or_default().extend(lines);
+        }
+    }
+    changed.retain(|_, lines| !lines.is_empty());
+    changed
+}
+
+/// The lines of the new file in a hunk header without its leading `@@ `, e.g.
+/// `3..5` for `-3 +3,2 @@`. Hunks only removing lines have no new lines.
+fn new_lines(hunk: &str) -> Option<std::ops::Range<usize>> {
+    let range = hunk
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match range.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse().ok()?),
+        None => (range.parse().ok()?, 1),
+    };
+    Some(start..start + count)
+}
+
+/// Keep only the mutants of the project at `root` on one of the `changed` lines.
+pub fn filter_mutants(mutants: Vec<Mutant>, changed: &ChangedLines, root: &Path) -> Vec<Mutant> {
+    mutants
+        .into_iter()
+        .filter(|mutant| {
+            let file = mutant
+                .file_path
+                .strip_prefix(root)
+                .unwrap_or(&mutant.file_path);
+            changed
+                .get(file)
+                .is_some_and(|lines| lines.contains(&mutant.line_number))
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct NotAGitRepository {
+    root: PathBuf,
+    /// The option that needs the repository.
+    option: &'static str,
+}
+
+impl Error for NotAGitRepository {}
+impl fmt::Display for NotAGitRepository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is not in a git repository, which {} requires",
+            self.root.display(),
+            self.option
+        )
+    }
+}
+
+#[derive(Debug)]
+struct DiffFailed {
+    base: String,
+    reason: String,
+}
+
+impl Error for DiffFailed {}
+impl fmt::Display for DiffFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not find the lines changed since {} with git ({})",
+            self.base, self.reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::{changed_lines, parse_diff};
+    use crate::plan::plan;
+    use crate::RunConfig;
+    use std::{collections::BTreeSet, fs, path::Path, process::Command};
+    use tempfile::tempdir;
+
+    fn git(root: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=pymute",
+                "-c",
+                "user.email=pymute@example.com",
+            ])
+            .args(args)
+            .current_dir(root)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[test]
+    fn test_parse_diff() {
+        let diff = "\
+diff --git a/app.py b/app.py
+index 1111111..2222222 100644
+--- a/app.py
++++ b/app.py
+@@ -2 +2 @@ def f(a, b):
+-    return a + b
++    return a - b
+@@ -10,2 +9,0 @@ def g():
+-    pass
+-    pass
+@@ -20,0 +21,3 @@ def h():
++    x = 1
++    y = 2
++    return x
+diff --git a/old.py b/pkg/new.py
+similarity index 90%
+rename from old.py
+rename to pkg/new.py
+--- a/old.py
++++ b/pkg/new.py
+@@ -5 +5 @@
+-x = 1
++x = 2
+diff --git a/gone.py b/gone.py
+deleted file mode 100644
+--- a/gone.py
++++ /dev/null
+@@ -1 +0,0 @@
+-x = 1
+";
+        let changed = parse_diff(diff);
+        let lines = |lines: &[usize]| lines.iter().copied().collect::<BTreeSet<_>>();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed[Path::new("app.py")], lines(&[2, 21, 22, 23]));
+        assert_eq!(changed[Path::new("pkg/new.py")], lines(&[5]));
+    }
+
+    #[test]
+    fn test_diff_base_selects_changed_lines() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        let code = "def f(a, b):\n    return a + b\n\n\ndef g(a, b):\n    return a * b\n";
+        fs::write(root.join("src/app.py"), code).unwrap();
+        fs::write(root.join("src/other.py"), "def h(a):\n    return a - 1\n").unwrap();
+        git(root, &["init", "--quiet"]);
+        git(root, &["add", "."]);
+        git(root, &["commit", "--quiet", "-m", "initial"]);
+        fs::write(root.join("src/app.py"), code.replace("a * b", "b * a")).unwrap();
+
+        let changed = changed_lines(root, "HEAD").unwrap();
+        assert_eq!(changed.len(), 1);
+        // the prefixes of the paths in the diff do not depend on the git config
+        for option in ["diff.mnemonicPrefix", "diff.noprefix"] {
+            git(root, &["config", option, "true"]);
+            assert_eq!(changed_lines(root, "HEAD").unwrap(), changed, "{option}");
+        }
+        let config = RunConfig::new(root).diff_base("HEAD");
+        let mutants: Vec<(String, usize)> = plan(&config)
+            .unwrap()
+            .mutants
+            .iter()
+            .map(|planned| {
+                let file = planned.mutant.file_path.strip_prefix(root).unwrap();
+                (file.display().to_string(), planned.mutant.line_number)
+            })
+            .collect();
+        let app = Path::new("src").join("app.py").display().to_string();
+        assert_eq!(mutants, vec![(app, 6)]);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_diff_base_outside_git_repository() {
+        let temp_dir = tempdir().unwrap();
+        let error = changed_lines(temp_dir.path(), "HEAD").unwrap_err();
+        assert!(
+            error.to_string().contains("is not in a git repository"),
+            "{error}"
+        );
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+//! Heuristics flagging mutants that are likely equivalent to the original code.
+//!
+//! Some replacements cannot change the behaviour of the code they are applied to,
+//! e.g. `x * 1` replaced by `x / 1`. Such mutants always survive and only dilute
+//! the mutation score, so they are flagged at discovery time (see
+//! `Mutant::likely_equivalent`) and excluded from the score unless
+//! `RunConfig::include_likely_equivalent` is set. The number of flagged mutants per
+//! rule is part of the summary, to judge whether the rules are worth it.
+//!
+//! ```
+//! use pymute::equivalence::{likely_equivalent, EquivalenceRule};
+//!
+//! let rule = likely_equivalent("    return x * 1", 12, " * ", " / ");
+//! assert_eq!(rule, Some(EquivalenceRule::MultiplyByOne));
+//! assert_eq!(likely_equivalent("    return x * 2", 12, " * ", " / "), None);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// A pattern of code whose mutants are likely equivalent.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquivalenceRule {
+    /// Multiplying or dividing by one, e.g. `x * 1` mutated to `x / 1`.
+    MultiplyByOne,
+    /// Adding or subtracting zero, e.g. `x + 0` mutated to `x - 0`.
+    AddZero,
+    /// A disjunction with `False`, e.g. `x or False` mutated to `x and False`.
+    OrFalse,
+    /// A conjunction with `True`, e.g. `x and True` mutated to `x or True`.
+    AndTrue,
+}
+
+impl fmt::Display for EquivalenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EquivalenceRule::MultiplyByOne => "multiply-by-one",
+            EquivalenceRule::AddZero => "add-zero",
+            EquivalenceRule::OrFalse => "or-false",
+            EquivalenceRule::AndTrue => "and-true",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Find the rule by which replacing `before` at byte offset `column` of `code` by
+/// `after` likely yields an equivalent mutant.
+///
+/// `code` is the line with its strings masked and its comment cut off (see
+/// `mutants::mask_strings`). All rules look at the operand right of the replaced
+/// operator or keyword.
+pub fn likely_equivalent(
+    code: &str,
+    column: usize,
+    before: &str,
+    after: &str,
+) -> Option<EquivalenceRule> {
+    let operand = right_operand(code.get(column + before.len()..)?);
+    let rule = match (before.trim(), after.trim()) {
+        ("*" | "/", "*" | "/" | "//") => EquivalenceRule::MultiplyByOne,
+        ("+" | "-", "+" | "-") => EquivalenceRule::AddZero,
+        ("or", _) => EquivalenceRule::OrFalse,
+        ("and", _) => EquivalenceRule::AndTrue,
+        _ => return None,
+    };
+    let neutral = match rule {
+        EquivalenceRule::MultiplyByOne => matches!(operand, "1" | "1.0"),
+        EquivalenceRule::AddZero => matches!(operand, "0" | "0.0"),
+        EquivalenceRule::OrFalse => operand == "False",
+        EquivalenceRule::AndTrue => operand == "True",
+    };
+    neutral.then_some(rule)
+}
+
+/// The name or literal at the start of `rest`, e.g. `1` for ` 1 + y`.
+fn right_operand(rest: &str) -> &str {
+    let rest = rest.trim_start();
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(rest.len());
+    &rest[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::equivalence::{likely_equivalent, EquivalenceRule};
+    use crate::mutants::{find_mutants_in_files, MutationType};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_likely_equivalent() {
+        use EquivalenceRule::*;
+
+        // (line, before, after, rule)
+        let cases = [
+            ("y = x * 1", " * ", " / ", Some(MultiplyByOne)),
+            ("y = x / 1.0", " / ", " * ", Some(MultiplyByOne)),
+            ("y = x*1", "*", "/", Some(MultiplyByOne)),
+            ("y = x * 2", " * ", " / ", None),
+            ("y = x * 10", " * ", " / ", None),
+            ("y = x * 1.5", " * ", " / ", None),
+            ("y = x * one", " * ", " / ", None),
+            ("y = x + 0", " + ", " - ", Some(AddZero)),
+            ("y = x - 0.0", " - ", " + ", Some(AddZero)),
+            ("y = x + 0 * z", " + ", " - ", Some(AddZero)),
+            ("y = x + 1", " + ", " - ", None),
+            ("y = x + 0x10", " + ", " - ", None),
+            ("y = x + 0", " + ", " * ", None),
+            ("y = x or False", " or ", " and ", Some(OrFalse)),
+            ("y = x or True", " or ", " and ", None),
+            ("y = x or False_", " or ", " and ", None),
+            ("y = x and True", " and ", " or ", Some(AndTrue)),
+            ("y = x and False", " and ", " or ", None),
+            ("y = True or x", " True ", " False ", None),
+        ];
+        for (line, before, after, rule) in cases {
+            let column = line.find(before).unwrap();
+            assert_eq!(
+                likely_equivalent(line, column, before, after),
+                rule,
+                "{line}"
+            );
+        }
+
+        // the operand must follow the replaced operator
+        assert_eq!(likely_equivalent("y = 1 * x", 5, " * ", " / "), None);
+        assert_eq!(likely_equivalent("y = x *", 5, " * ", " / "), None);
+    }
+
+    #[test]
+    fn test_discovery_flags_likely_equivalent() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("scale.py");
+        fs::write(
+            &file,
+            "def scale(x):\n    return x * 1  # keep the unit\n\ndef shift(x):\n    return x + 1\n",
+        )
+        .unwrap();
+
+        let mutants = find_mutants_in_files(&[file], &[MutationType::MathOps]).unwrap();
+        let flags: Vec<_> = mutants
+            .iter()
+            .map(|mutant| (mutant.line_number, mutant.likely_equivalent))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![(2, Some(EquivalenceRule::MultiplyByOne)), (5, None)]
+        );
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+//! Errors of the public API of pymute.
+//!
+//! The entry points of the library (`run`, `mutants::find_mutants`, the cache and
+//! the runner) return a `PymuteError`, so that callers can tell e.g. an interrupted
+//! run or a corrupt cache apart from other failures without downcasting. Errors of
+//! the modules that are not covered by a variant of their own are kept as
+//! `PymuteError::Other`.
+//!
+//! Since `PymuteError` implements `Error` and is `Send` and `Sync`, it converts
+//! into a `Box<dyn Error>` (or e.g. an `anyhow::Error`) with `?` and can be sent
+//! to other threads, e.g. by a job queue running the chunks of a `RunPlan`.
+
+use crate::mutants::MutantError;
+
+use std::{error::Error, fmt, io, path::PathBuf};
+
+/// Why pymute could not find or run the mutants of a project.
+#[derive(Debug)]
+pub enum PymuteError {
+    /// A glob expression (e.g. of `--modules`, `--exclude` or `--copy-exclude`) is
+    /// not a valid pattern.
+    InvalidGlob { pattern: String, reason: String },
+    /// A file or directory could not be read, written or removed.
+    Io { path: PathBuf, source: io::Error },
+    /// A row of the cache at `path` cannot be read. `row` is the line of the row in
+    /// the file, if known, and `content` the row itself, if it could be parsed.
+    CacheParse {
+        path: PathBuf,
+        row: Option<u64>,
+        content: Option<String>,
+        source: csv::Error,
+    },
+    /// There is no cache to report the results from.
+    NoCache { path: PathBuf },
+    /// A test process could not be started.
+    RunnerSpawn { program: PathBuf, source: io::Error },
+    /// A mutant could not be inserted, e.g. because it is stale.
+    Mutant(MutantError),
+    /// The file of a mutant inserted into the project itself (see
+    /// `RunConfig::in_place`) could not be restored from its snapshot, e.g.
+    /// because it was modified while the mutant was inserted, so `file` is left
+    /// mutated.
+    NotRestored {
+        file: PathBuf,
+        line_number: usize,
+        reason: String,
+    },
+    /// More mutants than `RunConfig::max_errors` could not be run, so the run was
+    /// stopped.
+    TooManyErrors { errors: usize, max_errors: usize },
+    /// The run was interrupted by the user.
+    Interrupted,
+    /// No mutant has the id `id` (e.g. of `--mutant-id`). `near_matches` are
+    /// similar ids of existing mutants.
+    NoSuchMutant {
+        id: String,
+        near_matches: Vec<String>,
+    },
+    /// A chunk of a `RunPlan` names the mutant `id`, which is not part of the plan.
+    NotInPlan { id: String },
+    /// Any other error, e.g. an invalid setting.
+    Other(Box<dyn Error + Send + Sync>),
+}
+
+impl PymuteError {
+    /// Wrap any other `error` as `PymuteError::Other`, for `map_err`.
+    pub(crate) fn other(error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        PymuteError::Other(error.into())
+    }
+
+    /// Map an `io::Error` on `path` to `PymuteError::Io`, for `map_err`.
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |source| PymuteError::Io { path, source }
+    }
+}
+
+impl Error for PymuteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PymuteError::Io { source, .. } => Some(source),
+            PymuteError::CacheParse { source, .. } => Some(source),
+            PymuteError::RunnerSpawn { source, .. } => Some(source),
+            // these show the message of the wrapped error as their own
+            PymuteError::Mutant(error) => error.source(),
+            PymuteError::Other(error) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PymuteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PymuteError::InvalidGlob { pattern, reason } => {
+                write!(f, "Invalid glob expression `{pattern}`: {reason}")
+            }
+            PymuteError::Io { path, source } => {
+                write!(f, "Could not access {}: {source}", path.display())
+            }
+            PymuteError::CacheParse {
+                path,
+                row,
+                content,
+                source,
+            } => {
+                write!(f, "Invalid row in the cache {}", path.display())?;
+                if let Some(row) = row {
+                    write!(f, " at line {row}")?;
+                }
+                write!(f, ": {}", csv_reason(source))?;
+                if let Some(content) = content {
+                    write!(f, " (row: {content:?})")?;
+                }
+                write!(
+                    f,
+                    ". Fix or remove the row, or skip invalid rows with `--cache-lenient`"
+                )
+            }
+            PymuteError::NoCache { path } => write!(
+                f,
+                "There are no results in {} yet, run the mutants with `pymute run` first",
+                path.display()
+            ),
+            PymuteError::RunnerSpawn { program, source } => {
+                write!(f, "Could not start `{}`: {source}", program.display())
+            }
+            PymuteError::Mutant(error) => write!(f, "{error}"),
+            PymuteError::NotRestored {
+                file,
+                line_number,
+                reason,
+            } => write!(
+                f,
+                "Could not restore {} after the mutant on line {line_number} ({reason}), the file is left mutated! Restore it with git, e.g. `git checkout -- {}`",
+                file.display(),
+                file.display()
+            ),
+            PymuteError::TooManyErrors { errors, max_errors } => write!(
+                f,
+                "Stopped the run after {errors} mutants could not be run (--max-errors {max_errors}), the results so far are cached"
+            ),
+            PymuteError::Interrupted => write!(f, "Program interrupted by user!"),
+            PymuteError::NoSuchMutant { id, near_matches } => {
+                write!(f, "There is no mutant with id {id}")?;
+                if !near_matches.is_empty() {
+                    write!(f, ", did you mean {}?", near_matches.join(", "))?;
+                }
+                write!(f, " Use `--list` to see the ids of all mutants.")
+            }
+            PymuteError::NotInPlan { id } => {
+                write!(f, "There is no mutant with id {id} in the plan!")
+            }
+            PymuteError::Other(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+/// The reason of a csv `error`, without the position it also names.
+fn csv_reason(error: &csv::Error) -> String {
+    match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+        csv::ErrorKind::Utf8 { err, .. } => err.to_string(),
+        _ => error.to_string(),
+    }
+}
+
+impl From<MutantError> for PymuteError {
+    fn from(error: MutantError) -> Self {
+        PymuteError::Mutant(error)
+    }
+}
+
+/// Errors of the modules that still return a boxed error keep their variant if
+/// they are a `PymuteError` or `MutantError`, and are `PymuteError::Other`
+/// otherwise.
+impl From<Box<dyn Error + Send + Sync>> for PymuteError {
+    fn from(error: Box<dyn Error + Send + Sync>) -> Self {
+        match error.downcast::<PymuteError>() {
+            Ok(error) => *error,
+            Err(error) => match error.downcast::<MutantError>() {
+                Ok(error) => PymuteError::Mutant(*error),
+                Err(error) => PymuteError::Other(error),
+            },
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Events emitted while running the mutants, for consumers other than the terminal.
+//!
+//! `run_mutants` notifies every `Observer` about the start and end of the run and
+//! of each mutant. Events serialize to JSON objects with an `event` field naming
+//! the event, e.g.
+//!
+//! ```json
+//! {"event":"mutant_finished","file_path":"pkg/calc.py","line_number":3,"column":12,"before":" + ","after":" - ","result":"caught","duration_ms":812}
+//! ```
+//!
+//! `SocketObserver` writes the events as newline-delimited JSON to a unix domain
+//! socket (or a named pipe on Windows), e.g. for a dashboard on another machine.
+//! Observers can also pause or stop the dispatching of new mutants (see
+//! `Observer::dispatch`), e.g. from the keybindings of `tui::Dashboard`.
+
+use crate::mutants::Mutant;
+use crate::runner::{MutantOutcome, MutantResult};
+
+use serde::Serialize;
+
+use std::{
+    fmt,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, SyncSender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Number of events buffered for a slow consumer before new events are dropped.
+const EVENT_BUFFER_SIZE: usize = 1024;
+
+/// Something that happened during a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// The run started with `mutants` mutants to run.
+    RunStarted { mutants: usize },
+    /// The test suite is about to run against a mutant.
+    MutantStarted {
+        file_path: PathBuf,
+        line_number: usize,
+        column: usize,
+    },
+    /// The test suite finished for a mutant.
+    MutantFinished {
+        file_path: PathBuf,
+        line_number: usize,
+        column: usize,
+        before: String,
+        after: String,
+        result: MutantResult,
+        duration_ms: u128,
+        /// Why pymute failed to run the tests, see `MutantOutcome::error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// All mutants have been run (or the run was interrupted).
+    RunFinished {
+        caught: usize,
+        missed: usize,
+        errors: usize,
+    },
+}
+
+impl Event {
+    /// The event for starting to run the tests against `mutant`.
+    pub fn mutant_started(mutant: &Mutant) -> Self {
+        Event::MutantStarted {
+            file_path: mutant.file_path.clone(),
+            line_number: mutant.line_number,
+            column: mutant.column,
+        }
+    }
+
+    /// The event for the tests having finished for a mutant after `duration`.
+    pub fn mutant_finished(outcome: &MutantOutcome, duration: Duration) -> Self {
+        let mutant = &outcome.mutant;
+        Event::MutantFinished {
+            file_path: mutant.file_path.clone(),
+            line_number: mutant.line_number,
+            column: mutant.column,
+            before: mutant.before.clone(),
+            after: mutant.after.clone(),
+            result: outcome.result,
+            duration_ms: duration.as_millis(),
+            error: outcome.error.clone(),
+        }
+    }
+
+    /// The event for the end of a run with the given outcomes.
+    pub fn run_finished(outcomes: &[MutantOutcome]) -> Self {
+        let count = |f: fn(&MutantResult) -> bool| {
+            outcomes.iter().filter(|outcome| f(&outcome.result)).count()
+        };
+        Event::RunFinished {
+            caught: count(|result| *result == MutantResult::Caught),
+            missed: count(|result| *result == MutantResult::Missed),
+            errors: count(|result| matches!(result, MutantResult::Error { .. })),
+        }
+    }
+}
+
+/// Whether the runner should start new mutants, see `Observer::dispatch`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Dispatch {
+    /// Start new mutants.
+    #[default]
+    Continue,
+    /// Do not start new mutants until all observers continue again.
+    Pause,
+    /// Do not start any more mutants. The run finishes with the outcomes of the
+    /// mutants run so far.
+    Stop,
+}
+
+/// Receives the events of a run.
+///
+/// Observers are notified from the worker threads, so `notify` must not block for
+/// long, otherwise it delays the run.
+pub trait Observer: Send + Sync {
+    /// Handle `event`.
+    fn notify(&self, event: &Event);
+
+    /// Asked before each mutant is started. If observers disagree, the most
+    /// restrictive answer wins.
+    fn dispatch(&self) -> Dispatch {
+        Dispatch::Continue
+    }
+}
+
+/// Observers added to a run from code (see `RunConfig::observer`), in addition to
+/// those configured by options such as `events_socket`.
+///
+/// They are not serialized with the configuration, and two lists are equal if they
+/// hold the same observers.
+#[derive(Clone, Default)]
+pub struct Observers(pub Vec<Arc<dyn Observer>>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+impl PartialEq for Observers {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(&other.0).all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+/// Writes events as newline-delimited JSON to a unix domain socket or, on
+/// Windows, a named pipe.
+///
+/// Writing happens on a background thread. If the consumer does not keep up and
+/// the buffer of `EVENT_BUFFER_SIZE` events is full, new events are dropped, so a
+/// slow or stalled consumer never stalls the run.
+pub struct SocketObserver {
+    sender: SyncSender<String>,
+}
+
+impl SocketObserver {
+    /// Connect to the socket (or named pipe) at `path`, which must already be
+    /// listening.
+    pub fn connect(path: &Path) -> io::Result<Self> {
+        let mut stream = connect_stream(path)?;
+        let (sender, receiver) = mpsc::sync_channel::<String>(EVENT_BUFFER_SIZE);
+        thread::spawn(move || {
+            for line in receiver {
+                // a consumer that went away does not fail the run
+                if stream.write_all(line.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(SocketObserver { sender })
+    }
+}
+
+impl Observer for SocketObserver {
+    fn notify(&self, event: &Event) {
+        if let Ok(mut line) = serde_json::to_string(event) {
+            line.push('\n');
+            // drop the event if the consumer is too slow or gone
+            let _ = self.sender.try_send(line);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect_stream(path: &Path) -> io::Result<Box<dyn Write + Send>> {
+    Ok(Box::new(std::os::unix::net::UnixStream::connect(path)?))
+}
+
+#[cfg(windows)]
+fn connect_stream(path: &Path) -> io::Result<Box<dyn Write + Send>> {
+    Ok(Box::new(
+        std::fs::OpenOptions::new().write(true).open(path)?,
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::events::{Event, Observer, SocketObserver};
+    use crate::mutants::Mutant;
+    use crate::runner::{MutantOutcome, MutantResult};
+    use std::{
+        io::{BufRead, BufReader},
+        os::unix::net::UnixListener,
+        path::PathBuf,
+        time::{Duration, Instant},
+    };
+    use tempfile::tempdir;
+
+    fn outcome() -> MutantOutcome {
+        MutantOutcome {
+            mutant: Mutant::new(
+                PathBuf::from("pkg/calc.py"),
+                3,
+                " + ",
+                " - ",
+                12,
+                "    return a + b",
+            ),
+            result: MutantResult::Caught,
+            duration: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_socket_observer_sends_events() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("events.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let observer = SocketObserver::connect(&path).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let outcome = outcome();
+        observer.notify(&Event::RunStarted { mutants: 1 });
+        observer.notify(&Event::mutant_started(&outcome.mutant));
+        observer.notify(&Event::mutant_finished(
+            &outcome,
+            Duration::from_millis(812),
+        ));
+        observer.notify(&Event::run_finished(&[outcome]));
+        drop(observer);
+
+        let lines: Vec<serde_json::Value> = BufReader::new(stream)
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["event"], "run_started");
+        assert_eq!(lines[0]["mutants"], 1);
+        assert_eq!(lines[1]["event"], "mutant_started");
+        assert_eq!(lines[2]["event"], "mutant_finished");
+        assert_eq!(lines[2]["result"], "caught");
+        assert_eq!(lines[2]["column"], 12);
+        assert_eq!(lines[2]["duration_ms"], 812);
+        assert_eq!(lines[3]["event"], "run_finished");
+        assert_eq!(lines[3]["caught"], 1);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_socket_observer_does_not_block_on_stalled_reader() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("events.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let observer = SocketObserver::connect(&path).unwrap();
+        // accept, but never read, so the socket buffer fills up
+        let (_stream, _) = listener.accept().unwrap();
+
+        let event = Event::mutant_finished(&outcome(), Duration::from_millis(1));
+        let mut slowest = Duration::ZERO;
+        let start = Instant::now();
+        for _ in 0..100_000 {
+            let notified = Instant::now();
+            observer.notify(&event);
+            slowest = slowest.max(notified.elapsed());
+        }
+
+        assert!(slowest < Duration::from_millis(100), "{slowest:?}");
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        temp_dir.close().unwrap();
+    }
+}
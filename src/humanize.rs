@@ -0,0 +1,122 @@
+//! Human-friendly formatting of durations, sizes and rates.
+//!
+//! All numbers shown to users (in the terminal and as formatted fields next to the
+//! raw values in JSON files) go through these functions, so that the same quantity
+//! is always written the same way.
+//!
+//! ```
+//! use pymute::humanize::{format_duration, format_rate, format_size};
+//! use std::time::Duration;
+//!
+//! assert_eq!(format_duration(Duration::from_secs(4325)), "1h 12m 5s");
+//! assert_eq!(format_size(312_000_000), "312 MB");
+//! assert_eq!(format_rate(21, Duration::from_secs(600)), "2.1 mutants/min");
+//! ```
+
+use std::time::Duration;
+
+/// Units of durations of at least a second, with their length in seconds.
+const DURATION_UNITS: [(&str, u64); 4] = [("d", 86_400), ("h", 3_600), ("m", 60), ("s", 1)];
+
+/// Decimal units of sizes.
+const SIZE_UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+
+/// Format a duration, e.g. as `1h 12m 5s`, `5s` or `450ms`.
+///
+/// Durations below a second are given in milliseconds, longer ones in whole
+/// seconds, leaving out units that are zero.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds == 0 {
+        return format!("{}ms", duration.as_millis());
+    }
+
+    let mut rest = seconds;
+    let mut parts = Vec::new();
+    for (unit, length) in DURATION_UNITS {
+        if rest >= length {
+            parts.push(format!("{}{unit}", rest / length));
+            rest %= length;
+        }
+    }
+    parts.join(" ")
+}
+
+/// Format a size in bytes with decimal units, e.g. as `312 MB` or `1.5 kB`.
+///
+/// Sizes below 10 of a unit are given with one decimal.
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    // move to the next unit where the rounded value would read 1000
+    while value >= 999.5 && unit < SIZE_UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    match value < 9.95 {
+        true => format!("{value:.1} {}", SIZE_UNITS[unit]),
+        false => format!("{value:.0} {}", SIZE_UNITS[unit]),
+    }
+}
+
+/// Format the rate of `mutants` run in `duration`, e.g. as `2.1 mutants/min`.
+pub fn format_rate(mutants: usize, duration: Duration) -> String {
+    match duration.is_zero() {
+        true => "n/a".into(),
+        false => format!(
+            "{:.1} mutants/min",
+            mutants as f64 / duration.as_secs_f64() * 60.0
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::humanize::{format_duration, format_rate, format_size};
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_duration() {
+        let format = |millis| format_duration(Duration::from_millis(millis));
+        assert_eq!(format(0), "0ms");
+        assert_eq!(format(450), "450ms");
+        assert_eq!(format(999), "999ms");
+        assert_eq!(format(1_000), "1s");
+        assert_eq!(format(1_999), "1s");
+        assert_eq!(format(60_000), "1m");
+        assert_eq!(format(61_000), "1m 1s");
+        assert_eq!(format(3_600_000), "1h");
+        assert_eq!(format(4_325_000), "1h 12m 5s");
+        assert_eq!(format(86_400_000), "1d");
+        assert_eq!(format(97_205_000), "1d 3h 5s");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(999), "999 B");
+        assert_eq!(format_size(1_000), "1.0 kB");
+        assert_eq!(format_size(1_500), "1.5 kB");
+        assert_eq!(format_size(9_949), "9.9 kB");
+        assert_eq!(format_size(9_950), "10 kB");
+        assert_eq!(format_size(999_499), "999 kB");
+        assert_eq!(format_size(999_500), "1.0 MB");
+        assert_eq!(format_size(312_000_000), "312 MB");
+        assert_eq!(format_size(5_000_000_000_000_000), "5000 TB");
+    }
+
+    #[test]
+    fn test_format_rate() {
+        assert_eq!(format_rate(0, Duration::ZERO), "n/a");
+        assert_eq!(format_rate(0, Duration::from_secs(1)), "0.0 mutants/min");
+        assert_eq!(format_rate(21, Duration::from_secs(600)), "2.1 mutants/min");
+        assert_eq!(
+            format_rate(3, Duration::from_millis(500)),
+            "360.0 mutants/min"
+        );
+    }
+}
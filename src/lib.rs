@@ -1,17 +1,397 @@
 //! Provide mutation testing functions for python codebases.
 
-use crate::mutants::{find_mutants, MutationType};
+use crate::cache::CacheEntry;
+use crate::mutants::{Mutant, MutationType};
+use crate::plan::{Order, PlannedMutant};
+use crate::runner::{MutantOutcome, MutantResult, OutputLevel};
+use crate::summary::{Phase, RunMetadata};
 
-use rand::{seq::IteratorRandom, SeedableRng};
+use colored::Colorize;
+use rand::{seq::SliceRandom, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 
-use std::{error::Error, fmt, path::PathBuf};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+pub mod cache;
+pub mod config;
+pub mod config_file;
+pub mod coverage;
+pub mod diff;
+pub mod equivalence;
+pub mod error;
+pub mod events;
+pub mod humanize;
 pub mod mutants;
+pub mod plan;
+pub mod pyproject;
+pub mod report;
 pub mod runner;
+pub mod sarif;
+pub mod scope;
+pub mod selection;
+pub mod shard;
+pub mod state;
+pub mod subprojects;
+pub mod summary;
+pub mod syntax;
+pub mod synthetic;
+pub mod tui;
+pub mod verdicts;
 
+pub use config::RunConfig;
+pub use error::PymuteError;
+pub use summary::RunSummary;
+
+/// Find mutants and run the test suite against each of them.
+///
+/// All options of the run are taken from `config`, see `RunConfig`. The returned
+/// summary holds the result for each mutant that was run. If `config.list` is set,
+/// the mutants are only printed and the summary is empty.
+///
+/// `run` keeps no process-global state, so runs on different projects can be
+/// started concurrently from multiple threads. A run fails if another run holds the
+/// lock on the same state directory.
+pub fn run(config: &RunConfig) -> Result<RunSummary, PymuteError> {
+    let start = Instant::now();
+    let root = &config.root;
+    let state = config.state();
+    if config.clear_cache {
+        let _lock = state.lock()?;
+        for path in [config.cache_file(), root.join(state::LEGACY_CACHE_FILE)] {
+            if path.is_file() {
+                fs::remove_file(&path).map_err(PymuteError::io(path))?;
+            }
+        }
+    }
+    // a legacy cache is only moved to the default location
+    if !config.no_cache && config.cache_path.is_none() {
+        state.migrate_legacy_cache(root)?;
+    }
+
+    let mut plan = plan::plan(config)?;
+    let mut phases = vec![(Phase::Discovery, start.elapsed())];
+
+    if config.list {
+        plan::write_mutant_list(
+            &mut io::stdout().lock(),
+            &plan.mutants,
+            root,
+            config.list_format,
+        )?;
+        return Ok(RunSummary::default());
+    }
+
+    if config.output_level != OutputLevel::Quiet {
+        report_invalid_files(&plan.invalid_files, root);
+        report_untested_files(&plan.untested_files);
+        report_out_of_scope(plan.out_of_scope);
+    }
+    if config.output_level == OutputLevel::Process {
+        let mutants: Vec<_> = plan.mutants.iter().map(|p| p.mutant.clone()).collect();
+        report_ambiguous_mutants(&cache::ambiguous_mutants(&mutants), root);
+    }
+
+    let lock = state.lock()?;
+    let cache_file = config.cache_file();
+    let (existing, invalid_cache_rows) = match (
+        config.no_cache || !cache_file.exists(),
+        config.cache_lenient,
+    ) {
+        (true, _) => (Vec::new(), 0),
+        (false, true) => cache::read_csv_cache_lenient(&cache_file, root)?,
+        (false, false) => (cache::read_csv_cache(&cache_file, root)?, 0),
+    };
+    let mutants: Vec<_> = plan.mutants.iter().map(|p| p.mutant.clone()).collect();
+    // mutants selected by id are run again even if their result is known
+    let mut outcomes = match config.rerun_all || config.no_cache || !config.mutant_ids.is_empty() {
+        true => Vec::new(),
+        false => cache::cached_outcomes(&existing, &mutants, root),
+    };
+    if !config.no_cache {
+        // results are kept even if the run is interrupted
+        let kept = match config.cache_overwrite {
+            true => Vec::new(),
+            false => existing.clone(),
+        };
+        let writer = cache::CacheWriter::new(&cache_file, root, kept, &mutants);
+        plan.config.observers.0.push(Arc::new(writer));
+    }
+    schedule_mutants(&mut plan.mutants, &existing, config.order, config.seed);
+    let cached = outcomes.len();
+    // cached results are matched per mutant rather than per id
+    let key = |mutant: &Mutant| {
+        let position = (mutant.file_path.clone(), mutant.line_number, mutant.column);
+        (position, mutant.before.clone(), mutant.after.clone())
+    };
+    let cached_mutants: HashSet<_> = outcomes.iter().map(|o| key(&o.mutant)).collect();
+    let mut seen = HashSet::new();
+    let ids: Vec<_> = plan
+        .mutants
+        .iter()
+        .filter(|planned| !cached_mutants.contains(&key(&planned.mutant)))
+        .filter(|planned| seen.insert(&planned.id))
+        .map(|planned| planned.id.clone())
+        .collect();
+
+    let execution = Instant::now();
+    outcomes.extend(plan::execute_chunk(&plan, &ids)?);
+    phases.push((Phase::Execution, execution.elapsed()));
+
+    let report = Instant::now();
+    let mut summary = plan::finalize(&plan, outcomes)?;
+    summary.cached = cached;
+    summary.invalid_cache_rows = invalid_cache_rows;
+    match (config.no_cache, config.cache_overwrite) {
+        (true, _) => {}
+        (false, true) => cache::write_csv_cache(&cache_file, &summary.outcomes, root)?,
+        (false, false) => cache::merge_entries(&cache_file, &existing, &summary.outcomes, root)?,
+    }
+    drop(lock);
+    phases.push((Phase::Report, report.elapsed()));
+    summary.duration = start.elapsed();
+    summary.phases = phases;
+
+    if let Some(path) = &config.summary_file {
+        let metadata = RunMetadata::new(&config.mutation_types, plan.population);
+        summary::write_summary_file(path, &summary, &metadata)?;
+    }
+    if let Some(path) = &config.report_json {
+        report::write_json_report(path, &summary, config)?;
+    }
+    let verdicts = verdicts::read_verdicts(&config.state().verdicts_file())?;
+    if let Some(path) = &config.report_junit {
+        report::write_junit_report(path, &summary, root, &verdicts)?;
+    }
+    if let Some(dir) = &config.report_html {
+        report::write_html_report(dir, &summary, root)?;
+    }
+    if let Some(path) = &config.report_sarif {
+        sarif::write_sarif(path, &summary, root, &verdicts, config.sarif_include_caught)?;
+    }
+
+    Ok(summary)
+}
+
+/// Sort the planned `mutants` into the `order` in which they are started, using
+/// the `entries` of the cache from the previous runs and `seed` for the random
+/// order.
+///
+/// Entries apply to the mutants with their id. For `Order::Duration`, mutants
+/// without a recorded duration come first, since they may be just as slow. Mutants
+/// that are equal in the order stay in the order they were found.
+fn schedule_mutants(
+    mutants: &mut [PlannedMutant],
+    entries: &[CacheEntry],
+    order: Order,
+    seed: u64,
+) {
+    let previous: HashMap<&str, &CacheEntry> = entries
+        .iter()
+        .filter_map(|entry| Some((entry.id.as_deref()?, entry)))
+        .collect();
+    let previous = |planned: &PlannedMutant| previous.get(planned.id.as_str()).copied();
+    match order {
+        Order::File => {}
+        Order::Priority => mutants.sort_by_key(|planned| {
+            previous(planned).is_none_or(|entry| entry.status != MutantResult::Missed)
+        }),
+        Order::Duration => mutants.sort_by_key(|planned| {
+            Reverse(
+                previous(planned)
+                    .and_then(|entry| entry.duration_ms)
+                    .unwrap_or(u64::MAX),
+            )
+        }),
+        Order::Random => mutants.shuffle(&mut ChaCha8Rng::seed_from_u64(seed)),
+    }
+}
+
+/// Find the mutants `run` would run for `config`, without running them.
+pub fn list_mutants(config: &RunConfig) -> Result<Vec<PlannedMutant>, PymuteError> {
+    Ok(plan::plan(config)?.mutants)
+}
+
+/// Summarize the results of previous runs in the cache of the project, without
+/// running anything.
+///
+/// The verdicts are applied as after a run. Ids are derived from the current
+/// content of the files, and likely equivalent mutants are not known, since the
+/// cache does not record them. The summary has no duration.
+///
+/// Only mutants of `RunConfig::mutation_types` are reported. Entries written before
+/// mutation types were recorded in the cache are always reported.
+pub fn report_from_cache(config: &RunConfig) -> Result<RunSummary, PymuteError> {
+    let root = &config.root;
+    let state = config.state();
+    let path = config.cache_file();
+    let path = match path.is_file() || config.cache_path.is_some() {
+        true => path,
+        false => root.join(state::LEGACY_CACHE_FILE),
+    };
+    if !path.is_file() {
+        return Err(PymuteError::NoCache {
+            path: config.cache_file(),
+        });
+    }
+
+    let (entries, invalid_cache_rows) = match config.cache_lenient {
+        true => cache::read_csv_cache_lenient(&path, root)?,
+        false => (cache::read_csv_cache(&path, root)?, 0),
+    };
+    let mut lines: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    let outcomes = entries
+        .into_iter()
+        .filter(|entry| {
+            entry
+                .mutation_type
+                .is_none_or(|mutation_type| config.mutation_types.contains(&mutation_type))
+        })
+        .map(|entry| {
+            let file_lines = lines.entry(entry.file_path.clone()).or_insert_with(|| {
+                fs::read_to_string(&entry.file_path)
+                    .map(|content| content.lines().map(String::from).collect())
+                    .unwrap_or_default()
+            });
+            let old_line = file_lines
+                .get(entry.line_number.wrapping_sub(1))
+                .cloned()
+                .unwrap_or_default();
+            let mut mutant = Mutant::new(
+                entry.file_path,
+                entry.line_number,
+                entry.before,
+                entry.after,
+                entry.column.unwrap_or_default(),
+                old_line,
+            );
+            mutant.mutation_type = entry.mutation_type;
+            MutantOutcome {
+                mutant,
+                result: entry.status,
+                duration: entry.duration_ms.map(Duration::from_millis),
+                error: entry.error.clone(),
+            }
+        })
+        .collect();
+
+    let mut summary = RunSummary::new(outcomes, Duration::ZERO);
+    summary.invalid_cache_rows = invalid_cache_rows;
+    summary.apply_verdicts(&verdicts::read_verdicts(&state.verdicts_file())?, root);
+    Ok(summary)
+}
+
+/// Delete the cache of the project (including one written by older versions to the
+/// root) and leftover working copies and logs of previous runs.
+///
+/// Verdicts, the history and the baseline are kept. Fails if a run holds the lock
+/// on the state directory. Returns the deleted paths.
+pub fn clean_cache(config: &RunConfig) -> Result<Vec<PathBuf>, PymuteError> {
+    let state = config.state();
+    if !state.path().is_dir()
+        && !config.cache_file().exists()
+        && !config.root.join(state::LEGACY_CACHE_FILE).exists()
+    {
+        return Ok(Vec::new());
+    }
+    let lock = state.lock()?;
+    let mut deleted = Vec::new();
+    for path in [
+        config.cache_file(),
+        config.root.join(state::LEGACY_CACHE_FILE),
+        state.workdirs_dir(),
+        state.logs_dir(),
+    ] {
+        if path.is_dir() {
+            fs::remove_dir_all(&path).map_err(PymuteError::io(&path))?;
+        } else if path.is_file() {
+            fs::remove_file(&path).map_err(PymuteError::io(&path))?;
+        } else {
+            continue;
+        }
+        deleted.push(path);
+    }
+    drop(lock);
+    Ok(deleted)
+}
+
+/// Print the files without any associated tests before running the mutants.
+fn report_untested_files(untested_files: &BTreeMap<PathBuf, usize>) {
+    if untested_files.is_empty() {
+        return;
+    }
+    let num_mutants: usize = untested_files.values().sum();
+    println!(
+        "{}: {} files have no associated tests — their {} mutants will almost certainly survive",
+        "Warning".yellow(),
+        untested_files.len(),
+        num_mutants
+    );
+    for (file, count) in untested_files {
+        println!("    {} ({count} mutants)", file.display());
+    }
+}
+
+/// Print the number of mutants excluded by `RunConfig::max_nesting` and
+/// `RunConfig::max_function_lines` before running the mutants.
+fn report_out_of_scope(out_of_scope: usize) {
+    if out_of_scope > 0 {
+        println!(
+            "{out_of_scope} mutants are nested too deeply or in too long functions and are not run"
+        );
+    }
+}
+
+/// Print the pairs of mutants the cache cannot tell apart before running them.
+fn report_ambiguous_mutants(pairs: &[(&mutants::Mutant, &mutants::Mutant)], root: &Path) {
+    for (mutant, _) in pairs {
+        let file = mutant
+            .file_path
+            .strip_prefix(root)
+            .unwrap_or(&mutant.file_path);
+        println!(
+            "{}: two mutants replace {:?} by {:?} at {}:{}, column {}; their results share a cache entry",
+            "Debug".cyan(),
+            mutant.before,
+            mutant.after,
+            file.display(),
+            mutant.line_number,
+            mutant.column
+        );
+    }
+}
+
+/// Print the files excluded because of syntax errors before running the mutants.
+fn report_invalid_files(invalid_files: &BTreeMap<PathBuf, String>, root: &Path) {
+    if invalid_files.is_empty() {
+        return;
+    }
+    println!(
+        "{}: {} files have syntax errors and are not mutated",
+        "Warning".yellow(),
+        invalid_files.len()
+    );
+    for (file, message) in invalid_files {
+        let file = file.strip_prefix(root).unwrap_or(file);
+        println!("    {}: {message}", file.display());
+    }
+}
+
+/// Run pymute with positional options.
+///
+/// This is the signature of `run` before `RunConfig` was introduced.
+#[deprecated(
+    since = "0.3.0",
+    note = "use `run(&RunConfig)` or `RunConfig::run` instead"
+)]
 #[allow(clippy::too_many_arguments)]
-pub fn run(
+pub fn run_with_args(
     root: &PathBuf,
     modules: &str,
     tests: &str,
@@ -22,64 +402,32 @@ pub fn run(
     mutation_types: &[MutationType],
     list: &bool,
     seed: &u64,
-) -> Result<(), Box<dyn Error>> {
-    let modules: PathBuf = [root, &PathBuf::from(modules)].iter().collect();
-
-    let mutants = match max_mutants {
-        Some(max) => {
-            let mut rng = ChaCha8Rng::seed_from_u64(*seed);
-
-            find_mutants(
-                modules
-                    .into_os_string()
-                    .to_str()
-                    .ok_or(InvalidGlobExpression {})?,
-                mutation_types,
-            )?
-            .into_iter()
-            .choose_multiple(&mut rng, *max)
-            .into_iter()
-            .collect()
-        }
-        None => find_mutants(
-            modules
-                .into_os_string()
-                .to_str()
-                .ok_or(InvalidGlobExpression {})?,
-            mutation_types,
-        )?,
+) -> Result<(), PymuteError> {
+    let config = RunConfig {
+        modules: vec![modules.into()],
+        tests: tests.into(),
+        output_level: *output_level,
+        runner: *runner,
+        environment: environment.clone(),
+        max_mutants: *max_mutants,
+        mutation_types: mutation_types.to_vec(),
+        list: *list,
+        seed: *seed,
+        ..RunConfig::new(root)
     };
-
-    if *list {
-        for mutant in &mutants {
-            println!("{mutant}");
-        }
-        return Ok(());
-    }
-
-    let _n_mutants = mutants.len();
-
-    runner::run_mutants(root, &mutants, runner, tests, environment, output_level)?;
-
+    run(&config)?;
     Ok(())
 }
 
-#[derive(Debug)]
-struct InvalidGlobExpression {}
-
-impl Error for InvalidGlobExpression {}
-impl fmt::Display for InvalidGlobExpression {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Program interrupted by user!")
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::mutants::MutationType;
-    use crate::run;
-    use crate::runner;
-    use std::{fs::File, io::Write, path::PathBuf};
+    use crate::cache::CacheEntry;
+    use crate::mutants::{Mutant, MutationType};
+    use crate::plan::{Order, PlannedMutant};
+    use crate::runner::{self, MutantResult, Runner};
+    use crate::summary::Phase;
+    use crate::{report_from_cache, run, schedule_mutants, RunConfig};
+    use std::{fs::File, io::Write, path::PathBuf, time::Duration};
     use tempfile::tempdir;
 
     #[test]
@@ -100,32 +448,231 @@ print(res) # print the result *
         let mut script1 = File::create(base_path.join("script.py")).unwrap();
         write!(script1, "{}", multiline_string_script).expect("Failed to write to temporary file");
 
-        run(
-            &PathBuf::from(base_path),
-            "**/*.py",
-            ".",
-            &runner::OutputLevel::Missed,
-            &runner::Runner::Pytest,
-            &None,
-            &Some(10),
-            &vec![
-                MutationType::MathOps,
-                MutationType::Conjunctions,
-                MutationType::Booleans,
-                MutationType::ControlFlow,
-                MutationType::CompOps,
-                MutationType::Numbers,
-            ],
-            &false,
-            &34,
+        let config = RunConfig::new(base_path).max_mutants(10).seed(34);
+        let summary = run(&config).unwrap();
+
+        assert_eq!(summary.total, 3);
+        assert_eq!(summary.outcomes.len(), 3);
+        assert_eq!(summary.caught + summary.missed, summary.total);
+
+        let cached = crate::cache::read_csv_cache(&config.cache_file(), base_path).unwrap();
+        assert_eq!(cached.len(), 3);
+        for (entry, outcome) in cached.iter().zip(&summary.outcomes) {
+            assert_eq!(entry.line_number, outcome.mutant.line_number);
+            assert_eq!(entry.status, outcome.result);
+        }
+
+        // best be safe and close it
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_run_phases() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("calc.py"),
+            "def add(a, b):\n    return a + b\n\nx = add(1, 2) * 3\n",
         )
         .unwrap();
 
-        // best be safe and close it
+        let summary = RunConfig::new(root)
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import sys; sys.exit(1)""#)
+            .output_level(runner::OutputLevel::Quiet)
+            .run()
+            .unwrap();
+
+        let phases: Vec<Phase> = summary.phases.iter().map(|(phase, _)| *phase).collect();
+        assert_eq!(phases, [Phase::Discovery, Phase::Execution, Phase::Report]);
+        let sum: Duration = summary.phases.iter().map(|(_, duration)| *duration).sum();
+        assert!(sum <= summary.duration);
+        assert!(
+            summary.duration - sum < Duration::from_millis(100),
+            "{:?} of {:?}",
+            summary.phases,
+            summary.duration
+        );
+
         temp_dir.close().unwrap();
     }
 
     #[test]
+    fn test_run_reuses_cached_results() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("calc.py"),
+            "def add(a, b):\n    return a + b\n\nx = add(1, 2) * 3\n",
+        )
+        .unwrap();
+        // every test command appends a line to the log
+        let log = root.join("runs.log");
+        let command = format!(
+            r#"python -c "open(r'{}', 'a').write('run\n'); raise SystemExit(1)""#,
+            log.display()
+        );
+        let runs = || {
+            std::fs::read_to_string(&log)
+                .unwrap_or_default()
+                .lines()
+                .count()
+        };
+        let config = RunConfig::new(root)
+            .modules("calc.py")
+            .runner(Runner::Custom)
+            .test_command(&command)
+            .output_level(runner::OutputLevel::Quiet);
+
+        let first = config.run().unwrap();
+        assert_eq!(first.cached, 0);
+        let executed = runs();
+        assert!(executed >= first.total, "{executed} runs");
+
+        let second = config.run().unwrap();
+        assert_eq!(runs(), executed);
+        assert_eq!(second.cached, first.total);
+        assert_eq!(second.caught, first.caught);
+
+        let third = config.clone().rerun_all(true).run().unwrap();
+        assert_eq!(third.cached, 0);
+        assert_eq!(runs(), 2 * executed);
+
+        // the report can be restricted to mutation types recorded in the cache
+        let report = report_from_cache(&config).unwrap();
+        assert_eq!(report.caught, first.caught);
+        let math_ops = report.mutation_types[&MutationType::MathOps].caught;
+        assert!(math_ops > 0 && math_ops < first.caught, "{math_ops}");
+        let report =
+            report_from_cache(&config.clone().mutation_types(&[MutationType::MathOps])).unwrap();
+        assert_eq!(report.caught, math_ops);
+        let report = report_from_cache(&config.mutation_types(&[MutationType::CompOps])).unwrap();
+        assert!(report.outcomes.is_empty());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_run_repeated_lines() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("calc.py"),
+            "def f(a):\n    return a + 1\n\ndef g(a):\n    return a + 1\n",
+        )
+        .unwrap();
+        let config = RunConfig::new(root)
+            .modules("calc.py")
+            .mutation_types(&[MutationType::MathOps])
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import sys; sys.exit(1)""#)
+            .output_level(runner::OutputLevel::Quiet);
+
+        // the same replacement on identical lines gives two mutants with their own ids
+        let first = config.run().unwrap();
+        assert_eq!(first.total, 2);
+        let ids: Vec<_> = first.outcomes.iter().map(|o| o.mutant.id(root)).collect();
+        assert_ne!(ids[0], ids[1]);
+
+        // a mutant without a cached result is run even if the other one has one
+        crate::cache::write_csv_cache(&config.cache_file(), &first.outcomes[..1], root).unwrap();
+        let second = config.run().unwrap();
+        assert_eq!((second.total, second.cached), (2, 1));
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_run_no_cache_and_clear_cache() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("calc.py"),
+            "def add(a, b):\n    return a + b\n\nx = add(1, 2) * 3\n",
+        )
+        .unwrap();
+        let config = RunConfig::new(root)
+            .modules("calc.py")
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import sys; sys.exit(1)""#)
+            .output_level(runner::OutputLevel::Quiet);
+        let cache_file = config.cache_file();
+
+        // no cache is written
+        config.clone().no_cache(true).run().unwrap();
+        assert!(!cache_file.exists());
+
+        // an existing cache is neither read nor written
+        config.run().unwrap();
+        let content = std::fs::read_to_string(&cache_file).unwrap();
+        let summary = config.clone().no_cache(true).run().unwrap();
+        assert_eq!(summary.cached, 0);
+        assert_eq!(std::fs::read_to_string(&cache_file).unwrap(), content);
+
+        // the cache is deleted before the run and holds its results afterwards
+        std::fs::write(&cache_file, "not a cache").unwrap();
+        let summary = config.clone().clear_cache(true).run().unwrap();
+        assert_eq!(summary.cached, 0);
+        let entries = crate::cache::read_csv_cache(&cache_file, root).unwrap();
+        assert_eq!(entries.len(), summary.total);
+
+        // the cache can be kept outside of the state directory
+        let config = config.cache_path("ci/mutants.csv");
+        let summary = config.run().unwrap();
+        assert_eq!(summary.cached, 0);
+        assert_eq!(config.cache_file(), root.join("ci/mutants.csv"));
+        assert_eq!(config.run().unwrap().cached, summary.total);
+        assert!(crate::clean_cache(&config)
+            .unwrap()
+            .contains(&config.cache_file()));
+        assert!(!config.cache_file().exists());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_interrupted_keeps_finished_results() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("calc.py"),
+            "def add(a, b):\n    return a + b\n\nx = add(1, 2) * 3\n",
+        )
+        .unwrap();
+        // the first test run fails, the second one is interrupted like by Ctrl-C
+        let marker = root.join("started");
+        let command = format!(
+            "python -c \"import os, signal; m = r'{}'; started = os.path.exists(m); open(m, 'w'); \
+             signal.signal(signal.SIGINT, signal.SIG_DFL); \
+             started and os.kill(os.getpid(), signal.SIGINT); raise SystemExit(1)\"",
+            marker.display()
+        );
+        let config = RunConfig::new(root)
+            .modules("calc.py")
+            .runner(Runner::Custom)
+            .test_command(command)
+            .num_threads(1)
+            .output_level(runner::OutputLevel::Quiet);
+
+        assert!(config.run().is_err());
+        let entries = crate::cache::read_csv_cache(&config.cache_file(), root).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].status, MutantResult::Caught);
+        assert!(!root.join(".pymute/cache.csv.tmp").exists());
+
+        // the run is resumed without the finished mutant
+        std::fs::remove_file(&marker).unwrap();
+        let summary = config.run().unwrap();
+        assert_eq!((summary.total, summary.cached), (2, 1));
+        let entries = crate::cache::read_csv_cache(&config.cache_file(), root).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    #[allow(deprecated)]
     fn test_run_no_max_mutants() {
         let multiline_string_script = "def add(a, b):
     return a + b
@@ -143,7 +690,7 @@ print(res) # print the result *
         let mut script1 = File::create(base_path.join("script.py")).unwrap();
         write!(script1, "{}", multiline_string_script).expect("Failed to write to temporary file");
 
-        run(
+        crate::run_with_args(
             &PathBuf::from(base_path),
             "**/*.py",
             ".",
@@ -151,7 +698,7 @@ print(res) # print the result *
             &runner::Runner::Pytest,
             &None,
             &None,
-            &vec![
+            &[
                 MutationType::MathOps,
                 MutationType::Conjunctions,
                 MutationType::Booleans,
@@ -167,4 +714,337 @@ print(res) # print the result *
         // best be safe and close it
         temp_dir.close().unwrap();
     }
+
+    #[test]
+    fn test_run_summary_file_has_no_paths() {
+        let multiline_string_script = "def add(a, b):
+    return a + b
+
+res = add(7, 8)
+";
+
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let project = base_path.join("secret_project");
+        std::fs::create_dir(&project).unwrap();
+        let mut script1 = File::create(project.join("confidential_module.py")).unwrap();
+        write!(script1, "{}", multiline_string_script).expect("Failed to write to temporary file");
+
+        let summary_path = base_path.join("summary.json");
+        RunConfig::new(&project)
+            .mutation_types(&[MutationType::MathOps])
+            .summary_file(&summary_path)
+            .run()
+            .unwrap();
+
+        let content = std::fs::read_to_string(&summary_path).unwrap();
+        let components = project
+            .components()
+            .filter_map(|c| match c {
+                std::path::Component::Normal(c) => c.to_str(),
+                _ => None,
+            })
+            .chain(["confidential_module", "return a + b", "add("]);
+        for component in components {
+            assert!(
+                !content.contains(component),
+                "Summary file leaks {component}: {content}"
+            );
+        }
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_run_report_json() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("calc.py"),
+            "def add(a, b):\n    return a + b\n\n\ndef check(a):\n    return a > 0\n",
+        )
+        .unwrap();
+        let report_path = root.join("report.json");
+        let summary = RunConfig::new(root)
+            .mutation_types(&[MutationType::MathOps, MutationType::CompOps])
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import sys; sys.exit(0)""#)
+            .output_level(runner::OutputLevel::Quiet)
+            .report_json(&report_path)
+            .run()
+            .unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+        assert_eq!(report["summary"]["total"], summary.total);
+        assert_eq!(report["summary"]["missed"], summary.missed);
+        assert_eq!(report["summary"]["caught"], summary.caught);
+        let mutants = report["mutants"].as_array().unwrap();
+        assert_eq!(mutants.len(), summary.outcomes.len());
+        let missed = mutants
+            .iter()
+            .filter(|mutant| mutant["status"] == "missed")
+            .count();
+        assert_eq!(missed, summary.missed);
+        assert_eq!(mutants[0]["file_path"], "calc.py");
+        assert_eq!(mutants[0]["mutation_type"], "math-ops");
+        assert!(mutants[0]["duration_ms"].is_u64());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_schedule_mutants() {
+        let planned: Vec<PlannedMutant> = (1..=4)
+            .map(|line| PlannedMutant {
+                id: format!("id{line}"),
+                mutant: Mutant::new(PathBuf::from("calc.py"), line, " + ", " - ", 5, "x = a + b"),
+            })
+            .collect();
+        let entry = |id: &str, status, duration_ms| CacheEntry {
+            id: Some(id.into()),
+            file_path: PathBuf::from("calc.py"),
+            line_number: 1,
+            column: Some(5),
+            before: " + ".into(),
+            after: " - ".into(),
+            status,
+            file_hash: None,
+            mutation_type: None,
+            duration_ms,
+            error: None,
+        };
+        // id4 has no entry, and the entry of id3 has no duration
+        let entries = vec![
+            entry("id1", MutantResult::Caught, Some(100)),
+            entry("id2", MutantResult::Missed, Some(50)),
+            entry("id3", MutantResult::Missed, None),
+        ];
+        let scheduled = |order, seed| {
+            let mut mutants = planned.clone();
+            schedule_mutants(&mut mutants, &entries, order, seed);
+            mutants
+                .into_iter()
+                .map(|planned| planned.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(scheduled(Order::File, 0), ["id1", "id2", "id3", "id4"]);
+        assert_eq!(scheduled(Order::Priority, 0), ["id2", "id3", "id1", "id4"]);
+        assert_eq!(scheduled(Order::Duration, 0), ["id3", "id4", "id1", "id2"]);
+        let random = scheduled(Order::Random, 3);
+        assert_eq!(scheduled(Order::Random, 3), random);
+        let mut sorted = random.clone();
+        sorted.sort();
+        assert_eq!(sorted, scheduled(Order::File, 0));
+    }
+
+    #[test]
+    fn test_run_records_durations() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n").unwrap();
+        let report_path = root.join("report.json");
+        let config = RunConfig::new(root)
+            .mutation_types(&[MutationType::MathOps])
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import time; time.sleep(0.3)""#)
+            .output_level(runner::OutputLevel::Quiet)
+            .report_json(&report_path);
+        let summary = config.run().unwrap();
+        let duration = summary.outcomes[0].duration.unwrap();
+        assert!(duration >= Duration::from_millis(300), "{duration:?}");
+        assert_eq!(summary.slowest(10).len(), 1);
+
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+        assert!(report["mutants"][0]["duration_ms"].as_u64().unwrap() >= 300);
+        let entries = crate::cache::read_csv_cache(&config.cache_file(), root).unwrap();
+        assert!(entries[0].duration_ms.unwrap() >= 300);
+
+        // cached results keep the duration of their test run
+        let cached = config.run().unwrap();
+        assert_eq!(cached.cached, 1);
+        assert_eq!(
+            cached.outcomes[0].duration.unwrap().as_millis(),
+            duration.as_millis()
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_run_writes_only_to_state_dir() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let mut script1 = File::create(base_path.join("script.py")).unwrap();
+        write!(script1, "def add(a, b):\n    return a + b\n").unwrap();
+        std::fs::write(base_path.join(".pymute_cache.csv"), "").unwrap();
+
+        RunConfig::new(base_path).run().unwrap();
+
+        let mut entries: Vec<String> = std::fs::read_dir(base_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec![".pymute", "script.py"]);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_run_workdirs_in_state_dir() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n").unwrap();
+        let log = temp_dir.path().join("cwd.log");
+        let command = format!(
+            r#"python -c "import os; open(r'{}', 'a').write(os.getcwd() + '\n')""#,
+            log.display()
+        );
+        let config = RunConfig::new(&root)
+            .runner(Runner::Custom)
+            .test_command(command)
+            .output_level(runner::OutputLevel::Quiet);
+        config.run().unwrap();
+
+        // the tests ran in working copies in the state directory
+        let workdirs = config.state().workdirs_dir().canonicalize().unwrap();
+        let cwds = std::fs::read_to_string(&log).unwrap();
+        assert!(!cwds.is_empty());
+        for cwd in cwds.lines() {
+            assert!(std::path::Path::new(cwd).starts_with(&workdirs), "{cwd}");
+        }
+
+        // working copies left over by a killed run are removed by `clean_cache`
+        std::fs::create_dir(workdirs.join(".tmpleftover")).unwrap();
+        let deleted = crate::clean_cache(&config).unwrap();
+        assert!(
+            deleted.contains(&config.state().workdirs_dir()),
+            "{deleted:?}"
+        );
+        assert!(!workdirs.exists());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_runs() {
+        let temp_dir = tempdir().unwrap();
+        // brackets in the root must not be interpreted as part of the glob
+        let caught_project = temp_dir.path().join("caught [1]");
+        let missed_project = temp_dir.path().join("missed");
+        for project in [&caught_project, &missed_project] {
+            std::fs::create_dir(project).unwrap();
+            for name in ["a.py", "b.py", "c.py"] {
+                std::fs::write(
+                    project.join(name),
+                    "def add(a, b):\n    return a + b\n\nx = add(1, 2) * 3\n",
+                )
+                .unwrap();
+            }
+        }
+
+        let configs = [
+            RunConfig::new(&caught_project)
+                .runner(Runner::Custom)
+                .test_command(r#"python -c "import sys; sys.exit(1)""#)
+                .num_threads(2),
+            RunConfig::new(&missed_project)
+                .runner(Runner::Custom)
+                .test_command(r#"python -c "import sys; sys.exit(0)""#)
+                .num_threads(2),
+        ];
+        let summaries: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = configs
+                .iter()
+                .map(|config| scope.spawn(move || run(config).unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let expected = [MutantResult::Caught, MutantResult::Missed];
+        for ((summary, config), result) in summaries.iter().zip(&configs).zip(expected) {
+            assert_eq!(summary.total, 6);
+            for outcome in &summary.outcomes {
+                assert!(outcome.mutant.file_path.starts_with(&config.root));
+                assert_eq!(outcome.result, result);
+            }
+            let cached = crate::cache::read_csv_cache(&config.cache_file(), &config.root).unwrap();
+            assert_eq!(cached.len(), 6);
+            assert!(!config.state().lock_file().exists());
+        }
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_run_skip_untested_files() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        for dir in ["pkg", "tests"] {
+            std::fs::create_dir(root.join(dir)).unwrap();
+        }
+        for file in ["pkg/calc.py", "pkg/orphan.py"] {
+            std::fs::write(root.join(file), "def add(a, b):\n    return a + b\n").unwrap();
+        }
+        std::fs::write(
+            root.join("tests/test_calc.py"),
+            "from pkg.calc import add\n",
+        )
+        .unwrap();
+
+        let config = RunConfig::new(root)
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import sys; sys.exit(0)""#);
+
+        let summary = run(&config).unwrap();
+        assert_eq!(summary.untested_files, vec![root.join("pkg/orphan.py")]);
+        assert_eq!((summary.missed, summary.untested), (2, 0));
+
+        let summary = run(&config.skip_untested_files(true)).unwrap();
+        assert_eq!((summary.missed, summary.untested), (1, 1));
+        for outcome in &summary.outcomes {
+            let expected = match outcome.mutant.file_path.ends_with("orphan.py") {
+                true => MutantResult::Untested,
+                false => MutantResult::Missed,
+            };
+            assert_eq!(outcome.result, expected);
+        }
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_run_check_syntax() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("valid.py"), "def add(a, b):\n    return a + b\n").unwrap();
+        std::fs::write(root.join("broken.py"), "def sub(a, b):\n    print a - b\n").unwrap();
+
+        let config = RunConfig::new(root)
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import sys; sys.exit(1)""#);
+
+        let summary = run(&config).unwrap();
+        assert_eq!(summary.caught, 1);
+        assert!(summary.outcomes[0].mutant.file_path.ends_with("valid.py"));
+        assert_eq!(
+            summary.invalid_files.keys().collect::<Vec<_>>(),
+            vec![&root.join("broken.py")]
+        );
+        assert!(summary.invalid_files[&root.join("broken.py")].contains("(line 2)"));
+
+        let config = config.check_syntax(false);
+        let summary = run(&config).unwrap();
+        assert_eq!((summary.caught, summary.invalid), (1, 1));
+        assert!(summary.invalid_files.is_empty());
+
+        let summary = run(&config.skip_invalid(false)).unwrap();
+        assert_eq!(summary.caught, 2);
+
+        temp_dir.close().unwrap();
+    }
 }
@@ -1,16 +1,89 @@
-use clap::Parser;
+use clap::{
+    parser::ValueSource, ArgAction, ArgMatches, Args, CommandFactory, FromArgMatches, Parser,
+    Subcommand, ValueEnum,
+};
 use colored::Colorize;
+use pymute::config::{self, Profile, RunConfig};
+use pymute::config_file::{ConfigFile, CONFIG_FILE};
+use pymute::humanize::{format_duration, format_rate};
 use pymute::mutants::MutationType;
-use pymute::{run, runner};
-use std::{path::PathBuf, process};
+use pymute::plan::{self, ListFormat, Order, Sampling};
+use pymute::pyproject::PyprojectSettings;
+use pymute::report;
+use pymute::runner::{self, MutantResult, PytestWorkers};
+use pymute::shard::Shard;
+use pymute::state::StateDir;
+use pymute::summary::ThresholdFailure;
+use pymute::tui::{Dashboard, MIN_TERMINAL_SIZE};
+use pymute::verdicts::{self, Verdict, VerdictKind};
+use pymute::{PymuteError, RunSummary};
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+};
 
 /// Pymute: A Mutation Testing Tool for Python/Pytest written in Rust.
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(
+    after_help = "Deprecated: running `pymute <ROOT> [OPTIONS]` without a subcommand is an alias for `pymute run <ROOT> [OPTIONS]` and will be removed in the next release."
+)]
 pub struct Arguments {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArguments,
+
+    /// When to color the output. With "auto", the output is colored if it goes to
+    /// a terminal and the NO_COLOR environment variable is not set.
+    #[arg(long, global = true)]
+    #[arg(value_enum, default_value = "auto")]
+    color: ColorChoice,
+}
+
+/// When to color the output.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color the output, given the value of the NO_COLOR environment
+    /// variable and whether stdout is a terminal. Like other tools, a NO_COLOR
+    /// that is set but empty is ignored.
+    fn colorize(self, no_color: Option<OsString>, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => is_terminal && no_color.is_none_or(|value| value.is_empty()),
+        }
+    }
+}
+
+/// Arguments of a run, for `run` and `list`.
+#[derive(Debug, Args)]
+struct RunArguments {
     /// Define the path to the root of the python project.
-    root: PathBuf,
+    #[arg(required = true)]
+    root: Option<PathBuf>,
+
+    /// Profile with defaults for a common use case ("quick", "standard",
+    /// "thorough" or "ci"), or one defined in the pymute.toml of the project.
+    /// Options given explicitly always take precedence over the profile. Run
+    /// `pymute profiles` to see what each built-in profile sets.
+    #[arg(long)]
+    #[arg(default_value = "standard")]
+    profile: String,
 
     /// Glob expression to modules for which
     /// mutants should be created. This should be
@@ -18,26 +91,41 @@ pub struct Arguments {
     /// By default, it will take all modules under the root.
     /// Pymute also filters out files that start with
     /// "test_" and end with "_test.py" to avoid scanning
-    /// tests for mutants.
+    /// tests for mutants (see `--include-tests`). Can be given multiple times to
+    /// combine several glob expressions.
     #[arg(short, long)]
-    #[arg(default_value = "**/*.py")]
-    modules: String,
+    #[arg(default_value = config::DEFAULT_MODULES)]
+    modules: Vec<String>,
+
+    /// Glob expression (relative to the root of the python project) of modules
+    /// not to mutate even if they match `--modules`, e.g. "src/generated/**".
+    /// A directory matching it excludes all modules below it. Can be given
+    /// multiple times.
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Also create mutants in test files ("test_*.py" and "*_test.py") matching
+    /// `--modules`, e.g. to mutate test helpers.
+    #[arg(long)]
+    include_tests: bool,
 
     /// Path for tests that should be run. This should be
     /// relative from the root of the python project.
     /// By default, it will simply use "."
-    /// (i.e. run all tests found under the root). This option is ignored when
+    /// (i.e. run all tests found under the root). With the unittest runner, it is
+    /// the start directory of test discovery, or a dotted test name such as
+    /// "tests.test_calc.TestAdd". This option is ignored when
     /// running your tests via tox, because tox will run whatever commands
     /// you specify in your `tox.ini` file. Instead set the `--environment` option
     /// to run specific tox test environments.
     #[arg(short, long)]
-    #[arg(default_value = ".")]
+    #[arg(default_value = config::DEFAULT_TESTS)]
     tests: String,
 
     /// Number of threads to run individual mutants in parallel in different
     /// temporary directories.
     #[arg(short, long)]
-    #[arg(default_value = "1")]
+    #[arg(default_value_t = config::DEFAULT_NUM_THREADS)]
     num_threads: usize,
 
     /// Output level of the program
@@ -52,69 +140,1299 @@ pub struct Arguments {
     #[arg(default_value_t = runner::Runner::Pytest)]
     runner: runner::Runner,
 
-    /// Tox environment to use. Ignored if pytest runner is used.
+    /// Tox environment to use, or comma-separated sessions (e.g. "lint,tests")
+    /// with the nox runner. Ignored by the pytest and unittest runners.
     #[arg(short, long)]
     #[arg(value_enum)]
     environment: Option<String>,
 
+    /// Test command to run when using the custom runner, e.g. "make test".
+    /// It is split using shell-style quoting and run in the root of each mutated
+    /// copy of the project. A non-zero exit status means the mutant was caught.
+    /// `--tests` and `--environment` are ignored when using the custom runner.
+    #[arg(long)]
+    test_command: Option<String>,
+
+    /// Run each test run of the pytest runner in parallel with pytest-xdist, which
+    /// must be installed, using this many workers or "auto" for one per CPU. Keep
+    /// `--num-threads` times the workers at about the number of CPUs.
+    #[arg(long)]
+    #[arg(value_name = "N|auto")]
+    pytest_workers: Option<PytestWorkers>,
+
+    /// Distribution mode of pytest-xdist with `--pytest-workers`, e.g. "loadfile".
+    #[arg(long)]
+    #[arg(value_name = "MODE")]
+    pytest_dist: Option<String>,
+
+    /// Run pytest with `--cache-clear`, so that no test run is affected by the
+    /// pytest cache (e.g. `--lf` or the step-wise state) of an earlier one. Useful
+    /// with --workspace and --in-place, where the cache is not reset between
+    /// mutants.
+    #[arg(long)]
+    pytest_cache_clear: bool,
+
+    /// Extra arguments for the test command, e.g. "-k 'not slow' --maxfail 1",
+    /// split using shell-style quoting. The tox runner passes them on after `--`.
+    #[arg(long)]
+    #[arg(value_name = "ARGS")]
+    #[arg(allow_hyphen_values = true)]
+    runner_args: Option<String>,
+
+    /// Extra arguments for the test command given after `--`, appended to those
+    /// of `--runner-args`.
+    #[arg(last = true)]
+    #[arg(value_name = "ARGS")]
+    trailing_runner_args: Vec<String>,
+
+    /// Python interpreter that runs pytest and unittest and compiles the files,
+    /// e.g. ".venv/bin/python" or "python3". Defaults to `python` on the PATH.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    python: Option<PathBuf>,
+
+    /// Executable of the tox or nox runner, e.g. ".venv/bin/tox". Defaults to
+    /// `tox` or `nox` on the PATH.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    runner_binary: Option<PathBuf>,
+
+    /// Environment variable to set for each test run, e.g.
+    /// "DJANGO_SETTINGS_MODULE=app.settings". Can be given multiple times. The
+    /// tests also see PYMUTE_MUTANT_ID, PYMUTE_MUTANT_FILE, PYMUTE_MUTANT_LINE
+    /// and PYMUTE_WORKDIR of the mutant being run.
+    #[arg(long)]
+    #[arg(value_name = "KEY=VALUE")]
+    #[arg(value_parser = parse_env_var)]
+    env: Vec<(String, String)>,
+
+    /// Directory to write the output of the test run of each mutant to, as
+    /// "<mutant id>.log", whatever the output level. The log of a surviving
+    /// mutant is printed with it.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    log_dir: Option<PathBuf>,
+
+    /// Keep the working copy of each surviving mutant for debugging, moved to
+    /// "<PATH>/<mutant id>" with the mutant still inserted. Its path is printed
+    /// with the mutant.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    keep_missed_dirs: Option<PathBuf>,
+
+    /// Keep the working copies of all mutants in --keep-missed-dirs, not only of
+    /// the surviving ones.
+    #[arg(long)]
+    #[arg(requires = "keep_missed_dirs")]
+    keep_all_dirs: bool,
+
+    /// Stop the run once more than N mutants could not be run at all, e.g.
+    /// because their working copy could not be created. Their errors are
+    /// reported and cached, and the run continues by default.
+    #[arg(long)]
+    #[arg(value_name = "N")]
+    max_errors: Option<usize>,
+
     /// Maximum number of mutants to be run. If set, will choose a random subset
     /// of n mutants. Consider setting the `--seed` option
     #[arg(long)]
     max_mutants: Option<usize>,
 
+    /// Only run one shard of the mutants, e.g. "2/4" for the second of four
+    /// parallel CI jobs. The shards of the same options and seed are disjoint and
+    /// together hold all mutants. With `--max-mutants`, the sample is sharded.
+    #[arg(long)]
+    #[arg(value_name = "INDEX/TOTAL")]
+    shard: Option<Shard>,
+
     /// Mutation types.
     #[arg(long)]
     #[arg(value_enum)]
-    #[arg(default_values_t = [
-	MutationType::MathOps,
-	MutationType::Conjunctions,
-	MutationType::Booleans,
-	MutationType::ControlFlow,
-	MutationType::CompOps,
-	MutationType::Numbers,
-    ], value_delimiter=',')]
+    #[arg(default_values_t = config::DEFAULT_MUTATION_TYPES, value_delimiter=',')]
     mutation_types: Vec<MutationType>,
 
-    /// List mutants and exit.
+    /// List mutants and exit. Deprecated, use `pymute list` instead.
     #[arg(short, long)]
     list: bool,
 
+    /// Format of the list of mutants: "human" (colored text), "plain" (one
+    /// tab-separated record of path, line, before, after, mutation type and id per
+    /// mutant) or "json" (an array of mutant objects).
+    #[arg(long)]
+    #[arg(value_enum, default_value = "human")]
+    list_format: ListFormat,
+
+    /// Do not run mutants in files that have no associated tests (no test file
+    /// named after the module and no test file importing it). These mutants
+    /// are reported as untested instead.
+    #[arg(long)]
+    skip_untested_files: bool,
+
+    /// Run the tests once with pytest-cov to find the tests covering each line,
+    /// and run each mutant against only the tests covering its line. Mutants on
+    /// lines no test executes are reported as uncovered without running them.
+    /// Requires the pytest runner and pytest-cov.
+    #[arg(long)]
+    coverage_guided: bool,
+
+    /// Coverage JSON report with test contexts (`pytest --cov-context=test` and
+    /// `coverage json --show-contexts`) to select the tests from, instead of
+    /// collecting it. Implies `--coverage-guided`.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    coverage_json: Option<PathBuf>,
+
+    /// Coverage.py JSON or XML report of the project (`coverage json` or
+    /// `coverage xml`). Mutants on lines it reports as not executed are reported as
+    /// uncovered instead of running them, since no test can catch them.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    coverage_file: Option<PathBuf>,
+
+    /// Compile the python files before looking for mutants and exclude files with
+    /// syntax errors. The excluded files are listed before the run.
+    #[arg(long)]
+    #[arg(default_value_t = true, action = ArgAction::Set)]
+    check_syntax: bool,
+
+    /// Compile each mutated file before running the tests and report mutants
+    /// that are not valid python as invalid instead of running the tests.
+    #[arg(long)]
+    #[arg(default_value_t = true, action = ArgAction::Set)]
+    skip_invalid: bool,
+
     /// Seed for random number generator if max_mutants is set.
     #[arg(short, long)]
-    #[arg(default_value = "42")]
+    #[arg(default_value_t = config::DEFAULT_SEED)]
     seed: u64,
+
+    /// How `--max-mutants` samples the mutants: "global" from all mutants at once,
+    /// or "per-file" with a budget per file that is proportional to its number of
+    /// mutants and at least one, so that small modules are not left out.
+    #[arg(long)]
+    #[arg(value_enum, default_value = "global")]
+    sampling: Sampling,
+
+    /// Order in which the mutants are started: "file" as they were found,
+    /// "priority" with the mutants that survived the previous run first,
+    /// "duration" with the mutants whose tests ran longest in the previous run
+    /// first, or "random".
+    #[arg(long)]
+    #[arg(value_enum, default_value = "file")]
+    order: Order,
+
+    /// Write a summary file with aggregate numbers of the run (score, counts,
+    /// duration, configured mutation types) as JSON. The file never contains
+    /// file paths or code, so it can be committed or uploaded as a CI artifact.
+    #[arg(long)]
+    summary_file: Option<PathBuf>,
+
+    /// Write a JSON report of every mutant of the run (file, line, replacement,
+    /// mutation type, status and duration) together with the configuration and
+    /// the counts of the summary, e.g. to annotate pull requests in CI.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    report_json: Option<PathBuf>,
+
+    /// Write a JUnit XML report with a test suite per source file and a test
+    /// case per mutant, in which surviving mutants are failures, so that CI
+    /// systems such as GitLab or Jenkins can show them.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    report_junit: Option<PathBuf>,
+
+    /// Write an HTML report to this directory: an index page with the score and
+    /// the results per file, and a page per file with its source, in which the
+    /// mutated lines are highlighted and annotated with their mutants.
+    #[arg(long)]
+    #[arg(value_name = "DIR")]
+    report_html: Option<PathBuf>,
+
+    /// Write the surviving mutants as a SARIF 2.1.0 log, e.g. to upload it to
+    /// GitHub code scanning, which then annotates the lines of pull requests.
+    #[arg(long)]
+    #[arg(value_name = "PATH")]
+    report_sarif: Option<PathBuf>,
+
+    /// Include the caught mutants as notes in the SARIF log.
+    #[arg(long)]
+    sarif_include_caught: bool,
+
+    /// Write progress and results as newline-delimited JSON events to this unix
+    /// domain socket (or named pipe on Windows), e.g. for an external dashboard.
+    /// The socket must already be listening. Events are dropped if the reader
+    /// does not keep up, so a slow reader never slows down the run.
+    #[arg(long)]
+    events_socket: Option<PathBuf>,
+
+    /// Follow the run in a full-screen dashboard. Falls back to the normal output
+    /// if stdout is not a terminal or the terminal is too small.
+    #[arg(long)]
+    tui: bool,
+
+    /// Additional glob patterns of files and directories that are not copied into
+    /// the working copy of the project for each mutant, separated by commas.
+    /// ".git", ".hg", "__pycache__", ".tox", ".nox", ".venv", "venv", ".mypy_cache",
+    /// ".pytest_cache" and the state directory are always excluded.
+    #[arg(long)]
+    #[arg(value_delimiter = ',')]
+    copy_exclude: Vec<String>,
+
+    /// Directory in which pymute keeps all files it produces for the project
+    /// (cache, history, logs, reports, ...). Relative paths are interpreted
+    /// relative to the root. By default, ".pymute" under the root is used.
+    #[arg(long)]
+    state_dir: Option<PathBuf>,
+
+    /// Keep the cache of mutant results in this file instead of the state
+    /// directory, e.g. to commit it to the repository. Relative paths are
+    /// interpreted relative to the root.
+    #[arg(long)]
+    cache_path: Option<PathBuf>,
+
+    /// Replace the cache with the results of this run. By default, the results are
+    /// merged into the cache, keeping the results of mutants that were not run
+    /// (e.g. in modules not selected this time) unless their file changed.
+    #[arg(long)]
+    cache_overwrite: bool,
+
+    /// Skip malformed rows of the cache (e.g. merge conflict markers) with a
+    /// warning instead of failing. The skipped rows are dropped from the cache.
+    #[arg(long)]
+    cache_lenient: bool,
+
+    /// Copy this directory into the working copy for each mutant instead of the
+    /// root, e.g. a prepared test image with an installed virtual environment.
+    /// Mutants are still found in the root, and the tests are run in the copy of
+    /// this directory. The files to mutate must be part of it with the same content.
+    #[arg(long)]
+    prepared_root: Option<PathBuf>,
+
+    /// Path in the prepared root under which the files of the project are found,
+    /// e.g. "lib/python3.11/site-packages". By default, they are found at the same
+    /// paths as in the root.
+    #[arg(long)]
+    #[arg(requires = "prepared_root")]
+    prepared_src_prefix: Option<PathBuf>,
+
+    /// Insert the mutants into this existing copy of the root (e.g. a clone with a
+    /// virtual environment and compiled extensions built in place) and run the
+    /// tests there, instead of in temporary copies. The mutated file is restored
+    /// from the root after each mutant, and the root itself is never modified.
+    /// Give it once per worker to run mutants in parallel; --num-threads is
+    /// ignored.
+    #[arg(long)]
+    #[arg(value_name = "DIR")]
+    #[arg(conflicts_with = "prepared_root")]
+    workspace: Vec<PathBuf>,
+
+    /// Insert the mutants into the files of the project itself instead of working
+    /// copies, one mutant at a time, and remove each after its tests ran. Refuses
+    /// to run if the git working tree has uncommitted changes, and stops if a file
+    /// could not be restored. --num-threads is ignored.
+    #[arg(long)]
+    #[arg(conflicts_with_all = ["prepared_root", "workspace", "keep_missed_dirs"])]
+    in_place: bool,
+
+    /// Run --in-place even if the git working tree has uncommitted changes.
+    #[arg(long)]
+    #[arg(requires = "in_place")]
+    allow_dirty: bool,
+
+    /// Do not run mutants on lines nested in more than N blocks. The body of a
+    /// function at module level has depth 1, an `if` in it depth 2. The number of
+    /// excluded mutants is printed before the run.
+    #[arg(long)]
+    #[arg(value_name = "N")]
+    max_nesting: Option<usize>,
+
+    /// Do not run mutants in functions longer than N lines (from the `def` to the
+    /// last line of the body). Methods and nested functions count on their own.
+    #[arg(long)]
+    #[arg(value_name = "N")]
+    max_function_lines: Option<usize>,
+
+    /// Count mutants that are likely equivalent to the original code (e.g. `x * 1`
+    /// mutated to `x / 1`) in the mutation score. By default they are excluded,
+    /// and the number of such mutants per rule is printed after the run.
+    #[arg(long)]
+    include_likely_equivalent: bool,
+
+    /// Count uncovered mutants (see `--coverage-file`) as survivors in the
+    /// mutation score. By default they are excluded and counted separately.
+    #[arg(long)]
+    include_uncovered: bool,
+
+    /// Only run the mutant with this id, as printed for surviving mutants and by
+    /// `pymute list`, e.g. to re-run a survivor while writing a test for it. Can be
+    /// given multiple times.
+    #[arg(long = "mutant-id")]
+    #[arg(value_name = "ID")]
+    mutant_ids: Vec<String>,
+
+    /// Only run the mutants on lines added or modified since this git ref
+    /// (including uncommitted changes), e.g. "origin/main" to focus on the
+    /// changes of a pull request. The root must be in a git repository.
+    #[arg(long)]
+    #[arg(value_name = "REF")]
+    diff_base: Option<String>,
+
+    /// Run all mutants. By default, mutants that were caught or missed by a
+    /// previous run are not run again as long as their file is unchanged, and
+    /// their result is taken from the cache. Use this option after changing the
+    /// tests. Mutants selected with `--mutant-id` are always run.
+    #[arg(long)]
+    rerun_all: bool,
+
+    /// Neither read nor write the cache, e.g. for a pristine run in CI. Implies
+    /// `--rerun-all`.
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Delete the cache before running the mutants, so that it only holds the
+    /// results of this run afterwards.
+    #[arg(long)]
+    clear_cache: bool,
+
+    /// Fail with exit code 2 if the mutation score (in percent) is below this.
+    #[arg(long)]
+    #[arg(value_name = "PERCENT")]
+    fail_under: Option<f64>,
+
+    /// Fail with exit code 2 if any mutant survives.
+    #[arg(long)]
+    fail_on_missed: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the test suite against the mutants of the project.
+    Run(Box<RunArguments>),
+    /// List the mutants of the project together with their ids, without running
+    /// them.
+    List(Box<RunArguments>),
+    /// Print the results of previous runs from the cache, without running
+    /// anything.
+    Report(ReportArguments),
+    /// Delete the cache and leftover working copies and logs of previous runs.
+    /// Verdicts, the history and the baseline are kept.
+    Clean(RootArguments),
+    /// List the available profiles and the options they set.
+    Profiles,
+    /// Record a verdict on a surviving mutant, e.g. to accept it as an intended
+    /// survivor. Accepted survivors are reported separately and do not count
+    /// against the mutation score.
+    Mark {
+        /// Id of the mutant, as printed for surviving mutants.
+        id: String,
+
+        /// The verdict on the mutant.
+        #[arg(long)]
+        #[arg(value_enum, default_value_t = VerdictKind::Accepted)]
+        verdict: VerdictKind,
+
+        /// Why the verdict was given.
+        #[arg(long)]
+        #[arg(default_value = "")]
+        note: String,
+
+        #[command(flatten)]
+        project: ProjectArguments,
+    },
+    /// Remove the verdict on a mutant.
+    Unmark {
+        /// Id of the mutant.
+        id: String,
+
+        #[command(flatten)]
+        project: ProjectArguments,
+    },
+}
+
+/// Arguments locating the state directory of a project for subcommands.
+#[derive(Debug, Args)]
+struct ProjectArguments {
+    /// Path to the root of the python project.
+    #[arg(long)]
+    #[arg(default_value = ".")]
+    root: PathBuf,
+
+    /// Directory in which pymute keeps all files it produces for the project.
+    /// Relative paths are interpreted relative to the root.
+    #[arg(long)]
+    state_dir: Option<PathBuf>,
+}
+
+impl ProjectArguments {
+    /// The state directory of the project.
+    fn state(&self) -> StateDir {
+        StateDir::new(&self.root, self.state_dir.as_deref())
+    }
+}
+
+/// Arguments locating the project for `report` and `clean`.
+#[derive(Debug, Args)]
+struct RootArguments {
+    /// Path to the root of the python project.
+    root: PathBuf,
+
+    /// Directory in which pymute keeps all files it produces for the project.
+    /// Relative paths are interpreted relative to the root.
+    #[arg(long)]
+    state_dir: Option<PathBuf>,
+
+    /// Path of the cache file, if it is not kept in the state directory. Relative
+    /// paths are interpreted relative to the root.
+    #[arg(long)]
+    cache_path: Option<PathBuf>,
+
+    /// Skip malformed rows of the cache with a warning instead of failing.
+    #[arg(long)]
+    cache_lenient: bool,
+}
+
+/// Arguments of `report`.
+#[derive(Debug, Args)]
+struct ReportArguments {
+    #[command(flatten)]
+    root: RootArguments,
+
+    /// Only report the mutants of these mutation types. Defaults to all types.
+    #[arg(long)]
+    #[arg(value_enum, value_delimiter = ',')]
+    mutation_types: Option<Vec<MutationType>>,
+
+    /// Write an HTML report of the results in the cache to this directory, see
+    /// `pymute run --report-html`.
+    #[arg(long)]
+    #[arg(value_name = "DIR")]
+    report_html: Option<PathBuf>,
+}
+
+impl RootArguments {
+    /// The configuration locating the project, its state directory and its cache.
+    fn config(&self) -> RunConfig {
+        RunConfig {
+            state_dir: self.state_dir.clone(),
+            cache_path: self.cache_path.clone(),
+            cache_lenient: self.cache_lenient,
+            ..RunConfig::new(&self.root)
+        }
+    }
+}
+
+impl ReportArguments {
+    /// The configuration locating the project and selecting the reported mutants.
+    fn config(&self) -> RunConfig {
+        RunConfig {
+            mutation_types: self
+                .mutation_types
+                .clone()
+                .unwrap_or_else(|| MutationType::value_variants().to_vec()),
+            ..self.root.config()
+        }
+    }
+}
+
+impl RunArguments {
+    /// Build the run configuration from the command line arguments.
+    ///
+    /// The options of the selected built-in profile, then those of the
+    /// `[tool.pymute]` table of the project's `pyproject.toml` and then those of
+    /// its `pymute.toml` for the selected profile are applied to all options that
+    /// were not given explicitly on the command line.
+    fn into_config(self, matches: &ArgMatches) -> Result<RunConfig, Box<dyn Error + Send + Sync>> {
+        let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        let mut config = RunConfig {
+            modules: self.modules,
+            tests: self.tests,
+            num_threads: self.num_threads,
+            output_level: self.output_level,
+            runner: self.runner,
+            environment: self.environment,
+            test_command: self.test_command,
+            pytest_workers: self.pytest_workers,
+            pytest_cache_clear: self.pytest_cache_clear,
+            pytest_dist: self.pytest_dist,
+            runner_args: runner_args(self.runner_args, &self.trailing_runner_args)?,
+            python: self.python,
+            runner_binary: self.runner_binary,
+            env: self.env.into_iter().collect(),
+            log_dir: self.log_dir,
+            keep_missed_dirs: self.keep_missed_dirs,
+            keep_all_dirs: self.keep_all_dirs,
+            max_errors: self.max_errors,
+            max_mutants: self.max_mutants,
+            mutation_types: self.mutation_types,
+            list: self.list,
+            list_format: self.list_format,
+            skip_untested_files: self.skip_untested_files,
+            coverage_guided: self.coverage_guided,
+            coverage_json: self.coverage_json,
+            coverage_file: self.coverage_file,
+            check_syntax: self.check_syntax,
+            skip_invalid: self.skip_invalid,
+            seed: self.seed,
+            sampling: self.sampling,
+            order: self.order,
+            summary_file: self.summary_file,
+            report_json: self.report_json,
+            report_junit: self.report_junit,
+            report_html: self.report_html,
+            report_sarif: self.report_sarif,
+            sarif_include_caught: self.sarif_include_caught,
+            events_socket: self.events_socket,
+            copy_exclude: self.copy_exclude,
+            state_dir: self.state_dir,
+            cache_path: self.cache_path,
+            cache_overwrite: self.cache_overwrite,
+            cache_lenient: self.cache_lenient,
+            prepared_root: self.prepared_root,
+            prepared_src_prefix: self.prepared_src_prefix,
+            workspaces: self.workspace,
+            in_place: self.in_place,
+            allow_dirty: self.allow_dirty,
+            max_nesting: self.max_nesting,
+            max_function_lines: self.max_function_lines,
+            include_likely_equivalent: self.include_likely_equivalent,
+            include_uncovered: self.include_uncovered,
+            mutant_ids: self.mutant_ids,
+            exclude: self.exclude,
+            include_tests: self.include_tests,
+            diff_base: self.diff_base,
+            shard: self.shard,
+            rerun_all: self.rerun_all,
+            no_cache: self.no_cache,
+            clear_cache: self.clear_cache,
+            fail_under: self.fail_under,
+            fail_on_missed: self.fail_on_missed,
+            ..RunConfig::new(self.root.unwrap_or_default())
+        };
+
+        let profile = Profile::from_str(&self.profile, false)
+            .map(|profile| profile.settings(&config.state()))
+            .unwrap_or_default();
+        if let (Some(output_level), false) = (profile.output_level, explicit("output_level")) {
+            config.output_level = output_level;
+        }
+        if let (Some(max_mutants), false) = (profile.max_mutants, explicit("max_mutants")) {
+            config.max_mutants = max_mutants;
+        }
+        if let (Some(mutation_types), false) = (profile.mutation_types, explicit("mutation_types"))
+        {
+            config.mutation_types = mutation_types;
+        }
+        if let (Some(diff_base), false) = (profile.diff_base, explicit("diff_base")) {
+            config.diff_base = Some(diff_base);
+        }
+        if let (Some(fail_under), false) = (profile.fail_under, explicit("fail_under")) {
+            config.fail_under = Some(fail_under);
+        }
+        if let (Some(path), false) = (profile.report_json, explicit("report_json")) {
+            config.report_json = Some(path);
+        }
+        if let (Some(path), false) = (profile.report_junit, explicit("report_junit")) {
+            config.report_junit = Some(path);
+        }
+        let config_file = ConfigFile::read(&config.root)?;
+        for key in &config_file.unknown_keys {
+            eprintln!(
+                "{}: Unknown option `{key}` in {CONFIG_FILE} is ignored.",
+                "Warning".yellow()
+            );
+        }
+        PyprojectSettings::read(&config.root)?
+            .merge(config_file.settings(&self.profile)?)
+            .apply(&mut config, explicit);
+        Ok(config)
+    }
 }
 
 fn main() {
-    let args = Arguments::parse();
+    let matches = Arguments::command().get_matches();
+    let arguments = Arguments::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    colored::control::set_override(
+        arguments
+            .color
+            .colorize(env::var_os("NO_COLOR"), io::stdout().is_terminal()),
+    );
 
-    match rayon::ThreadPoolBuilder::new()
-        .num_threads(args.num_threads)
-        .build_global()
-    {
-        Ok(_) => {}
-        Err(err) => {
-            println!("{}: {}", "Error".red(), err);
-            process::exit(1);
-        }
-    }
-
-    match run(
-        &args.root,
-        &args.modules,
-        &args.tests,
-        &args.output_level,
-        &args.runner,
-        &args.environment,
-        &args.max_mutants,
-        &args.mutation_types,
-        &args.list,
-        &args.seed,
-    ) {
-        Ok(_) => println!("{}!", "Success".green()),
+    let result = match arguments.command {
+        Some(command) => run_command(command, &matches),
+        None => {
+            eprintln!(
+                "{}: Running pymute without a subcommand is deprecated, use `pymute run` instead.",
+                "Warning".yellow()
+            );
+            run_mutants(arguments.run, &matches)
+        }
+    };
+    if let Err(err) = result {
+        println!("{}: {}", "Error".red(), err);
+        // like a shell, exit with 128 + SIGINT when interrupted by Ctrl+C, and
+        // tell failed thresholds apart from errors running pymute
+        if matches!(err.downcast_ref(), Some(PymuteError::Interrupted)) {
+            process::exit(130);
+        } else if err.is::<ThresholdFailure>() {
+            process::exit(2);
+        }
+        process::exit(1);
+    }
+}
+
+/// Run the mutants of the project, or only list them for `--list`.
+fn run_mutants(
+    arguments: RunArguments,
+    matches: &ArgMatches,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let tui = arguments.tui;
+    let mut config = arguments.into_config(matches)?;
+    warn_ignored_options(&config);
+
+    let dashboard = match (tui, config.list) {
+        (true, false) => start_dashboard(),
+        _ => None,
+    };
+    if let Some(dashboard) = &dashboard {
+        // the dashboard shows the results instead
+        config = config
+            .output_level(runner::OutputLevel::Quiet)
+            .observer(dashboard.clone());
+    }
+
+    let result = config.run();
+    if let Some(dashboard) = dashboard {
+        if let Err(err) = dashboard.finish() {
+            eprintln!("{}: {}", "Warning".yellow(), err);
+        }
+    }
+
+    let summary = result?;
+    if !config.list {
+        print_summary(&summary);
+    }
+    summary.check(config.fail_under, config.fail_on_missed)?;
+    println!("{}!", "Success".green());
+    Ok(())
+}
+
+/// Print the mutants of the project together with their ids.
+fn list_mutants(
+    arguments: RunArguments,
+    matches: &ArgMatches,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let config = arguments.into_config(matches)?;
+    let mutants = pymute::list_mutants(&config)?;
+    plan::write_mutant_list(
+        &mut io::stdout().lock(),
+        &mutants,
+        &config.root,
+        config.list_format,
+    )
+}
+
+/// Warn about the malformed rows of the cache that were skipped.
+fn report_invalid_cache_rows(summary: &RunSummary) {
+    if summary.invalid_cache_rows > 0 {
+        eprintln!(
+            "{}: skipped {} malformed rows of the cache",
+            "Warning".yellow(),
+            summary.invalid_cache_rows
+        );
+    }
+}
+
+/// Print the surviving mutants and the overview of the results in the cache, and
+/// write them as HTML for `--report-html`.
+fn report_from_cache(arguments: ReportArguments) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let config = arguments.config();
+    let summary = pymute::report_from_cache(&config)?;
+    let verdicts = verdicts::read_verdicts(&config.state().verdicts_file())?;
+    for outcome in &summary.outcomes {
+        let id = outcome.mutant.id(&config.root);
+        if outcome.result == MutantResult::Missed && !verdicts.contains_key(&id) {
+            println!(
+                "[{}] Mutant Survived: {} (id {id})",
+                "MISSED".red(),
+                outcome.mutant
+            );
+        }
+    }
+    let score = match summary.score() {
+        Some(score) => format!("{score:.1}%"),
+        None => "n/a".into(),
+    };
+    println!(
+        "{} mutants in the cache: {} {}, {} {}, {} {}, mutation score {}",
+        summary.outcomes.len(),
+        summary.caught,
+        "caught".green(),
+        summary.missed,
+        "missed".red(),
+        summary.accepted,
+        "accepted".blue(),
+        score.yellow(),
+    );
+    for (mutation_type, counts) in &summary.mutation_types {
+        println!("    {mutation_type}: {counts}");
+    }
+    report_invalid_cache_rows(&summary);
+    if let Some(dir) = &arguments.report_html {
+        report::write_html_report(dir, &summary, &config.root)?;
+        println!(
+            "Wrote the HTML report to {}",
+            dir.join("index.html").display()
+        );
+    }
+    Ok(())
+}
+
+/// Start the dashboard for `--tui`, or warn and return None if the normal output
+/// has to be used.
+fn start_dashboard() -> Option<Arc<Dashboard>> {
+    match Dashboard::start() {
+        Ok(Some(dashboard)) => Some(dashboard),
+        Ok(None) => {
+            eprintln!(
+                "{}: --tui needs a terminal of at least {}x{}, using the normal output.",
+                "Warning".yellow(),
+                MIN_TERMINAL_SIZE.0,
+                MIN_TERMINAL_SIZE.1
+            );
+            None
+        }
         Err(err) => {
-            println!("{}: {}", "Error".red(), err);
-            process::exit(1);
+            eprintln!(
+                "{}: Could not start the dashboard ({err}), using the normal output.",
+                "Warning".yellow()
+            );
+            None
+        }
+    }
+}
+
+/// Run a subcommand. `matches` are those of the whole command line.
+fn run_command(command: Command, matches: &ArgMatches) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (_, matches) = matches.subcommand().expect("a subcommand is given");
+    match command {
+        Command::Run(arguments) => run_mutants(*arguments, matches)?,
+        Command::List(arguments) => list_mutants(*arguments, matches)?,
+        Command::Report(arguments) => report_from_cache(arguments)?,
+        Command::Clean(arguments) => {
+            let deleted = pymute::clean_cache(&arguments.config())?;
+            for path in &deleted {
+                println!("Deleted {}", path.display());
+            }
+            if deleted.is_empty() {
+                println!("Nothing to clean.");
+            }
+        }
+        Command::Profiles => print_profiles(),
+        Command::Mark {
+            id,
+            verdict,
+            note,
+            project,
+        } => {
+            let name = value_name(&verdict);
+            let verdict = Verdict {
+                id: id.clone(),
+                verdict,
+                note,
+            };
+            verdicts::mark(&project.state().verdicts_file(), verdict)?;
+            println!("Marked mutant {id} as {name}.");
+        }
+        Command::Unmark { id, project } => {
+            match verdicts::unmark(&project.state().verdicts_file(), &id)? {
+                true => println!("Removed the verdict on mutant {id}."),
+                false => println!("There is no verdict on mutant {id}."),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print each profile together with the options it sets.
+fn print_profiles() {
+    for profile in Profile::value_variants() {
+        let value = profile.to_possible_value().expect("no profile is skipped");
+        println!(
+            "{}: {}",
+            value.get_name().yellow(),
+            value.get_help().map(|h| h.to_string()).unwrap_or_default()
+        );
+
+        let settings = profile.settings(&StateDir::new(Path::new(""), None));
+        if let Some(output_level) = settings.output_level {
+            println!("    --output-level {}", value_name(&output_level));
+        }
+        match settings.max_mutants {
+            Some(Some(max_mutants)) => println!("    --max-mutants {max_mutants}"),
+            Some(None) => println!("    all mutants (no sampling)"),
+            None => {}
+        }
+        if let Some(mutation_types) = settings.mutation_types {
+            let names: Vec<_> = mutation_types.iter().map(value_name).collect();
+            println!("    --mutation-types {}", names.join(","));
+        }
+        if let Some(diff_base) = settings.diff_base {
+            println!("    --diff-base {diff_base}");
+        }
+        if let Some(path) = settings.report_json {
+            println!("    --report-json {}", path.display());
+        }
+        if let Some(path) = settings.report_junit {
+            println!("    --report-junit {}", path.display());
+        }
+        if let Some(fail_under) = settings.fail_under {
+            println!("    --fail-under {fail_under}");
+        }
+    }
+}
+
+/// Name of a value as given on the command line.
+fn value_name(value: &impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .expect("no value is skipped")
+        .get_name()
+        .to_string()
+}
+
+/// The runner arguments given with `--runner-args` followed by those after `--`,
+/// which are quoted again to be split with the others.
+fn runner_args(
+    runner_args: Option<String>,
+    trailing: &[String],
+) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+    if trailing.is_empty() {
+        return Ok(runner_args);
+    }
+    let trailing = shlex::try_join(trailing.iter().map(String::as_str))?;
+    Ok(Some(match runner_args {
+        Some(runner_args) => format!("{runner_args} {trailing}"),
+        None => trailing,
+    }))
+}
+
+/// Parse an environment variable given as `KEY=VALUE`.
+fn parse_env_var(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() && !key.contains(char::is_whitespace) => {
+            Ok((key.into(), value.into()))
         }
+        _ => Err(format!(
+            "invalid environment variable `{value}`, expected KEY=VALUE"
+        )),
+    }
+}
+
+/// Warn about options that have no effect with the configured runner.
+fn warn_ignored_options(config: &RunConfig) {
+    if config.runner != runner::Runner::Pytest && config.pytest_workers.is_some() {
+        eprintln!(
+            "{}: --pytest-workers is ignored when not using the pytest runner.",
+            "Warning".yellow()
+        );
+    }
+    if config.runner != runner::Runner::Pytest && config.pytest_cache_clear {
+        eprintln!(
+            "{}: --pytest-cache-clear is ignored when not using the pytest runner.",
+            "Warning".yellow()
+        );
+    }
+    if config.pytest_workers.is_none() && config.pytest_dist.is_some() {
+        eprintln!(
+            "{}: --pytest-dist is ignored without --pytest-workers.",
+            "Warning".yellow()
+        );
+    }
+    let uses_binary = matches!(config.runner, runner::Runner::Tox | runner::Runner::Nox);
+    if !uses_binary && config.runner_binary.is_some() {
+        eprintln!(
+            "{}: --runner-binary is ignored when not using the tox or nox runner.",
+            "Warning".yellow()
+        );
+    }
+    if config.runner != runner::Runner::Custom {
+        return;
+    }
+    if config.tests != config::DEFAULT_TESTS {
+        eprintln!(
+            "{}: --tests is ignored when using the custom runner.",
+            "Warning".yellow()
+        );
+    }
+    if config.environment.is_some() {
+        eprintln!(
+            "{}: --environment is ignored when using the custom runner.",
+            "Warning".yellow()
+        );
+    }
+}
+
+/// Number of mutants listed as the slowest after a run.
+const SLOWEST_MUTANTS: usize = 10;
+
+/// Print a short overview of the results of a run.
+fn print_summary(summary: &RunSummary) {
+    let score = match summary.score() {
+        Some(score) => format!("{score:.1}%"),
+        None => "n/a".into(),
     };
+    eprintln!(
+        "{} mutants tested in {} ({}): {} {}, {} {}, mutation score {}",
+        summary.total,
+        format_duration(summary.duration),
+        format_rate(summary.total, summary.duration),
+        summary.caught,
+        "caught".green(),
+        summary.missed,
+        "missed".red(),
+        score.yellow(),
+    );
+    for (mutation_type, counts) in &summary.mutation_types {
+        eprintln!("    {mutation_type}: {counts}");
+    }
+    if !summary.phases.is_empty() {
+        let phases: Vec<String> = summary
+            .phases
+            .iter()
+            .map(|(phase, duration)| format!("{phase} {}", format_duration(*duration)))
+            .collect();
+        eprintln!("Time spent on {}", phases.join(", "));
+    }
+    let slowest = summary.slowest(SLOWEST_MUTANTS);
+    if !slowest.is_empty() {
+        eprintln!("{} slowest mutants:", slowest.len());
+        for outcome in slowest {
+            let duration = format_duration(outcome.duration.unwrap_or_default());
+            eprintln!("    {duration:>8}  {}", outcome.mutant);
+        }
+    }
+    if summary.cached > 0 {
+        eprintln!(
+            "{} results were taken from the {} of previous runs and {} mutants were run, use `--rerun-all` to run all mutants",
+            summary.cached,
+            "cache".blue(),
+            summary.outcomes.len() - summary.cached,
+        );
+    }
+    for subproject in &summary.subprojects {
+        eprintln!("    {subproject}");
+    }
+    report_invalid_cache_rows(summary);
+    if summary.errors > 0 {
+        eprintln!(
+            "{} mutants {} (e.g. killed by a signal or tests failing to import) and are excluded from the score",
+            summary.errors,
+            "errored".magenta(),
+        );
+        for outcome in &summary.outcomes {
+            if let Some(error) = &outcome.error {
+                eprintln!("    {}: {}", outcome.mutant, error.red());
+            }
+        }
+    }
+    if summary.no_tests > 0 {
+        eprintln!(
+            "{} mutants had {} and are excluded from the score",
+            summary.no_tests,
+            "no tests collected".yellow(),
+        );
+    }
+    if summary.invalid > 0 {
+        eprintln!(
+            "{} mutants were {} and are excluded from the score",
+            summary.invalid,
+            "not valid python".yellow(),
+        );
+    }
+    if summary.accepted > 0 {
+        eprintln!(
+            "{} surviving mutants were {} and are excluded from the score",
+            summary.accepted,
+            "accepted".blue(),
+        );
+    }
+    if !summary.equivalence_rules.is_empty() {
+        let rules: Vec<String> = summary
+            .equivalence_rules
+            .iter()
+            .map(|(rule, count)| format!("{rule} {count}"))
+            .collect();
+        let excluded = match summary.likely_equivalent > 0 {
+            true => "excluded from",
+            false => "included in",
+        };
+        eprintln!(
+            "{} mutants are {} and {excluded} the score ({})",
+            summary.equivalence_rules.values().sum::<usize>(),
+            "likely equivalent".blue(),
+            rules.join(", "),
+        );
+    }
+    if !summary.stale_verdicts.is_empty() {
+        eprintln!(
+            "{}: {} mutants with a verdict are caught now, consider removing their verdicts with `pymute unmark`: {}",
+            "Note".yellow(),
+            summary.stale_verdicts.len(),
+            summary.stale_verdicts.join(", "),
+        );
+    }
+    if summary.untested > 0 {
+        eprintln!(
+            "{} mutants in {} files without tests were {}",
+            summary.untested,
+            summary.untested_files.len(),
+            "untested".yellow(),
+        );
+    }
+    if summary.uncovered > 0 {
+        eprintln!(
+            "{} mutants on lines no test executes were {}",
+            summary.uncovered,
+            "uncovered".yellow(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arguments, ColorChoice, Command};
+    use clap::{CommandFactory, FromArgMatches};
+    use pymute::config::DEFAULT_MUTATION_TYPES;
+    use pymute::mutants::MutationType;
+    use pymute::runner::OutputLevel;
+    use pymute::RunConfig;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn config_from(args: &[&str]) -> RunConfig {
+        let matches = Arguments::command().get_matches_from(args);
+        let arguments = Arguments::from_arg_matches(&matches).unwrap();
+        match arguments.command {
+            Some(Command::Run(run) | Command::List(run)) => {
+                let (_, matches) = matches.subcommand().unwrap();
+                run.into_config(matches).unwrap()
+            }
+            _ => arguments.run.into_config(&matches).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_color_choice() {
+        assert!(ColorChoice::Auto.colorize(None, true));
+        assert!(ColorChoice::Auto.colorize(Some("".into()), true));
+        assert!(!ColorChoice::Auto.colorize(Some("1".into()), true));
+        assert!(!ColorChoice::Auto.colorize(None, false));
+        assert!(ColorChoice::Always.colorize(Some("1".into()), false));
+        assert!(!ColorChoice::Never.colorize(None, true));
+
+        let matches =
+            Arguments::command().get_matches_from(["pymute", "list", ".", "--color", "never"]);
+        let arguments = Arguments::from_arg_matches(&matches).unwrap();
+        assert_eq!(arguments.color, ColorChoice::Never);
+    }
+
+    #[test]
+    fn test_runner_args() {
+        let config = config_from(&["pymute", "run", ".", "--runner-args", "-x -q"]);
+        assert_eq!(config.runner_args.as_deref(), Some("-x -q"));
+        let config = config_from(&[
+            "pymute",
+            "run",
+            ".",
+            "--runner-args",
+            "-x",
+            "--",
+            "-k",
+            "a and b",
+        ]);
+        assert_eq!(config.runner_args.as_deref(), Some("-x -k 'a and b'"));
+        let config = config_from(&["pymute", ".", "--", "--maxfail", "1"]);
+        assert_eq!(config.runner_args.as_deref(), Some("--maxfail 1"));
+    }
+
+    #[test]
+    fn test_env() {
+        let config = config_from(&[
+            "pymute",
+            "run",
+            ".",
+            "--env",
+            "DJANGO_SETTINGS_MODULE=app.settings",
+            "--env",
+            "DATABASE_URL=postgres://localhost/db?a=b",
+            "--env",
+            "EMPTY=",
+        ]);
+        assert_eq!(config.env["DJANGO_SETTINGS_MODULE"], "app.settings");
+        assert_eq!(config.env["DATABASE_URL"], "postgres://localhost/db?a=b");
+        assert_eq!(config.env["EMPTY"], "");
+
+        for malformed in ["NO_VALUE", "=value", "MY KEY=value"] {
+            let result = Arguments::command()
+                .try_get_matches_from(["pymute", "run", ".", "--env", malformed]);
+            assert!(result.is_err(), "{malformed}");
+        }
+    }
+
+    #[test]
+    fn test_profiles_effective_config() {
+        let standard = config_from(&["pymute", "."]);
+        assert_eq!(standard, RunConfig::new("."));
+        assert_eq!(
+            config_from(&["pymute", ".", "--profile", "standard"]),
+            standard
+        );
+
+        let quick = config_from(&["pymute", ".", "--profile", "quick"]);
+        assert_eq!(quick.max_mutants, Some(100));
+        assert_eq!(quick.diff_base.as_deref(), Some("HEAD"));
+
+        let thorough = config_from(&["pymute", ".", "--profile", "thorough"]);
+        assert_eq!(thorough.max_mutants, None);
+        assert!(thorough.mutation_types.contains(&MutationType::Time));
+
+        let ci = config_from(&["pymute", ".", "--profile", "ci"]);
+        assert_eq!(ci.output_level, OutputLevel::Quiet);
+        assert_eq!(ci.mutation_types, DEFAULT_MUTATION_TYPES.to_vec());
+        assert_eq!(
+            ci.report_json,
+            Some(PathBuf::from("./.pymute/reports/mutants.json"))
+        );
+        assert_eq!(
+            ci.report_junit,
+            Some(PathBuf::from("./.pymute/reports/junit.xml"))
+        );
+        assert_eq!(ci.fail_under, Some(80.0));
+
+        let ci = config_from(&["pymute", ".", "--profile", "ci", "--state-dir", "state"]);
+        assert_eq!(
+            ci.report_json,
+            Some(PathBuf::from("./state/reports/mutants.json"))
+        );
+    }
+
+    #[test]
+    fn test_run_subcommand() {
+        let args = ["--profile", "quick", "--max-mutants", "5", "-m", "src/*.py"];
+        let alias = config_from(&[&["pymute", "."], &args[..]].concat());
+        assert_eq!(alias.max_mutants, Some(5));
+        assert_eq!(alias.output_level, OutputLevel::Missed);
+        assert_eq!(
+            config_from(&[&["pymute", "run", "."], &args[..]].concat()),
+            alias
+        );
+        assert_eq!(
+            config_from(&[&["pymute", "list", "."], &args[..]].concat()),
+            alias
+        );
+    }
+
+    #[test]
+    fn test_explicit_options_override_profile() {
+        let quick = config_from(&["pymute", ".", "--profile", "quick", "--max-mutants", "5"]);
+        assert_eq!(quick.max_mutants, Some(5));
+
+        let thorough = config_from(&[
+            "pymute",
+            ".",
+            "--profile",
+            "thorough",
+            "--mutation-types",
+            "numbers",
+        ]);
+        assert_eq!(thorough.mutation_types, vec![MutationType::Numbers]);
+
+        // explicitly passing the default value also takes precedence
+        let ci = config_from(&["pymute", ".", "--profile", "ci", "--output-level", "missed"]);
+        assert_eq!(ci.output_level, OutputLevel::Missed);
+
+        let ci = config_from(&[
+            "pymute",
+            ".",
+            "--profile",
+            "ci",
+            "--report-json",
+            "report.json",
+            "--fail-under",
+            "50",
+        ]);
+        assert_eq!(ci.report_json, Some(PathBuf::from("report.json")));
+        assert_eq!(ci.fail_under, Some(50.0));
+    }
+
+    #[test]
+    fn test_pyproject_settings() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        fs::write(
+            temp_dir.path().join("pyproject.toml"),
+            "[tool.pymute]\nmax_mutants = 20\noutput_level = \"missed\"\nseed = 7\n",
+        )
+        .unwrap();
+
+        // the file takes precedence over the defaults and the profile
+        let config = config_from(&["pymute", root, "--profile", "ci"]);
+        assert_eq!(config.max_mutants, Some(20));
+        assert_eq!(config.output_level, OutputLevel::Missed);
+        assert_eq!(config.seed, 7);
+
+        // explicit options take precedence over the file
+        let config = config_from(&["pymute", root, "--max-mutants", "5", "--seed", "0"]);
+        assert_eq!(config.max_mutants, Some(5));
+        assert_eq!(config.output_level, OutputLevel::Missed);
+        assert_eq!(config.seed, 0);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_config_file_profiles() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        fs::write(
+            temp_dir.path().join("pymute.toml"),
+            r#"max_mutants = 200
+
+[profile.quick]
+max_mutants = 50
+mutation_types = ["math-ops"]
+
+[profile.full]
+mutation_types = ["math-ops", "comp-ops", "numbers"]
+"#,
+        )
+        .unwrap();
+
+        let quick = config_from(&["pymute", root, "--profile", "quick"]);
+        assert_eq!(quick.max_mutants, Some(50));
+        assert_eq!(quick.mutation_types, vec![MutationType::MathOps]);
+
+        let full = config_from(&["pymute", root, "--profile", "full"]);
+        assert_eq!(full.max_mutants, Some(200));
+        assert_eq!(
+            full.mutation_types,
+            vec![
+                MutationType::MathOps,
+                MutationType::CompOps,
+                MutationType::Numbers
+            ]
+        );
+
+        // built-in profiles without a table only get the top-level options
+        let ci = config_from(&["pymute", root, "--profile", "ci"]);
+        assert_eq!(ci.max_mutants, Some(200));
+        assert_eq!(ci.output_level, OutputLevel::Quiet);
+
+        let quick = config_from(&["pymute", root, "--profile", "quick", "--max-mutants", "5"]);
+        assert_eq!(quick.max_mutants, Some(5));
+
+        temp_dir.close().unwrap();
+    }
 }
@@ -49,7 +49,8 @@
 //! }
 //! ```
 //!
-//! To use this module to find and apply mutations in place (removal is not well-tested and reliable as of yet):
+//! To use this module to find and apply mutations in place, snapshot the file on insertion so that
+//! the original content is restored completely afterwards:
 //!
 //! ```
 //! use pymute::mutants::{find_mutants, MutationType};
@@ -59,8 +60,9 @@
 //! let mutants = find_mutants(glob_pattern, mutation_types).expect("Error finding mutants");
 //!
 //! for mutant in mutants {
-//!     mutant.insert().expect("Error inserting mutant");
-//!     mutant.remove().expect("Error removing mutant")
+//!     let snapshot = mutant.insert().expect("Error inserting mutant");
+//!     // ... run the tests ...
+//!     snapshot.restore().expect("Error restoring file");
 //! }
 //! ```
 //!
@@ -70,21 +72,31 @@
 //! manipulation, and `colored` for enhancing output readability by coloring text.
 //!
 
+use crate::equivalence::{self, EquivalenceRule};
+use crate::error::PymuteError;
+
 use clap::ValueEnum;
 use colored::Colorize;
 use glob::glob;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use std::error::Error;
 use std::fmt;
-use std::fs::{self, File};
-use std::io::{BufRead, BufReader};
+use std::fs;
+use std::io;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
 /// A semantic grouping of different types of possible mutations.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum MutationType {
     /// Mutate mathematical operators (e.g. "*,+,-,/")
     MathOps,
+    /// Mutate modulo, floor division and power operators (e.g. "%,//,**"). Not used
+    /// by default.
+    MathOpsExtended,
     /// Mutate conjunctions in boolean expressions (e.g. "and/or").
     Conjunctions,
     /// Mutate booleans (e.g. "True/False").
@@ -93,8 +105,54 @@ pub enum MutationType {
     ControlFlow,
     /// Mutate comparison operators (e.g. "<,>,==,!=").
     CompOps,
+    /// Mutate the boundaries of comparisons (e.g. "<=/<", ">/>="). Not used by
+    /// default.
+    CompBoundary,
+    /// Mutate bitwise and shift operators (e.g. "&/|", "<</>>"). Not used by
+    /// default.
+    BitwiseOps,
     /// Mutate numbers (e.g. off-by-one errors)
     Numbers,
+    /// Mutate augmented assignments (e.g. "+=,-=,*=,/=").
+    AugAssign,
+    /// Mutate membership tests (e.g. "in/not in").
+    Membership,
+    /// Mutate identity tests (e.g. "is/is not").
+    Identity,
+    /// Remove negations (e.g. "not valid/valid"). Not used by default.
+    NotRemoval,
+    /// Replace None assigned to names or as keyword defaults (e.g.
+    /// "timeout=None/timeout=0"). Not used by default.
+    NoneSwaps,
+    /// Mutate loop control and early returns (e.g. "break/continue", "return/pass").
+    /// Not used by default.
+    LoopControl,
+    /// Replace returned values by None and flip returned booleans (e.g. "return
+    /// x/return None"), in addition to any other mutant of the line. Not used by
+    /// default.
+    ReturnValues,
+    /// Replace string literals by a sentinel (e.g. "\"bad input\"/\"XXPYMUTEXX\""),
+    /// in addition to any other mutant of the line. Docstrings, imports and
+    /// dunder assignments (e.g. `__all__`) are skipped. Not used by default.
+    Strings,
+    /// Swap the first two arguments of calls (e.g. "merge(base, override)"). Not
+    /// used by default.
+    ArgSwap,
+    /// Swap builtins of data processing (e.g. "min(/max(", "any(/all(",
+    /// "reverse=True/reverse=False" in sorting). Not used by default.
+    Builtins,
+    /// Mutate datetime and timedelta arithmetic (e.g. "days=/hours=", "utcnow()/now()").
+    /// Not used by default.
+    Time,
+}
+
+impl fmt::Display for MutationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self
+            .to_possible_value()
+            .expect("no mutation type is skipped");
+        write!(f, "{}", value.get_name())
+    }
 }
 
 /// Find potential python mutants from files that match the glob expression.
@@ -110,39 +168,67 @@ pub enum MutationType {
 pub fn find_mutants(
     glob_expression: &str,
     mutation_types: &[MutationType],
-) -> Result<Vec<Mutant>, Box<dyn Error>> {
+) -> Result<Vec<Mutant>, PymuteError> {
+    find_mutants_in_files(&find_files(glob_expression)?, mutation_types)
+}
+
+/// Find the python files to mutate that match the glob expression.
+///
+/// Test files (test_*.py and *_test.py) are excluded.
+pub fn find_files(glob_expression: &str) -> Result<Vec<PathBuf>, PymuteError> {
+    find_files_matching(&[glob_expression], false)
+}
+
+/// Find the python files matching any of the glob expressions, sorted and without
+/// duplicates.
+///
+/// Test files (test_*.py and *_test.py) are excluded unless `include_tests` is set.
+pub fn find_files_matching(
+    glob_expressions: &[&str],
+    include_tests: bool,
+) -> Result<Vec<PathBuf>, PymuteError> {
+    let mut files = Vec::new();
+    for glob_expression in glob_expressions {
+        files.extend(
+            glob(glob_expression)
+                .map_err(|error| PymuteError::InvalidGlob {
+                    pattern: glob_expression.to_string(),
+                    reason: error.to_string(),
+                })?
+                .filter_map(Result::ok)
+                .filter(|path| include_tests || !is_test_file(path)),
+        );
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Whether `path` is a pytest test file, i.e. named test_*.py or *_test.py.
+fn is_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|file_name| file_name.to_str())
+        .is_some_and(|file_name| file_name.starts_with("test_") || file_name.ends_with("_test.py"))
+}
+
+/// Find potential python mutants in the given files.
+pub fn find_mutants_in_files(
+    files: &[PathBuf],
+    mutation_types: &[MutationType],
+) -> Result<Vec<Mutant>, PymuteError> {
     let mut possible_mutants = Vec::<Mutant>::new();
 
     let replacements = build_replacements(mutation_types);
 
-    for entry in glob(glob_expression).expect("Failed to read glob pattern") {
-        match entry {
-            Ok(path) => {
-                let file_name = match path.file_name() {
-                    Some(f) => f,
-                    None => continue,
-                };
-                let file_name = match file_name.to_str() {
-                    Some(f) => f,
-                    None => continue,
-                };
-                if file_name.starts_with("test_") {
-                    continue;
-                }
-                if file_name.ends_with("_test.py") {
-                    continue;
-                }
-                let _ = add_mutants_from_file(&mut possible_mutants, &path, &replacements);
-            }
-            Err(_e) => {}
-        }
+    for path in files {
+        let _ = add_mutants_from_file(&mut possible_mutants, path, &replacements);
     }
 
     Ok(possible_mutants)
 }
 
 /// Define parameters of a potential mutant for a python program.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Mutant {
     /// Path to python file that can be mutated.
     pub file_path: PathBuf,
@@ -152,11 +238,70 @@ pub struct Mutant {
     pub before: String,
     /// The replacement string.
     pub after: String,
+    /// Byte offset in the line at which `before` starts.
+    pub column: usize,
     /// The line before inserting the mutant.
     old_line: String,
+    /// The rule by which the mutant is likely equivalent to the original code, if
+    /// any, see `equivalence::likely_equivalent`.
+    #[serde(default)]
+    pub likely_equivalent: Option<EquivalenceRule>,
+    /// The mutation type the mutant was found for. Mutants that were not found by
+    /// `find_mutants` have none.
+    #[serde(default)]
+    pub mutation_type: Option<MutationType>,
 }
 
 impl Mutant {
+    /// Create a mutant replacing `before` by `after` at byte offset `column` of
+    /// `old_line`, which is line `line_number` of the file at `file_path`.
+    pub fn new(
+        file_path: PathBuf,
+        line_number: usize,
+        before: impl Into<String>,
+        after: impl Into<String>,
+        column: usize,
+        old_line: impl Into<String>,
+    ) -> Self {
+        Mutant {
+            file_path,
+            line_number,
+            before: before.into(),
+            after: after.into(),
+            column,
+            old_line: old_line.into(),
+            likely_equivalent: None,
+            mutation_type: None,
+        }
+    }
+
+    /// A short identifier of the mutant that stays the same across runs.
+    ///
+    /// The identifier is derived from the path of the file relative to `root`, the
+    /// line number, the content of the line, the column and the replacement, so
+    /// that the same replacement on identical lines of a file gets different ids.
+    /// It does not depend on the indentation of the line.
+    pub fn id(&self, root: &Path) -> String {
+        let relative = self.file_path.strip_prefix(root).unwrap_or(&self.file_path);
+        let relative: Vec<_> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+
+        let indent = self.old_line.len() - self.old_line.trim_start().len();
+
+        let key = format!(
+            "{}\0{}\0{}\0{}\0{}\0{}",
+            relative.join("/"),
+            self.line_number,
+            self.old_line.trim(),
+            self.column.saturating_sub(indent),
+            self.before,
+            self.after
+        );
+        format!("{:012x}", stable_hash(key.as_bytes()) >> 16)
+    }
+
     /// Actually insert the mutant into a file.
     ///
     /// This will take the mutant and insert it in a copy of the python project.
@@ -170,35 +315,23 @@ impl Mutant {
     /// root. The mutant is then inserted into the copied version of the file
     /// where the potential mutant was found (i.e. it will be inserted into
     /// new_root / mutant_file_path_stripped_of_root)
-    pub fn insert_in_new_root(&self, root: &Path, new_root: &Path) -> Result<(), Box<dyn Error>> {
+    pub fn insert_in_new_root(&self, root: &Path, new_root: &Path) -> Result<(), MutantError> {
         let abs_path_file = self
             .file_path
             .canonicalize()
-            .expect("Failed to resolve path to file.");
-        let abs_path_file = abs_path_file.as_path();
-
-        let abs_path_root = root
-            .canonicalize()
-            .expect("Failed to resolve path to root.");
-
-        let abs_path_root = abs_path_root.as_path();
-
-        let file_from_root = abs_path_file.strip_prefix(abs_path_root)?;
+            .map_err(|e| self.io_error(&self.file_path, e))?;
+        let abs_path_root = root.canonicalize().map_err(|e| self.io_error(root, e))?;
+
+        let file_from_root =
+            abs_path_file
+                .strip_prefix(&abs_path_root)
+                .map_err(|_| MutantError::OutsideRoot {
+                    file_path: self.file_path.clone(),
+                    root: root.to_path_buf(),
+                })?;
         let path_to_mutant = new_root.join(file_from_root);
 
-        let file = File::open(&path_to_mutant)?;
-        let reader = BufReader::new(file);
-
-        // read all lines into a vector
-        let mut lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
-        lines[self.line_number - 1] =
-            lines[self.line_number - 1].replace(&self.before, &self.after);
-
-        let last = lines.pop().unwrap();
-        lines.push(format!("{last}\n"));
-        fs::write(&path_to_mutant, lines.join("\n"))
-            .expect("Failed to write to file upon mutant insertion!");
-
+        self.rewrite_line(&path_to_mutant, |line| self.mutate_line(line))?;
         Ok(())
     }
 
@@ -206,45 +339,120 @@ impl Mutant {
     ///
     /// This will attempt to insert the mutant in the related file in the original
     /// python project (i.e. in place/where the mutant was found).
-    pub fn insert(&self) -> Result<(), Box<dyn Error>> {
-        let file_path = self.file_path.as_path();
-        let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
+    ///
+    /// Returns a snapshot of the file as it was before inserting. Restoring it
+    /// brings back the original content of the file unless it was changed by
+    /// someone else (e.g. a formatter) in between, and `remove` uses it to check
+    /// that only the mutant is removed.
+    pub fn insert(&self) -> Result<FileSnapshot, MutantError> {
+        self.rewrite_line(&self.file_path, |line| self.mutate_line(line))
+    }
+
+    /// Replace the line of the mutant in the file at `path` by the result of `f`.
+    ///
+    /// `f` gets the bytes of the line without its line ending. All other bytes of
+    /// the file are kept as they are, including the line endings (`\n` or `\r\n`),
+    /// a missing newline at the end of the file and content that is not valid
+    /// UTF-8 (e.g. a latin-1 comment), so that removing the mutant again restores
+    /// the original file exactly.
+    ///
+    /// Returns a `MutantError::StaleMutant` without writing anything if the file
+    /// no longer has the line, e.g. because it shrank since the mutant was found.
+    /// Otherwise returns a snapshot of the content that was rewritten.
+    fn rewrite_line(
+        &self,
+        path: &Path,
+        f: impl FnOnce(&[u8]) -> Result<Vec<u8>, MutantError>,
+    ) -> Result<FileSnapshot, MutantError> {
+        let content = fs::read(path).map_err(|e| self.io_error(path, e))?;
+        let lines: Vec<&[u8]> = content.split_inclusive(|byte| *byte == b'\n').collect();
+        let index = self
+            .line_number
+            .checked_sub(1)
+            .filter(|index| *index < lines.len())
+            .ok_or_else(|| self.stale(true))?;
+        let start: usize = lines[..index].iter().map(|line| line.len()).sum();
+        let end = start + lines[index].len();
+        let (line, ending) = split_line_ending(lines[index]);
+        let line = f(line)?;
+
+        let rewritten = [&content[..start], &line, ending, &content[end..]].concat();
+        fs::write(path, &rewritten).map_err(|e| self.io_error(path, e))?;
+        Ok(FileSnapshot {
+            path: path.to_path_buf(),
+            line_number: self.line_number,
+            inserted_hash: stable_hash(&rewritten),
+            original: content,
+        })
+    }
 
-        // read all lines into a vector
-        let mut lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
-        lines[self.line_number - 1] =
-            lines[self.line_number - 1].replace(&self.before, &self.after);
+    /// Replace `before` by `after` exactly at the mutant's column, so that only
+    /// the intended occurrence of `before` is replaced.
+    ///
+    /// Returns an error if `line` does not contain `before` at the column, e.g.
+    /// because the file changed since the mutant was found.
+    fn mutate_line(&self, line: &[u8]) -> Result<Vec<u8>, MutantError> {
+        let end = self.column + self.before.len();
+        match line.get(self.column..end) {
+            Some(found) if found == self.before.as_bytes() => {
+                Ok([&line[..self.column], self.after.as_bytes(), &line[end..]].concat())
+            }
+            _ => Err(self.stale(false)),
+        }
+    }
 
-        let last = lines.pop().unwrap();
-        lines.push(format!("{last}\n"));
-        fs::write(file_path, lines.join("\n"))
-            .expect("Failed to write to file upon mutant insertion!");
+    /// The error for the file at `path` of the mutant failing with `source`.
+    fn io_error(&self, path: &Path, source: io::Error) -> MutantError {
+        MutantError::Io {
+            path: path.to_path_buf(),
+            line_number: self.line_number,
+            source,
+        }
+    }
 
-        Ok(())
+    /// The error for the file of the mutant having changed, such that its line is
+    /// `missing_line` or no longer contains `before`.
+    fn stale(&self, missing_line: bool) -> MutantError {
+        MutantError::StaleMutant {
+            file_path: self.file_path.clone(),
+            line_number: self.line_number,
+            column: self.column,
+            before: self.before.clone(),
+            missing_line,
+        }
     }
 
     /// Remove the mutant.
     ///
-    /// Remove a mutant from the original file after it has been inserted in place.
-    /// This method is not well tested and in general the temporary directory
-    /// workflow should be preferred over in place operations at the moment.
-    pub fn remove(&self) -> Result<(), Box<dyn Error>> {
-        let file_path = self.file_path.as_path();
-        let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
-
-        // read all lines into a vector
-        let mut lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
-        // revert the insert
-        lines[self.line_number - 1] = self.old_line.clone();
-
-        let last = lines.pop().unwrap();
-        lines.push(format!("{last}\n"));
-        fs::write(file_path, lines.join("\n"))
-            .expect("Failed to write to file upon mutant removal!");
-
-        Ok(())
+    /// Remove a mutant from the original file after it has been inserted in place
+    /// with `insert`, which returned the `snapshot`. Only the mutated line is
+    /// restored, see `FileSnapshot::restore` for restoring the whole file.
+    ///
+    /// Returns a `MutantError::NotInserted` without writing anything if the line
+    /// of the mutant is not the mutated line, e.g. because the file changed or
+    /// the mutant was already removed. The restored file is checked to be the same
+    /// as the snapshot, and a `MutantError::ChangedFile` is returned otherwise.
+    pub fn remove(&self, snapshot: &FileSnapshot) -> Result<(), MutantError> {
+        self.rewrite_line(&self.file_path, |line| {
+            let mutated = self.mutate_line(self.old_line.as_bytes())?;
+            match line == mutated.as_slice() {
+                true => Ok(self.old_line.clone().into_bytes()),
+                false => Err(MutantError::NotInserted {
+                    file_path: self.file_path.clone(),
+                    line_number: self.line_number,
+                    expected: String::from_utf8_lossy(&mutated).into_owned(),
+                    found: String::from_utf8_lossy(line).into_owned(),
+                }),
+            }
+        })?;
+        let content = fs::read(&self.file_path).map_err(|e| self.io_error(&self.file_path, e))?;
+        match content == snapshot.original {
+            true => Ok(()),
+            false => Err(MutantError::ChangedFile {
+                file_path: self.file_path.clone(),
+                line_number: self.line_number,
+            }),
+        }
     }
 }
 
@@ -267,221 +475,1458 @@ impl fmt::Display for Mutant {
                 .expect("Failed to convert file path to string!")
                 .yellow(),
             self.line_number.to_string().yellow(),
-        )
+        )?;
+        match self.mutation_type {
+            Some(mutation_type) => write!(f, " ({mutation_type})"),
+            None => Ok(()),
+        }
     }
 }
 
-/// Search for potential mutants in a file given some replacements.
-/// The replacement tuples in the Vec give the (before, after) string
-/// values i.e. before can be replaced by after.
-fn add_mutants_from_file(
-    mutant_vec: &mut Vec<Mutant>,
-    path: &PathBuf,
-    replacements: &[(String, String)],
-) -> Result<(), Box<dyn Error>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
+/// Content of a file before a mutant was inserted in place.
+#[derive(Debug)]
+pub struct FileSnapshot {
+    path: PathBuf,
+    /// Line of the inserted mutant.
+    line_number: usize,
+    original: Vec<u8>,
+    /// Hash of the file right after inserting the mutant.
+    inserted_hash: u64,
+}
 
-    let mut in_docstring = false;
-    let docstring_markers = ["\"\"\"", "'''"];
+impl FileSnapshot {
+    /// Write the original content back to the file.
+    ///
+    /// If the file was modified by a third party since the mutant was inserted, it
+    /// is left as it is for inspection and the original content is written next
+    /// to it instead (see `original_path`). Fails with `PymuteError::NotRestored`
+    /// in that case or if the file cannot be written.
+    pub fn restore(self) -> Result<(), PymuteError> {
+        let not_restored = |reason: String| PymuteError::NotRestored {
+            file: self.path.clone(),
+            line_number: self.line_number,
+            reason,
+        };
+        let modified = match fs::read(&self.path) {
+            Ok(current) => stable_hash(&current) != self.inserted_hash,
+            Err(_) => true,
+        };
+        if modified {
+            let original = self.original_path();
+            let reason = match fs::write(&original, &self.original) {
+                Ok(()) => format!(
+                    "it was modified while the mutant was inserted, its original content is in {}",
+                    original.display()
+                ),
+                Err(error) => format!(
+                    "it was modified while the mutant was inserted, and its original content could not be written to {}: {error}",
+                    original.display()
+                ),
+            };
+            return Err(not_restored(reason));
+        }
+        fs::write(&self.path, &self.original).map_err(|error| not_restored(error.to_string()))
+    }
 
-    for (line_nr, line_result) in reader.lines().enumerate() {
-        // ignore comments
-        let line = line_result?;
+    /// Path the original content is written to if the file cannot be restored,
+    /// e.g. `app.py.pymute-original` for `app.py`.
+    pub fn original_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".pymute-original");
+        self.path.with_file_name(name)
+    }
+}
 
-        if docstring_markers
-            .iter()
-            .any(|&marker| line.matches(marker).count() == 2)
-        {
-            continue;
-        }
+/// Split `line` into its content and its line ending, i.e. `\r\n`, `\n` or
+/// nothing for the last line of a file without a newline at the end.
+///
+/// Like `BufRead::lines`, a `\r` is only part of the line ending if followed by a
+/// `\n`.
+fn split_line_ending(line: &[u8]) -> (&[u8], &[u8]) {
+    let content = line
+        .strip_suffix(b"\r\n")
+        .or_else(|| line.strip_suffix(b"\n"))
+        .unwrap_or(line);
+    line.split_at(content.len())
+}
 
-        if docstring_markers
-            .iter()
-            .any(|&marker| line.contains(marker))
-        {
-            in_docstring = !in_docstring;
-        }
-        if line.starts_with('#') {
-            continue;
-        }
+/// FNV-1a hash of `bytes`, which unlike `std::hash::DefaultHasher` is guaranteed
+/// to be stable across runs and versions of Rust. Used for all hashes of file
+/// content, including those written to disk.
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
 
-        if in_docstring {
-            continue;
-        }
+/// Why a mutant could not be inserted into or removed from its file.
+#[derive(Debug)]
+pub enum MutantError {
+    /// A file of the mutant (or the root) could not be resolved, read or written.
+    Io {
+        path: PathBuf,
+        line_number: usize,
+        source: io::Error,
+    },
+    /// The file of the mutant is not below the root of the project.
+    OutsideRoot { file_path: PathBuf, root: PathBuf },
+    /// The line of the mutant no longer exists, or no longer contains `before` at
+    /// the column of the mutant, i.e. the file changed since it was found.
+    StaleMutant {
+        file_path: PathBuf,
+        line_number: usize,
+        column: usize,
+        before: String,
+        missing_line: bool,
+    },
+    /// The line of a mutant to remove is `found` instead of the mutated line
+    /// `expected`, so the file was left untouched.
+    NotInserted {
+        file_path: PathBuf,
+        line_number: usize,
+        expected: String,
+        found: String,
+    },
+    /// After removing a mutant, its file differs from its content before the
+    /// mutant was inserted.
+    ChangedFile {
+        file_path: PathBuf,
+        line_number: usize,
+    },
+}
 
-        // also only consider stuff on left of comment
-        let line_split = line.split('#').collect::<Vec<_>>()[0];
-        let replacement = replacement_from_line(line_split, replacements);
-        match replacement {
-            Some((before, after)) => {
-                let mutant = Mutant {
-                    file_path: path.clone(),
-                    line_number: line_nr + 1,
-                    before,
-                    after,
-                    old_line: line,
-                };
-                mutant_vec.push(mutant);
-            }
+impl Error for MutantError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MutantError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
-            None => continue,
-        };
+impl fmt::Display for MutantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MutantError::Io {
+                path,
+                line_number,
+                source,
+            } => write!(
+                f,
+                "Could not access {} for the mutant on line {line_number}: {source}",
+                path.display()
+            ),
+            MutantError::OutsideRoot { file_path, root } => write!(
+                f,
+                "The file {} of the mutant is not below the root {}",
+                file_path.display(),
+                root.display()
+            ),
+            MutantError::StaleMutant {
+                file_path,
+                line_number,
+                missing_line: true,
+                ..
+            } => write!(
+                f,
+                "Line {line_number} does not exist in {}. Has the file changed?",
+                file_path.display()
+            ),
+            MutantError::StaleMutant {
+                file_path,
+                line_number,
+                column,
+                before,
+                missing_line: false,
+            } => write!(
+                f,
+                "Expected {before:?} at line {line_number}, column {column} of {}. Has the file changed?",
+                file_path.display()
+            ),
+            MutantError::NotInserted {
+                file_path,
+                line_number,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Cannot remove the mutant on line {line_number} of {}: expected the mutated line {expected:?}, found {found:?}. The file was left untouched, has it changed or was the mutant already removed?",
+                file_path.display()
+            ),
+            MutantError::ChangedFile {
+                file_path,
+                line_number,
+            } => write!(
+                f,
+                "{} differs from its original content after removing the mutant on line {line_number}. Was it modified while the mutant was inserted?",
+                file_path.display()
+            ),
+        }
     }
-    Ok(())
 }
 
-/// Remove quotes so that python strings are ignored.
-fn remove_quotes(input: &str) -> String {
-    let re = Regex::new(r#"'[^']*'|"[^"]*""#).unwrap();
-    re.replace_all(input, "").to_string()
+/// A possible replacement of `before` by `after` in a line.
+#[derive(Debug, Clone)]
+struct Replacement {
+    before: String,
+    after: String,
+    /// How `before` is matched in a line.
+    kind: MatchKind,
+    /// Compiled pattern for keyword (word boundaries) and number (literals)
+    /// replacements.
+    pattern: Option<Regex>,
+    /// Only replace `before` where it appears inside a call to this function
+    /// (e.g. `days=` only inside `timedelta(...)`).
+    within_call: Option<&'static str>,
+    /// Do not replace `before` in the header of a `for` loop or comprehension
+    /// (e.g. `in` in `for x in items:`).
+    outside_for_header: bool,
+    /// Only replace a `not` that negates an expression, i.e. not the `not` of
+    /// `is not` and `not in`.
+    negation_only: bool,
+    /// Do not replace `before` in type annotations (e.g. `|` in `int | None`).
+    outside_annotation: bool,
+    /// The mutation type the replacement belongs to, see `build_replacements`.
+    mutation_type: Option<MutationType>,
 }
 
-/// Find a before/after replacement tuple in `line`. Possible tuples are
-/// specified in `replacements`.
-///If no possible replacement is found, it returns None.
-fn replacement_from_line(
-    line: &str,
-    replacements: &[(String, String)],
-) -> Option<(String, String)> {
-    let line = remove_quotes(line);
+/// How the `before` string of a replacement is matched in a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MatchKind {
+    /// Match the exact string anywhere.
+    Literal,
+    /// Match a binary operator with or without surrounding whitespace, but not as
+    /// part of another operator (e.g. `+` in `+=`) or as a unary operator.
+    Operator,
+    /// Match a keyword (or keywords) on word boundaries.
+    Keyword,
+    /// Match a whole number literal, but not as part of an identifier or of an
+    /// assignment to a dunder (e.g. `__version__`), and replace it by the literal
+    /// with its integer part incremented (e.g. `10` by `11`, `0.5` by `1.5`).
+    Number,
+    /// Match the argument list of a call with at least two simple arguments and
+    /// replace it by the list with the first two arguments swapped.
+    ArgSwap,
+    /// Match a keyword that is a whole statement on its own (e.g. an indented
+    /// `break`).
+    Statement,
+    /// Match `None` assigned by a single `=` (e.g. `x = None` or `timeout=None`),
+    /// but not compared (e.g. `x == None`), and replace it by `0`.
+    NoneAssignment,
+    /// Match the whole value of a `return` statement and replace it by `None`, or
+    /// a returned boolean by its negation.
+    ReturnValue,
+    /// Match the first string literal and replace its content by
+    /// `STRING_SENTINEL`, or an empty string by `"XX"`.
+    StringLiteral,
+}
 
-    replacements
-        .iter()
-        .find(|(from, _)| line.contains(from))
-        .map(|(from, to)| (from.into(), to.into()))
+impl MatchKind {
+    /// Whether a match is a mutant in addition to the single mutant chosen for a
+    /// line by `replacement_from_line`.
+    fn is_additional(self) -> bool {
+        matches!(self, MatchKind::ReturnValue | MatchKind::StringLiteral)
+    }
 }
 
-/// Build a Vec of before/after replacement tuples from the specified types of
-/// mutations.
-fn build_replacements(mutation_types: &[MutationType]) -> Vec<(String, String)> {
-    let mut replacements = Vec::new();
+impl Replacement {
+    fn new(before: &str, after: &str, kind: MatchKind) -> Self {
+        let pattern = match kind {
+            MatchKind::Keyword => {
+                let token = before.trim();
+                let end = match token.ends_with(|c: char| c.is_alphanumeric()) {
+                    true => r"\b",
+                    false => "",
+                };
+                Regex::new(&format!(r"\b{}{end}", regex::escape(token))).ok()
+            }
+            MatchKind::Number => Regex::new(NUMBER_LITERAL).ok(),
+            MatchKind::ReturnValue => Regex::new(RETURN_VALUE).ok(),
+            MatchKind::NoneAssignment => Regex::new(NONE_ASSIGNMENT).ok(),
+            _ => None,
+        };
+        Replacement {
+            before: before.into(),
+            after: after.into(),
+            kind,
+            pattern,
+            within_call: None,
+            outside_for_header: false,
+            negation_only: false,
+            outside_annotation: false,
+            mutation_type: None,
+        }
+    }
 
-    let mut numbers = Vec::new();
-    for n in 0..10 {
-        numbers.push((n.to_string(), (n + 1).to_string()));
+    /// Only allow the replacement inside calls to `function`.
+    fn within_call(mut self, function: &'static str) -> Self {
+        self.within_call = Some(function);
+        self
     }
 
-    mutation_types
-        .iter()
-        .for_each(|mutation_type| match mutation_type {
-            MutationType::MathOps => {
-                replacements.append(&mut vec![
-                    (" + ".into(), " - ".into()),
-                    (" - ".into(), " + ".into()),
-                    (" * ".into(), " / ".into()),
-                    (" / ".into(), " * ".into()),
-                ]);
-            }
-            MutationType::Conjunctions => {
-                replacements.append(&mut vec![
-                    (" and ".into(), " or ".into()),
-                    (" or ".into(), " and ".into()),
-                ]);
+    /// Do not allow the replacement in the header of `for` loops and
+    /// comprehensions.
+    fn outside_for_header(mut self) -> Self {
+        self.outside_for_header = true;
+        self
+    }
+
+    /// Only allow the replacement of a `not` negating an expression.
+    fn negation_only(mut self) -> Self {
+        self.negation_only = true;
+        self
+    }
+
+    /// Do not allow the replacement in type annotations.
+    fn outside_annotation(mut self) -> Self {
+        self.outside_annotation = true;
+        self
+    }
+
+    /// Find the first match of this replacement in `line` that satisfies its
+    /// context requirements.
+    ///
+    /// Returns the byte offset of the match together with the matched string and
+    /// its replacement. If `before` is padded with spaces (e.g. `" + "`), the match
+    /// includes the spaces adjacent to the operator or keyword where present, so
+    /// that `a + b` and `a+b` are both matched.
+    fn find_in(&self, line: &str) -> Option<(usize, String, String)> {
+        let token = self.before.trim();
+        let candidates: Vec<usize> = match self.kind {
+            MatchKind::Literal => line
+                .match_indices(self.before.as_str())
+                .map(|(column, _)| column)
+                .collect(),
+            MatchKind::Operator => line
+                .match_indices(token)
+                .map(|(column, _)| column)
+                .filter(|&column| is_binary_operator(line, column, token.len()))
+                .collect(),
+            MatchKind::Keyword => self
+                .pattern
+                .as_ref()?
+                .find_iter(line)
+                .map(|m| m.start())
+                .collect(),
+            MatchKind::Number => return self.find_number_in(line),
+            MatchKind::ArgSwap => return find_arg_swap(line),
+            MatchKind::ReturnValue => return self.find_return_value_in(line),
+            MatchKind::NoneAssignment => {
+                let none = self.pattern.as_ref()?.captures(line)?.get(1)?;
+                return Some((none.start(), none.as_str().into(), self.after.clone()));
             }
-            MutationType::Booleans => {
-                replacements.append(&mut vec![
-                    (" True ".into(), " False ".into()),
-                    (" False ".into(), " True ".into()),
-                ]);
+            // needs the line with its strings, see `find_string_literal_in`
+            MatchKind::StringLiteral => return None,
+            MatchKind::Statement => {
+                let statement = line.trim_start();
+                match statement.trim_end() == self.before {
+                    true => vec![line.len() - statement.len()],
+                    false => vec![],
+                }
             }
-            MutationType::ControlFlow => {
-                replacements.append(&mut vec![
-                    (" else: ".into(), " elif False: ".into()),
-                    (" if not ".into(), " if ".into()),
-                    (" if ".into(), " if not ".into()),
-                ]);
+        };
+
+        let column = candidates
+            .into_iter()
+            .filter(|&column| !(self.outside_for_header && in_for_header(line, column)))
+            .filter(|&column| !self.negation_only || is_negation(line, column))
+            .filter(|&column| !(self.outside_annotation && in_annotation(line, column)))
+            .find(|&column| match self.within_call {
+                Some(function) => inside_call(line, column, function),
+                None => true,
+            })?;
+
+        if matches!(self.kind, MatchKind::Literal | MatchKind::Statement) {
+            return Some((column, self.before.clone(), self.after.clone()));
+        }
+
+        // keep the spaces around the match if the replacement asks for them
+        let mut start = column;
+        let mut end = column + token.len();
+        let (mut left, mut right) = ("", "");
+        if self.before.starts_with(' ') && line[..start].ends_with(' ') {
+            start -= 1;
+            left = " ";
+        }
+        if self.before.ends_with(' ') && line[end..].starts_with(' ') {
+            end += 1;
+            // a removed keyword leaves a single space behind
+            if !self.after.trim().is_empty() {
+                right = " ";
             }
-            MutationType::CompOps => {
-                replacements.append(&mut vec![
-                    (" > ".into(), " < ".into()),
-                    (" < ".into(), " > ".into()),
-                    ("==".into(), "!=".into()),
-                    ("!=".into(), "==".into()),
-                ]);
+        }
+        Some((
+            start,
+            line[start..end].to_string(),
+            format!("{left}{}{right}", self.after.trim()),
+        ))
+    }
+
+    /// Find the value returned in `line` and its replacement.
+    ///
+    /// Values that are already `None` and values continued on the next line are
+    /// skipped.
+    fn find_return_value_in(&self, line: &str) -> Option<(usize, String, String)> {
+        let value = self.pattern.as_ref()?.captures(line)?.get(1)?;
+        let after = match value.as_str() {
+            "" | "None" => return None,
+            "True" => "False",
+            "False" => "True",
+            _ => "None",
+        };
+
+        let mut depth = 0;
+        for byte in value.as_str().bytes() {
+            match byte {
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => depth -= 1,
+                _ => {}
             }
-            MutationType::Numbers => replacements.append(&mut numbers),
-        });
+        }
+        if depth != 0 || value.as_str().ends_with('\\') {
+            return None;
+        }
+        Some((value.start(), value.as_str().into(), after.into()))
+    }
 
-    replacements
+    /// Find the first standalone number literal in `line` and its increment.
+    fn find_number_in(&self, line: &str) -> Option<(usize, String, String)> {
+        if is_dunder_assignment(line) {
+            return None;
+        }
+        let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+        self.pattern.as_ref()?.find_iter(line).find_map(|m| {
+            let standalone = !line[..m.start()].ends_with(|c: char| is_identifier(c) || c == '.')
+                && !line[m.end()..].starts_with(is_identifier);
+            match standalone {
+                true => Some((m.start(), m.as_str().into(), increment_number(m.as_str())?)),
+                false => None,
+            }
+        })
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::mutants::{self, build_replacements, MutationType};
-    use colored::Colorize;
-    use std::{
-        fs::{self, read_to_string, File},
-        io::Write,
-    };
-    use tempfile::{tempdir, NamedTempFile};
+/// Content string literals are replaced by.
+const STRING_SENTINEL: &str = "XXPYMUTEXX";
 
-    #[test]
-    fn test_find_mutants() {
-        let temp_dir = tempdir().unwrap();
-        let base_path = temp_dir.path();
+/// Find the first string literal of `line` that can be replaced, given `code`,
+/// which is `line` with its strings masked (see `mask_strings`).
+///
+/// The match is the whole literal including its prefix and quotes, and so is its
+/// replacement, e.g. `f"hello {name}"` and `f"XXPYMUTEXX"`. Lines that are nothing
+/// but strings (e.g. docstrings), imports and dunder assignments (e.g. `__all__`)
+/// are skipped, as are literals that do not end on the line.
+fn find_string_literal_in(code: &str, line: &str) -> Option<(usize, String, String)> {
+    let statement = code.trim_start();
+    if statement
+        .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+        .is_empty()
+        || statement.starts_with("import ")
+        || statement.starts_with("from ")
+        || is_dunder_assignment(code)
+    {
+        return None;
+    }
+    // the line is scanned from its start, which must not be inside a string
+    // continued from a previous line
+    if mask_strings(line, &mut None) != code {
+        return None;
+    }
 
-        let multiline_string_script_1 = "def add(a, b):
-    return a + b
+    let bytes = line.as_bytes();
+    let i = bytes
+        .iter()
+        .position(|byte| matches!(byte, b'"' | b'\'' | b'#'))
+        .filter(|&i| bytes[i] != b'#')?;
+    let start = i - string_prefix_len(&bytes[..i]);
+    let quote = delimiter_at(&bytes[i..]);
+    let string = OpenString {
+        delimiter: quote,
+        formatted: false,
+    };
+    let end = mask_string_body(bytes, &mut bytes.to_vec(), i + quote.len(), string)?;
 
-# this is a + comment
-def sub(a, b):
-    return a - b
+    let sentinel = match end - i == 2 * quote.len() {
+        true => "XX",
+        false => STRING_SENTINEL,
+    };
+    let after = format!("{}{quote}{sentinel}{quote}", &line[start..i]);
+    Some((start, line[start..end].into(), after))
+}
 
-res = sub(5, 6) * add(7, 8)
-print(res) # print the result *
-";
+/// Pattern of `None` assigned by a single `=`, capturing the `None`. The character
+/// before the `=` must not make it part of another operator (e.g. `==`, `<=` or
+/// `:=`).
+const NONE_ASSIGNMENT: &str = r"(?:^|[^=!<>+\-*/%&|^@:])=[ \t]*(None)\b";
 
-        let multiline_string_script_2 = "def div(a, b):
-    return a / b
+/// Pattern of a `return` statement, capturing its value.
+const RETURN_VALUE: &str = r"^\s*return\s+(.*?)\s*$";
 
-# this is a + comment
-def mul(a, b):
-    return a * b
+/// Pattern of decimal integer, float and imaginary literals.
+const NUMBER_LITERAL: &str =
+    r"(?:[0-9][0-9_]*(?:\.[0-9_]*)?|\.[0-9][0-9_]*)(?:[eE][+-]?[0-9][0-9_]*)?[jJ]?";
 
-res = div(5, 6) - mul(7, 8)
-print(res) # print the result +
-";
-        let multiline_string_script_3 = "def print_number(a, b):
-    res = a + b
-    print(\"a + b = {res}\")
+/// Increment the integer part of a number literal, e.g. `9` to `10`, `.5` to
+/// `1.5` or `1e-5` to `2e-5`.
+///
+/// Returns None if the integer part is too large.
+fn increment_number(literal: &str) -> Option<String> {
+    let end = literal
+        .find(|c: char| !(c.is_ascii_digit() || c == '_'))
+        .unwrap_or(literal.len());
+    let (integer, rest) = literal.split_at(end);
+    let integer: u128 = match integer.replace('_', "").as_str() {
+        "" => 0,
+        digits => digits.parse().ok()?,
+    };
+    Some(format!("{}{rest}", integer.checked_add(1)?))
+}
 
-# this is a + comment
+/// Find the first call in `line` whose first two positional arguments are simple
+/// (e.g. names, attributes or numbers) and swap them, e.g. `merge(base, override)`
+/// to `merge(override, base)`.
+///
+/// Returns the byte offset of the argument list together with the argument list
+/// and the argument list with the first two arguments swapped. Arguments with
+/// nested brackets, keyword and unpacked arguments, calls that are not closed on
+/// the line or contain string literals, and function and class definitions are
+/// skipped.
+fn find_arg_swap(line: &str) -> Option<(usize, String, String)> {
+    let stripped = line.trim_start();
+    if ["def ", "async def ", "class "]
+        .iter()
+        .any(|keyword| stripped.starts_with(keyword))
+    {
+        return None;
+    }
 
-";
+    let is_simple = |argument: &str| {
+        !argument.trim().is_empty()
+            && !argument.contains(['(', ')', '[', ']', '{', '}', '=', '*', ':', '\0'])
+    };
+    line.match_indices('(').find_map(|(open, _)| {
+        // a call directly follows a name or a closing bracket, but not a keyword
+        // (e.g. `if (a, b)`)
+        let callee = &line[..open];
+        let name = callee
+            .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .next()
+            .unwrap_or_default();
+        if !callee.ends_with(|c: char| c.is_alphanumeric() || "_)]".contains(c))
+            || NON_OPERAND_KEYWORDS.contains(&name)
+        {
+            return None;
+        }
 
-        let multiline_string_script_test_1 = "def print_number(a, b):
-    res = a + b
-    print(\"a + b = {res}\")
+        let start = open + 1;
+        let (arguments, end) = split_arguments(&line[start..])?;
+        let before = &line[start..start + end];
+        let (first, second) = (&before[arguments[0].clone()], &before[arguments[1].clone()]);
+        if before.contains('\0')
+            || !is_simple(first)
+            || !is_simple(second)
+            || first.trim() == second.trim()
+        {
+            return None;
+        }
 
-# this is a + comment
+        // swap the arguments but keep the whitespace around them
+        let token = |range: &Range<usize>| {
+            let argument = &before[range.clone()];
+            let start = range.start + argument.len() - argument.trim_start().len();
+            start..start + argument.trim().len()
+        };
+        let (first, second) = (token(&arguments[0]), token(&arguments[1]));
+        let after = format!(
+            "{}{}{}{}{}",
+            &before[..first.start],
+            &before[second.clone()],
+            &before[first.end..second.start],
+            &before[first],
+            &before[second.end..]
+        );
+        Some((start, before.to_string(), after))
+    })
+}
 
-";
-        let multiline_string_script_test_2 = "def print_number(a, b):
-    res = a + b
-    print(\"a + b = {res}\")
+/// Split the arguments of a call, starting right after its opening parenthesis,
+/// at the commas outside of nested brackets.
+///
+/// Returns the byte ranges of the arguments and the offset of the closing
+/// parenthesis, or None if the call is not closed or has fewer than two
+/// arguments.
+fn split_arguments(arguments: &str) -> Option<(Vec<Range<usize>>, usize)> {
+    let mut ranges = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, byte) in arguments.bytes().enumerate() {
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' if depth == 0 => {
+                ranges.push(start..i);
+                return match ranges.len() >= 2 {
+                    true => Some((ranges, i)),
+                    false => None,
+                };
+            }
+            b')' | b']' | b'}' => depth -= 1,
+            b',' if depth == 0 => {
+                ranges.push(start..i);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
 
-# this is a + comment
+/// Whether `line` assigns to a dunder name, e.g. `__version__ = (1, 2)`.
+fn is_dunder_assignment(line: &str) -> bool {
+    let Some(name) = line.trim_start().strip_prefix("__") else {
+        return false;
+    };
+    let end = name
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(name.len());
+    let rest = name[end..].trim_start();
+    name[..end].ends_with("__") && rest.starts_with('=') && !rest.starts_with("==")
+}
 
-";
+impl From<(&str, &str)> for Replacement {
+    fn from((before, after): (&str, &str)) -> Self {
+        Replacement::new(before, after, MatchKind::Literal)
+    }
+}
 
-        // creating a nested directory structure
-        let sub_dir1 = base_path.join("dir1");
-        let sub_dir1_1 = sub_dir1.join("dir1_1");
-        let sub_dir1_1_1 = sub_dir1_1.join("dir1_1_1");
+/// Characters that can be part of a python operator.
+const OPERATOR_CHARS: &str = "+-*/%@<>=!&|^~:";
+
+/// Keywords after which an operator is unary (e.g. `return -1`) or not an
+/// operator at all (e.g. `lambda *args`, `import *`).
+const NON_OPERAND_KEYWORDS: [&str; 14] = [
+    "return", "yield", "lambda", "import", "in", "not", "and", "or", "if", "else", "is", "await",
+    "assert", "del",
+];
+
+/// Check whether the operator of length `len` at byte offset `column` of `line`
+/// is a binary operator on its own, i.e. it is not part of a longer operator and
+/// has an operand on both sides.
+fn is_binary_operator(line: &str, column: usize, len: usize) -> bool {
+    let before = &line[..column];
+    let after = &line[column + len..];
+
+    // part of a longer operator, e.g. `+=`, `**`, `->` or `<=`
+    if before.ends_with(|c| OPERATOR_CHARS.contains(c))
+        || after.starts_with(|c| OPERATOR_CHARS.contains(c))
+    {
+        return false;
+    }
 
-        // ensure all directories are created
-        fs::create_dir_all(&sub_dir1_1_1).unwrap();
+    // there has to be an operand on the right
+    let after = after.trim_start();
+    if after.is_empty() || after.starts_with(['\\', '#']) {
+        return false;
+    }
 
-        let script1 = sub_dir1.join("script1.py");
-        let mut script1 = File::create(script1).unwrap();
+    // the left operand has to end in a name, a number, a closing bracket or a
+    // string (which is masked by NUL bytes)
+    let before = before.trim_end();
+    let Some(last) = before.chars().last() else {
+        return false;
+    };
+    if !(last.is_alphanumeric() || "_)]}.\0".contains(last)) {
+        return false;
+    }
+    // exponents of float literals like `1e-5`
+    if before.ends_with(['e', 'E'])
+        && before[..before.len() - 1].ends_with(|c: char| c.is_ascii_digit())
+    {
+        let word_start = before
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+            .map_or(0, |i| i + 1);
+        if before[word_start..].starts_with(|c: char| c.is_ascii_digit()) {
+            return false;
+        }
+    }
+    let last_word = before
+        .rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or_default();
+    !NON_OPERAND_KEYWORDS.contains(&last_word)
+}
 
-        write!(script1, "{}", multiline_string_script_1)
-            .expect("Failed to write to temporary file");
+/// Check whether the `not` at byte offset `column` of `line` negates an
+/// expression, i.e. it is not part of `is not` or `not in`.
+fn is_negation(line: &str, column: usize) -> bool {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    let before = line[..column].trim_end();
+    let after = line[column + "not".len()..].trim_start();
+    let is_not = before
+        .strip_suffix("is")
+        .is_some_and(|rest| !rest.ends_with(is_identifier));
+    let not_in = after
+        .strip_prefix("in")
+        .is_some_and(|rest| !rest.starts_with(is_identifier));
+    !is_not && !not_in
+}
 
-        // create a decoy txt file that should not be matched
-        let decoy = base_path.join("script1.txt");
-        let mut decoy = File::create(decoy).unwrap();
+/// Check whether byte offset `column` of `line` lies within a type annotation, where
+/// `|` is the union of types (e.g. `int | None`) rather than a bitwise or.
+///
+/// Whole `def` lines (including default values of parameters) and everything
+/// after a `->` count as annotations, as does everything between the colon after a
+/// name and an assigned value (e.g. `x: int | None = None`, also for parameters on
+/// lines of their own).
+fn in_annotation(line: &str, column: usize) -> bool {
+    let before = &line[..column];
+    let statement = before.trim_start();
+    if statement.starts_with("def ") || statement.starts_with("async def ") || before.contains("->")
+    {
+        return true;
+    }
+
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    let name_end = statement
+        .find(|c: char| !is_identifier(c))
+        .unwrap_or(statement.len());
+    let name = &statement[..name_end];
+    if name.is_empty() || ["else", "try", "finally", "lambda"].contains(&name) {
+        return false;
+    }
+    let Some(annotation) = statement[name_end..].trim_start().strip_prefix(':') else {
+        return false;
+    };
+    // an assignment (or `:=`) ends the annotation, comparisons like `==` do not
+    let bytes = annotation.as_bytes();
+    !(0..bytes.len()).any(|i| {
+        bytes[i] == b'='
+            && (i == 0 || !OPERATOR_CHARS.as_bytes().contains(&bytes[i - 1]))
+            && bytes.get(i + 1) != Some(&b'=')
+    })
+}
+
+/// Check whether byte offset `column` of `line` lies within the header of a `for`
+/// loop or comprehension before its iterable, e.g. the `in` of `for x in items:`.
+///
+/// Everything after an `if` of a comprehension is a condition again.
+fn in_for_header(line: &str, column: usize) -> bool {
+    let is_identifier = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80;
+    let bytes = line.as_bytes();
+    let mut depth = 0;
+    let mut i = column;
+    while i > 0 {
+        i -= 1;
+        match bytes[i] {
+            b')' | b']' | b'}' => depth += 1,
+            // the start of the enclosing brackets
+            b'(' | b'[' | b'{' if depth == 0 => return false,
+            b'(' | b'[' | b'{' => depth -= 1,
+            byte if depth == 0 && is_identifier(byte) => {
+                let end = i + 1;
+                while i > 0 && is_identifier(bytes[i - 1]) {
+                    i -= 1;
+                }
+                match &line[i..end] {
+                    "for" => return true,
+                    "if" => return false,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Check whether byte offset `column` of `line` lies within the parentheses of a
+/// call to `function`.
+fn inside_call(line: &str, column: usize, function: &str) -> bool {
+    let mut depth = 0;
+    for (i, byte) in line.as_bytes()[..column].iter().enumerate().rev() {
+        match byte {
+            b')' => depth += 1,
+            b'(' if depth > 0 => depth -= 1,
+            b'(' => return line[..i].trim_end().ends_with(function),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Comments that exclude a line from mutation.
+const NO_MUTATE_PRAGMAS: [&str; 2] = ["pragma: no mutate", "nomutate"];
+/// Number of lines at the start of a file in which a pragma can exclude the
+/// whole file, e.g. `# pragma: no mutate file`.
+const FILE_PRAGMA_LINES: usize = 3;
+
+/// Where a no mutate pragma applies.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PragmaScope {
+    /// Only the line with the pragma is not mutated.
+    Line,
+    /// The whole file is not mutated.
+    File,
+}
+
+/// Find a no mutate pragma in the comment of a line.
+///
+/// A pragma followed by `file` (e.g. `# pragma: no mutate file`) applies to the
+/// whole file.
+fn no_mutate_pragma(comment: &str) -> Option<PragmaScope> {
+    NO_MUTATE_PRAGMAS.iter().find_map(|pragma| {
+        let (_, rest) = comment.split_once(pragma)?;
+        match rest.split_whitespace().next() {
+            Some("file") => Some(PragmaScope::File),
+            _ => Some(PragmaScope::Line),
+        }
+    })
+}
+
+/// Search for potential mutants in a file given some replacements.
+/// The replacement tuples in the Vec give the (before, after) string
+/// values i.e. before can be replaced by after.
+///
+/// Each line yields at most one mutant, plus one replacing the value of a
+/// `return` statement and one replacing a string literal if the `ReturnValues` and
+/// `Strings` mutation types are used.
+///
+/// Lines with a `# pragma: no mutate` (or `# nomutate`) comment are skipped, and
+/// a `# pragma: no mutate file` comment in the first three lines skips the whole
+/// file.
+fn add_mutants_from_file(
+    mutant_vec: &mut Vec<Mutant>,
+    path: &PathBuf,
+    replacements: &[Replacement],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let content = fs::read(path)?;
+
+    // a triple quoted string spanning multiple lines
+    let mut open_string = None;
+    let first_mutant = mutant_vec.len();
+
+    // lines are split like `Mutant::rewrite_line` does, so that line numbers match
+    let lines = content.split_inclusive(|byte| *byte == b'\n');
+    for (line_nr, line) in lines.enumerate() {
+        let (line, _) = split_line_ending(line);
+        // a line that is not valid UTF-8 (e.g. a latin-1 comment) is not mutated,
+        // but may still open or close a string
+        let line = match std::str::from_utf8(line) {
+            Ok(line) => line.to_string(),
+            Err(_) => {
+                mask_strings(&String::from_utf8_lossy(line), &mut open_string);
+                continue;
+            }
+        };
+
+        // only consider code, i.e. neither strings nor comments
+        let code = mask_strings(&line, &mut open_string);
+        // the comment is everything after the code
+        match no_mutate_pragma(&line[code.len()..]) {
+            Some(PragmaScope::File) if line_nr < FILE_PRAGMA_LINES => {
+                mutant_vec.truncate(first_mutant);
+                return Ok(());
+            }
+            Some(_) => continue,
+            None => {}
+        }
+        if code
+            .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+            .is_empty()
+        {
+            continue;
+        }
+        // for compound statements with a body on the same line (e.g.
+        // `def f(x): return x + 1`) only the body is considered
+        let offset = compound_statement_body(&code).unwrap_or(0);
+        let replacement = replacement_from_line(&code[offset..], replacements);
+        let mut additional =
+            additional_replacements_from_line(&code[offset..], &line[offset..], replacements);
+        additional.retain(|additional| Some(additional) != replacement.as_ref());
+        for ((column, before, after), mutation_type) in replacement.into_iter().chain(additional) {
+            let column = offset + column;
+            let likely_equivalent = equivalence::likely_equivalent(&code, column, &before, &after);
+            // strings are masked in the code, but the offsets are the same as in the line
+            let before = line
+                .get(column..column + before.len())
+                .map_or(before, String::from);
+            mutant_vec.push(Mutant {
+                file_path: path.clone(),
+                line_number: line_nr + 1,
+                before,
+                after,
+                column,
+                old_line: line.clone(),
+                likely_equivalent,
+                mutation_type,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// A string literal that is still open, e.g. at the end of a line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct OpenString {
+    /// The quotes that close the string.
+    delimiter: &'static str,
+    /// Whether the string is an f-string, whose replacement fields are code.
+    formatted: bool,
+}
+
+/// Mask string literals and cut off comments in a line of python code.
+///
+/// Every byte of a string literal (including its quotes and prefix such as `r`
+/// or `f`) is replaced by a NUL byte, so that byte offsets in the masked line are
+/// the same as in `line`. Expressions in the replacement fields of f-strings
+/// (e.g. `a + b` in `f"{a + b}"`) are code and are kept. `open_string` holds a
+/// triple quoted string that is still open at the start of the line and is
+/// updated for the next line, so docstrings spanning multiple lines are masked
+/// entirely while code after their closing delimiter is kept.
+pub(crate) fn mask_strings(line: &str, open_string: &mut Option<OpenString>) -> String {
+    let bytes = line.as_bytes();
+    let mut masked = bytes.to_vec();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let string = match *open_string {
+            Some(string) => string,
+            None => match bytes[i] {
+                b'#' => {
+                    masked.truncate(i);
+                    break;
+                }
+                b'"' | b'\'' => {
+                    let prefix = string_prefix_len(&bytes[..i]);
+                    masked[i - prefix..i].fill(0);
+                    let string = OpenString {
+                        delimiter: delimiter_at(&bytes[i..]),
+                        formatted: bytes[i - prefix..i]
+                            .iter()
+                            .any(|byte| byte.eq_ignore_ascii_case(&b'f')),
+                    };
+                    masked[i..i + string.delimiter.len()].fill(0);
+                    i += string.delimiter.len();
+                    string
+                }
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            },
+        };
+
+        match mask_string_body(bytes, &mut masked, i, string) {
+            Some(end) => {
+                i = end;
+                *open_string = None;
+            }
+            None => {
+                // strings delimited by a single quote cannot span multiple lines
+                if string.delimiter.len() == 3 {
+                    *open_string = Some(string);
+                }
+                break;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&masked).into_owned()
+}
+
+/// Mask the body of `string` from byte `start` on, up to and including its
+/// closing delimiter.
+///
+/// Returns the index after the closing delimiter, or None if the string does not
+/// end in `bytes`.
+fn mask_string_body(
+    bytes: &[u8],
+    masked: &mut [u8],
+    start: usize,
+    string: OpenString,
+) -> Option<usize> {
+    let delimiter = string.delimiter.as_bytes();
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(delimiter) {
+            masked[i..i + delimiter.len()].fill(0);
+            return Some(i + delimiter.len());
+        }
+        let len = match bytes[i] {
+            // escaped characters never close a string, also in raw strings
+            b'\\' => 2.min(bytes.len() - i),
+            // `{{` is a literal brace
+            b'{' if string.formatted && bytes.get(i + 1) == Some(&b'{') => 2,
+            b'{' if string.formatted => {
+                masked[i] = 0;
+                i = skip_replacement_field(bytes, masked, i + 1);
+                continue;
+            }
+            _ => 1,
+        };
+        masked[i..i + len].fill(0);
+        i += len;
+    }
+    None
+}
+
+/// Skip the expression of an f-string replacement field starting at byte `start`
+/// (right after its `{`), masking only strings in it, its conversion, its format
+/// spec and its closing brace.
+///
+/// Returns the index after the closing brace.
+fn skip_replacement_field(bytes: &[u8], masked: &mut [u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth > 0 => depth -= 1,
+            b'"' | b'\'' => {
+                let string = OpenString {
+                    delimiter: delimiter_at(&bytes[i..]),
+                    formatted: false,
+                };
+                masked[i..i + string.delimiter.len()].fill(0);
+                match mask_string_body(bytes, masked, i + string.delimiter.len(), string) {
+                    Some(end) => i = end,
+                    None => return bytes.len(),
+                }
+                continue;
+            }
+            // the conversion (e.g. `!r`) or format spec (e.g. `:>10`) up to the
+            // closing brace is not code
+            b'!' | b':' | b'}' if depth == 0 && !bytes[i..].starts_with(b"!=") => {
+                let mut nested = 0;
+                while i < bytes.len() {
+                    masked[i] = 0;
+                    match bytes[i] {
+                        b'{' => nested += 1,
+                        b'}' if nested == 0 => return i + 1,
+                        b'}' => nested -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                return i;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+/// The quotes that open a string literal at the start of `code`.
+fn delimiter_at(code: &[u8]) -> &'static str {
+    match code {
+        [b'"', b'"', b'"', ..] => "\"\"\"",
+        [b'\'', b'\'', b'\'', ..] => "'''",
+        [b'"', ..] => "\"",
+        _ => "'",
+    }
+}
+
+/// Length of the string prefix (e.g. `r`, `f` or `rb`) at the end of `code`.
+fn string_prefix_len(code: &[u8]) -> usize {
+    let prefix = code
+        .iter()
+        .rev()
+        .take_while(|byte| b"rRbBfFuU".contains(byte))
+        .count();
+    let is_identifier = |byte: &u8| byte.is_ascii_alphanumeric() || *byte == b'_';
+    match code[..code.len() - prefix].last() {
+        Some(byte) if is_identifier(byte) => 0,
+        _ if prefix <= 2 => prefix,
+        _ => 0,
+    }
+}
+
+/// Find the start of the body of a compound statement that is written on the
+/// same line as its header (e.g. `if x: return y`).
+///
+/// Returns the byte offset right after the first `:` outside of brackets and
+/// strings, or None if the line is not a compound statement header or has no
+/// body on the same line.
+fn compound_statement_body(line: &str) -> Option<usize> {
+    let keywords = [
+        "def ",
+        "async def ",
+        "class ",
+        "if ",
+        "elif ",
+        "else:",
+        "for ",
+        "async for ",
+        "while ",
+        "with ",
+        "async with ",
+        "try:",
+        "except ",
+        "except:",
+        "finally:",
+    ];
+    let stripped = line.trim_start();
+    if !keywords.iter().any(|keyword| stripped.starts_with(keyword)) {
+        return None;
+    }
+
+    let masked = mask_strings(line, &mut None);
+    let bytes = masked.as_bytes();
+    let mut depth = 0i32;
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            // a `:=` is an assignment expression, not the end of the header
+            b':' if depth == 0 && bytes.get(i + 1) != Some(&b'=') => {
+                let body = &line[i + 1..];
+                return match body.trim().is_empty() {
+                    true => None,
+                    false => Some(i + 1),
+                };
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Find the replacements in `line` that are mutants in addition to the replacement
+/// from `replacement_from_line` (see `MatchKind::is_additional`). `code` is `line`
+/// with its strings masked. Each replacement is returned with its mutation type.
+fn additional_replacements_from_line(
+    code: &str,
+    line: &str,
+    replacements: &[Replacement],
+) -> Vec<((usize, String, String), Option<MutationType>)> {
+    replacements
+        .iter()
+        .filter(|replacement| replacement.kind.is_additional())
+        .filter_map(|replacement| {
+            let found = match replacement.kind {
+                MatchKind::StringLiteral => find_string_literal_in(code, line),
+                _ => replacement.find_in(code),
+            };
+            Some((found?, replacement.mutation_type))
+        })
+        .collect()
+}
+
+/// Find a before/after replacement tuple in `line`. Possible tuples are
+/// specified in `replacements`. The byte offset of the replaced string in `line`
+/// is returned together with the tuple, followed by the mutation type of the
+/// replacement. If no possible replacement is found, it returns None.
+///
+/// The match starting earliest in the line is used, and of several matches at the
+/// same position the longest one (e.g. `<=` instead of `<`). Of equally long
+/// matches at the same position the first replacement (in order) wins, see
+/// `build_replacements`.
+fn replacement_from_line(
+    line: &str,
+    replacements: &[Replacement],
+) -> Option<((usize, String, String), Option<MutationType>)> {
+    let line = mask_strings(line, &mut None);
+
+    let matches: Vec<_> = replacements
+        .iter()
+        .filter(|replacement| !replacement.kind.is_additional())
+        .filter_map(|replacement| Some((replacement.find_in(&line)?, replacement.mutation_type)))
+        .collect();
+    // the span of the matched token, without the surrounding spaces
+    let span = |((column, before, _), _): &((usize, String, String), _)| {
+        let start = column + before.len() - before.trim_start().len();
+        start..start + before.trim().len()
+    };
+    // `min_by_key` returns the first of several equal matches
+    matches
+        .into_iter()
+        .min_by_key(|found| (span(found).start, Reverse(span(found).len())))
+}
+
+/// Build a Vec of before/after replacements from the specified types of
+/// mutations.
+///
+/// The replacements are ordered by mutation type (in the order of `MutationType`)
+/// regardless of the order of `mutation_types`, so that the same mutants are found
+/// however the types are given.
+fn build_replacements(mutation_types: &[MutationType]) -> Vec<Replacement> {
+    let mut replacements = Vec::new();
+    let mut mutation_types = mutation_types.to_vec();
+    mutation_types.sort();
+    mutation_types.dedup();
+
+    let operator = |before, after| Replacement::new(before, after, MatchKind::Operator);
+    let keyword = |before, after| Replacement::new(before, after, MatchKind::Keyword);
+
+    for mutation_type in mutation_types {
+        let first = replacements.len();
+        match mutation_type {
+            MutationType::MathOps => {
+                replacements.append(&mut vec![
+                    operator(" + ", " - "),
+                    operator(" - ", " + "),
+                    operator(" * ", " / "),
+                    operator(" / ", " * "),
+                ]);
+            }
+            MutationType::MathOpsExtended => {
+                // `//` and `**` are never matched as `/` and `*` of `MathOps`, see
+                // `is_binary_operator`
+                replacements.append(&mut vec![
+                    operator(" % ", " / "),
+                    operator(" // ", " / "),
+                    operator(" / ", " // "),
+                    operator(" ** ", " * "),
+                ]);
+            }
+            MutationType::Conjunctions => {
+                replacements.append(&mut vec![
+                    keyword(" and ", " or "),
+                    keyword(" or ", " and "),
+                ]);
+            }
+            MutationType::Booleans => {
+                replacements.append(&mut vec![
+                    keyword(" True ", " False "),
+                    keyword(" False ", " True "),
+                ]);
+            }
+            MutationType::ControlFlow => {
+                replacements.append(&mut vec![
+                    keyword(" else: ", " elif False: "),
+                    keyword(" if not ", " if "),
+                    keyword(" if ", " if not "),
+                ]);
+            }
+            MutationType::CompOps => {
+                replacements.append(&mut vec![
+                    operator(" > ", " < "),
+                    operator(" < ", " > "),
+                    operator("==", "!="),
+                    operator("!=", "=="),
+                ]);
+            }
+            MutationType::CompBoundary => {
+                replacements.append(&mut vec![
+                    operator(" <= ", " < "),
+                    operator(" >= ", " > "),
+                    operator(" < ", " <= "),
+                    operator(" > ", " >= "),
+                ]);
+            }
+            MutationType::BitwiseOps => {
+                // `|` is also the union of types in annotations
+                replacements.append(&mut vec![
+                    operator(" & ", " | "),
+                    operator(" | ", " & ").outside_annotation(),
+                    operator(" ^ ", " & "),
+                    operator(" << ", " >> "),
+                    operator(" >> ", " << "),
+                ]);
+            }
+            MutationType::Numbers => {
+                replacements.push(Replacement::new("", "", MatchKind::Number));
+            }
+            MutationType::AugAssign => {
+                replacements.append(&mut vec![
+                    operator(" += ", " -= "),
+                    operator(" -= ", " += "),
+                    operator(" *= ", " /= "),
+                    operator(" /= ", " *= "),
+                    operator(" //= ", " *= "),
+                    operator(" %= ", " *= "),
+                ]);
+            }
+            MutationType::Membership => {
+                // `not in` first so that its `in` is not negated again
+                replacements.append(&mut vec![
+                    keyword(" not in ", " in ").outside_for_header(),
+                    keyword(" in ", " not in ").outside_for_header(),
+                ]);
+            }
+            MutationType::Identity => {
+                replacements.append(&mut vec![
+                    keyword(" is not ", " is "),
+                    keyword(" is ", " is not "),
+                ]);
+            }
+            MutationType::NotRemoval => {
+                replacements.push(keyword(" not ", "").negation_only());
+            }
+            MutationType::NoneSwaps => {
+                replacements.push(Replacement::new("None", "0", MatchKind::NoneAssignment));
+            }
+            MutationType::LoopControl => {
+                // `pass` is not replaced by `return`, which is a syntax error outside
+                // of functions (e.g. in `class Error(Exception): pass`)
+                let statement =
+                    |before, after| Replacement::new(before, after, MatchKind::Statement);
+                replacements.append(&mut vec![
+                    statement("break", "continue"),
+                    statement("continue", "break"),
+                    statement("return", "pass"),
+                ]);
+            }
+            MutationType::ReturnValues => {
+                replacements.push(Replacement::new("", "", MatchKind::ReturnValue));
+            }
+            MutationType::Strings => {
+                replacements.push(Replacement::new("", "", MatchKind::StringLiteral));
+            }
+            MutationType::ArgSwap => {
+                replacements.push(Replacement::new("", "", MatchKind::ArgSwap));
+            }
+            MutationType::Builtins => {
+                // the names only match on a word boundary, so that e.g. `argmax(`
+                // is left alone
+                replacements.append(&mut vec![
+                    keyword("min(", "max("),
+                    keyword("max(", "min("),
+                    keyword("any(", "all("),
+                    keyword("all(", "any("),
+                    keyword("sum(", "len("),
+                    keyword("reverse=True", "reverse=False").within_call("sorted"),
+                    keyword("reverse=False", "reverse=True").within_call("sorted"),
+                    keyword("reverse=True", "reverse=False").within_call(".sort"),
+                    keyword("reverse=False", "reverse=True").within_call(".sort"),
+                ]);
+            }
+            MutationType::Time => {
+                // longer patterns first so that `.utcnow()` is not matched as `.now()`
+                replacements.append(&mut vec![
+                    (".utcnow()", ".now()").into(),
+                    (".now()", ".utcnow()").into(),
+                    ("+ timedelta", "- timedelta").into(),
+                    ("- timedelta", "+ timedelta").into(),
+                    Replacement::from(("days=", "hours=")).within_call("timedelta"),
+                    Replacement::from(("hours=", "days=")).within_call("timedelta"),
+                    Replacement::from(("minutes=", "seconds=")).within_call("timedelta"),
+                    Replacement::from(("seconds=", "minutes=")).within_call("timedelta"),
+                ]);
+            }
+        }
+        for replacement in &mut replacements[first..] {
+            replacement.mutation_type = Some(mutation_type);
+        }
+    }
+
+    replacements
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::DEFAULT_MUTATION_TYPES;
+    use crate::error::PymuteError;
+    use crate::mutants::{
+        self, build_replacements, Mutant, MutantError, MutationType, Replacement,
+    };
+    use colored::Colorize;
+    use std::{
+        fs::{self, read_to_string, File},
+        io::Write,
+        path::{Path, PathBuf},
+    };
+    use tempfile::{tempdir, NamedTempFile};
+
+    /// The replacement `mutants::replacement_from_line` finds, without its
+    /// mutation type.
+    fn replacement_from_line(
+        line: &str,
+        replacements: &[Replacement],
+    ) -> Option<(usize, String, String)> {
+        mutants::replacement_from_line(line, replacements).map(|(found, _)| found)
+    }
+
+    #[test]
+    fn test_find_mutants_records_mutation_type() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("calc.py");
+        fs::write(
+            &file,
+            "def f(a, b):\n    return a + b\n\ndef g(a, b):\n    return a < b\n",
+        )
+        .unwrap();
+
+        let mutation_types = [MutationType::CompOps, MutationType::MathOps];
+        let mutants = mutants::find_mutants_in_files(&[file], &mutation_types).unwrap();
+        let types: Vec<_> = mutants.iter().map(|mutant| mutant.mutation_type).collect();
+        assert_eq!(
+            types,
+            vec![Some(MutationType::MathOps), Some(MutationType::CompOps)]
+        );
+        assert!(mutants[1].to_string().ends_with(" (comp-ops)"));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_find_mutants() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+
+        let multiline_string_script_1 = "def add(a, b):
+    return a + b
+
+# this is a + comment
+def sub(a, b):
+    return a - b
+
+res = sub(5, 6) * add(7, 8)
+print(res) # print the result *
+";
+
+        let multiline_string_script_2 = "def div(a, b):
+    return a / b
+
+# this is a + comment
+def mul(a, b):
+    return a * b
+
+res = div(5, 6) - mul(7, 8)
+print(res) # print the result +
+";
+        let multiline_string_script_3 = "def print_number(a, b):
+    res = a + b
+    print(\"a + b = {res}\")
+
+# this is a + comment
+
+";
+
+        let multiline_string_script_test_1 = "def print_number(a, b):
+    res = a + b
+    print(\"a + b = {res}\")
+
+# this is a + comment
+
+";
+        let multiline_string_script_test_2 = "def print_number(a, b):
+    res = a + b
+    print(\"a + b = {res}\")
+
+# this is a + comment
+
+";
+
+        // creating a nested directory structure
+        let sub_dir1 = base_path.join("dir1");
+        let sub_dir1_1 = sub_dir1.join("dir1_1");
+        let sub_dir1_1_1 = sub_dir1_1.join("dir1_1_1");
+
+        // ensure all directories are created
+        fs::create_dir_all(&sub_dir1_1_1).unwrap();
+
+        let script1 = sub_dir1.join("script1.py");
+        let mut script1 = File::create(script1).unwrap();
+
+        write!(script1, "{}", multiline_string_script_1)
+            .expect("Failed to write to temporary file");
+
+        // create a decoy txt file that should not be matched
+        let decoy = base_path.join("script1.txt");
+        let mut decoy = File::create(decoy).unwrap();
 
         write!(decoy, "{}", multiline_string_script_1).expect("Failed to write txt file.");
 
@@ -491,27 +1936,349 @@ print(res) # print the result +
         write!(script2, "{}", multiline_string_script_2)
             .expect("Failed to write to temporary file");
 
-        let script3 = sub_dir1_1_1.join("script3.py");
-        let mut script3 = File::create(script3).unwrap();
+        let script3 = sub_dir1_1_1.join("script3.py");
+        let mut script3 = File::create(script3).unwrap();
+
+        write!(script3, "{}", multiline_string_script_3)
+            .expect("Failed to write to temporary file");
+
+        let test_script = sub_dir1_1_1.join("test_script.py");
+        let mut test_script = File::create(test_script).unwrap();
+
+        write!(test_script, "{}", multiline_string_script_test_1)
+            .expect("Failed to write to temporary file");
+
+        let script_test = sub_dir1_1_1.join("script_test.py");
+        let mut script_test = File::create(script_test).unwrap();
+
+        write!(script_test, "{}", multiline_string_script_test_2)
+            .expect("Failed to write to temporary file");
+
+        let glob_expr = base_path.to_str().unwrap();
+        let glob_expr = format!("{glob_expr}/**/*.py");
+
+        let mutation_types = vec![
+            MutationType::MathOps,
+            MutationType::Conjunctions,
+            MutationType::Booleans,
+            MutationType::ControlFlow,
+            MutationType::CompOps,
+            MutationType::Numbers,
+        ];
+        let mutants_vec = mutants::find_mutants(&glob_expr, &mutation_types).unwrap();
+
+        assert_eq!(mutants_vec.len(), 7);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_find_files_invalid_glob() {
+        let error = mutants::find_files("src/[.py").unwrap_err();
+        assert!(
+            matches!(&error, PymuteError::InvalidGlob { pattern, .. } if pattern == "src/[.py"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_replacement_from_line_with_single_quotes() {
+        let line = r#"print('a + b')"#;
+        let mutation_types = vec![
+            MutationType::MathOps,
+            MutationType::Conjunctions,
+            MutationType::Booleans,
+            MutationType::ControlFlow,
+            MutationType::CompOps,
+            MutationType::Numbers,
+        ];
+
+        let replacements = build_replacements(&mutation_types);
+
+        let option = replacement_from_line(line, &replacements);
+        assert!(option.is_none(), "Expected the option to be None");
+    }
+
+    #[test]
+    fn test_replacement_from_line_with_double_quotes() {
+        let line = r#"print("a + b")"#;
+        let mutation_types = vec![
+            MutationType::MathOps,
+            MutationType::Conjunctions,
+            MutationType::Booleans,
+            MutationType::ControlFlow,
+            MutationType::CompOps,
+            MutationType::Numbers,
+        ];
+
+        let replacements = build_replacements(&mutation_types);
+
+        let option = replacement_from_line(line, &replacements);
+        assert!(option.is_none(), "Expected the option to be None");
+    }
+
+    #[test]
+    fn test_replacement_from_line_string_scanner() {
+        let replacements = build_replacements(&DEFAULT_MUTATION_TYPES);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        // escaped quotes do not end the string
+        let (column, before, _) = find(r#"msg = "he said \"hi\" + x" + name"#).unwrap();
+        assert_eq!((column, before.as_str()), (26, " + "));
+        assert!(find(r#"msg = "he said \"hi + there\"""#).is_none());
+
+        // an apostrophe inside a double quoted string does not open a string
+        let (column, before, _) = find(r#"print("don't " + name)"#).unwrap();
+        assert_eq!((column, before.as_str()), (14, " + "));
+
+        // adjacent string literals
+        assert!(find(r#"x = "a + b" 'c - d' f"e * f""#).is_none());
+
+        // replacement fields of f-strings are code, the rest is not
+        let (column, before, after) = find(r#"print(f"{x > 0}")"#).unwrap();
+        assert_eq!(
+            (column, before.as_str(), after.as_str()),
+            (10, " > ", " < ")
+        );
+        let (column, before, _) = find(r#"s = f"a + b = {a + b}""#).unwrap();
+        assert_eq!((column, before.as_str()), (16, " + "));
+        assert!(find(r#"s = f"{{a + b}} {name!r:>10}""#).is_none());
+        let (column, before, _) = find(r#"s = f"{d['a - b'] * 2}""#).unwrap();
+        assert_eq!((column, before.as_str()), (17, " * "));
+    }
+
+    #[test]
+    fn test_add_mutants_from_file() {
+        let multiline_string = "def add(a, b):
+    return a + b";
+
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
+
+        let mutation_types = vec![
+            MutationType::MathOps,
+            MutationType::Conjunctions,
+            MutationType::Booleans,
+            MutationType::ControlFlow,
+            MutationType::CompOps,
+            MutationType::Numbers,
+        ];
+
+        let replacements = build_replacements(&mutation_types);
+
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        let _ = mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        );
+
+        assert_eq!(possible_mutants.len(), 1);
+        assert_eq!(possible_mutants[0].line_number, 2);
+        assert_eq!(possible_mutants[0].before, String::from(" + "));
+        assert_eq!(possible_mutants[0].after, String::from(" - "));
+    }
+
+    #[test]
+    fn test_add_mutants_from_file_trickier() {
+        let multiline_string = "def add(a, b):
+    return a + b
+
+# this is a + comment
+def sub(a, b):
+    return a - b
+
+res = sub(5, 6) * add(7, 8)
+print(res) # print the result *
+";
+
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
+
+        let mutation_types = vec![
+            MutationType::MathOps,
+            MutationType::Conjunctions,
+            MutationType::Booleans,
+            MutationType::ControlFlow,
+            MutationType::CompOps,
+            MutationType::Numbers,
+        ];
+
+        let replacements = build_replacements(&mutation_types);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        let _ = mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        );
+
+        assert_eq!(possible_mutants.len(), 3);
+
+        assert_eq!(possible_mutants[0].line_number, 2);
+        assert_eq!(possible_mutants[0].before, String::from(" + "));
+        assert_eq!(possible_mutants[0].after, String::from(" - "));
+
+        assert_eq!(possible_mutants[1].line_number, 6);
+        assert_eq!(possible_mutants[1].before, String::from(" - "));
+        assert_eq!(possible_mutants[1].after, String::from(" + "));
+
+        // the first number comes before the operator
+        assert_eq!(possible_mutants[2].line_number, 8);
+        assert_eq!(possible_mutants[2].before, String::from("5"));
+        assert_eq!(possible_mutants[2].after, String::from("6"));
+    }
+
+    #[test]
+    fn test_replacement_from_line_none() {
+        let line = "print('Hello World')";
+        let mutation_types = vec![
+            MutationType::MathOps,
+            MutationType::Conjunctions,
+            MutationType::Booleans,
+            MutationType::ControlFlow,
+            MutationType::CompOps,
+            MutationType::Numbers,
+        ];
+
+        let replacements = build_replacements(&mutation_types);
+        let option = replacement_from_line(line, &replacements);
+        println!("{:?}", option);
+        assert!(option.is_none(), "Expected the option to be None");
+    }
+
+    #[test]
+    fn test_replacement_from_line_math_operators() {
+        let mutation_types = vec![
+            MutationType::MathOps,
+            MutationType::Conjunctions,
+            MutationType::Booleans,
+            MutationType::ControlFlow,
+            MutationType::CompOps,
+            MutationType::Numbers,
+        ];
+
+        let replacements = build_replacements(&mutation_types);
+
+        let line = "a + 5";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (1, " + ".into(), " - ".into()));
+
+        let line = "a - 5";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (1, " - ".into(), " + ".into()));
+
+        let line = "a * 5";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (1, " * ".into(), " / ".into()));
 
-        write!(script3, "{}", multiline_string_script_3)
-            .expect("Failed to write to temporary file");
+        let line = "a / 5";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (1, " / ".into(), " * ".into()));
+    }
 
-        let test_script = sub_dir1_1_1.join("test_script.py");
-        let mut test_script = File::create(test_script).unwrap();
+    #[test]
+    fn test_replacement_from_line_math_operators_extended() {
+        let replacements = build_replacements(&[MutationType::MathOps]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+        // the longer operators are not mutated as `/` and `*`
+        assert_eq!(find("x = a // b"), None);
+        assert_eq!(find("x = a ** 2"), None);
+        // the extended operators are opt-in
+        let replacements = build_replacements(&DEFAULT_MUTATION_TYPES);
+        assert_eq!(replacement_from_line("x = a // b", &replacements), None);
+
+        let replacements =
+            build_replacements(&[MutationType::MathOps, MutationType::MathOpsExtended]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+        assert_eq!(find("x = a // b"), Some((5, " // ".into(), " / ".into())));
+        assert_eq!(find("x = a % n"), Some((5, " % ".into(), " / ".into())));
+        assert_eq!(find("x = a ** b"), Some((5, " ** ".into(), " * ".into())));
+        assert_eq!(find("x = a//b"), Some((5, "//".into(), "/".into())));
+        // `/` is mutated by `MathOps` first
+        assert_eq!(find("x = a / b"), Some((5, " / ".into(), " * ".into())));
+        let replacements = build_replacements(&[MutationType::MathOpsExtended]);
+        assert_eq!(
+            replacement_from_line("x = a / b", &replacements),
+            Some((5, " / ".into(), " // ".into()))
+        );
+        // not as part of augmented assignments or unpacking
+        assert_eq!(replacement_from_line("x //= 2", &replacements), None);
+        assert_eq!(replacement_from_line("f(**kwargs)", &replacements), None);
 
-        write!(test_script, "{}", multiline_string_script_test_1)
+        // each line produces exactly one mutant with the whole operator
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "x = a // b\nx = a % n\nx = a ** 2\n")
             .expect("Failed to write to temporary file");
+        let replacements =
+            build_replacements(&[MutationType::MathOps, MutationType::MathOpsExtended]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
+        let found: Vec<(usize, &str)> = possible_mutants
+            .iter()
+            .map(|m| (m.line_number, m.before.as_str()))
+            .collect();
+        assert_eq!(found, vec![(1, " // "), (2, " % "), (3, " ** ")]);
+    }
 
-        let script_test = sub_dir1_1_1.join("script_test.py");
-        let mut script_test = File::create(script_test).unwrap();
+    #[test]
+    fn test_replacement_from_line_order_independent() {
+        let multiline_string = "def check(a, b, limit=10):
+    if a + b > limit and b != 0:
+        return a * 2 < b
+    ratio = a // b ** 2
+    return ratio >= limit - 1 or a is not None
+";
 
-        write!(script_test, "{}", multiline_string_script_test_2)
-            .expect("Failed to write to temporary file");
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
+        let path = temp_file.path().to_path_buf();
 
-        let glob_expr = base_path.to_str().unwrap();
-        let glob_expr = format!("{glob_expr}/**/*.py");
+        let mut mutation_types = vec![
+            MutationType::MathOps,
+            MutationType::MathOpsExtended,
+            MutationType::Conjunctions,
+            MutationType::ControlFlow,
+            MutationType::CompOps,
+            MutationType::CompBoundary,
+            MutationType::Numbers,
+            MutationType::Identity,
+        ];
+        let find = |mutation_types: &[MutationType]| {
+            let mut possible_mutants = Vec::<mutants::Mutant>::new();
+            mutants::add_mutants_from_file(
+                &mut possible_mutants,
+                &path,
+                &build_replacements(mutation_types),
+            )
+            .unwrap();
+            possible_mutants
+        };
+        let forward = find(&mutation_types);
+        mutation_types.reverse();
+        assert_eq!(find(&mutation_types), forward);
+        assert_eq!(
+            find(&[MutationType::CompOps, MutationType::MathOps]),
+            find(&[MutationType::MathOps, MutationType::CompOps])
+        );
+
+        // the earliest match wins, and the longest of several at the same position
+        let found: Vec<(usize, &str)> = forward
+            .iter()
+            .map(|m| (m.line_number, m.before.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(1, "10"), (2, " if "), (3, " * "), (4, " // "), (5, " >= ")]
+        );
+    }
 
+    #[test]
+    fn test_replacement_from_line_conjunctions() {
         let mutation_types = vec![
             MutationType::MathOps,
             MutationType::Conjunctions,
@@ -520,16 +2287,19 @@ print(res) # print the result +
             MutationType::CompOps,
             MutationType::Numbers,
         ];
-        let mutants_vec = mutants::find_mutants(&glob_expr, &mutation_types).unwrap();
 
-        assert_eq!(mutants_vec.len(), 7);
+        let replacements = build_replacements(&mutation_types);
+        let line = "done and False";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (4, " and ".into(), " or ".into()));
 
-        temp_dir.close().unwrap();
+        let line = "done or False";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (4, " or ".into(), " and ".into()));
     }
 
     #[test]
-    fn test_replacement_from_line_with_single_quotes() {
-        let line = r#"print('a + b')"#;
+    fn test_replacement_from_line_comparison_operators() {
         let mutation_types = vec![
             MutationType::MathOps,
             MutationType::Conjunctions,
@@ -539,260 +2309,1186 @@ print(res) # print the result +
             MutationType::Numbers,
         ];
 
-        let replacements = build_replacements(&mutation_types);
+        let replacements = build_replacements(&mutation_types);
+
+        let line = "a == 5";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (2, "==".into(), "!=".into()));
+
+        let line = "a != 5";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (2, "!=".into(), "==".into()));
+
+        let line = "a > 5";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (1, " > ".into(), " < ".into()));
+
+        let line = "a < 5";
+        let option = replacement_from_line(line, &replacements);
+        assert_eq!(option.unwrap(), (1, " < ".into(), " > ".into()));
+    }
+
+    #[test]
+    fn test_replacement_from_line_token_aware() {
+        let replacements = build_replacements(&crate::config::DEFAULT_MUTATION_TYPES);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        // operators without surrounding whitespace
+        assert_eq!(find("a+b"), Some((1, "+".into(), "-".into())));
+        assert_eq!(find("value= a*b"), Some((8, "*".into(), "/".into())));
+        assert_eq!(find("a>b"), Some((1, ">".into(), "<".into())));
+
+        // operators that are part of longer operators or unary are not mutated
+        assert_eq!(find("a<=b"), None);
+        let math_ops = build_replacements(&[MutationType::MathOps]);
+        assert_eq!(replacement_from_line("a += b", &math_ops), None);
+        assert_eq!(replacement_from_line("a ** b", &math_ops), None);
+        assert_eq!(replacement_from_line("a // b", &math_ops), None);
+        assert_eq!(find("def f(*args, **kwargs) -> None: pass"), None);
+        assert_eq!(find("return -b"), None);
+        assert_eq!(find("x = y[a:-b]"), None);
+        assert_eq!(find("from a import *"), None);
+
+        // keywords only match on word boundaries
+        assert_eq!(find("x and_y"), None);
+        assert_eq!(find("sand = band or_candy"), None);
+        assert_eq!(
+            find("return True"),
+            Some((6, " True".into(), " False".into()))
+        );
+        assert_eq!(find("if x:"), Some((0, "if ".into(), "if not ".into())));
+        assert_eq!(find("elif x:"), None);
+
+        // numbers are not matched inside identifiers
+        assert_eq!(find("randint(1,2)"), Some((8, "1".into(), "2".into())));
+        assert_eq!(find("x2 = y"), None);
+        // the sign of an exponent is not a binary operator
+        assert_eq!(find("x = 1e-5"), Some((4, "1e-5".into(), "2e-5".into())));
+        assert_eq!(replacement_from_line("x = 1e-5", &math_ops), None);
+    }
+
+    #[test]
+    fn test_replacement_from_line_comp_boundary() {
+        let replacements = build_replacements(&[MutationType::CompOps, MutationType::CompBoundary]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        assert_eq!(
+            find("if x <= limit:"),
+            Some((4, " <= ".into(), " < ".into()))
+        );
+        assert_eq!(find("if x>=limit:"), Some((4, ">=".into(), ">".into())));
+        // plain comparisons are swapped by CompOps first
+        assert_eq!(find("if x < limit:"), Some((4, " < ".into(), " > ".into())));
+
+        let boundary = build_replacements(&[MutationType::CompBoundary]);
+        let find = |line: &str| replacement_from_line(line, &boundary);
+        assert_eq!(
+            find("if x < limit:"),
+            Some((4, " < ".into(), " <= ".into()))
+        );
+        assert_eq!(
+            find("if x > limit:"),
+            Some((4, " > ".into(), " >= ".into()))
+        );
+        assert_eq!(find("x = a << b"), None);
+
+        // the longest match wins, independent of the order of the replacements
+        let literals: Vec<Replacement> = vec![("<", ">").into(), ("<=", "<").into()];
+        assert_eq!(
+            replacement_from_line("x <= y", &literals),
+            Some((2, "<=".into(), "<".into()))
+        );
+    }
+
+    #[test]
+    fn test_replacement_from_line_arg_swap() {
+        let replacements = build_replacements(&[MutationType::ArgSwap]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        // simple arguments
+        assert_eq!(
+            find("assert_allclose(expected, actual)"),
+            Some((16, "expected, actual".into(), "actual, expected".into()))
+        );
+        assert_eq!(
+            find("x = merge(self.base,override, strict=True)"),
+            Some((
+                10,
+                "self.base,override, strict=True".into(),
+                "override,self.base, strict=True".into()
+            ))
+        );
+        // the first call with simple arguments is used
+        assert_eq!(
+            find("f(g(x), y) + minimum(a, 1)"),
+            Some((21, "a, 1".into(), "1, a".into()))
+        );
+
+        // nested, keyword, unpacked and identical arguments are skipped
+        assert_eq!(find("f(g(x), y)"), None);
+        assert_eq!(find("f([a, b], c)"), None);
+        assert_eq!(find("merge(base=a, override=b)"), None);
+        assert_eq!(find("merge(a, override=b)"), None);
+        assert_eq!(find("f(*args, b)"), None);
+        assert_eq!(find("max(a, a)"), None);
+        // as are single arguments, unclosed calls, strings, tuples and definitions
+        assert_eq!(find("f(a)"), None);
+        assert_eq!(find("f(a, b,"), None);
+        assert_eq!(find(r#"f("a", b)"#), None);
+        assert_eq!(find("x = (a, b)"), None);
+        assert_eq!(find("if (a, b) in pairs:"), None);
+        assert_eq!(find("def merge(base, override):"), None);
+        assert_eq!(find("class Foo(Base, Mixin):"), None);
+
+        // not used by default
+        assert!(!DEFAULT_MUTATION_TYPES.contains(&MutationType::ArgSwap));
+    }
+
+    #[test]
+    fn test_arg_swap_insertion() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("merge.py");
+        fs::write(
+            &path,
+            "def merge(base, override):\n    return {**base, **override}\n\nresult = merge( defaults , user )  # swap\n",
+        )
+        .unwrap();
+
+        let replacements = build_replacements(&[MutationType::ArgSwap]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(&mut possible_mutants, &path, &replacements).unwrap();
+        assert_eq!(possible_mutants.len(), 1);
+
+        possible_mutants[0].insert().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap().lines().last(),
+            Some("result = merge( user , defaults )  # swap")
+        );
+        assert!(crate::syntax::check_syntax(Path::new("python"), &[path])
+            .unwrap()
+            .is_empty());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_replacement_from_line_membership_identity() {
+        let replacements = build_replacements(&[MutationType::Membership, MutationType::Identity]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        assert_eq!(
+            find("if key in cache:"),
+            Some((6, " in ".into(), " not in ".into()))
+        );
+        assert_eq!(
+            find("if key not in cache:"),
+            Some((6, " not in ".into(), " in ".into()))
+        );
+        assert_eq!(
+            find("if x is None:"),
+            Some((4, " is ".into(), " is not ".into()))
+        );
+        assert_eq!(
+            find("if x is not None:"),
+            Some((4, " is not ".into(), " is ".into()))
+        );
+
+        // the `in` of for loops and comprehensions is not mutated
+        assert_eq!(find("for x in items:"), None);
+        assert_eq!(find("    async for (a, b) in pairs:"), None);
+        assert_eq!(find("total = sum(x for x in items)"), None);
+        // but conditions in comprehensions are
+        assert_eq!(
+            find("keys = [k for k in keys if k in cache]"),
+            Some((28, " in ".into(), " not in ".into()))
+        );
+        assert_eq!(
+            find("flags = [x in cache for x in items]"),
+            Some((10, " in ".into(), " not in ".into()))
+        );
+        // and names containing the keywords are not matched
+        assert_eq!(find("index = isinstance(x, int)"), None);
+    }
+
+    #[test]
+    fn test_replacement_from_line_bitwise_ops() {
+        let replacements = build_replacements(&[MutationType::BitwiseOps]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        assert_eq!(find("mask = a & b"), Some((8, " & ".into(), " | ".into())));
+        assert_eq!(find("flags = a|b"), Some((9, "|".into(), "&".into())));
+        assert_eq!(find("x = a ^ b"), Some((5, " ^ ".into(), " & ".into())));
+        assert_eq!(find("x = 1 << n"), Some((5, " << ".into(), " >> ".into())));
+        assert_eq!(find("x = n >> 2"), Some((5, " >> ".into(), " << ".into())));
+        // not as part of augmented assignments
+        assert_eq!(find("flags |= READ"), None);
+        assert_eq!(find("x <<= 1"), None);
+
+        // `|` in type annotations is the union of types
+        assert_eq!(find("def f(x: int | None) -> int | None:"), None);
+        assert_eq!(find("    value: int | None = None"), None);
+        assert_eq!(find("    timeout: float | None,"), None);
+        assert_eq!(find(") -> dict[str, int] | None:"), None);
+        // but values assigned to annotated names are code
+        assert_eq!(
+            find("mode: int = READ | WRITE"),
+            Some((16, " | ".into(), " & ".into()))
+        );
+        assert_eq!(
+            find("mode = READ | WRITE"),
+            Some((11, " | ".into(), " & ".into()))
+        );
+    }
+
+    #[test]
+    fn test_replacement_from_line_none_swaps() {
+        let replacements = build_replacements(&[MutationType::NoneSwaps]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        assert_eq!(find("def f(x=None):"), Some((8, "None".into(), "0".into())));
+        assert_eq!(find("y = None"), Some((4, "None".into(), "0".into())));
+        assert_eq!(
+            find("    value: Optional[int] = None"),
+            Some((27, "None".into(), "0".into()))
+        );
+        assert_eq!(
+            find("fetch(url, timeout=None)"),
+            Some((19, "None".into(), "0".into()))
+        );
+
+        // comparisons and annotations are left alone
+        assert_eq!(find("if x is None:"), None);
+        assert_eq!(find("if x == None:"), None);
+        assert_eq!(find("if x != None:"), None);
+        assert_eq!(find("def f(x) -> None:"), None);
+        assert_eq!(find("y = NoneType"), None);
+        assert_eq!(find("f(None)"), None);
+    }
+
+    #[test]
+    fn test_replacement_from_line_not_removal() {
+        let replacements = build_replacements(&[MutationType::NotRemoval]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        assert_eq!(find("return not x"), Some((6, " not ".into(), " ".into())));
+        assert_eq!(
+            find("    while not done:"),
+            Some((9, " not ".into(), " ".into()))
+        );
+        assert_eq!(find("if not valid:"), Some((2, " not ".into(), " ".into())));
+        assert_eq!(
+            find("ok = a and not b"),
+            Some((10, " not ".into(), " ".into()))
+        );
+        assert_eq!(find("assert(not x)"), Some((7, "not ".into(), "".into())));
+
+        // `is not` and `not in` are left to the identity and membership types
+        assert_eq!(find("if x is not None:"), None);
+        assert_eq!(find("if x not in y:"), None);
+        // but `in` and `is` have to be whole words
+        assert_eq!(
+            find("if not inside:"),
+            Some((2, " not ".into(), " ".into()))
+        );
+        assert_eq!(
+            find("if not is_valid:"),
+            Some((2, " not ".into(), " ".into()))
+        );
+        assert_eq!(find("knot = notes"), None);
+    }
+
+    #[test]
+    fn test_replacement_from_line_numbers() {
+        let replacements = build_replacements(&[MutationType::Numbers]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
+
+        // whole literals are mutated
+        assert_eq!(find("x = 10"), Some((4, "10".into(), "11".into())));
+        assert_eq!(find("x = 9"), Some((4, "9".into(), "10".into())));
+        assert_eq!(find("arr[0]"), Some((4, "0".into(), "1".into())));
+        assert_eq!(find("ratio = 0.5"), Some((8, "0.5".into(), "1.5".into())));
+        assert_eq!(find("ratio = .5"), Some((8, ".5".into(), "1.5".into())));
+        assert_eq!(find("n = 1_000"), Some((4, "1_000".into(), "1001".into())));
+
+        // but not digits in identifiers, strings or hexadecimal literals
+        assert_eq!(find("foo2(1)"), Some((5, "1".into(), "2".into())));
+        assert_eq!(find("value2 = x"), None);
+        assert_eq!(find("x = 0x1F"), None);
+        assert_eq!(find(r#"name = "item 3""#), None);
+
+        // nor assignments to dunders like the version
+        assert_eq!(find(r#"__version__ = "1.0""#), None);
+        assert_eq!(find("__version_info__ = (1, 0)"), None);
+        assert_eq!(
+            find("if __debug__ == 1: pass"),
+            Some((16, "1".into(), "2".into()))
+        );
+    }
+
+    #[test]
+    fn test_mutant_insert() {
+        let multiline_string = "def add(a, b):
+    return a + b";
+
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let file_path_original = base_path.join("script.py");
+
+        let _temp_dir_copy = tempdir().unwrap();
+        let base_path_copy = temp_dir.path();
+        let file_path_copy = base_path_copy.join("script.py");
+
+        let mut file_original = File::create(&file_path_original).unwrap();
+        let mut file_copy = File::create(&file_path_copy).unwrap();
+        write!(file_original, "{}", multiline_string).expect("Failed to write to temporary file");
+        write!(file_copy, "{}", multiline_string).expect("Failed to write to temporary file");
+
+        let mutant = mutants::Mutant {
+            file_path: file_path_original.clone(),
+            line_number: 2,
+            before: " + ".into(),
+            after: " - ".into(),
+            column: 12,
+            old_line: "    return a + b".into(),
+            likely_equivalent: None,
+            mutation_type: None,
+        };
+
+        // the file has no newline at the end, which is kept
+        let snapshot = mutant.insert().unwrap();
+
+        let result = read_to_string(&file_path_original).unwrap();
+        let desired_result = String::from("def add(a, b):\n    return a - b");
+        assert_eq!(result, desired_result);
+
+        mutant.remove(&snapshot).unwrap();
+
+        let result = read_to_string(&file_path_original).unwrap();
+        let desired_result = String::from("def add(a, b):\n    return a + b");
+        assert_eq!(result, desired_result);
+
+        mutant
+            .insert_in_new_root(base_path, base_path_copy)
+            .unwrap();
+        let result = read_to_string(file_path_copy).unwrap();
+        let desired_result = String::from("def add(a, b):\n    return a - b");
+        assert_eq!(result, desired_result);
+
+        let file_name_str = file_path_original.clone().into_os_string();
+        let _file_name_str = file_name_str
+            .to_str()
+            .expect("Failed to convert file path to string!")
+            .yellow();
+
+        let _display = format!("{mutant}");
+    }
+
+    #[test]
+    fn test_insert_keeps_line_endings() {
+        let root = tempdir().unwrap();
+        let copy = tempdir().unwrap();
+        let path = root.path().join("calc.py");
+        let line = "    return a + b";
+
+        for content in [
+            // CRLF line endings, including an empty line
+            "def add(a, b):\r\n    return a + b\r\n\r\nx = 1\r\n",
+            // mixed line endings
+            "def add(a, b):\n    return a + b\r\nx = 1\n",
+            // no newline at the end of the file
+            "def add(a, b):\n    return a + b",
+            "def add(a, b):\r\n    return a + b",
+        ] {
+            fs::write(&path, content).unwrap();
+            fs::write(copy.path().join("calc.py"), content).unwrap();
+            let mutant = Mutant::new(path.clone(), 2, " + ", " - ", 12, line);
+            let expected: Vec<String> = content
+                .split_inclusive('\n')
+                .enumerate()
+                .map(|(index, line)| match index {
+                    1 => line.replacen(" + ", " - ", 1),
+                    _ => line.to_string(),
+                })
+                .collect();
+
+            let snapshot = mutant.insert().unwrap();
+            assert_eq!(fs::read(&path).unwrap(), expected.concat().as_bytes());
+            mutant.remove(&snapshot).unwrap();
+            assert_eq!(fs::read(&path).unwrap(), content.as_bytes());
+
+            mutant.insert_in_new_root(root.path(), copy.path()).unwrap();
+            assert_eq!(
+                fs::read(copy.path().join("calc.py")).unwrap(),
+                expected.concat().as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_insert_keeps_invalid_utf8() {
+        let root = tempdir().unwrap();
+        let path = root.path().join("legacy.py");
+        // a latin-1 comment, which is not valid UTF-8
+        let content =
+            b"# caf\xe9\r\ndef add(a, b):\r\n    return a + b  # \xe9t\xe9\r\nx = 1 - 2\r\n";
+        fs::write(&path, content).unwrap();
+
+        // lines that are not valid UTF-8 are not mutated, but keep the line numbers
+        let found =
+            mutants::find_mutants_in_files(std::slice::from_ref(&path), &[MutationType::MathOps])
+                .unwrap();
+        assert_eq!(
+            found
+                .iter()
+                .map(|mutant| (mutant.line_number, mutant.column))
+                .collect::<Vec<_>>(),
+            vec![(4, 5)]
+        );
+
+        let mutant = &found[0];
+        let snapshot = mutant.insert().unwrap();
+        let inserted = fs::read(&path).unwrap();
+        assert_eq!(
+            inserted,
+            b"# caf\xe9\r\ndef add(a, b):\r\n    return a + b  # \xe9t\xe9\r\nx = 1 + 2\r\n"
+        );
+        mutant.remove(&snapshot).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), content);
+
+        // a mutant on a line next to invalid UTF-8 is inserted as well
+        let mutant = Mutant::new(path.clone(), 2, "a, b", "b, a", 8, "def add(a, b):");
+        mutant.insert().unwrap();
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            b"# caf\xe9\r\ndef add(b, a):\r\n    return a + b  # \xe9t\xe9\r\nx = 1 - 2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_stale_mutant() {
+        let root = tempdir().unwrap();
+        let copy = tempdir().unwrap();
+        let content = "def add(a, b):\n    return a + b\n";
+        fs::write(root.path().join("calc.py"), content).unwrap();
+        let line = "    return a + b";
+        let path = root.path().join("calc.py");
+        let stale = |mutant: &Mutant, missing: bool| {
+            let err = mutant
+                .insert_in_new_root(root.path(), copy.path())
+                .unwrap_err();
+            assert!(
+                matches!(err, MutantError::StaleMutant { missing_line, line_number, .. }
+                    if missing_line == missing && line_number == mutant.line_number),
+                "{err}"
+            );
+            assert!(err.to_string().contains("Has the file changed?"), "{err}");
+            // nothing is written
+            assert_eq!(
+                read_to_string(copy.path().join("calc.py")).unwrap(),
+                content
+            );
+        };
+
+        // the file shrank since the mutant was found, e.g. in the cache
+        fs::write(copy.path().join("calc.py"), content).unwrap();
+        stale(&Mutant::new(path.clone(), 10, " + ", " - ", 12, line), true);
+        stale(&Mutant::new(path.clone(), 0, " + ", " - ", 12, line), true);
+
+        // the operator has been edited away
+        let content = "def add(a, b):\n    return a * b\n";
+        fs::write(copy.path().join("calc.py"), content).unwrap();
+        let mutant = Mutant::new(path.clone(), 2, " + ", " - ", 12, line);
+        let err = mutant
+            .insert_in_new_root(root.path(), copy.path())
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                MutantError::StaleMutant {
+                    missing_line: false,
+                    ..
+                }
+            ),
+            "{err}"
+        );
+        assert_eq!(
+            read_to_string(copy.path().join("calc.py")).unwrap(),
+            content
+        );
+
+        // a missing file is an error naming it
+        fs::remove_file(copy.path().join("calc.py")).unwrap();
+        let err = mutant
+            .insert_in_new_root(root.path(), copy.path())
+            .unwrap_err();
+        assert!(matches!(err, MutantError::Io { .. }), "{err}");
+        assert!(err.to_string().contains("calc.py"), "{err}");
+    }
+
+    #[test]
+    fn test_mutant_insert_at_column() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "def total(a, b, c):\n    return a + b + c\n").unwrap();
+        let path = temp_file.path().to_path_buf();
+        let line = "    return a + b + c";
+
+        let first = mutants::Mutant::new(path.clone(), 2, " + ", " - ", 12, line);
+        let second = mutants::Mutant::new(path.clone(), 2, " + ", " - ", 16, line);
+
+        let first_snapshot = first.insert().unwrap();
+        assert!(read_to_string(&path)
+            .unwrap()
+            .contains("return a - b + c\n"));
+        first.remove(&first_snapshot).unwrap();
+
+        let second_snapshot = second.insert().unwrap();
+        assert!(read_to_string(&path)
+            .unwrap()
+            .contains("return a + b - c\n"));
+        second.remove(&second_snapshot).unwrap();
+
+        // the file changed since the mutant was found
+        fs::write(&path, "def total(a, b, c):\n    return a * b + c\n").unwrap();
+        let err = first.insert().unwrap_err();
+        assert!(err.to_string().contains("Has the file changed?"), "{err}");
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            "def total(a, b, c):\n    return a * b + c\n"
+        );
+    }
+
+    #[test]
+    fn test_mutant_remove_verifies() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        let original = "def add(a, b):\n    return a + b\n\nx = a - b\n";
+        write!(temp_file, "{original}").unwrap();
+        let path = temp_file.path().to_path_buf();
+        let mutant = mutants::Mutant::new(path.clone(), 2, " + ", " - ", 12, "    return a + b");
+
+        let snapshot = mutant.insert().unwrap();
+        assert!(read_to_string(&path).unwrap().contains("return a - b"));
+        mutant.remove(&snapshot).unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), original);
+
+        // removing again leaves the file untouched
+        let err = mutant.remove(&snapshot).unwrap_err();
+        assert!(matches!(err, MutantError::NotInserted { .. }), "{err}");
+        assert!(err.to_string().contains("already removed"), "{err}");
+        assert_eq!(read_to_string(&path).unwrap(), original);
+
+        // the lines shifted while the mutant was inserted, so that its line now holds
+        // other code
+        let snapshot = mutant.insert().unwrap();
+        let shifted = "# formatted\ndef add(a, b):\n    return a - b\n\nx = a - b\n";
+        fs::write(&path, shifted).unwrap();
+        let err = mutant.remove(&snapshot).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                MutantError::NotInserted { expected, found, .. }
+                    if expected == "    return a - b" && found == "def add(a, b):"
+            ),
+            "{err}"
+        );
+        assert_eq!(read_to_string(&path).unwrap(), shifted);
+
+        // another line changed while the mutant was inserted
+        fs::write(&path, original).unwrap();
+        let snapshot = mutant.insert().unwrap();
+        fs::write(&path, "def add(a, b):\n    return a - b\n\nx = a * b\n").unwrap();
+        let err = mutant.remove(&snapshot).unwrap_err();
+        assert!(matches!(err, MutantError::ChangedFile { .. }), "{err}");
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            "def add(a, b):\n    return a + b\n\nx = a * b\n"
+        );
+    }
+
+    #[test]
+    fn test_mutant_id() {
+        let mutant = mutants::Mutant::new(
+            PathBuf::from("/project/pkg/calc.py"),
+            2,
+            " + ",
+            " - ",
+            12,
+            "    return a + b",
+        );
+        let id = mutant.id(Path::new("/project"));
+        assert_eq!(id.len(), 12);
+
+        // the id neither depends on the location of the project, nor on the
+        // indentation
+        let moved = mutants::Mutant::new(
+            PathBuf::from("/elsewhere/pkg/calc.py"),
+            2,
+            " + ",
+            " - ",
+            8,
+            "return a + b",
+        );
+        assert_eq!(moved.id(Path::new("/elsewhere")), id);
+
+        let other = mutants::Mutant::new(
+            PathBuf::from("/project/pkg/calc.py"),
+            2,
+            " + ",
+            " * ",
+            12,
+            "    return a + b",
+        );
+        assert_ne!(other.id(Path::new("/project")), id);
+
+        // the same replacement on an identical line elsewhere in the file
+        let repeated = mutants::Mutant::new(
+            PathBuf::from("/project/pkg/calc.py"),
+            5,
+            " + ",
+            " - ",
+            12,
+            "    return a + b",
+        );
+        assert_ne!(repeated.id(Path::new("/project")), id);
+    }
+
+    #[test]
+    fn test_mutant_snapshot_restore() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        let original = "def add(a, b):\n    return a + b\n\n\n# trailing comment";
+        write!(temp_file, "{original}").unwrap();
+        let path = temp_file.path().to_path_buf();
+        let mutant = mutants::Mutant::new(path.clone(), 2, " + ", " - ", 12, "    return a + b");
+
+        let snapshot = mutant.insert().unwrap();
+        assert!(read_to_string(&path).unwrap().contains("return a - b"));
+        snapshot.restore().unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), original);
+
+        // a formatter shifts the lines while the mutant is inserted, so the file is
+        // left as it is and the original is written next to it
+        let snapshot = mutant.insert().unwrap();
+        let original_path = snapshot.original_path();
+        let formatted = "# formatted\n\ndef add(a, b):\n    return a - b\n";
+        fs::write(&path, formatted).unwrap();
+        let error = snapshot.restore().unwrap_err();
+        assert!(
+            matches!(error, PymuteError::NotRestored { line_number: 2, .. }),
+            "{error}"
+        );
+        assert!(error.to_string().contains("pymute-original"), "{error}");
+        assert_eq!(read_to_string(&path).unwrap(), formatted);
+        assert_eq!(read_to_string(&original_path).unwrap(), original);
+        fs::remove_file(original_path).unwrap();
+    }
+
+    #[test]
+    fn test_add_mutants_from_file_one_line_function_bodies() {
+        let multiline_string = "def is_even(n): return n % 2 == 0
+def inc(x=1): return x + 1
+";
+
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
+
+        let replacements = build_replacements(&[MutationType::CompOps, MutationType::Numbers]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
+
+        assert_eq!(possible_mutants.len(), 2);
+        assert_eq!(possible_mutants[0].line_number, 1);
+        assert_eq!(possible_mutants[0].before, String::from("2"));
+        assert_eq!(possible_mutants[0].column, 27);
+
+        // the default value in the header must not be mutated
+        assert_eq!(possible_mutants[1].line_number, 2);
+        assert_eq!(possible_mutants[1].before, String::from("1"));
+        assert_eq!(possible_mutants[1].column, 25);
+
+        for mutant in &possible_mutants {
+            let snapshot = mutant.insert().unwrap();
+            let result = read_to_string(temp_file.path()).unwrap();
+            mutant.remove(&snapshot).unwrap();
+            match mutant.line_number {
+                1 => assert!(result.contains("def is_even(n): return n % 3 == 0\n")),
+                _ => assert!(result.contains("def inc(x=1): return x + 2\n")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_mutants_from_file_same_line_docstrings() {
+        let multiline_string = "def add(a, b): \"\"\"Return a + b.\"\"\"
+    return a + b
+
+def sub(a, b):
+    \"\"\"Return a - b.\"\"\"
+    return a - b
+
+x = \"\"\"a + b\"\"\" + c
+";
+
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
+
+        let replacements = build_replacements(&[MutationType::MathOps]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
+
+        let lines: Vec<usize> = possible_mutants.iter().map(|m| m.line_number).collect();
+        assert_eq!(lines, vec![2, 6, 8]);
+        assert_eq!(possible_mutants[2].column, 15);
+    }
+
+    #[test]
+    fn test_add_mutants_from_file_multiline_docstrings() {
+        let multiline_string = r#""""Module docstring.
+
+x = a + b is not code
+"""
+
+def add(a, b):
+    r"""Return a + b.
+
+    The sum a * b is not code either, and neither is '''a - b'''.
+    """
+    return a + b
+
+def mul(a, b):
+    return f'''{a} * {b}
+    ''' + b"""
+    still not code: a - b
+    """ * a
 
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert!(option.is_none(), "Expected the option to be None");
+x = """a""" + """b"""
+y = b"a - b" # a / b
+"#;
+
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
+
+        let replacements = build_replacements(&[MutationType::MathOps]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
+
+        let found: Vec<(usize, &str, usize)> = possible_mutants
+            .iter()
+            .map(|m| (m.line_number, m.before.as_str(), m.column))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (11, " + ", 12),
+                (15, " + ", 7),
+                (17, " * ", 7),
+                (19, " + ", 11)
+            ]
+        );
     }
 
     #[test]
-    fn test_replacement_from_line_with_double_quotes() {
-        let line = r#"print("a + b")"#;
-        let mutation_types = vec![
-            MutationType::MathOps,
-            MutationType::Conjunctions,
-            MutationType::Booleans,
-            MutationType::ControlFlow,
-            MutationType::CompOps,
-            MutationType::Numbers,
-        ];
+    fn test_add_mutants_from_file_aug_assign() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        writeln!(temp_file, "x += 1").expect("Failed to write to temporary file");
 
-        let replacements = build_replacements(&mutation_types);
+        let replacements = build_replacements(&[MutationType::MathOps, MutationType::AugAssign]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
 
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert!(option.is_none(), "Expected the option to be None");
+        // `+=` is not also mutated as a plain `+`
+        assert_eq!(possible_mutants.len(), 1);
+        assert_eq!(
+            (
+                possible_mutants[0].column,
+                possible_mutants[0].after.as_str()
+            ),
+            (1, " -= ")
+        );
+
+        let aug_assign = build_replacements(&[MutationType::AugAssign]);
+        let find = |line: &str| replacement_from_line(line, &aug_assign);
+        assert_eq!(
+            find("total -= price"),
+            Some((5, " -= ".into(), " += ".into()))
+        );
+        assert_eq!(find("self.n*=2"), Some((6, "*=".into(), "/=".into())));
+        assert_eq!(find("x /= 2"), Some((1, " /= ".into(), " *= ".into())));
+        assert_eq!(find("x //= 2"), Some((1, " //= ".into(), " *= ".into())));
+        assert_eq!(find("x %= 2"), Some((1, " %= ".into(), " *= ".into())));
+        assert_eq!(find("x **= 2"), None);
+        assert_eq!(find("x = a + b"), None);
     }
 
     #[test]
-    fn test_add_mutants_from_file() {
-        let multiline_string = "def add(a, b):
-    return a + b";
-
+    fn test_add_mutants_from_file_loop_control() {
+        let multiline_string = "def first_even(items):
+    for item in items:
+        if item % 2:
+            continue
+        breakpoint()
+        if item > 100: break
+        return item
+    return
+";
         let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
         write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
 
-        let mutation_types = vec![
-            MutationType::MathOps,
-            MutationType::Conjunctions,
-            MutationType::Booleans,
-            MutationType::ControlFlow,
-            MutationType::CompOps,
-            MutationType::Numbers,
-        ];
-
-        let replacements = build_replacements(&mutation_types);
-
+        let replacements = build_replacements(&[MutationType::LoopControl]);
         let mut possible_mutants = Vec::<mutants::Mutant>::new();
-        let _ = mutants::add_mutants_from_file(
+        mutants::add_mutants_from_file(
             &mut possible_mutants,
             &temp_file.path().to_path_buf(),
             &replacements,
-        );
+        )
+        .unwrap();
 
-        assert_eq!(possible_mutants.len(), 1);
-        assert_eq!(possible_mutants[0].line_number, 2);
-        assert_eq!(possible_mutants[0].before, String::from(" + "));
-        assert_eq!(possible_mutants[0].after, String::from(" - "));
+        let found: Vec<(usize, usize, &str, &str)> = possible_mutants
+            .iter()
+            .map(|m| (m.line_number, m.column, m.before.as_str(), m.after.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (4, 12, "continue", "break"),
+                (6, 23, "break", "continue"),
+                (8, 4, "return", "pass"),
+            ]
+        );
     }
 
     #[test]
-    fn test_add_mutants_from_file_trickier() {
+    fn test_add_mutants_from_file_return_values() {
         let multiline_string = "def add(a, b):
     return a + b
 
-# this is a + comment
-def sub(a, b):
-    return a - b
+def check(x):
+    if x:
+        return True
+    return False
 
-res = sub(5, 6) * add(7, 8)
-print(res) # print the result *
-";
+def nothing():
+    return None
+
+def pair(a, b):
+    return (a,
+            b)
 
+def greet(name):
+    return f\"hello {name}\"
+";
         let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
         write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
 
-        let mutation_types = vec![
-            MutationType::MathOps,
-            MutationType::Conjunctions,
-            MutationType::Booleans,
-            MutationType::ControlFlow,
-            MutationType::CompOps,
-            MutationType::Numbers,
-        ];
-
-        let replacements = build_replacements(&mutation_types);
+        let replacements = build_replacements(&[MutationType::MathOps, MutationType::ReturnValues]);
         let mut possible_mutants = Vec::<mutants::Mutant>::new();
-        let _ = mutants::add_mutants_from_file(
+        mutants::add_mutants_from_file(
             &mut possible_mutants,
             &temp_file.path().to_path_buf(),
             &replacements,
-        );
+        )
+        .unwrap();
 
-        assert_eq!(possible_mutants.len(), 3);
+        let found: Vec<(usize, usize, &str, &str)> = possible_mutants
+            .iter()
+            .map(|m| (m.line_number, m.column, m.before.as_str(), m.after.as_str()))
+            .collect();
+        // the return value is mutated in addition to the operator on the same line
+        assert_eq!(
+            found,
+            vec![
+                (2, 12, " + ", " - "),
+                (2, 11, "a + b", "None"),
+                (6, 15, "True", "False"),
+                (7, 11, "False", "True"),
+                (17, 11, "f\"hello {name}\"", "None"),
+            ]
+        );
+    }
 
-        assert_eq!(possible_mutants[0].line_number, 2);
-        assert_eq!(possible_mutants[0].before, String::from(" + "));
-        assert_eq!(possible_mutants[0].after, String::from(" - "));
+    #[test]
+    fn test_find_string_literal() {
+        let find = |line: &str| {
+            let code = mutants::mask_strings(line, &mut None);
+            mutants::find_string_literal_in(&code, line)
+        };
 
-        assert_eq!(possible_mutants[1].line_number, 6);
-        assert_eq!(possible_mutants[1].before, String::from(" - "));
-        assert_eq!(possible_mutants[1].after, String::from(" + "));
+        assert_eq!(
+            find("raise ValueError(\"bad input\")"),
+            Some((17, "\"bad input\"".into(), "\"XXPYMUTEXX\"".into()))
+        );
+        assert_eq!(find("key = ''"), Some((6, "''".into(), "'XX'".into())));
+        assert_eq!(
+            find("print(f\"hello {name}\", end='')"),
+            Some((6, "f\"hello {name}\"".into(), "f\"XXPYMUTEXX\"".into()))
+        );
+        assert_eq!(
+            find("x = rb'\\d' # 'comment'"),
+            Some((4, "rb'\\d'".into(), "rb'XXPYMUTEXX'".into()))
+        );
 
-        assert_eq!(possible_mutants[2].line_number, 8);
-        assert_eq!(possible_mutants[2].before, String::from(" * "));
-        assert_eq!(possible_mutants[2].after, String::from(" / "));
+        // docstrings, imports, dunder assignments and comments are skipped
+        assert_eq!(find("    \"\"\"docstring\"\"\""), None);
+        assert_eq!(find("    'just a string'"), None);
+        assert_eq!(find("from pkg import \"name\""), None);
+        assert_eq!(find("__all__ = [\"add\", \"mul\"]"), None);
+        assert_eq!(find("x = 1  # \"quoted\""), None);
+        // strings continued from a previous line are not mutated
+        let mut open_string = None;
+        mutants::mask_strings("x = \"\"\"start", &mut open_string);
+        let line = "end\"\"\" + y";
+        let code = mutants::mask_strings(line, &mut open_string);
+        assert_eq!(mutants::find_string_literal_in(&code, line), None);
     }
 
     #[test]
-    fn test_replacement_from_line_none() {
-        let line = "print('Hello World')";
-        let mutation_types = vec![
-            MutationType::MathOps,
-            MutationType::Conjunctions,
-            MutationType::Booleans,
-            MutationType::ControlFlow,
-            MutationType::CompOps,
-            MutationType::Numbers,
-        ];
+    fn test_add_mutants_from_file_strings() {
+        let multiline_string = "def check(value):
+    \"\"\"Check the value.\"\"\"
+    if value < 0:
+        raise ValueError(\"bad input\")
+    return value
+";
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
 
-        let replacements = build_replacements(&mutation_types);
-        let option = mutants::replacement_from_line(line, &replacements);
-        println!("{:?}", option);
-        assert!(option.is_none(), "Expected the option to be None");
+        let replacements = build_replacements(&[MutationType::CompOps, MutationType::Strings]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
+
+        let found: Vec<(usize, &str, &str)> = possible_mutants
+            .iter()
+            .map(|m| (m.line_number, m.before.as_str(), m.after.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(3, " < ", " > "), (4, "\"bad input\"", "\"XXPYMUTEXX\""),]
+        );
     }
 
     #[test]
-    fn test_replacement_from_line_math_operators() {
-        let mutation_types = vec![
-            MutationType::MathOps,
-            MutationType::Conjunctions,
-            MutationType::Booleans,
-            MutationType::ControlFlow,
-            MutationType::CompOps,
-            MutationType::Numbers,
-        ];
+    fn test_add_mutants_from_file_pragmas() {
+        let multiline_string = "__version__ = \"1.2.3\"
+MAX_RETRIES = 3  # pragma: no mutate
+TIMEOUT = 10  # noqa: E501  # nomutate
+url = \"http://host/#pragma: no mutate\" + path
+def add(a, b):
+    return a + b
+";
 
-        let replacements = build_replacements(&mutation_types);
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
 
-        let line = "5 + 5";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), (" + ".into(), " - ".into()));
+        let replacements = build_replacements(&DEFAULT_MUTATION_TYPES);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
 
-        let line = "5 - 5";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), (" - ".into(), " + ".into()));
+        // a pragma inside a string is not a comment
+        let lines: Vec<usize> = possible_mutants.iter().map(|m| m.line_number).collect();
+        assert_eq!(lines, vec![4, 6]);
 
-        let line = "5 * 5";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), (" * ".into(), " / ".into()));
+        // a file-level pragma in the first lines excludes everything
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(
+            temp_file,
+            "x = 1 + 2\n#!/usr/bin/env python\n# pragma: no mutate file\n{multiline_string}"
+        )
+        .expect("Failed to write to temporary file");
 
-        let line = "5 / 5";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), (" / ".into(), " * ".into()));
-    }
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
+        assert!(possible_mutants.is_empty());
 
-    #[test]
-    fn test_replacement_from_line_conjunctions() {
-        let mutation_types = vec![
-            MutationType::MathOps,
-            MutationType::Conjunctions,
-            MutationType::Booleans,
-            MutationType::ControlFlow,
-            MutationType::CompOps,
-            MutationType::Numbers,
-        ];
+        // but only in the first lines
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        writeln!(temp_file, "{multiline_string}# pragma: no mutate file")
+            .expect("Failed to write to temporary file");
 
-        let replacements = build_replacements(&mutation_types);
-        let line = "True and False";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), (" and ".into(), " or ".into()));
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
+        assert_eq!(possible_mutants.len(), 2);
+    }
 
-        let line = "True or False";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), (" or ".into(), " and ".into()));
+    #[test]
+    fn test_compound_statement_body() {
+        assert_eq!(
+            mutants::compound_statement_body("def f(x: int) -> int: return x"),
+            Some(21)
+        );
+        assert_eq!(
+            mutants::compound_statement_body("    if d[\"a:b\"]: pass"),
+            Some(16)
+        );
+        assert_eq!(mutants::compound_statement_body("if x:"), None);
+        assert_eq!(
+            mutants::compound_statement_body("while (n := n - 1):  "),
+            None
+        );
+        assert_eq!(mutants::compound_statement_body("elsewhere: int = 5"), None);
+        assert_eq!(mutants::compound_statement_body("x = {1: 2}"), None);
     }
 
     #[test]
-    fn test_replacement_from_line_comparison_operators() {
-        let mutation_types = vec![
-            MutationType::MathOps,
-            MutationType::Conjunctions,
-            MutationType::Booleans,
-            MutationType::ControlFlow,
-            MutationType::CompOps,
-            MutationType::Numbers,
-        ];
+    fn test_add_mutants_from_file_time() {
+        let multiline_string = "from datetime import datetime, timedelta
 
-        let replacements = build_replacements(&mutation_types);
+def deadline(start):
+    return start + timedelta(3)
 
-        let line = "5 == 5";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), ("==".into(), "!=".into()));
+def reminder(start):
+    return start - timedelta(2)
 
-        let line = "5 != 5";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), ("!=".into(), "==".into()));
+def delay():
+    return timedelta(days=3)
 
-        let line = "5 > 5";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), (" > ".into(), " < ".into()));
+def grace_period():
+    return timedelta(minutes=5, hours=1)
 
-        let line = "5 < 5";
-        let option = mutants::replacement_from_line(line, &replacements);
-        assert_eq!(option.unwrap(), (" < ".into(), " > ".into()));
-    }
+def stamp():
+    return datetime.utcnow()
 
-    #[test]
-    fn test_mutant_insert() {
-        let multiline_string = "def add(a, b):
-    return a + b";
+def local():
+    return datetime.now()
 
-        let temp_dir = tempdir().unwrap();
-        let base_path = temp_dir.path();
-        let file_path_original = base_path.join("script.py");
+def not_a_timedelta(days):
+    return dict(days=days)
+";
 
-        let _temp_dir_copy = tempdir().unwrap();
-        let base_path_copy = temp_dir.path();
-        let file_path_copy = base_path_copy.join("script.py");
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
 
-        let mut file_original = File::create(&file_path_original).unwrap();
-        let mut file_copy = File::create(&file_path_copy).unwrap();
-        write!(file_original, "{}", multiline_string).expect("Failed to write to temporary file");
-        write!(file_copy, "{}", multiline_string).expect("Failed to write to temporary file");
+        let replacements = build_replacements(&[MutationType::Time]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
 
-        let mutant = mutants::Mutant {
-            file_path: file_path_original.clone(),
-            line_number: 2,
-            before: " + ".into(),
-            after: " - ".into(),
-            old_line: "    return a + b".into(),
-        };
+        let found: Vec<(usize, &str, &str)> = possible_mutants
+            .iter()
+            .map(|m| (m.line_number, m.before.as_str(), m.after.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (4, "+ timedelta", "- timedelta"),
+                (7, "- timedelta", "+ timedelta"),
+                (10, "days=", "hours="),
+                (13, "minutes=", "seconds="),
+                (16, ".utcnow()", ".now()"),
+                (19, ".now()", ".utcnow()"),
+            ]
+        );
+    }
 
-        mutant.insert().unwrap();
+    #[test]
+    fn test_replacement_from_line_builtins() {
+        let replacements = build_replacements(&[MutationType::Builtins]);
+        let find = |line: &str| replacement_from_line(line, &replacements);
 
-        let result = read_to_string(&file_path_original).unwrap();
-        let desired_result = String::from("def add(a, b):\n    return a - b\n");
-        assert_eq!(result, desired_result);
+        assert_eq!(
+            find("best = max(scores)"),
+            Some((7, "max(".into(), "min(".into()))
+        );
+        assert_eq!(
+            find("    if any(checks):"),
+            Some((7, "any(".into(), "all(".into()))
+        );
+        assert_eq!(
+            find("total = sum(values)"),
+            Some((8, "sum(".into(), "len(".into()))
+        );
+        assert_eq!(
+            find("ranked = sorted(items, key=len, reverse=True)"),
+            Some((32, "reverse=True".into(), "reverse=False".into()))
+        );
+        assert_eq!(
+            find("items.sort(reverse=False)"),
+            Some((11, "reverse=False".into(), "reverse=True".into()))
+        );
 
-        mutant.remove().unwrap();
+        // only whole names of builtins and only flags of sorting
+        assert_eq!(find("best = argmax(scores)"), None);
+        assert_eq!(find("top = maximum(a, b)"), None);
+        assert_eq!(find("digest = checksum(data)"), None);
+        assert_eq!(find("plot(xs, reverse=True)"), None);
+    }
 
-        let result = read_to_string(&file_path_original).unwrap();
-        let desired_result = String::from("def add(a, b):\n    return a + b\n");
-        assert_eq!(result, desired_result);
+    #[test]
+    fn test_add_mutants_from_file_builtins() {
+        let multiline_string = "import numpy as np
 
-        mutant
-            .insert_in_new_root(base_path, base_path_copy)
-            .unwrap();
-        let result = read_to_string(file_path_copy).unwrap();
-        let desired_result = String::from("def add(a, b):\n    return a - b\n");
-        assert_eq!(result, desired_result);
+def best(scores):
+    return max(scores)
 
-        let file_name_str = file_path_original.clone().into_os_string();
-        let _file_name_str = file_name_str
-            .to_str()
-            .expect("Failed to convert file path to string!")
-            .yellow();
+def valid(checks):
+    if any(checks):
+        return True
+    return np.argmax(checks)
+";
 
-        let _display = format!("{mutant}");
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temporary file");
+        write!(temp_file, "{}", multiline_string).expect("Failed to write to temporary file");
+
+        let replacements = build_replacements(&[MutationType::Builtins]);
+        let mut possible_mutants = Vec::<mutants::Mutant>::new();
+        mutants::add_mutants_from_file(
+            &mut possible_mutants,
+            &temp_file.path().to_path_buf(),
+            &replacements,
+        )
+        .unwrap();
+
+        let found: Vec<(usize, &str, &str)> = possible_mutants
+            .iter()
+            .map(|m| (m.line_number, m.before.as_str(), m.after.as_str()))
+            .collect();
+        assert_eq!(found, vec![(4, "max(", "min("), (7, "any(", "all(")]);
+        assert!(!crate::config::DEFAULT_MUTATION_TYPES.contains(&MutationType::Builtins));
+    }
+
+    #[test]
+    fn test_time_not_in_defaults() {
+        assert!(!crate::config::DEFAULT_MUTATION_TYPES.contains(&MutationType::Time));
     }
 }
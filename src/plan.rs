@@ -0,0 +1,932 @@
+//! Two-phase execution of a run, for embedding pymute in job queues.
+//!
+//! `plan` finds the mutants to run and resolves everything needed to run them into
+//! a serializable `RunPlan`. Any subset of the planned mutants can then be run with
+//! `execute_chunk`, e.g. in separate jobs on separate machines, and `finalize`
+//! combines the outcomes of all chunks into the summary of the run. `run` is
+//! exactly these three steps in a single process.
+//!
+//! None of the functions keep process-global state, and `execute_chunk` does not
+//! take the lock on the state directory, so chunks of the same plan can run
+//! concurrently. They fail with a `PymuteError`, which can be sent to other
+//! threads.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use pymute::{plan, RunConfig};
+//!
+//! let plan = plan::plan(&RunConfig::new("path/to/python/project")).unwrap();
+//! let ids = plan.ids();
+//! let (first, second) = ids.split_at(ids.len() / 2);
+//!
+//! let mut outcomes = plan::execute_chunk(&plan, first).unwrap();
+//! outcomes.extend(plan::execute_chunk(&plan, second).unwrap());
+//! let summary = plan::finalize(&plan, outcomes).unwrap();
+//! ```
+
+use crate::coverage::{Coverage, CoverageMap};
+use crate::error::PymuteError;
+use crate::mutants::{find_files_matching, find_mutants_in_files, Mutant, MutationType};
+use crate::runner::{self, MutantOutcome, MutantResult};
+use crate::selection::TestMapping;
+use crate::subprojects::{SubprojectSummary, Subprojects};
+use crate::{diff, scope, syntax, verdicts, RunConfig, RunSummary};
+
+use clap::ValueEnum;
+use glob::Pattern;
+use rand::{seq::IteratorRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Stable identifier of a mutant, see `Mutant::id`.
+pub type MutantId = String;
+
+/// A mutant together with its id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedMutant {
+    /// Id of the mutant. Only mutants that cannot be told apart (the same
+    /// replacement at the same position of a file) share their id.
+    pub id: MutantId,
+    /// The mutant.
+    pub mutant: Mutant,
+}
+
+/// Format of the list of mutants, see `write_mutant_list`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListFormat {
+    /// human: one colored line per mutant, as printed during a run.
+    #[default]
+    Human,
+    /// plain: one tab-separated record per mutant, without color.
+    Plain,
+    /// json: an array of mutant objects.
+    Json,
+}
+
+/// How `RunConfig::max_mutants` mutants are sampled.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sampling {
+    /// global: sample from all mutants at once.
+    #[default]
+    Global,
+    /// per-file: split the budget across files proportionally to their numbers
+    /// of mutants, with at least one mutant per file if the budget allows.
+    PerFile,
+}
+
+/// Order in which the mutants of a run are started.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    /// file: in the order they were found, i.e. by file and line.
+    #[default]
+    File,
+    /// priority: mutants that survived the previous run first.
+    Priority,
+    /// duration: mutants whose tests ran longest in the previous run first, so
+    /// that no long mutant is left running alone at the end.
+    Duration,
+    /// random: in a random order, using the seed.
+    Random,
+}
+
+/// A mutant in the JSON list of mutants.
+#[derive(Serialize)]
+struct ListedMutant<'a> {
+    id: &'a str,
+    /// Path of the file relative to the root of the project.
+    file_path: &'a Path,
+    line_number: usize,
+    column: usize,
+    before: &'a str,
+    after: &'a str,
+    mutation_type: Option<MutationType>,
+}
+
+/// How the mutants are kept apart from each other and from the project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Isolation {
+    /// Each mutant is inserted into a working copy of the project, see
+    /// `runner::WorkdirPool`.
+    WorkingCopies {
+        /// Glob patterns of files and directories not copied into the working
+        /// copies.
+        excludes: Vec<String>,
+    },
+}
+
+/// Everything needed to run the mutants of a run, possibly in chunks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunPlan {
+    /// Configuration of the run.
+    pub config: RunConfig,
+    /// Mutants to run.
+    pub mutants: Vec<PlannedMutant>,
+    /// Mutants that are not run because their file has no associated tests (see
+    /// `RunConfig::skip_untested_files`).
+    pub untested: Vec<PlannedMutant>,
+    /// Files without any associated tests, with their number of mutants.
+    pub untested_files: BTreeMap<PathBuf, usize>,
+    /// Mutants that are not run because no test covers their line (see
+    /// `RunConfig::coverage_guided`).
+    #[serde(default)]
+    pub uncovered: Vec<PlannedMutant>,
+    /// Tests covering each line, for coverage-guided runs.
+    #[serde(default)]
+    pub coverage: Option<CoverageMap>,
+    /// Files excluded because of syntax errors, with the error message.
+    pub invalid_files: BTreeMap<PathBuf, String>,
+    /// Number of mutants discovered before any sampling.
+    pub population: usize,
+    /// Number of discovered mutants excluded by `RunConfig::max_nesting` and
+    /// `RunConfig::max_function_lines`.
+    #[serde(default)]
+    pub out_of_scope: usize,
+    /// The test command run for each mutant, with the program first.
+    pub command: Vec<String>,
+    /// How the mutants are isolated.
+    pub isolation: Isolation,
+    /// Subprojects with their own settings, see `subprojects`.
+    #[serde(default)]
+    pub subprojects: Subprojects,
+}
+
+impl RunPlan {
+    /// Ids of all mutants to run, in order and without duplicates.
+    pub fn ids(&self) -> Vec<MutantId> {
+        let mut seen = HashSet::new();
+        self.mutants
+            .iter()
+            .filter(|planned| seen.insert(&planned.id))
+            .map(|planned| planned.id.clone())
+            .collect()
+    }
+}
+
+/// Find the mutants to run for `config` and resolve how to run them.
+pub fn plan(config: &RunConfig) -> Result<RunPlan, PymuteError> {
+    let root = &config.root;
+    // the interpreter also compiles the files before discovery
+    runner::validate_programs(config)?;
+    // the glob is anchored at the root, so the root itself must not be interpreted
+    // as a pattern
+    let not_utf8 = |pattern: &Path| PymuteError::InvalidGlob {
+        pattern: pattern.display().to_string(),
+        reason: "the path is not valid UTF-8".into(),
+    };
+    let root_pattern = Pattern::escape(root.to_str().ok_or_else(|| not_utf8(root))?);
+    let modules = config
+        .modules
+        .iter()
+        .map(|modules| {
+            let modules: PathBuf = [&PathBuf::from(&root_pattern), &PathBuf::from(modules)]
+                .iter()
+                .collect();
+            modules
+                .to_str()
+                .map(String::from)
+                .ok_or_else(|| not_utf8(&modules))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let modules: Vec<&str> = modules.iter().map(String::as_str).collect();
+
+    let excludes = config
+        .exclude
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|error| PymuteError::InvalidGlob {
+                pattern: pattern.clone(),
+                reason: error.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let files: Vec<PathBuf> = find_files_matching(&modules, config.include_tests)?
+        .into_iter()
+        .filter(|file| !is_excluded_module(file.strip_prefix(root).unwrap_or(file), &excludes))
+        .collect();
+
+    // files that do not compile would only produce mutants failing at collection
+    let invalid_files = match config.check_syntax {
+        true => syntax::check_syntax(&runner::python(config), &files)?,
+        false => BTreeMap::new(),
+    };
+    let subprojects = Subprojects::discover(root, &files)?;
+    let files: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| !invalid_files.contains_key(file) && !subprojects.skips(file))
+        .collect();
+
+    // consecutive files with the same mutation types are searched together
+    let mutation_types = |file: &PathBuf| subprojects.mutation_types(file, &config.mutation_types);
+    let mut mutants = Vec::new();
+    for files in files.chunk_by(|a, b| mutation_types(a) == mutation_types(b)) {
+        mutants.extend(find_mutants_in_files(files, mutation_types(&files[0]))?);
+    }
+    let population = mutants.len();
+    let mutants = select_mutant_ids(mutants, &config.mutant_ids, root)?;
+    let mutants = match &config.diff_base {
+        Some(base) => diff::filter_mutants(mutants, &diff::changed_lines(root, base)?, root),
+        None => mutants,
+    };
+    let (mutants, out_of_scope) =
+        scope::filter_mutants(mutants, config.max_nesting, config.max_function_lines)?;
+
+    let mutants = match config.max_mutants {
+        Some(max) => sample_mutants(mutants, max, config.sampling, config.seed),
+        None => mutants,
+    };
+    // every shard splits the same sample
+    let mutants = match config.shard {
+        Some(shard) => shard.filter_mutants(mutants),
+        None => mutants,
+    };
+
+    let mapping = TestMapping::new(root, &config.tests)?;
+    let untested_files = mapping.untested_files(&mutants);
+
+    let planned = |mutant: Mutant| PlannedMutant {
+        id: mutant.id(root),
+        mutant,
+    };
+    let (mutants, untested): (Vec<_>, Vec<_>) =
+        mutants.into_iter().map(planned).partition(|planned| {
+            !config.skip_untested_files || !untested_files.contains_key(&planned.mutant.file_path)
+        });
+    let coverage = CoverageMap::for_config(config)?;
+    let (mutants, uncovered): (Vec<_>, Vec<_>) = mutants.into_iter().partition(|planned| {
+        coverage
+            .as_ref()
+            .is_none_or(|coverage| coverage.coverage(&planned.mutant, root) != Coverage::Uncovered)
+    });
+
+    let excludes = runner::copy_excludes(config)?
+        .iter()
+        .map(|pattern| pattern.as_str().to_string())
+        .collect();
+
+    Ok(RunPlan {
+        config: config.clone(),
+        mutants,
+        untested,
+        untested_files,
+        uncovered,
+        coverage,
+        invalid_files,
+        population,
+        out_of_scope,
+        command: runner::test_command_line(config)?,
+        isolation: Isolation::WorkingCopies { excludes },
+        subprojects,
+    })
+}
+
+/// Choose `max` of the `mutants` at random, using `sampling` and `seed`.
+fn sample_mutants(mutants: Vec<Mutant>, max: usize, sampling: Sampling, seed: u64) -> Vec<Mutant> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    if sampling == Sampling::Global || max >= mutants.len() {
+        return mutants.into_iter().choose_multiple(&mut rng, max);
+    }
+
+    let mut files: Vec<(PathBuf, Vec<Mutant>)> = Vec::new();
+    for mutant in mutants {
+        match files.iter_mut().find(|(file, _)| *file == mutant.file_path) {
+            Some((_, file_mutants)) => file_mutants.push(mutant),
+            None => files.push((mutant.file_path.clone(), vec![mutant])),
+        }
+    }
+    let counts: Vec<usize> = files.iter().map(|(_, mutants)| mutants.len()).collect();
+    let budgets = split_budget(&counts, max);
+    files
+        .into_iter()
+        .zip(budgets)
+        .flat_map(|((_, mutants), budget)| mutants.into_iter().choose_multiple(&mut rng, budget))
+        .collect()
+}
+
+/// Split `budget` (less than the sum of `counts`) into a budget per count that is
+/// proportional to the count. If the budget allows, every budget is at least one.
+///
+/// Each count first gets its minimum, and the rest of the budget is split with the
+/// largest remainder method, so that no budget exceeds its count.
+fn split_budget(counts: &[usize], budget: usize) -> Vec<usize> {
+    let (mut budgets, capacities): (Vec<usize>, Vec<usize>) = match budget >= counts.len() {
+        true => counts.iter().map(|&count| (1, count - 1)).unzip(),
+        false => counts.iter().map(|&count| (0, count)).unzip(),
+    };
+    let rest = budget - budgets.iter().sum::<usize>();
+    let capacity: usize = capacities.iter().sum();
+    let mut remainders = Vec::new();
+    for (index, &count) in capacities.iter().enumerate() {
+        budgets[index] += rest * count / capacity;
+        remainders.push((rest * count % capacity, index));
+    }
+    let missing = budget - budgets.iter().sum::<usize>();
+    // the largest remainders first, earlier counts first among equal ones
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for &(_, index) in &remainders[..missing] {
+        budgets[index] += 1;
+    }
+    budgets
+}
+
+/// Whether the module at `relative` (to the root) or one of its directories matches
+/// any of the `excludes`.
+fn is_excluded_module(relative: &Path, excludes: &[Pattern]) -> bool {
+    relative
+        .ancestors()
+        .filter(|path| !path.as_os_str().is_empty())
+        .any(|path| excludes.iter().any(|pattern| pattern.matches_path(path)))
+}
+
+/// Keep only the mutants with one of the given `ids` (relative to `root`), or all
+/// mutants if there are no ids.
+///
+/// Fails with `PymuteError::NoSuchMutant` if an id does not belong to any mutant,
+/// suggesting similar ids.
+fn select_mutant_ids(
+    mutants: Vec<Mutant>,
+    ids: &[MutantId],
+    root: &Path,
+) -> Result<Vec<Mutant>, PymuteError> {
+    if ids.is_empty() {
+        return Ok(mutants);
+    }
+
+    let known: Vec<MutantId> = mutants.iter().map(|mutant| mutant.id(root)).collect();
+    if let Some(id) = ids.iter().find(|id| !known.contains(id)) {
+        return Err(PymuteError::NoSuchMutant {
+            id: id.clone(),
+            near_matches: near_matches(id, &known),
+        });
+    }
+    Ok(mutants
+        .into_iter()
+        .zip(known)
+        .filter(|(_, id)| ids.contains(id))
+        .map(|(mutant, _)| mutant)
+        .collect())
+}
+
+/// The ids in `known` that are probably meant by `id`, i.e. that differ from it in
+/// at most two characters or start with it (e.g. a truncated id), most similar first.
+fn near_matches(id: &str, known: &[MutantId]) -> Vec<MutantId> {
+    let mut near: Vec<(usize, &MutantId)> = known
+        .iter()
+        .map(|candidate| (edit_distance(id, candidate), candidate))
+        .filter(|(distance, candidate)| {
+            *distance <= 2 || (id.len() >= 4 && candidate.starts_with(id))
+        })
+        .collect();
+    near.sort();
+    near.dedup_by_key(|(_, candidate)| *candidate);
+    near.into_iter()
+        .take(MAX_NEAR_MATCHES)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+/// Number of similar ids suggested for an unknown id.
+const MAX_NEAR_MATCHES: usize = 5;
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Run the planned mutants with the given `ids`.
+///
+/// All mutants sharing one of the ids are run. Fails with
+/// `PymuteError::NotInPlan` if an id is not part of the plan.
+pub fn execute_chunk(plan: &RunPlan, ids: &[MutantId]) -> Result<Vec<MutantOutcome>, PymuteError> {
+    let known: HashSet<&MutantId> = plan.mutants.iter().map(|planned| &planned.id).collect();
+    if let Some(id) = ids.iter().find(|id| !known.contains(id)) {
+        return Err(PymuteError::NotInPlan { id: id.clone() });
+    }
+
+    let ids: HashSet<&MutantId> = ids.iter().collect();
+    let mutants: Vec<Mutant> = plan
+        .mutants
+        .iter()
+        .filter(|planned| ids.contains(&planned.id))
+        .map(|planned| planned.mutant.clone())
+        .collect();
+    runner::run_mutants_with_coverage(
+        &plan.config,
+        &mutants,
+        &plan.subprojects,
+        plan.coverage.as_ref(),
+    )
+}
+
+/// Combine the outcomes of all chunks of `plan` into the summary of the run.
+///
+/// The untested and uncovered mutants of the plan are added with their result,
+/// and the verdicts in the state directory are applied. Each subproject is scored
+/// on its own mutants. The duration of the summary is zero, since the chunks may
+/// have run anywhere.
+pub fn finalize(
+    plan: &RunPlan,
+    mut outcomes: Vec<MutantOutcome>,
+) -> Result<RunSummary, PymuteError> {
+    outcomes.extend(plan.untested.iter().map(|planned| MutantOutcome {
+        mutant: planned.mutant.clone(),
+        result: MutantResult::Untested,
+        duration: None,
+        error: None,
+    }));
+    outcomes.extend(plan.uncovered.iter().map(|planned| MutantOutcome {
+        mutant: planned.mutant.clone(),
+        result: MutantResult::Uncovered,
+        duration: None,
+        error: None,
+    }));
+
+    let config = &plan.config;
+    let verdicts = verdicts::read_verdicts(&config.state().verdicts_file())?;
+    let summarize = |outcomes: Vec<MutantOutcome>| {
+        let mut summary = RunSummary::new(outcomes, Duration::ZERO);
+        summary.include_uncovered = config.include_uncovered;
+        if !config.include_likely_equivalent {
+            summary.exclude_likely_equivalent();
+        }
+        summary.apply_verdicts(&verdicts, &config.root);
+        summary
+    };
+
+    let subprojects = plan
+        .subprojects
+        .0
+        .iter()
+        .map(|subproject| {
+            let outcomes = outcomes
+                .iter()
+                .filter(|outcome| {
+                    plan.subprojects.find(&outcome.mutant.file_path) == Some(subproject)
+                })
+                .cloned()
+                .collect();
+            SubprojectSummary::new(subproject, &summarize(outcomes))
+        })
+        .collect();
+
+    let mut summary = summarize(outcomes);
+    summary.untested_files = plan.untested_files.keys().cloned().collect();
+    summary.invalid_files = plan.invalid_files.clone();
+    summary.subprojects = subprojects;
+    Ok(summary)
+}
+
+/// Write the planned `mutants` of the project at `root` to `out` in `format`.
+///
+/// The plain format has one record per line with the fields path (relative to the
+/// root), line, before, after, mutation type and id separated by tabs. Mutants
+/// without a mutation type have an empty field.
+pub fn write_mutant_list(
+    out: &mut impl Write,
+    mutants: &[PlannedMutant],
+    root: &Path,
+    format: ListFormat,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let relative = |mutant: &PlannedMutant| -> PathBuf {
+        let path = &mutant.mutant.file_path;
+        path.strip_prefix(root).unwrap_or(path).to_path_buf()
+    };
+    match format {
+        ListFormat::Human => {
+            for planned in mutants {
+                writeln!(out, "{} (id {})", planned.mutant, planned.id)?;
+            }
+        }
+        ListFormat::Plain => {
+            for planned in mutants {
+                let mutant = &planned.mutant;
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    relative(planned).display(),
+                    mutant.line_number,
+                    mutant.before,
+                    mutant.after,
+                    mutant
+                        .mutation_type
+                        .map_or(String::new(), |mutation_type| mutation_type.to_string()),
+                    planned.id
+                )?;
+            }
+        }
+        ListFormat::Json => {
+            let paths: Vec<PathBuf> = mutants.iter().map(relative).collect();
+            let listed: Vec<ListedMutant> = mutants
+                .iter()
+                .zip(&paths)
+                .map(|(planned, file_path)| ListedMutant {
+                    id: &planned.id,
+                    file_path,
+                    line_number: planned.mutant.line_number,
+                    column: planned.mutant.column,
+                    before: &planned.mutant.before,
+                    after: &planned.mutant.after,
+                    mutation_type: planned.mutant.mutation_type,
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut *out, &listed)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutants::MutationType;
+    use crate::plan::{
+        edit_distance, execute_chunk, finalize, near_matches, plan, split_budget,
+        write_mutant_list, ListFormat, RunPlan, Sampling,
+    };
+    use crate::runner::{MutantOutcome, MutantResult, Runner};
+    use crate::{run, PymuteError, RunConfig};
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// The outcomes in a fixed order, without their durations.
+    fn sorted(outcomes: &[MutantOutcome]) -> Vec<String> {
+        let mut outcomes: Vec<String> = outcomes
+            .iter()
+            .map(|outcome| format!("{:?} {:?}", outcome.mutant, outcome.result))
+            .collect();
+        outcomes.sort();
+        outcomes
+    }
+
+    #[test]
+    fn test_write_mutant_list() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/calc.py"),
+            "def add(a, b):\n    return a + b\n",
+        )
+        .unwrap();
+        let config = RunConfig::new(root).mutation_types(&[MutationType::MathOps]);
+        let plan = plan(&config).unwrap();
+        let mutant = &plan.mutants[0];
+
+        let mut plain = Vec::new();
+        write_mutant_list(&mut plain, &plan.mutants, root, ListFormat::Plain).unwrap();
+        let plain = String::from_utf8(plain).unwrap();
+        assert_eq!(plain.lines().count(), plan.mutants.len());
+        let fields: Vec<&str> = plain.lines().next().unwrap().split('\t').collect();
+        assert_eq!(
+            fields,
+            [
+                "src/calc.py",
+                "2",
+                " + ",
+                &mutant.mutant.after,
+                "math-ops",
+                &mutant.id
+            ]
+        );
+        assert!(!plain.contains('\x1b'));
+
+        let mut json = Vec::new();
+        write_mutant_list(&mut json, &plan.mutants, root, ListFormat::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let listed = json.as_array().unwrap();
+        assert_eq!(listed.len(), plan.mutants.len());
+        assert_eq!(listed[0]["id"], mutant.id.as_str());
+        assert_eq!(listed[0]["file_path"], "src/calc.py");
+        assert_eq!(listed[0]["line_number"], 2);
+        assert_eq!(listed[0]["before"], " + ");
+        assert_eq!(listed[0]["mutation_type"], "math-ops");
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_per_file_sampling() {
+        assert_eq!(split_budget(&[100, 10, 2], 12), [9, 2, 1]);
+        assert_eq!(split_budget(&[100, 10, 2], 3), [1, 1, 1]);
+        assert_eq!(split_budget(&[100, 10, 2], 2), [2, 0, 0]);
+        assert_eq!(split_budget(&[3, 3], 5), [3, 2]);
+
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let lines = |count: usize| "x = a + b\n".repeat(count);
+        fs::write(root.join("big.py"), lines(60)).unwrap();
+        fs::write(root.join("medium.py"), lines(8)).unwrap();
+        fs::write(root.join("small.py"), lines(1)).unwrap();
+        let config = RunConfig::new(root)
+            .mutation_types(&[MutationType::MathOps])
+            .max_mutants(4);
+        let count = |plan: &RunPlan, file: &str| {
+            plan.mutants
+                .iter()
+                .filter(|planned| planned.mutant.file_path.ends_with(file))
+                .count()
+        };
+
+        let per_file = plan(&config.clone().sampling(Sampling::PerFile)).unwrap();
+        assert_eq!(per_file.mutants.len(), 4);
+        for file in ["big.py", "medium.py", "small.py"] {
+            assert!(count(&per_file, file) >= 1, "{file}");
+        }
+        assert_eq!(count(&per_file, "big.py"), 2);
+        // the sample is reproducible
+        assert_eq!(
+            plan(&config.clone().sampling(Sampling::PerFile))
+                .unwrap()
+                .mutants,
+            per_file.mutants
+        );
+        assert_eq!(plan(&config).unwrap().mutants.len(), 4);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_near_matches() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("0123", "0123"), 0);
+
+        let known: Vec<String> = ["0123456789ab", "0123456789ac", "ffffffffffff"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        assert_eq!(
+            near_matches("0123456789ab", &known),
+            vec!["0123456789ab", "0123456789ac"]
+        );
+        // a typo and a truncated id
+        assert_eq!(near_matches("f0ffffffffff", &known), vec!["ffffffffffff"]);
+        assert_eq!(
+            near_matches("012345", &known),
+            vec!["0123456789ab", "0123456789ac"]
+        );
+        assert!(near_matches("abcdefabcdef", &known).is_empty());
+    }
+
+    #[test]
+    fn test_exclude_modules() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        for dir in [
+            "src/generated",
+            "src/migrations/versions",
+            "src/utils",
+            "scripts",
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "src/app.py",
+            "src/generated/models.py",
+            "src/migrations/versions/0001.py",
+            "src/utils/test_helpers.py",
+            "scripts/release.py",
+        ] {
+            fs::write(root.join(file), "def f(a, b):\n    return a + b\n").unwrap();
+        }
+        let files = |config: &RunConfig| {
+            let mut files: Vec<String> = plan(config)
+                .unwrap()
+                .mutants
+                .iter()
+                .map(|planned| {
+                    let file = planned.mutant.file_path.strip_prefix(root).unwrap();
+                    file.to_str().unwrap().replace('\\', "/")
+                })
+                .collect();
+            files.dedup();
+            files
+        };
+
+        let config = RunConfig::new(root)
+            .modules("src/**/*.py")
+            .exclude(&["src/generated/**", "src/migrations"]);
+        assert_eq!(files(&config), vec!["src/app.py"]);
+
+        let config = config.add_modules("scripts/*.py");
+        assert_eq!(files(&config), vec!["scripts/release.py", "src/app.py"]);
+
+        let config = config.include_tests(true);
+        assert_eq!(
+            files(&config),
+            vec![
+                "scripts/release.py",
+                "src/app.py",
+                "src/utils/test_helpers.py"
+            ]
+        );
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_coverage_guided_plan() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("tests")).unwrap();
+        fs::write(
+            root.join("calc.py"),
+            "def add(a, b):\n    return a + b\n\ndef sub(a, b):\n    return a - b\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("coverage.json"),
+            r#"{"files": {"calc.py": {
+                "missing_lines": [5],
+                "contexts": {"1": [""], "2": ["tests/test_calc.py::test_add|run"], "4": [""]}
+            }}}"#,
+        )
+        .unwrap();
+
+        let config = RunConfig::new(root)
+            .modules("calc.py")
+            .tests("tests")
+            .coverage_json(root.join("coverage.json"));
+        let guided = plan(&config).unwrap();
+        assert_eq!(guided.mutants.len(), 1);
+        assert_eq!(guided.mutants[0].mutant.line_number, 2);
+        assert_eq!(guided.uncovered.len(), 1);
+        assert_eq!(guided.uncovered[0].mutant.line_number, 5);
+        assert!(guided.coverage.is_some());
+
+        // uncovered mutants are excluded from the score unless they are included
+        let summary = finalize(&guided, Vec::new()).unwrap();
+        assert_eq!(summary.uncovered, 1);
+        assert_eq!(summary.outcomes[0].result, MutantResult::Uncovered);
+        assert_eq!(summary.score(), None);
+        let mut included = guided.clone();
+        included.config.include_uncovered = true;
+        assert_eq!(finalize(&included, Vec::new()).unwrap().score(), Some(0.0));
+
+        // a report without contexts only finds the uncovered mutants, for any runner
+        fs::write(
+            root.join("coverage.xml"),
+            r#"<coverage><packages><package><classes><class filename="calc.py"><lines>
+            <line number="2" hits="1"/><line number="5" hits="0"/>
+            </lines></class></classes></package></packages></coverage>"#,
+        )
+        .unwrap();
+        let config = RunConfig::new(root)
+            .modules("calc.py")
+            .runner(Runner::Custom)
+            .test_command("make test")
+            .coverage_file(root.join("coverage.xml"));
+        let uncovered = plan(&config).unwrap();
+        assert_eq!(uncovered.mutants.len(), 1);
+        assert_eq!(uncovered.uncovered[0].mutant.line_number, 5);
+        let config = config.coverage_file(root.join("coverage.json"));
+        assert_eq!(plan(&config).unwrap().uncovered.len(), 1);
+
+        // only pytest can run single tests
+        let config = config.coverage_json(root.join("coverage.json"));
+        assert!(plan(&config).is_err());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_subprojects() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        for package in ["billing", "checkout"] {
+            fs::create_dir(root.join(package)).unwrap();
+        }
+        fs::write(
+            root.join("billing/invoice.py"),
+            "def total(a, b):\n    return a + b\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("checkout/cart.py"),
+            "def full(n):\n    return n > 10\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("checkout/legacy.py"),
+            "def old(a):\n    return a - 1\n",
+        )
+        .unwrap();
+        // billing's command runs in its directory and catches every mutant
+        fs::write(
+            root.join("billing/pymute.toml"),
+            r#"test-command = "python -c 'import os, sys; sys.exit(int(os.path.isfile(\"pymute.toml\")))'"
+min-score = 90
+"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("checkout/pymute.toml"),
+            r#"test-command = "python -c pass"
+min-score = 85
+mutation-types = ["comp-ops"]
+skip = ["legacy.py"]
+"#,
+        )
+        .unwrap();
+
+        let config = RunConfig::new(root)
+            .runner(Runner::Custom)
+            .test_command("python -c 'import sys; sys.exit(1)'");
+        let plan = plan(&config).unwrap();
+        assert_eq!(plan.subprojects.0.len(), 2);
+        let mutants: Vec<(&str, &str)> = plan
+            .mutants
+            .iter()
+            .map(|planned| {
+                (
+                    planned.mutant.before.as_str(),
+                    planned.mutant.after.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(mutants, vec![(" + ", " - "), (" > ", " < ")]);
+
+        let summary = run(&config).unwrap();
+        let subprojects: Vec<String> = summary
+            .subprojects
+            .iter()
+            .map(|subproject| subproject.to_string())
+            .collect();
+        assert_eq!(
+            subprojects,
+            vec!["billing: 100.0% ≥ 90.0% ok", "checkout: 0.0% < 85.0% FAIL"]
+        );
+        assert!(!summary.passed());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_chunked_run_equals_single_run() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("calc.py"),
+            "def add(a, b):\n    return a + b\n\ndef mul(a, b):\n    return a * b\n\nLIMIT = 10\n",
+        )
+        .unwrap();
+
+        // only the mutant replacing `+` by `-` is caught
+        let config = RunConfig::new(root)
+            .runner(Runner::Custom)
+            .test_command(
+                r#"python -c "import sys; sys.exit(int('a - b' in open('calc.py').read()))""#,
+            )
+            .num_threads(2);
+
+        let plan = plan(&config).unwrap();
+        assert_eq!(plan.mutants.len(), 3);
+        assert_eq!(plan.command[0], "python");
+
+        // the plan survives a round trip through JSON, e.g. to be sent to a job
+        let plan: RunPlan = serde_json::from_str(&serde_json::to_string(&plan).unwrap()).unwrap();
+
+        let ids = plan.ids();
+        let mut outcomes = execute_chunk(&plan, &ids[..1]).unwrap();
+        outcomes.extend(execute_chunk(&plan, &ids[1..]).unwrap());
+        let chunked = finalize(&plan, outcomes).unwrap();
+
+        let single = run(&config).unwrap();
+        assert_eq!(
+            (chunked.caught, chunked.missed, chunked.untested),
+            (single.caught, single.missed, single.untested)
+        );
+        assert_eq!((chunked.caught, chunked.missed), (1, 2));
+        assert_eq!(sorted(&chunked.outcomes), sorted(&single.outcomes));
+
+        let error = execute_chunk(&plan, &["unknown".into()]).unwrap_err();
+        assert!(matches!(error, PymuteError::NotInPlan { ref id } if id == "unknown"));
+
+        // errors of the plan can be sent to other threads, e.g. of a job queue
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PymuteError>();
+
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,353 @@
+//! Options of a run read from the `[tool.pymute]` table of `pyproject.toml`.
+//!
+//! Like other python QA tools, pymute reads its settings for a project from the
+//! project's `pyproject.toml`:
+//!
+//! ```toml
+//! [tool.pymute]
+//! modules = ["src/**/*.py"]
+//! tests = "tests"
+//! runner = "pytest"
+//! mutation_types = ["math-ops", "comp-ops"]
+//! max_mutants = 200
+//! num_threads = 4
+//! output_level = "missed"
+//! seed = 7
+//! ```
+//!
+//! Options given explicitly on the command line take precedence over those in the
+//! file, which take precedence over the profile and the built-in defaults. The same
+//! options can be set in a `config_file::CONFIG_FILE`, which takes precedence over
+//! `pyproject.toml`.
+
+use crate::mutants::MutationType;
+use crate::runner::{OutputLevel, Runner};
+use crate::RunConfig;
+
+use serde::{de::DeserializeOwned, Deserialize};
+use toml::Table;
+
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the file with the settings of a python project.
+pub const PYPROJECT_FILE: &str = "pyproject.toml";
+
+/// Keys of the `[tool.pymute]` table.
+pub const KEYS: [&str; 9] = [
+    "modules",
+    "tests",
+    "runner",
+    "environment",
+    "mutation_types",
+    "max_mutants",
+    "num_threads",
+    "output_level",
+    "seed",
+];
+
+/// Glob expressions for modules, given either as a single string or as a list.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Modules {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Options set in the `[tool.pymute]` table or in a `config_file::CONFIG_FILE`.
+/// Options that are None are not set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PyprojectSettings {
+    /// Glob expressions for modules to mutate.
+    pub modules: Option<Vec<String>>,
+    /// Path of the tests to run.
+    pub tests: Option<String>,
+    /// Runner for the test suite.
+    pub runner: Option<Runner>,
+    /// Tox environment to run.
+    pub environment: Option<String>,
+    /// Mutation types to look for.
+    pub mutation_types: Option<Vec<MutationType>>,
+    /// Maximum number of mutants to run.
+    pub max_mutants: Option<usize>,
+    /// Number of mutants to run in parallel.
+    pub num_threads: Option<usize>,
+    /// Output level to use.
+    pub output_level: Option<OutputLevel>,
+    /// Seed for sampling mutants.
+    pub seed: Option<u64>,
+}
+
+impl PyprojectSettings {
+    /// Read the settings from the `PYPROJECT_FILE` of the project at `root`.
+    ///
+    /// A missing file or a file without a `[tool.pymute]` table sets no options.
+    pub fn read(root: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let path = root.join(PYPROJECT_FILE);
+        if !path.is_file() {
+            return Ok(PyprojectSettings::default());
+        }
+        let table = read_table(&path)?;
+        let Some(settings) = table.get("tool").and_then(|tool| tool.get("pymute")) else {
+            return Ok(PyprojectSettings::default());
+        };
+        let (settings, unknown_keys) = Self::from_table(
+            as_table(settings, "tool.pymute", &path)?,
+            "tool.pymute.",
+            &path,
+        )?;
+        if let Some(key) = unknown_keys.into_iter().next() {
+            return Err(Box::new(InvalidSettingsFile {
+                path,
+                key: Some(key),
+                reason: format!("unknown option, expected one of {}", KEYS.join(", ")),
+            }));
+        }
+        Ok(settings)
+    }
+
+    /// Parse the options in `table` of the file at `path`. The keys of the table are
+    /// named with `prefix` in errors and in the returned keys that are not options.
+    pub(crate) fn from_table(
+        table: &Table,
+        prefix: &str,
+        path: &Path,
+    ) -> Result<(Self, Vec<String>), InvalidSettingsFile> {
+        let unknown_keys = table
+            .keys()
+            .filter(|key| !KEYS.contains(&key.as_str()))
+            .map(|key| format!("{prefix}{key}"))
+            .collect();
+
+        let modules: Option<Modules> = value(table, "modules", prefix, path)?;
+        let settings = PyprojectSettings {
+            modules: modules.map(|modules| match modules {
+                Modules::One(modules) => vec![modules],
+                Modules::Many(modules) => modules,
+            }),
+            tests: value(table, "tests", prefix, path)?,
+            runner: value(table, "runner", prefix, path)?,
+            environment: value(table, "environment", prefix, path)?,
+            mutation_types: value(table, "mutation_types", prefix, path)?,
+            max_mutants: value(table, "max_mutants", prefix, path)?,
+            num_threads: value(table, "num_threads", prefix, path)?,
+            output_level: value(table, "output_level", prefix, path)?,
+            seed: value(table, "seed", prefix, path)?,
+        };
+        Ok((settings, unknown_keys))
+    }
+
+    /// These settings with the options set in `other` replaced.
+    pub fn merge(self, other: PyprojectSettings) -> Self {
+        PyprojectSettings {
+            modules: other.modules.or(self.modules),
+            tests: other.tests.or(self.tests),
+            runner: other.runner.or(self.runner),
+            environment: other.environment.or(self.environment),
+            mutation_types: other.mutation_types.or(self.mutation_types),
+            max_mutants: other.max_mutants.or(self.max_mutants),
+            num_threads: other.num_threads.or(self.num_threads),
+            output_level: other.output_level.or(self.output_level),
+            seed: other.seed.or(self.seed),
+        }
+    }
+
+    /// Set the options of `config` that are set here, except those for which
+    /// `explicit` returns true given their key.
+    pub fn apply(&self, config: &mut RunConfig, explicit: impl Fn(&str) -> bool) {
+        if let (Some(modules), false) = (&self.modules, explicit("modules")) {
+            config.modules = modules.clone();
+        }
+        if let (Some(tests), false) = (&self.tests, explicit("tests")) {
+            config.tests = tests.clone();
+        }
+        if let (Some(runner), false) = (self.runner, explicit("runner")) {
+            config.runner = runner;
+        }
+        if let (Some(environment), false) = (&self.environment, explicit("environment")) {
+            config.environment = Some(environment.clone());
+        }
+        if let (Some(mutation_types), false) = (&self.mutation_types, explicit("mutation_types")) {
+            config.mutation_types = mutation_types.clone();
+        }
+        if let (Some(max_mutants), false) = (self.max_mutants, explicit("max_mutants")) {
+            config.max_mutants = Some(max_mutants);
+        }
+        if let (Some(num_threads), false) = (self.num_threads, explicit("num_threads")) {
+            config.num_threads = num_threads;
+        }
+        if let (Some(output_level), false) = (self.output_level, explicit("output_level")) {
+            config.output_level = output_level;
+        }
+        if let (Some(seed), false) = (self.seed, explicit("seed")) {
+            config.seed = seed;
+        }
+    }
+}
+
+/// Read the TOML file at `path`.
+pub(crate) fn read_table(path: &Path) -> Result<Table, Box<dyn Error + Send + Sync>> {
+    let content = fs::read_to_string(path)?;
+    let table = toml::from_str(&content).map_err(|error| InvalidSettingsFile {
+        path: path.to_path_buf(),
+        key: None,
+        reason: error.message().into(),
+    })?;
+    Ok(table)
+}
+
+/// `value`, which must be a table, at `key` of the file at `path`.
+pub(crate) fn as_table<'a>(
+    value: &'a toml::Value,
+    key: &str,
+    path: &Path,
+) -> Result<&'a Table, InvalidSettingsFile> {
+    value.as_table().ok_or_else(|| InvalidSettingsFile {
+        path: path.to_path_buf(),
+        key: Some(key.into()),
+        reason: "expected a table".into(),
+    })
+}
+
+/// The value of `key` in `table` of the file at `path`, if it is set. Each key is
+/// deserialized on its own to name it (with `prefix`) in the error.
+fn value<T: DeserializeOwned>(
+    table: &Table,
+    key: &str,
+    prefix: &str,
+    path: &Path,
+) -> Result<Option<T>, InvalidSettingsFile> {
+    table
+        .get(key)
+        .cloned()
+        .map(toml::Value::try_into)
+        .transpose()
+        .map_err(|error: toml::de::Error| InvalidSettingsFile {
+            path: path.to_path_buf(),
+            key: Some(format!("{prefix}{key}")),
+            reason: error.message().trim().into(),
+        })
+}
+
+#[derive(Debug)]
+pub(crate) struct InvalidSettingsFile {
+    path: PathBuf,
+    key: Option<String>,
+    reason: String,
+}
+
+impl Error for InvalidSettingsFile {}
+impl fmt::Display for InvalidSettingsFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.key {
+            Some(key) => write!(
+                f,
+                "Invalid option `{key}` in {}: {}",
+                self.path.display(),
+                self.reason
+            ),
+            None => write!(f, "Could not read {}: {}", self.path.display(), self.reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutants::MutationType;
+    use crate::pyproject::PyprojectSettings;
+    use crate::runner::{OutputLevel, Runner};
+    use crate::RunConfig;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_pyproject() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(
+            PyprojectSettings::read(root).unwrap(),
+            PyprojectSettings::default()
+        );
+        fs::write(
+            root.join("pyproject.toml"),
+            "[tool.black]\nline-length = 88\n",
+        )
+        .unwrap();
+        assert_eq!(
+            PyprojectSettings::read(root).unwrap(),
+            PyprojectSettings::default()
+        );
+
+        fs::write(
+            root.join("pyproject.toml"),
+            r#"[project]
+name = "julearn"
+
+[tool.pymute]
+modules = "julearn/**/*.py"
+tests = "julearn/tests"
+runner = "tox"
+environment = "py311"
+mutation_types = ["comp-ops", "numbers"]
+max_mutants = 20
+num_threads = 3
+output_level = "missed"
+seed = 7
+"#,
+        )
+        .unwrap();
+        let settings = PyprojectSettings::read(root).unwrap();
+        let mut config = RunConfig::new(root);
+        settings.apply(&mut config, |_| false);
+        let expected = RunConfig::new(root)
+            .modules("julearn/**/*.py")
+            .tests("julearn/tests")
+            .runner(Runner::Tox)
+            .environment("py311")
+            .mutation_types(&[MutationType::CompOps, MutationType::Numbers])
+            .max_mutants(20)
+            .num_threads(3)
+            .output_level(OutputLevel::Missed)
+            .seed(7);
+        assert_eq!(config, expected);
+
+        // explicit options are kept
+        let mut config = RunConfig::new(root).seed(1);
+        settings.apply(&mut config, |key| key == "seed");
+        assert_eq!(config.seed, 1);
+        assert_eq!(config.num_threads, 3);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_invalid_pyproject() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let error = |settings: &str| {
+            fs::write(root.join("pyproject.toml"), settings).unwrap();
+            PyprojectSettings::read(root).unwrap_err().to_string()
+        };
+
+        let message = error("[tool.pymute]\nrunner = \"nose\"\n");
+        assert!(message.contains("`tool.pymute.runner`"), "{message}");
+        assert!(message.contains("nose"), "{message}");
+        let message = error("[tool.pymute]\nmutation_types = [\"math-ops\", \"maths\"]\n");
+        assert!(
+            message.contains("`tool.pymute.mutation_types`"),
+            "{message}"
+        );
+        let message = error("[tool.pymute]\nmax_mutants = \"all\"\n");
+        assert!(message.contains("`tool.pymute.max_mutants`"), "{message}");
+        let message = error("[tool.pymute]\nmax-mutants = 5\n");
+        assert!(message.contains("`tool.pymute.max-mutants`"), "{message}");
+        assert!(message.contains("unknown option"), "{message}");
+        let message = error("[tool.pymute\n");
+        assert!(message.contains("Could not read"), "{message}");
+
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,740 @@
+//! Reports of every mutant of a run and its outcome.
+//!
+//! Unlike the summary file (see `summary`), the reports contain file paths and
+//! code, so that they can be post-processed, e.g. to annotate the lines of a pull
+//! request with their surviving mutants or to follow mutants across runs by id.
+//!
+//! ## JSON
+//!
+//! The JSON report (see `write_json_report`) holds the configuration of the run,
+//! the counts of the summary and one entry per mutant:
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "pymute_version": "0.2.1",
+//!   "timestamp": 1767225600,
+//!   "config": {"modules": ["src/**/*.py"], "mutation_types": ["math-ops"], "seed": 0, "max_mutants": null},
+//!   "summary": {"total": 2, "caught": 1, "missed": 1, "score": 50.0, ...},
+//!   "mutants": [
+//!     {"id": "3fa2c81b09de", "file_path": "src/calc.py", "line_number": 2, "column": 12,
+//!      "before": " + ", "after": " - ", "mutation_type": "math-ops", "status": "missed",
+//!      "duration_ms": 812, "duration": "812ms"}
+//!   ]
+//! }
+//! ```
+//!
+//! File paths are relative to the root of the project. Durations are given in
+//! milliseconds as `duration_ms` and formatted for reading as `duration`, like in the
+//! summary file. Mutants whose result was taken from a cache written before durations
+//! were recorded have no duration. The schema is versioned like the summary file.
+//!
+//! ## JUnit XML
+//!
+//! The JUnit report (see `write_junit_report`) lets CI systems such as GitLab or
+//! Jenkins show surviving mutants as failed tests. Each source file is a test
+//! suite and each mutant a test case named after its line and replacement, e.g.
+//! `line 2: + → -`. Missed mutants are failures, errored mutants are errors, and
+//! survivors accepted with a verdict (see `verdicts`), untested and uncovered
+//! mutants as well as those without collected tests are skipped.
+//!
+//! ## HTML
+//!
+//! The HTML report (see `write_html_report`) is a directory with an `index.html`
+//! showing the score and the results per file, and a page per file under `files/`
+//! (e.g. `files/pkg/calc.py.html`) listing its source with the mutated lines
+//! highlighted and a note for each mutant.
+
+use crate::humanize::format_duration;
+use crate::mutants::{Mutant, MutationType};
+use crate::runner::{MutantOutcome, MutantResult};
+use crate::verdicts::Verdicts;
+use crate::{RunConfig, RunSummary};
+
+use serde::Serialize;
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Component, Path},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Version of the schema of the report, see the module documentation of `summary`.
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// On-disk representation of the report.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    pymute_version: &'a str,
+    /// Seconds since the Unix epoch at which the report was written.
+    timestamp: u64,
+    config: ReportConfig<'a>,
+    summary: ReportSummary,
+    mutants: Vec<ReportMutant<'a>>,
+}
+
+/// The options of the run that select its mutants.
+#[derive(Serialize)]
+struct ReportConfig<'a> {
+    modules: &'a [String],
+    mutation_types: &'a [MutationType],
+    seed: u64,
+    max_mutants: Option<usize>,
+}
+
+/// The counts of `RunSummary`.
+#[derive(Serialize)]
+struct ReportSummary {
+    total: usize,
+    caught: usize,
+    missed: usize,
+    accepted: usize,
+    untested: usize,
+    uncovered: usize,
+    no_tests: usize,
+    invalid: usize,
+    errors: usize,
+    cached: usize,
+    likely_equivalent: usize,
+    score: Option<f64>,
+    duration_ms: u128,
+    /// `duration_ms` formatted for reading, see `format_duration`.
+    duration: String,
+}
+
+/// A mutant of the run together with its outcome.
+#[derive(Serialize)]
+struct ReportMutant<'a> {
+    id: String,
+    /// Path of the file relative to the root of the project.
+    file_path: &'a Path,
+    line_number: usize,
+    column: usize,
+    before: &'a str,
+    after: &'a str,
+    mutation_type: Option<MutationType>,
+    status: MutantResult,
+    /// How long the test suite ran against the mutant, see
+    /// `MutantOutcome::duration`.
+    duration_ms: Option<u128>,
+    /// `duration_ms` formatted for reading, see `format_duration`.
+    duration: Option<String>,
+    /// Why the mutant could not be run, see `MutantOutcome::error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+/// Write the report of a run configured by `config` as JSON to `path`, creating
+/// its directory if needed.
+pub fn write_json_report(
+    path: &Path,
+    summary: &RunSummary,
+    config: &RunConfig,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let root = &config.root;
+    let mutants = summary
+        .outcomes
+        .iter()
+        .map(|outcome| {
+            let mutant = &outcome.mutant;
+            ReportMutant {
+                id: mutant.id(root),
+                file_path: mutant
+                    .file_path
+                    .strip_prefix(root)
+                    .unwrap_or(&mutant.file_path),
+                line_number: mutant.line_number,
+                column: mutant.column,
+                before: &mutant.before,
+                after: &mutant.after,
+                mutation_type: mutant.mutation_type,
+                status: outcome.result,
+                duration_ms: outcome.duration.map(|duration| duration.as_millis()),
+                duration: outcome.duration.map(format_duration),
+                error: outcome.error.as_deref(),
+            }
+        })
+        .collect();
+    let report = JsonReport {
+        schema_version: REPORT_SCHEMA_VERSION,
+        pymute_version: env!("CARGO_PKG_VERSION"),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs()),
+        config: ReportConfig {
+            modules: &config.modules,
+            mutation_types: &config.mutation_types,
+            seed: config.seed,
+            max_mutants: config.max_mutants,
+        },
+        summary: ReportSummary {
+            total: summary.total,
+            caught: summary.caught,
+            missed: summary.missed,
+            accepted: summary.accepted,
+            untested: summary.untested,
+            uncovered: summary.uncovered,
+            no_tests: summary.no_tests,
+            invalid: summary.invalid,
+            errors: summary.errors,
+            cached: summary.cached,
+            likely_equivalent: summary.likely_equivalent,
+            score: summary.score(),
+            duration_ms: summary.duration.as_millis(),
+            duration: format_duration(summary.duration),
+        },
+        mutants,
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    Ok(())
+}
+
+/// Write the report of a run of the project at `root` as JUnit XML to `path`,
+/// creating its directory if needed.
+///
+/// Mutants without a duration (see `MutantOutcome::duration`) have no `time`
+/// attribute. Survivors accepted in `verdicts` are skipped with the note of their
+/// verdict instead of failing.
+pub fn write_junit_report(
+    path: &Path,
+    summary: &RunSummary,
+    root: &Path,
+    verdicts: &Verdicts,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut files: BTreeMap<&Path, Vec<&MutantOutcome>> = BTreeMap::new();
+    for outcome in &summary.outcomes {
+        let file = &outcome.mutant.file_path;
+        files
+            .entry(file.strip_prefix(root).unwrap_or(file))
+            .or_default()
+            .push(outcome);
+    }
+    let verdict = |outcome: &MutantOutcome| match outcome.result {
+        MutantResult::Missed => verdicts.get(&outcome.mutant.id(root)),
+        _ => None,
+    };
+    let counts = |outcomes: &[&MutantOutcome]| {
+        let count = |f: &dyn Fn(&MutantOutcome) -> bool| {
+            outcomes.iter().filter(|outcome| f(outcome)).count()
+        };
+        format!(
+            r#"tests="{}" failures="{}" errors="{}" skipped="{}""#,
+            outcomes.len(),
+            count(&|outcome| outcome.result == MutantResult::Missed && verdict(outcome).is_none()),
+            count(&|outcome| matches!(outcome.result, MutantResult::Error { .. })),
+            count(&|outcome| {
+                verdict(outcome).is_some()
+                    || matches!(
+                        outcome.result,
+                        MutantResult::Untested
+                            | MutantResult::Uncovered
+                            | MutantResult::NoTests
+                            | MutantResult::Invalid
+                    )
+            }),
+        )
+    };
+
+    let all: Vec<_> = summary.outcomes.iter().collect();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml += &format!(
+        "<testsuites name=\"pymute\" {} time=\"{:.3}\">\n",
+        counts(&all),
+        summary.duration.as_secs_f64()
+    );
+    for (file, outcomes) in &files {
+        let file = escape_xml(&file.display().to_string());
+        let time: Duration = outcomes.iter().filter_map(|outcome| outcome.duration).sum();
+        xml += &format!(
+            "  <testsuite name=\"{file}\" {} time=\"{:.3}\">\n",
+            counts(outcomes),
+            time.as_secs_f64()
+        );
+        for outcome in outcomes {
+            let mutant = &outcome.mutant;
+            let name = format!(
+                "line {}: {} → {}",
+                mutant.line_number,
+                mutant.before.trim(),
+                mutant.after.trim()
+            );
+            let time = outcome
+                .duration
+                .map(|duration| format!(" time=\"{:.3}\"", duration.as_secs_f64()))
+                .unwrap_or_default();
+            let testcase = format!(
+                "    <testcase name=\"{}\" classname=\"{file}\"{time}",
+                escape_xml(&name)
+            );
+            let description = escape_xml(&describe(mutant, root));
+            xml += &match (outcome.result, verdict(outcome)) {
+                (MutantResult::Missed, Some(verdict)) => format!(
+                    "{testcase}>\n      <skipped message=\"Accepted survivor: {}\"/>\n    </testcase>\n",
+                    escape_xml(&verdict.note)
+                ),
+                (MutantResult::Caught, _) => format!("{testcase}/>\n"),
+                (MutantResult::Missed, _) => format!(
+                    "{testcase}>\n      <failure message=\"Mutant survived\">{description}</failure>\n    </testcase>\n"
+                ),
+                (MutantResult::Error { signal, code }, _) => {
+                    let message = match (signal, code) {
+                        (Some(signal), _) => format!("Test run killed by signal {signal}"),
+                        (None, Some(code)) => format!("Tests could not run (exit code {code})"),
+                        (None, None) => "Test run did not finish normally".into(),
+                    };
+                    format!(
+                        "{testcase}>\n      <error message=\"{message}\">{description}</error>\n    </testcase>\n"
+                    )
+                }
+                (MutantResult::Untested, _) => format!(
+                    "{testcase}>\n      <skipped message=\"No tests for the file\"/>\n    </testcase>\n"
+                ),
+                (MutantResult::NoTests, _) => format!(
+                    "{testcase}>\n      <skipped message=\"No tests collected\"/>\n    </testcase>\n"
+                ),
+                (MutantResult::Uncovered, _) => format!(
+                    "{testcase}>\n      <skipped message=\"No tests cover the line\"/>\n    </testcase>\n"
+                ),
+                (MutantResult::Invalid, _) => format!(
+                    "{testcase}>\n      <skipped message=\"Mutant is not valid python\"/>\n    </testcase>\n"
+                ),
+            };
+        }
+        xml += "  </testsuite>\n";
+    }
+    xml += "</testsuites>\n";
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, xml)?;
+    Ok(())
+}
+
+/// Stylesheet shared by the pages of the HTML report.
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.8em; text-align: left; }
+.source td { padding: 0 0.5em; vertical-align: top; }
+.source pre { margin: 0; }
+.number { color: #888; text-align: right; }
+tr.caught { background: #e6f4e6; }
+tr.missed { background: #fbe3e3; }
+tr.error, tr.untested { background: #fff5d6; }
+.mutant { font-family: monospace; font-size: 0.9em; margin: 0.1em 0 0.3em 2em; }
+.mutant.missed { color: #b00000; }
+.mutant.caught { color: #006000; }";
+
+/// Write the report of a run of the project at `root` as static HTML pages to the
+/// directory `dir`, which is created if needed.
+///
+/// The source of each file is read from `root`. Files that cannot be read only
+/// list their mutants.
+pub fn write_html_report(
+    dir: &Path,
+    summary: &RunSummary,
+    root: &Path,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut files: BTreeMap<&Path, Vec<&MutantOutcome>> = BTreeMap::new();
+    for outcome in &summary.outcomes {
+        let file = &outcome.mutant.file_path;
+        files
+            .entry(file.strip_prefix(root).unwrap_or(file))
+            .or_default()
+            .push(outcome);
+    }
+    let score = |caught: usize, missed: usize| match caught + missed {
+        0 => "n/a".to_string(),
+        total => format!("{:.1}%", caught as f64 / total as f64 * 100.0),
+    };
+
+    let mut rows = String::new();
+    for (file, outcomes) in &files {
+        let page = html_page_path(file);
+        let count = |result| outcomes.iter().filter(|o| o.result == result).count();
+        let (caught, missed) = (count(MutantResult::Caught), count(MutantResult::Missed));
+        rows += &format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{caught}</td><td>{missed}</td><td>{}</td></tr>\n",
+            escape_xml(&page),
+            escape_xml(&file.display().to_string()),
+            outcomes.len(),
+            score(caught, missed),
+        );
+
+        let path = dir.join(&page);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let index = "../".repeat(page.matches('/').count()) + "index.html";
+        fs::write(&path, html_file_page(file, outcomes, root, &index))?;
+    }
+
+    let score = summary
+        .score()
+        .map_or("n/a".to_string(), |score| format!("{score:.1}%"));
+    let body = format!(
+        "<h1>Mutation score {score}</h1>
+<p>{} mutants tested: {} caught, {} missed, {} accepted, {} errored, {} untested, {} uncovered.</p>
+<table>
+<tr><th>File</th><th>Mutants</th><th>Caught</th><th>Missed</th><th>Score</th></tr>
+{rows}</table>",
+        summary.total,
+        summary.caught,
+        summary.missed,
+        summary.accepted,
+        summary.errors,
+        summary.untested,
+        summary.uncovered,
+    );
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join("index.html"),
+        html_document("pymute report", &body),
+    )?;
+    Ok(())
+}
+
+/// Path of the page of `file` (relative to the root of the project) in the HTML
+/// report, relative to the directory of the report and with `/` as separator.
+fn html_page_path(file: &Path) -> String {
+    let components: Vec<_> = file
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect();
+    format!("files/{}.html", components.join("/"))
+}
+
+/// The page of the HTML report for `file` (relative to `root`) with `outcomes`,
+/// linking back to the index at `index`.
+fn html_file_page(file: &Path, outcomes: &[&MutantOutcome], root: &Path, index: &str) -> String {
+    let mut lines: BTreeMap<usize, Vec<&MutantOutcome>> = BTreeMap::new();
+    for outcome in outcomes {
+        lines
+            .entry(outcome.mutant.line_number)
+            .or_default()
+            .push(outcome);
+    }
+    let notes = |outcomes: &[&MutantOutcome]| -> String {
+        outcomes
+            .iter()
+            .map(|outcome| {
+                let mutant = &outcome.mutant;
+                let class = match outcome.result {
+                    MutantResult::Error { .. } => "error".to_string(),
+                    result => result.to_string(),
+                };
+                format!(
+                    "<div class=\"mutant {class}\" title=\"{}\">{}: {} → {}</div>",
+                    escape_xml(&describe(mutant, root)),
+                    outcome.result,
+                    escape_xml(mutant.before.trim()),
+                    escape_xml(mutant.after.trim()),
+                )
+            })
+            .collect()
+    };
+    // the line is as bad as its worst mutant
+    let class = |outcomes: &[&MutantOutcome]| {
+        let has = |f: fn(&MutantResult) -> bool| outcomes.iter().any(|o| f(&o.result));
+        if has(|result| *result == MutantResult::Missed) {
+            "missed"
+        } else if has(|result| matches!(result, MutantResult::Error { .. } | MutantResult::NoTests))
+        {
+            "error"
+        } else if has(|result| {
+            matches!(
+                result,
+                MutantResult::Untested | MutantResult::Uncovered | MutantResult::Invalid
+            )
+        }) {
+            "untested"
+        } else {
+            "caught"
+        }
+    };
+
+    let source = fs::read_to_string(root.join(file)).unwrap_or_default();
+    let mut rows = String::new();
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let (class, notes) = match lines.remove(&number) {
+            Some(outcomes) => (format!(" class=\"{}\"", class(&outcomes)), notes(&outcomes)),
+            None => (String::new(), String::new()),
+        };
+        rows += &format!(
+            "<tr id=\"L{number}\"{class}><td class=\"number\">{number}</td><td><pre>{}</pre>{notes}</td></tr>\n",
+            escape_xml(line),
+        );
+    }
+    // mutants on lines the file no longer has, e.g. if it was deleted
+    for (number, outcomes) in &lines {
+        rows += &format!(
+            "<tr id=\"L{number}\" class=\"{}\"><td class=\"number\">{number}</td><td>{}</td></tr>\n",
+            class(outcomes),
+            notes(outcomes),
+        );
+    }
+
+    let file = escape_xml(&file.display().to_string());
+    let body = format!(
+        "<p><a href=\"{index}\">Back to the overview</a></p>
+<h1>{file}</h1>
+<table class=\"source\">
+{rows}</table>"
+    );
+    html_document(&file, &body)
+}
+
+/// A complete HTML document with `title` (already escaped) and `body`.
+fn html_document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+{HTML_STYLE}
+</style>
+</head>
+<body>
+{body}
+</body>
+</html>
+"
+    )
+}
+
+/// A description of `mutant` of the project at `root` without colors, e.g.
+/// `+ replaced by - in pkg/calc.py on line 2 (math-ops), id 3fa2c81b09de`.
+fn describe(mutant: &Mutant, root: &Path) -> String {
+    let file = mutant
+        .file_path
+        .strip_prefix(root)
+        .unwrap_or(&mutant.file_path);
+    let mutation_type = mutant
+        .mutation_type
+        .map(|mutation_type| format!(" ({mutation_type})"))
+        .unwrap_or_default();
+    format!(
+        "{} replaced by {} in {} on line {}{mutation_type}, id {}",
+        mutant.before.trim(),
+        mutant.after.trim(),
+        file.display(),
+        mutant.line_number,
+        mutant.id(root)
+    )
+}
+
+/// `text` with the characters that are special in XML and HTML escaped.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutants::{Mutant, MutationType};
+    use crate::report::{write_html_report, write_json_report, write_junit_report};
+    use crate::runner::{MutantOutcome, MutantResult};
+    use crate::verdicts::{Verdict, VerdictKind, Verdicts};
+    use crate::{RunConfig, RunSummary};
+    use std::{path::Path, time::Duration};
+    use tempfile::tempdir;
+
+    fn root() -> &'static Path {
+        Path::new("/projects/project")
+    }
+
+    /// A run of two mutants: a surviving one that ran for 812ms and a caught one
+    /// taken from a cache without durations.
+    fn two_mutant_run() -> RunSummary {
+        let mut run = Mutant::new(
+            root().join("pkg/calc.py"),
+            2,
+            " + ",
+            " - ",
+            12,
+            "    return a + b",
+        );
+        run.mutation_type = Some(MutationType::MathOps);
+        let cached = Mutant::new(
+            root().join("pkg/calc.py"),
+            5,
+            " < ",
+            " > ",
+            8,
+            "    if a < b:",
+        );
+        let outcomes = vec![
+            MutantOutcome {
+                mutant: run,
+                result: MutantResult::Missed,
+                duration: Some(Duration::from_millis(812)),
+                error: None,
+            },
+            MutantOutcome {
+                mutant: cached,
+                result: MutantResult::Caught,
+                duration: None,
+                error: None,
+            },
+        ];
+        RunSummary::new(outcomes, Duration::from_secs(2))
+    }
+
+    #[test]
+    fn test_write_json_report() {
+        let temp_dir = tempdir().unwrap();
+        let summary = two_mutant_run();
+        let config = RunConfig::new(root()).modules("pkg/*.py").seed(7);
+
+        let path = temp_dir.path().join("report.json");
+        write_json_report(&path, &summary, &config).unwrap();
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(report["schema_version"], 1);
+        assert_eq!(report["config"]["modules"][0], "pkg/*.py");
+        assert_eq!(report["config"]["seed"], 7);
+        assert_eq!(report["summary"]["missed"], 1);
+        assert_eq!(report["summary"]["score"], 50.0);
+        assert_eq!(report["summary"]["duration_ms"], 2000);
+        assert_eq!(report["summary"]["duration"], "2s");
+
+        let mutant = &report["mutants"][0];
+        assert_eq!(mutant["id"], summary.outcomes[0].mutant.id(root()));
+        assert_eq!(mutant["file_path"], "pkg/calc.py");
+        assert_eq!(mutant["mutation_type"], "math-ops");
+        assert_eq!(mutant["status"], "missed");
+        assert_eq!(mutant["duration_ms"], 812);
+        assert_eq!(mutant["duration"], "812ms");
+        assert_eq!(
+            report["mutants"][1]["mutation_type"],
+            serde_json::Value::Null
+        );
+        assert_eq!(report["mutants"][1]["duration_ms"], serde_json::Value::Null);
+        assert_eq!(report["mutants"][1]["duration"], serde_json::Value::Null);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_write_junit_report() {
+        let temp_dir = tempdir().unwrap();
+        let summary = two_mutant_run();
+
+        // the directory of the report is created
+        let path = temp_dir.path().join("reports/junit.xml");
+        write_junit_report(&path, &summary, root(), &Verdicts::new()).unwrap();
+        let expected = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="pymute" tests="2" failures="1" errors="0" skipped="0" time="2.000">
+  <testsuite name="pkg/calc.py" tests="2" failures="1" errors="0" skipped="0" time="0.812">
+    <testcase name="line 2: + → -" classname="pkg/calc.py" time="0.812">
+      <failure message="Mutant survived">+ replaced by - in pkg/calc.py on line 2 (math-ops), id {}</failure>
+    </testcase>
+    <testcase name="line 5: &lt; → &gt;" classname="pkg/calc.py"/>
+  </testsuite>
+</testsuites>
+"#,
+            summary.outcomes[0].mutant.id(root())
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+
+        // an accepted survivor is skipped with the note of its verdict
+        let id = summary.outcomes[0].mutant.id(root());
+        let verdict = Verdict {
+            id: id.clone(),
+            verdict: VerdictKind::Accepted,
+            note: "only a <log> message".into(),
+        };
+        let verdicts: Verdicts = [(id, verdict)].into_iter().collect();
+        write_junit_report(&path, &summary, root(), &verdicts).unwrap();
+        let xml = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            xml.contains(r#"tests="2" failures="0" errors="0" skipped="1""#),
+            "{xml}"
+        );
+        assert!(
+            xml.contains(r#"<skipped message="Accepted survivor: only a &lt;log&gt; message"/>"#),
+            "{xml}"
+        );
+        assert!(!xml.contains("<failure"), "{xml}");
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_write_html_report() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir_all(root.join("pkg")).unwrap();
+        let code = "def check(a, b):\n    if a < b:\n        return a + b\n";
+        std::fs::write(root.join("pkg/check.py"), code).unwrap();
+        std::fs::write(root.join("main.py"), "x = 1 + 2\n").unwrap();
+        let outcome = |file: &str, line_number, before, after, column, result| {
+            let line = code.lines().nth(line_number - 1).unwrap_or("x = 1 + 2");
+            MutantOutcome {
+                mutant: Mutant::new(root.join(file), line_number, before, after, column, line),
+                result,
+                duration: None,
+                error: None,
+            }
+        };
+        let summary = RunSummary::new(
+            vec![
+                outcome("pkg/check.py", 2, " < ", " > ", 8, MutantResult::Missed),
+                outcome("pkg/check.py", 3, " + ", " - ", 16, MutantResult::Caught),
+                outcome("main.py", 1, " + ", " - ", 5, MutantResult::Caught),
+            ],
+            Duration::from_secs(1),
+        );
+
+        let dir = temp_dir.path().join("html");
+        write_html_report(&dir, &summary, &root).unwrap();
+        let index = std::fs::read_to_string(dir.join("index.html")).unwrap();
+        assert!(index.contains("Mutation score 66.7%"), "{index}");
+        let links: Vec<&str> = index
+            .split("href=\"")
+            .skip(1)
+            .map(|rest| rest.split('"').next().unwrap())
+            .collect();
+        assert_eq!(links, ["files/main.py.html", "files/pkg/check.py.html"]);
+        for link in links {
+            assert!(dir.join(link).is_file(), "{link}");
+        }
+
+        let page = std::fs::read_to_string(dir.join("files/pkg/check.py.html")).unwrap();
+        assert!(page.contains("<a href=\"../../index.html\">"), "{page}");
+        let line = page
+            .lines()
+            .find(|line| line.starts_with("<tr id=\"L2\""))
+            .unwrap();
+        assert!(line.contains("class=\"missed\""), "{line}");
+        assert!(line.contains("<pre>    if a &lt; b:</pre>"), "{line}");
+        assert!(line.contains("missed: &lt; → &gt;"), "{line}");
+        assert!(page.contains("<tr id=\"L1\"><td class=\"number\">1</td>"));
+
+        temp_dir.close().unwrap();
+    }
+}
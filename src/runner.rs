@@ -1,4 +1,4 @@
-//! Module to run pytest or tox for each mutant in a temporary directory in parallel.
+//! Module to run the tests (pytest, unittest, tox or nox) for each mutant in a temporary directory in parallel.
 //!
 //! This Rust module provides functionalities to execute test suites against Python code mutants. It facilitates
 //! the identification of weaknesses in test suites by running them against code variations (mutants) where
@@ -8,10 +8,11 @@
 //! ## Features
 //!
 //! - **Parallel Execution**: Utilizes `rayon` for concurrent execution of tests across multiple mutants.
-//! - **Flexible Test Runners**: Supports different test runners like Pytest and Tox, providing versatility in
-//!   how Python tests are executed.
-//! - **Isolated Test Environments**: Employs `tempfile` for creating temporary directories, ensuring that
-//!   test runs do not interfere with each other and the original codebase remains unaltered.
+//! - **Flexible Test Runners**: Supports different test runners like Pytest, Unittest, Tox and Nox, providing
+//!   versatility in how Python tests are executed. Any other test command (e.g. `make test`) can be run as a custom runner.
+//! - **Isolated Test Environments**: Employs `tempfile` for creating one working copy of the project per
+//!   thread (see `WorkdirPool`), ensuring that test runs do not interfere with each other and the original
+//!   codebase remains unaltered.
 //! - **Detailed Progress Tracking**: Integrates `indicatif` for real-time progress tracking and logging, enhancing
 //!   visibility into the testing process.
 //! - **Output Customization**: Offers different levels of output verbosity to tailor the feedback from the test
@@ -26,17 +27,17 @@
 //! ```
 //! use pymute::runner::{Runner, OutputLevel, run_mutants};
 //! use pymute::mutants::{find_mutants, MutationType};
-//! use std::path::PathBuf;
+//! use pymute::RunConfig;
 //!
-//! let root = PathBuf::from("path/to/python/project");
 //! let mutation_types = &[MutationType::MathOps, MutationType::Booleans];
 //! let glob_pattern = "my_module/**/*.py";
 //! let mutants = find_mutants(glob_pattern, mutation_types).expect("Error finding mutants");
-//! let tests = "my_module/tests/".to_string();
-//! let runner = Runner::Pytest;
-//! let output_level = OutputLevel::Process;
+//! let config = RunConfig::new("path/to/python/project")
+//!     .tests("my_module/tests/")
+//!     .runner(Runner::Pytest)
+//!     .output_level(OutputLevel::Process);
 //!
-//! run_mutants(&root, &mutants, &runner, &tests, &None, &output_level);
+//! run_mutants(&config, &mutants);
 //! ```
 //!
 //! ## Dependencies
@@ -45,39 +46,67 @@
 //! directories, `indicatif` for progress reporting, and `cp_r` for directory copying.
 //!
 
-use crate::mutants::Mutant;
+use crate::config::DEFAULT_COPY_EXCLUDES;
+use crate::coverage::{Coverage, CoverageMap};
+use crate::diff;
+use crate::error::PymuteError;
+use crate::events::{Dispatch, Event, Observer, SocketObserver};
+use crate::humanize::format_duration;
+use crate::mutants::{FileSnapshot, Mutant, MutantError};
+use crate::subprojects::Subprojects;
+use crate::syntax;
+use crate::verdicts::{read_verdicts, Verdicts};
+use crate::RunConfig;
 use cp_r::CopyOptions;
-use indicatif::{self, style::ProgressStyle, ParallelProgressIterator, ProgressBar};
+use indicatif::{self, style::ProgressStyle, ParallelProgressIterator, ProgressBar, ProgressState};
 
 use clap::ValueEnum;
+use glob::Pattern;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use std::{
+    collections::BTreeSet,
     error::Error,
     fmt,
-    path::PathBuf,
-    process::{Command, Stdio},
+    fs::{self, DirEntry, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    str::FromStr,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
     },
+    thread,
+    time::{Duration, Instant},
 };
-use tempfile::{tempdir, tempdir_in, TempDir};
+use tempfile::{tempdir_in, TempDir};
 
 use colored::Colorize;
 
 /// Define the runner to use to run the test suite.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Runner {
     /// Run with Pytest.
     Pytest,
     /// Run with Tox.
     Tox,
+    /// Run with `python -m unittest`.
+    Unittest,
+    /// Run with Nox.
+    Nox,
+    /// Run a custom test command (see `--test-command`).
+    Custom,
 }
 
 /// Define the output level when running the tests for mutants.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputLevel {
+    /// quiet: print nothing while running the mutants.
+    Quiet,
     /// missed: print out only mutants that were missed by the tests.
     Missed,
     /// caught: print out also mutants that were caught by the tests.
@@ -86,173 +115,1670 @@ pub enum OutputLevel {
     Process,
 }
 
-/// Run tests for all mutants each in their own temporary directory.
+/// Number of pytest-xdist workers for each test run of the pytest runner.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PytestWorkers {
+    /// As many workers as the machine has CPUs.
+    Auto,
+    /// A fixed number of workers.
+    Count(usize),
+}
+
+impl FromStr for PytestWorkers {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(PytestWorkers::Auto),
+            _ => match value.parse() {
+                Ok(count) if count > 0 => Ok(PytestWorkers::Count(count)),
+                _ => Err(format!(
+                    "invalid number of workers `{value}`, expected a positive number or `auto`"
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for PytestWorkers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PytestWorkers::Auto => write!(f, "auto"),
+            PytestWorkers::Count(count) => write!(f, "{count}"),
+        }
+    }
+}
+
+/// Run tests for all mutants in working copies of the project.
 ///
-/// Run in parallel using rayon.
+/// Run in parallel using rayon. The mutants are started in the given order, and
+/// their outcomes are returned in that order.
 ///
 /// Parameters
 /// ----------
-/// root: PathBuf to the root of the original python project.
-/// mutants: Vec of Mutants for which to run tests in individual sub-processes.
-/// runner: Which runner to use to run the test suite.
-/// tests: Path to the tests to run via tests as string. Only relevant if the runner
-/// is runner::Runner::Pytest.
-/// environment: If running via Tox, this environment is passed over to the `-e` option.
-/// output_level: How much to print while running the mutant.
+/// config: Configuration of the run. The root, runner, tests, environment,
+/// test command, output level and number of threads are taken from it.
+/// mutants: Mutants for which to run tests in individual sub-processes.
 pub fn run_mutants(
-    root: &PathBuf,
-    mutants: &Vec<Mutant>,
-    runner: &Runner,
-    tests: &str,
-    environment: &Option<String>,
-    output_level: &OutputLevel,
-) -> Result<(), Box<dyn Error>> {
-    let bar = ProgressBar::new(mutants.len().try_into()?);
-    bar.set_style(ProgressStyle::with_template(
-        "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
-    )?);
-
-    let top_level_temp_dir = tempdir()?;
-
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-        println!("Ctrl+C pressed. Exiting...");
-    })?;
+    config: &RunConfig,
+    mutants: &[Mutant],
+) -> Result<Vec<MutantOutcome>, PymuteError> {
+    run_mutants_in_subprojects(config, mutants, &Subprojects::default())
+}
 
-    mutants
-        .par_iter()
-        .progress_with(bar.clone())
-        .for_each(|mutant| {
-            if running.load(Ordering::SeqCst) {
-                bar.set_message(format!("[{}]: {mutant}\r", "RUNNING".yellow()));
-                let result = run_mutant(
-                    &top_level_temp_dir,
-                    mutant,
-                    root,
-                    tests,
-                    output_level,
-                    runner,
-                    environment,
-                )
-                .unwrap_or_else(|_| panic!("Mutant run failed for {mutant}"));
+/// Run tests for all mutants like `run_mutants`, running the test command of their
+/// subproject (if it has one) in the subproject's directory of the working copy.
+pub fn run_mutants_in_subprojects(
+    config: &RunConfig,
+    mutants: &[Mutant],
+    subprojects: &Subprojects,
+) -> Result<Vec<MutantOutcome>, PymuteError> {
+    run_mutants_with_coverage(config, mutants, subprojects, None)
+}
 
-                match result {
-                    MutantResult::Missed => {
-                        bar.println(format!("[{}] Mutant Survived: {}", "MISSED".red(), mutant));
-                    }
-                    _ => {
-                        if let OutputLevel::Missed = output_level {
-                        } else {
-                            bar.println(format!(
-                                "[{}] Mutant Killed: {}",
-                                "CAUGHT".green(),
-                                mutant
-                            ));
-                        };
+/// Run tests for all mutants like `run_mutants_in_subprojects`. If there is a
+/// `coverage` map, pytest only runs the tests covering the line of each mutant,
+/// or all tests if the coverage of the line is not known.
+pub fn run_mutants_with_coverage(
+    config: &RunConfig,
+    mutants: &[Mutant],
+    subprojects: &Subprojects,
+    coverage: Option<&CoverageMap>,
+) -> Result<Vec<MutantOutcome>, PymuteError> {
+    let output_level = &config.output_level;
+    // fail early if the test command can not be built, e.g. a missing custom command
+    validate_programs(config)?;
+    test_command(config, None)?;
+    for subproject in &subprojects.0 {
+        if let Some(test_command) = &subproject.settings.test_command {
+            split_test_command(Some(test_command))?;
+        }
+    }
+    let excludes = copy_excludes(config)?;
+    validate_tests_path(config, &excludes)?;
+    if let Some(log_dir) = &config.log_dir {
+        fs::create_dir_all(log_dir).map_err(PymuteError::io(log_dir))?;
+    }
+    let bar = match output_level {
+        OutputLevel::Quiet => ProgressBar::hidden(),
+        _ => ProgressBar::new(mutants.len().try_into().map_err(PymuteError::other)?),
+    };
+    let remaining_time = Arc::new(RemainingTime::new(worker_threads(config)));
+    let estimate = remaining_time.clone();
+    bar.set_style(
+        ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} ETA {remaining} {msg}",
+        )
+        .map_err(PymuteError::other)?
+        .with_key(
+            "remaining",
+            move |state: &ProgressState, w: &mut dyn fmt::Write| {
+                let remaining = state.len().unwrap_or(0).saturating_sub(state.pos());
+                let _ = match estimate.estimate(remaining.try_into().unwrap_or(usize::MAX)) {
+                    Some(estimate) => write!(w, "{}", format_duration(estimate)),
+                    None => write!(w, "?"),
+                };
+            },
+        ),
+    );
+
+    let verdicts = read_verdicts(&config.state().verdicts_file())?;
+    let observers = observers(config)?;
+    let notify = |event: Event| {
+        observers
+            .iter()
+            .for_each(|observer| observer.notify(&event))
+    };
+
+    let threads = worker_threads(config);
+    // the working copies are kept in the state directory, so that `pymute clean`
+    // finds those left over by a killed run
+    let state = config.state();
+    let workdirs_dir = state.workdirs_dir();
+    state.create().map_err(PymuteError::io(state.path()))?;
+    fs::create_dir_all(&workdirs_dir).map_err(PymuteError::io(&workdirs_dir))?;
+    let mut workdirs = WorkdirPool::new_in(&config.root, threads, excludes, &workdirs_dir)?;
+    if config.in_place {
+        check_in_place(config)?;
+        workdirs = workdirs.in_place();
+    } else if !config.workspaces.is_empty() {
+        workdirs = workdirs.workspaces(&config.workspaces)?;
+        workdirs.validate(mutants)?;
+    } else if let Some(prepared_root) = &config.prepared_root {
+        let src_prefix = config.prepared_src_prefix.clone().unwrap_or_default();
+        workdirs = workdirs.prepared(prepared_root, &src_prefix)?;
+        workdirs.validate(mutants)?;
+    }
+
+    let interrupts = interrupt_count()?;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(PymuteError::other)?;
+
+    // cancelled when a test run of this run is interrupted
+    let cancelled = AtomicBool::new(false);
+    let interrupted =
+        || INTERRUPTS.load(Ordering::SeqCst) != interrupts || cancelled.load(Ordering::SeqCst);
+    // no further mutants are started once too many could not be run
+    let errors = AtomicUsize::new(0);
+    let too_many_errors = || {
+        config
+            .max_errors
+            .is_some_and(|max_errors| errors.load(Ordering::SeqCst) > max_errors)
+    };
+    // a mutant that could not be removed from the root stops the run
+    let not_restored = Mutex::new(None);
+
+    notify(Event::RunStarted {
+        mutants: mutants.len(),
+    });
+    // the mutants are started in their order, and each worker takes the next one
+    let mut outcomes: Vec<(usize, Option<MutantOutcome>)> = pool.install(|| {
+        mutants
+            .iter()
+            .enumerate()
+            .par_bridge()
+            .progress_with(bar.clone())
+            .map(|(index, mutant)| {
+                if !interrupted() && !too_many_errors() && may_dispatch(&observers, interrupted) {
+                    bar.set_message(format!("[{}]: {mutant}\r", "RUNNING".yellow()));
+                    notify(Event::mutant_started(mutant));
+                    let tests = coverage.and_then(|coverage| {
+                        match coverage.coverage(mutant, &config.root) {
+                            Coverage::Tests(tests) => Some(tests),
+                            _ => None,
+                        }
+                    });
+                    let start = Instant::now();
+                    let run = run_mutant(
+                        &workdirs,
+                        mutant,
+                        config,
+                        subprojects,
+                        tests,
+                        &bar,
+                        &interrupted,
+                    );
+                    // a failure to run a single mutant (e.g. to copy the project)
+                    // does not lose the results of the others
+                    let (result, error) = match run {
+                        Ok(result) => {
+                            if let Some(dir) = mutant_kept_dir(config, mutant, result) {
+                                if let Err(error) = workdirs.keep(&dir) {
+                                    bar.suspend(|| {
+                                        eprintln!(
+                                            "{}: Could not keep the working copy of {mutant}: {error}",
+                                            "Warning".yellow()
+                                        )
+                                    });
+                                }
+                            }
+                            (result, None)
+                        }
+                        Err(error) => {
+                            errors.fetch_add(1, Ordering::SeqCst);
+                            let result = MutantResult::Error {
+                                signal: None,
+                                code: None,
+                            };
+                            if let Some(PymuteError::NotRestored {
+                                file,
+                                line_number,
+                                reason,
+                            }) = error.downcast_ref::<PymuteError>()
+                            {
+                                cancelled.store(true, Ordering::SeqCst);
+                                *not_restored.lock().unwrap_or_else(PoisonError::into_inner) =
+                                    Some((file.clone(), *line_number, reason.clone()));
+                            }
+                            (result, Some(error.to_string()))
+                        }
+                    };
+                    // a test run interrupted by the user cancels the whole run
+                    if result
+                        == (MutantResult::Error {
+                            signal: Some(SIGINT),
+                            code: None,
+                        })
+                    {
+                        cancelled.store(true, Ordering::SeqCst);
                     }
+
+                    let duration = start.elapsed();
+                    remaining_time.record(duration);
+                    let outcome = MutantOutcome {
+                        mutant: mutant.clone(),
+                        result,
+                        duration: Some(duration),
+                        error,
+                    };
+                    print_outcome(&bar, &outcome, config, &verdicts);
+                    notify(Event::mutant_finished(&outcome, duration));
+                    (index, Some(outcome))
+                } else {
+                    (index, None)
                 }
-            }
-        });
+            })
+            .collect()
+    });
+    outcomes.sort_by_key(|(index, _)| *index);
 
-    top_level_temp_dir.close()?;
+    workdirs.close()?;
+    let outcomes: Vec<MutantOutcome> = outcomes
+        .into_iter()
+        .filter_map(|(_, outcome)| outcome)
+        .collect();
+    notify(Event::run_finished(&outcomes));
 
+    if let Some((file, line_number, reason)) = not_restored
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner)
+    {
+        return Err(PymuteError::NotRestored {
+            file,
+            line_number,
+            reason,
+        });
+    }
     // Check if the program was interrupted
-    if !running.load(Ordering::SeqCst) {
+    if interrupted() {
         println!("Interrupted. Cleaning up...");
-        return Err(Box::new(KeyboardInterrupt {}));
+        return Err(PymuteError::Interrupted);
+    }
+    if let (true, Some(max_errors)) = (too_many_errors(), config.max_errors) {
+        return Err(PymuteError::TooManyErrors {
+            errors: errors.into_inner(),
+            max_errors,
+        });
+    }
+    Ok(outcomes)
+}
+
+/// Number of mutants run in parallel: one in place, one per workspace if there are
+/// any, see `RunConfig::workspaces`, and `RunConfig::num_threads` otherwise.
+pub fn worker_threads(config: &RunConfig) -> usize {
+    match (config.in_place, config.workspaces.len()) {
+        (true, _) => 1,
+        (false, 0) => config.num_threads,
+        (false, workspaces) => workspaces,
+    }
+}
+
+/// Estimates the remaining time of a run from the durations of its finished
+/// mutants, for the progress bar.
+///
+/// The estimate of indicatif assumes that the mutants finish at a steady rate,
+/// which is far off for mutants of uneven duration running in parallel.
+struct RemainingTime {
+    /// Sum of the durations of the finished mutants in milliseconds.
+    total_ms: AtomicU64,
+    /// Number of finished mutants.
+    finished: AtomicUsize,
+    /// Number of mutants running in parallel.
+    threads: usize,
+}
+
+impl RemainingTime {
+    fn new(threads: usize) -> Self {
+        RemainingTime {
+            total_ms: AtomicU64::new(0),
+            finished: AtomicUsize::new(0),
+            threads,
+        }
+    }
+
+    /// Record that a mutant finished after `duration`.
+    fn record(&self, duration: Duration) {
+        let millis = duration.as_millis().try_into().unwrap_or(u64::MAX);
+        self.total_ms.fetch_add(millis, Ordering::Relaxed);
+        self.finished.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The estimated time until the `remaining` mutants are finished.
+    fn estimate(&self, remaining: usize) -> Option<Duration> {
+        estimate_remaining(
+            Duration::from_millis(self.total_ms.load(Ordering::Relaxed)),
+            self.finished.load(Ordering::Relaxed),
+            remaining,
+            self.threads,
+        )
+    }
+}
+
+/// Estimate how long `remaining` mutants run on `threads` threads, given that
+/// `finished` mutants took `total` together: the mean duration of the finished
+/// mutants times the number of remaining mutants per thread. Returns None before
+/// the first mutant finished.
+fn estimate_remaining(
+    total: Duration,
+    finished: usize,
+    remaining: usize,
+    threads: usize,
+) -> Option<Duration> {
+    if finished == 0 {
+        return None;
+    }
+    Some(total.mul_f64(remaining as f64 / (finished * threads.max(1)) as f64))
+}
+
+/// Observers to notify about the events of the run, see `events`.
+fn observers(config: &RunConfig) -> Result<Vec<Arc<dyn Observer>>, Box<dyn Error + Send + Sync>> {
+    let mut observers = config.observers.0.clone();
+    if let Some(path) = &config.events_socket {
+        let observer = SocketObserver::connect(path).map_err(|error| {
+            format!(
+                "Could not connect to events socket {}: {error}",
+                path.display()
+            )
+        })?;
+        observers.push(Arc::new(observer));
+    }
+    Ok(observers)
+}
+
+/// Interval in which the observers are asked again while they pause the run.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wait while any observer pauses the run.
+///
+/// Returns whether the next mutant may start, i.e. false if an observer stops the
+/// run or the run is interrupted while paused.
+fn may_dispatch(observers: &[Arc<dyn Observer>], interrupted: impl Fn() -> bool) -> bool {
+    loop {
+        let dispatch = observers
+            .iter()
+            .map(|observer| observer.dispatch())
+            .max()
+            .unwrap_or_default();
+        match dispatch {
+            Dispatch::Continue => return true,
+            Dispatch::Pause if !interrupted() => thread::sleep(PAUSE_POLL_INTERVAL),
+            Dispatch::Pause | Dispatch::Stop => return false,
+        }
+    }
+}
+
+/// Print the outcome of a mutant above the progress bar according to the
+/// output level.
+///
+/// Survivors are printed with their id (and their log file, see
+/// `RunConfig::log_dir`), and survivors accepted in `verdicts` are only printed
+/// like caught mutants.
+fn print_outcome(
+    bar: &ProgressBar,
+    outcome: &MutantOutcome,
+    config: &RunConfig,
+    verdicts: &Verdicts,
+) {
+    let mutant = &outcome.mutant;
+    let output_level = &config.output_level;
+    match (outcome.result, output_level) {
+        (_, OutputLevel::Quiet) => {}
+        (MutantResult::Missed, _) => {
+            let id = mutant.id(&config.root);
+            let mut log = match mutant_log_file(config, mutant) {
+                Some(log) => format!(", log {}", log.display()),
+                None => String::new(),
+            };
+            if let Some(dir) = mutant_kept_dir(config, mutant, outcome.result) {
+                if dir.is_dir() {
+                    log.push_str(&format!(", kept in {}", dir.display()));
+                }
+            }
+            match (verdicts.contains_key(&id), output_level) {
+                (false, _) => bar.println(format!(
+                    "[{}] Mutant Survived: {} (id {id}{log})",
+                    "MISSED".red(),
+                    mutant
+                )),
+                (true, OutputLevel::Missed) => {}
+                (true, _) => bar.println(format!(
+                    "[{}] Mutant Survived: {} (id {id})",
+                    "ACCEPTED".blue(),
+                    mutant
+                )),
+            }
+        }
+        // untested files are reported before the run, uncovered mutants are not run
+        (MutantResult::Untested | MutantResult::Uncovered, _) => {}
+        (MutantResult::Error { signal, code }, _) => {
+            let reason = match (signal, code, &outcome.error) {
+                (Some(signal), _, _) => format!("Test run killed by signal {signal}"),
+                (None, Some(code), _) => format!("Tests could not run (exit code {code})"),
+                (None, None, Some(error)) => format!("Could not run the mutant ({error})"),
+                (None, None, None) => "Test run failed".into(),
+            };
+            match outcome.error {
+                Some(_) => bar.println(format!("[{}] {reason}: {}", "ERROR".red(), mutant)),
+                None => bar.println(format!("[{}] {reason}: {}", "ERROR".magenta(), mutant)),
+            }
+        }
+        (MutantResult::Invalid, OutputLevel::Missed) => {}
+        (MutantResult::Invalid, _) => {
+            bar.println(format!(
+                "[{}] Mutant is not valid python: {}",
+                "INVALID".yellow(),
+                mutant
+            ));
+        }
+        (MutantResult::NoTests, _) => {
+            bar.println(format!(
+                "[{}] No tests collected: {}",
+                "NO TESTS".yellow(),
+                mutant
+            ));
+        }
+        (MutantResult::Caught, OutputLevel::Missed) => {}
+        (MutantResult::Caught, _) => {
+            bar.println(format!("[{}] Mutant Killed: {}", "CAUGHT".green(), mutant));
+        }
+    }
+}
+
+/// Number of times the user pressed Ctrl+C in this process.
+static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Get the number of times the user pressed Ctrl+C so far, for a run to check
+/// whether it was interrupted since it started.
+///
+/// The ctrlc crate only allows a single handler per process, so the handler is
+/// installed once and shared by all subsequent calls to `run_mutants`. Since each
+/// run only compares against its own start, an interrupted run does not affect the
+/// runs after it, e.g. of a process embedding pymute.
+fn interrupt_count() -> Result<usize, Box<dyn Error + Send + Sync>> {
+    static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+    let mut installed = INSTALLED.lock().unwrap_or_else(PoisonError::into_inner);
+    if !*installed {
+        ctrlc::set_handler(|| {
+            INTERRUPTS.fetch_add(1, Ordering::SeqCst);
+            println!("Ctrl+C pressed. Exiting...");
+        })?;
+        *installed = true;
+    }
+    Ok(INTERRUPTS.load(Ordering::SeqCst))
+}
+
+/// Glob patterns of files and directories not to copy into the working copies.
+///
+/// Consists of the default excludes, the user supplied patterns and the state
+/// directory if it lies inside the root.
+pub(crate) fn copy_excludes(config: &RunConfig) -> Result<Vec<Pattern>, PymuteError> {
+    let mut patterns = DEFAULT_COPY_EXCLUDES
+        .iter()
+        .copied()
+        .chain(config.copy_exclude.iter().map(String::as_str))
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|error| PymuteError::InvalidGlob {
+                pattern: pattern.to_string(),
+                reason: error.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let state = config.state();
+    if let Ok(relative) = state.path().strip_prefix(&config.root) {
+        if let Some(relative) = relative.to_str() {
+            let pattern = Pattern::escape(relative);
+            patterns.push(
+                Pattern::new(&pattern).map_err(|error| PymuteError::InvalidGlob {
+                    pattern,
+                    reason: error.to_string(),
+                })?,
+            );
+        }
+    }
+    Ok(patterns)
+}
+
+/// Copy the project at `root` to `dest`, skipping everything matching `excludes`.
+///
+/// A pattern matches an entry if it matches either its name or its path relative
+/// to the root, so that e.g. `__pycache__` excludes such directories at any depth.
+pub fn copy_project(root: &Path, dest: &Path, excludes: &[Pattern]) -> Result<(), PymuteError> {
+    CopyOptions::new()
+        .filter(|relative: &Path, _: &DirEntry| Ok(!is_excluded(relative, excludes)))
+        .copy_tree(root, dest)
+        .map_err(|error| PymuteError::Io {
+            path: dest.to_path_buf(),
+            source: io::Error::other(error),
+        })?;
+    Ok(())
+}
+
+/// Whether the entry at `relative` (to the root) is not copied because of
+/// `excludes`, see `copy_project`.
+fn is_excluded(relative: &Path, excludes: &[Pattern]) -> bool {
+    let name = relative.file_name().and_then(|name| name.to_str());
+    excludes.iter().any(|pattern| {
+        pattern.matches_path(relative) || name.is_some_and(|name| pattern.matches(name))
+    })
+}
+
+/// Split the `tests` argument of pytest into the path of the tests and the rest
+/// of a node id, e.g. `tests/test_x.py::TestFoo` into `tests/test_x.py` and
+/// `::TestFoo`.
+pub(crate) fn split_tests_argument(tests: &str) -> (&str, &str) {
+    tests.split_at(tests.find("::").unwrap_or(tests.len()))
+}
+
+/// The `tests` argument of pytest relative to the root of the working copies,
+/// with `/` as separator on all platforms.
+///
+/// Absolute paths inside the root are made relative, as they would otherwise run
+/// the tests of the original project instead of those of the working copy.
+fn normalized_tests_argument(config: &RunConfig) -> String {
+    let (path, node) = split_tests_argument(&config.tests);
+    let path = Path::new(path);
+    let relative = match path.is_absolute() {
+        false => path.to_path_buf(),
+        true => match (config.root.canonicalize(), path.canonicalize()) {
+            (Ok(root), Ok(path)) if path.starts_with(&root) => {
+                path.strip_prefix(&root).unwrap_or(&path).to_path_buf()
+            }
+            // tests outside the project are run as they are
+            _ => return config.tests.clone(),
+        },
+    };
+
+    let components: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    match components.is_empty() {
+        true => format!(".{node}"),
+        false => format!("{}{node}", components.join("/")),
+    }
+}
+
+/// Check that the tests run by pytest exist in the working copies of the
+/// project, i.e. they exist in the project and are not excluded from copying.
+///
+/// Otherwise pytest would fail for every mutant, and all mutants would be
+/// reported as caught. For node ids (e.g. `tests/test_x.py::TestFoo`) only the
+/// file is checked.
+fn validate_tests_path(
+    config: &RunConfig,
+    excludes: &[Pattern],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if config.runner != Runner::Pytest {
+        return Ok(());
+    }
+    let tests = normalized_tests_argument(config);
+    let (path, _) = split_tests_argument(&tests);
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return Ok(());
+    }
+
+    let not_found = |reason: String| TestsNotFound {
+        tests: config.tests.clone(),
+        reason,
+    };
+    // the tests run in the working copies, which are copies of the prepared root,
+    // or in the workspaces
+    let root = config
+        .workspaces
+        .first()
+        .or(config.prepared_root.as_ref())
+        .unwrap_or(&config.root);
+    if !root.join(path).exists() {
+        return Err(Box::new(not_found(format!(
+            "{} does not exist in {}",
+            path.display(),
+            root.display()
+        ))));
+    }
+    // nothing is copied into the workspaces or when running in place
+    if config.in_place || !config.workspaces.is_empty() {
+        return Ok(());
+    }
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        relative.push(component);
+        if is_excluded(&relative, excludes) {
+            return Err(Box::new(not_found(format!(
+                "{} is excluded from the working copies of the project (see `--copy-exclude` and the default excludes)",
+                relative.display()
+            ))));
+        }
+    }
+    Ok(())
+}
+
+/// Check that the mutants can be inserted into the root itself: its git working
+/// tree has to be clean, unless `RunConfig::allow_dirty` is set, so that the
+/// project can be restored with git if a mutant can not be removed.
+fn check_in_place(config: &RunConfig) -> Result<(), PymuteError> {
+    if config.num_threads > 1 {
+        eprintln!(
+            "{}: Mutants inserted in place run one at a time, ignoring --num-threads {}",
+            "Warning".yellow(),
+            config.num_threads
+        );
+    }
+    if config.allow_dirty {
+        return Ok(());
+    }
+    let changes = diff::uncommitted_changes(&config.root)?;
+    if !changes.is_empty() {
+        return Err(PymuteError::other(DirtyWorkingTree {
+            root: config.root.clone(),
+            changes,
+        }));
     }
     Ok(())
 }
 
+/// Working copies of the project, one per worker thread of the rayon pool.
+///
+/// Instead of copying the whole project for every mutant, each worker gets its own
+/// copy when it runs its first mutant. Before the next mutant is inserted, only the
+/// file that was mutated last is restored from the root.
+///
+/// Instead of the project, a prepared tree (e.g. with an installed virtual
+/// environment) can be copied, see `WorkdirPool::prepared`, or existing copies of
+/// the project can be used, see `WorkdirPool::workspaces`.
+///
+/// The working copies are removed when the pool is dropped, also if a run fails or
+/// a worker panics, so that no copies are left behind in the temporary directory.
+/// Workspaces are restored instead.
+pub struct WorkdirPool {
+    root: PathBuf,
+    /// The tree copied into the working copies, the root unless prepared.
+    template: PathBuf,
+    /// Path in the template under which the files of the project are found.
+    src_prefix: PathBuf,
+    excludes: Vec<Pattern>,
+    // dropped before the directory that contains them
+    workdirs: Vec<Mutex<Option<Workdir>>>,
+    temp_dir: TempDir,
+}
+
+struct Workdir {
+    /// Root of the working copy.
+    path: PathBuf,
+    /// The copy made by the pool, None for a workspace.
+    temp: Option<TempDir>,
+    /// The tree the mutated file is restored from.
+    template: PathBuf,
+    /// Path of the file mutated last, relative to the root of the working copy.
+    last_mutated: Option<PathBuf>,
+    /// Whether the working copy is the root itself, see `WorkdirPool::in_place`.
+    in_place: bool,
+    /// Snapshot of the file of the mutant inserted into the root, if the working
+    /// copy is the root.
+    inserted: Option<FileSnapshot>,
+}
+
+impl Workdir {
+    /// A working copy at `path` whose mutated files are restored from `template`.
+    fn new(path: PathBuf, temp: Option<TempDir>, template: PathBuf) -> Self {
+        Workdir {
+            path,
+            temp,
+            template,
+            last_mutated: None,
+            in_place: false,
+            inserted: None,
+        }
+    }
+
+    /// Restore the file mutated last from the template, or the file of the mutant
+    /// inserted into the root from its snapshot.
+    fn restore(&mut self) -> Result<(), PymuteError> {
+        if let Some(snapshot) = self.inserted.take() {
+            // a file changed while the tests ran (e.g. by a formatter) is left for
+            // inspection instead of being overwritten
+            return snapshot.restore();
+        }
+        if let Some(relative) = self.last_mutated.take() {
+            let copy = self.path.join(&relative);
+            fs::copy(self.template.join(&relative), &copy).map_err(PymuteError::io(copy))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Workdir {
+    fn drop(&mut self) {
+        // a workspace or the root is left as it was found, also if the run fails
+        if self.temp.is_none() {
+            if let Err(error) = self.restore() {
+                eprintln!("{}: {error}", "Error".red());
+            }
+        }
+    }
+}
+
+impl WorkdirPool {
+    /// Create a pool of up to `size` working copies of the project at `root`.
+    ///
+    /// Files and directories matching `excludes` are not copied.
+    pub fn new(root: &Path, size: usize, excludes: Vec<Pattern>) -> Result<Self, PymuteError> {
+        Self::new_in(root, size, excludes, &std::env::temp_dir())
+    }
+
+    /// Create a pool like `WorkdirPool::new`, with the working copies in a new
+    /// directory in `parent` instead of the temporary directory of the system.
+    pub fn new_in(
+        root: &Path,
+        size: usize,
+        excludes: Vec<Pattern>,
+        parent: &Path,
+    ) -> Result<Self, PymuteError> {
+        let root = root.canonicalize().map_err(PymuteError::io(root))?;
+        Ok(WorkdirPool {
+            template: root.clone(),
+            src_prefix: PathBuf::new(),
+            root,
+            excludes,
+            workdirs: (0..size.max(1)).map(|_| Mutex::new(None)).collect(),
+            temp_dir: tempdir_in(parent).map_err(PymuteError::io(parent))?,
+        })
+    }
+
+    /// Copy the prepared tree at `template` into the working copies instead of the
+    /// project. The files of the project are found under `src_prefix` in it (e.g.
+    /// `lib/python3.11/site-packages`), at the same path relative to the prefix as
+    /// relative to the root.
+    ///
+    /// The tests are run in the copies of `template`. Use `validate` to check that
+    /// the prepared tree contains the files to mutate.
+    pub fn prepared(mut self, template: &Path, src_prefix: &Path) -> Result<Self, PymuteError> {
+        self.template = template.canonicalize().map_err(PymuteError::io(template))?;
+        self.src_prefix = src_prefix.to_path_buf();
+        Ok(self)
+    }
+
+    /// Insert the mutants into the existing copies of the root at `workspaces`
+    /// instead of temporary copies, one per worker. The mutated file is restored
+    /// from the root after each mutant and when the pool is dropped, and the
+    /// workspaces are never removed.
+    ///
+    /// Use `validate` to check that the workspaces contain the files to mutate.
+    pub fn workspaces(mut self, workspaces: &[PathBuf]) -> Result<Self, PymuteError> {
+        let mut workdirs = Vec::new();
+        for workspace in workspaces {
+            let path = workspace
+                .canonicalize()
+                .map_err(PymuteError::io(workspace))?;
+            // the mutants would be inserted into the original files
+            if path == self.root {
+                return Err(PymuteError::other(WorkspaceIsRoot { workspace: path }));
+            }
+            let workdir = Workdir::new(path, None, self.root.clone());
+            workdirs.push(Mutex::new(Some(workdir)));
+        }
+        self.template = self.root.clone();
+        self.src_prefix = PathBuf::new();
+        self.workdirs = workdirs;
+        Ok(self)
+    }
+
+    /// Insert the mutants into the files of the root itself instead of working
+    /// copies, one at a time. The file of each mutant is restored from a snapshot
+    /// taken before inserting it, right after its tests ran and when the pool is
+    /// dropped. A file changed in the meantime is not overwritten, see
+    /// `FileSnapshot::restore`.
+    pub fn in_place(mut self) -> Self {
+        let mut workdir = Workdir::new(self.root.clone(), None, self.root.clone());
+        workdir.in_place = true;
+        self.workdirs = vec![Mutex::new(Some(workdir))];
+        self
+    }
+
+    /// Check that the files of `mutants` are part of the copied tree (or of each
+    /// workspace) with the same content as in the root, so that each mutant is
+    /// inserted into the file it was found in.
+    pub fn validate(&self, mutants: &[Mutant]) -> Result<(), PymuteError> {
+        let workspaces = self.workspace_paths();
+        let workspace = !workspaces.is_empty();
+        let trees = match workspace {
+            true => workspaces,
+            false => vec![self.template.join(&self.src_prefix)],
+        };
+        let files: BTreeSet<&PathBuf> = mutants.iter().map(|mutant| &mutant.file_path).collect();
+        for file in files {
+            let relative = self.relative_path(file)?;
+            for tree in &trees {
+                let prepared = tree.join(&relative);
+                let mismatch = |reason| PreparedFileMismatch {
+                    file: file.clone(),
+                    prepared: prepared.clone(),
+                    reason,
+                    workspace,
+                };
+                match fs::read(&prepared) {
+                    Err(_) => return Err(PymuteError::other(mismatch("does not exist"))),
+                    Ok(content) if content != fs::read(file).map_err(PymuteError::io(file))? => {
+                        return Err(PymuteError::other(mismatch("differs from the original")))
+                    }
+                    Ok(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The workspaces of the pool, see `WorkdirPool::workspaces`.
+    fn workspace_paths(&self) -> Vec<PathBuf> {
+        self.workdirs
+            .iter()
+            .filter_map(|slot| {
+                let slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+                slot.as_ref()
+                    .filter(|workdir| workdir.temp.is_none() && !workdir.in_place)
+                    .map(|workdir| workdir.path.clone())
+            })
+            .collect()
+    }
+
+    /// Insert `mutant` into the working copy of the current worker and call `f`
+    /// with the root of that copy.
+    ///
+    /// The working copy is reserved for the duration of `f`.
+    pub fn with_mutant<T>(
+        &self,
+        mutant: &Mutant,
+        f: impl FnOnce(&Path) -> T,
+    ) -> Result<T, PymuteError> {
+        let mut slot = self.slot();
+        let workdir = match slot.take() {
+            Some(workdir) => workdir,
+            None => {
+                let dir =
+                    tempdir_in(&self.temp_dir).map_err(PymuteError::io(self.temp_dir.path()))?;
+                copy_project(&self.template, dir.path(), &self.excludes)?;
+                Workdir::new(dir.path().to_path_buf(), Some(dir), self.template.clone())
+            }
+        };
+        // the working copy stays in its slot if it can not be restored
+        let workdir = slot.insert(workdir);
+        workdir.restore()?;
+
+        // the root is restored right away, so that it is only mutated while the
+        // tests run
+        if workdir.in_place {
+            let snapshot = mutant.insert()?;
+            workdir.inserted = Some(snapshot);
+            let result = f(&workdir.path);
+            workdir.restore()?;
+            return Ok(result);
+        }
+
+        // the path is known before inserting, so a mutated file is always restored
+        let mutated = self.mutated_path(mutant)?;
+        let src_root = workdir.path.join(&self.src_prefix);
+        workdir.last_mutated = Some(mutated);
+        mutant.insert_in_new_root(&self.root, &src_root)?;
+
+        Ok(f(&workdir.path))
+    }
+
+    /// Move the working copy of the current worker to `dest`, replacing anything at
+    /// `dest`. The mutant it ran last stays inserted, and the worker gets a new
+    /// copy for its next mutant. A workspace is copied to `dest` instead.
+    pub fn keep(&self, dest: &Path) -> Result<(), PymuteError> {
+        let mut slot = self.slot();
+        let workdir = match slot.as_mut() {
+            Some(workdir) => workdir,
+            None => return Ok(()),
+        };
+        if dest.exists() {
+            fs::remove_dir_all(dest).map_err(PymuteError::io(dest))?;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(PymuteError::io(parent))?;
+        }
+        let dir = match workdir.temp.take() {
+            Some(temp) => {
+                // the moved copy keeps its mutant
+                workdir.last_mutated = None;
+                temp.into_path()
+            }
+            None => return copy_project(&workdir.path, dest, &self.excludes),
+        };
+        *slot = None;
+        // the temporary directory may be on another file system
+        if fs::rename(&dir, dest).is_err() {
+            copy_project(&dir, dest, &[])?;
+            fs::remove_dir_all(&dir).map_err(PymuteError::io(&dir))?;
+        }
+        Ok(())
+    }
+
+    /// The working copy of the current worker.
+    fn slot(&self) -> MutexGuard<'_, Option<Workdir>> {
+        let index = rayon::current_thread_index().unwrap_or(0) % self.workdirs.len();
+        self.workdirs[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Path of the file of `mutant` in the working copy at `dir`.
+    pub fn mutated_file(&self, dir: &Path, mutant: &Mutant) -> Result<PathBuf, PymuteError> {
+        Ok(dir.join(self.mutated_path(mutant)?))
+    }
+
+    /// Path of the file of `mutant` relative to the root of a working copy.
+    fn mutated_path(&self, mutant: &Mutant) -> Result<PathBuf, PymuteError> {
+        Ok(self.src_prefix.join(self.relative_path(&mutant.file_path)?))
+    }
+
+    /// Path of `file` of the project relative to its root.
+    fn relative_path(&self, file: &Path) -> Result<PathBuf, PymuteError> {
+        let path = file.canonicalize().map_err(PymuteError::io(file))?;
+        match path.strip_prefix(&self.root) {
+            Ok(relative) => Ok(relative.to_path_buf()),
+            Err(_) => Err(PymuteError::Mutant(MutantError::OutsideRoot {
+                file_path: file.to_path_buf(),
+                root: self.root.clone(),
+            })),
+        }
+    }
+
+    /// Remove all working copies and restore the workspaces.
+    pub fn close(self) -> Result<(), PymuteError> {
+        for slot in self.workdirs {
+            if let Some(mut workdir) = slot.into_inner().unwrap_or_else(PoisonError::into_inner) {
+                workdir.restore()?;
+            }
+        }
+        let path = self.temp_dir.path().to_path_buf();
+        self.temp_dir.close().map_err(PymuteError::io(path))?;
+        Ok(())
+    }
+}
+
+/// Run the test suite against `mutant` in a working copy, or only the `tests`
+/// with these pytest node ids if given. With `RunConfig::skip_invalid`, a mutant
+/// that is not valid python is not run at all.
+///
+/// With `OutputLevel::Process`, the output of the tests is printed above the `bar`
+/// as one block once they finish, see `print_process_output`.
+///
+/// If the run is `interrupted` while the tests are running, the test process is
+/// killed and the result is an error by SIGINT, which cancels the run.
 fn run_mutant(
-    work_dir: &TempDir,
+    workdirs: &WorkdirPool,
     mutant: &Mutant,
-    root: &PathBuf,
-    tests_glob: &str,
-    output_level: &OutputLevel,
-    runner: &Runner,
-    environment: &Option<String>,
-) -> Result<MutantResult, Box<dyn Error>> {
-    let dir = tempdir_in(work_dir).expect("Failed to create temporary directory!");
-
-    let root_path = root;
-    let _stats = CopyOptions::new()
-        .copy_tree(root_path, dir.path())
-        .expect("Failed to copy the Python project root!");
-
-    mutant
-        .insert_in_new_root(root_path, dir.path())
-        .expect("Failed to insert mutant");
-
-    // build the correct command depending on arguments
-    let program = match runner {
-        Runner::Pytest => "python",
-        Runner::Tox => "tox",
+    config: &RunConfig,
+    subprojects: &Subprojects,
+    tests: Option<&BTreeSet<String>>,
+    bar: &ProgressBar,
+    interrupted: &dyn Fn() -> bool,
+) -> Result<MutantResult, Box<dyn Error + Send + Sync>> {
+    // the test command of the subproject runs in its directory
+    let (mut command, subdir, runner) = match subprojects.find(&mutant.file_path) {
+        Some(subproject) if subproject.settings.test_command.is_some() => {
+            let (program, args) = split_test_command(subproject.settings.test_command.as_deref())?;
+            let mut command = Command::new(program);
+            command.args(args);
+            let subdir = subproject
+                .dir
+                .strip_prefix(&config.root)
+                .unwrap_or(Path::new(""));
+            (command, subdir.to_path_buf(), Runner::Custom)
+        }
+        _ => (test_command(config, tests)?, PathBuf::new(), config.runner),
     };
-    let mut command = Command::new(program);
+    // conftest hooks can tell which mutant is run, see `mutant_env`
+    command
+        .envs(&config.env)
+        .envs(mutant_env(mutant, &config.root));
+
+    workdirs.with_mutant(mutant, |dir| {
+        // a mutant that does not compile would only fail at test collection
+        if config.skip_invalid {
+            let file = workdirs.mutated_file(dir, mutant)?;
+            if !syntax::check_syntax(&python(config), &[file])?.is_empty() {
+                return Ok(MutantResult::Invalid);
+            }
+        }
+        // each mutant writes its own log or buffer, so parallel test runs do not
+        // interleave
+        let mut captured = None;
+        match (mutant_log_file(config, mutant), config.output_level) {
+            (Some(log), _) => {
+                let log = File::create(log)?;
+                command.stdout(log.try_clone()?).stderr(log);
+            }
+            (None, OutputLevel::Process) => {
+                let capture = tempfile::tempfile()?;
+                command
+                    .stdout(capture.try_clone()?)
+                    .stderr(capture.try_clone()?);
+                captured = Some(capture);
+            }
+            (None, _) => {
+                command.stdout(Stdio::null()).stderr(Stdio::null());
+            }
+        };
+        let mut child = command
+            .env("PYMUTE_WORKDIR", dir)
+            .current_dir(dir.join(&subdir))
+            .spawn()
+            .map_err(|source| PymuteError::RunnerSpawn {
+                program: PathBuf::from(command.get_program()),
+                source,
+            })?;
+        let status = wait_or_kill(&mut child, interrupted)?;
+        if let Some(mut capture) = captured {
+            print_process_output(bar, mutant, &config.root, &mut capture)?;
+        }
+        Ok(match status {
+            Some(status) => classify_exit_status(status, runner),
+            None => MutantResult::Error {
+                signal: Some(SIGINT),
+                code: None,
+            },
+        })
+    })?
+}
+
+/// Print the output of the test run of `mutant`, as captured in `capture`, to
+/// stdout above the `bar` in one block headed by the mutant.
+fn print_process_output(
+    bar: &ProgressBar,
+    mutant: &Mutant,
+    root: &Path,
+    capture: &mut File,
+) -> io::Result<()> {
+    let mut output = Vec::new();
+    capture.seek(SeekFrom::Start(0))?;
+    capture.read_to_end(&mut output)?;
+    // unlike `println`, `suspend` also prints if the bar is hidden (not a terminal)
+    bar.suspend(|| {
+        let mut stdout = io::stdout().lock();
+        writeln!(
+            stdout,
+            "[{}] Test run of {} (id {})",
+            "OUTPUT".cyan(),
+            mutant,
+            mutant.id(root)
+        )?;
+        stdout.write_all(&output)?;
+        if !output.is_empty() && !output.ends_with(b"\n") {
+            writeln!(stdout)?;
+        }
+        stdout.flush()
+    })
+}
+
+/// The file in `RunConfig::log_dir` that holds the output of the test run of
+/// `mutant`, named after its id.
+pub fn mutant_log_file(config: &RunConfig, mutant: &Mutant) -> Option<PathBuf> {
+    let log_dir = config.log_dir.as_ref()?;
+    Some(log_dir.join(format!("{}.log", mutant.id(&config.root))))
+}
 
-    match runner {
+/// The directory in `RunConfig::keep_missed_dirs` that the working copy of
+/// `mutant` is kept in after its test run with `result`, named after its id.
+///
+/// Only the working copies of surviving mutants are kept, unless
+/// `RunConfig::keep_all_dirs` is set.
+pub fn mutant_kept_dir(
+    config: &RunConfig,
+    mutant: &Mutant,
+    result: MutantResult,
+) -> Option<PathBuf> {
+    let keep_dir = config.keep_missed_dirs.as_ref()?;
+    (result == MutantResult::Missed || config.keep_all_dirs)
+        .then(|| keep_dir.join(mutant.id(&config.root)))
+}
+
+/// The environment variables describing `mutant` for its test run: its id, its
+/// file relative to `root` and its line number. The working copy is given in
+/// `PYMUTE_WORKDIR`.
+pub fn mutant_env(mutant: &Mutant, root: &Path) -> [(&'static str, String); 3] {
+    let file = mutant
+        .file_path
+        .strip_prefix(root)
+        .unwrap_or(&mutant.file_path);
+    [
+        ("PYMUTE_MUTANT_ID", mutant.id(root)),
+        ("PYMUTE_MUTANT_FILE", file.display().to_string()),
+        ("PYMUTE_MUTANT_LINE", mutant.line_number.to_string()),
+    ]
+}
+
+/// Longest interval in which a running test process is checked.
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Wait for `child` to exit, or kill it as soon as the run is `interrupted`.
+///
+/// Returns None if the run was interrupted, also if the child exited by itself
+/// meanwhile, since a test run receiving the user's Ctrl+C (e.g. pytest exiting
+/// with code 2) does not tell anything about the mutant.
+fn wait_or_kill(
+    child: &mut Child,
+    interrupted: impl Fn() -> bool,
+) -> io::Result<Option<ExitStatus>> {
+    // short test runs are not delayed by the full interval
+    let mut interval = Duration::from_millis(1);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((!interrupted()).then_some(status));
+        }
+        if interrupted() {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(interval);
+        interval = (interval * 2).min(CHILD_POLL_INTERVAL);
+    }
+}
+
+/// Signal number of SIGINT.
+pub(crate) const SIGINT: i32 = 2;
+
+/// Exit code of pytest (and unittest since python 3.12) if no tests were collected.
+const PYTEST_NO_TESTS: i32 = 5;
+
+/// Classify the exit status of a test run of `runner`.
+///
+/// A test run terminated by a signal (e.g. by the OOM killer) is an error rather
+/// than a caught mutant. For pytest, unittest, tox (which exits with the exit
+/// code of the failing command) and nox, only exit code 1 means that tests
+/// failed. The other exit codes mean that the tests could not be run, e.g. 2 if a
+/// mutant breaks the import of a test module for pytest, and 5 if no tests were
+/// collected. The custom runner catches a mutant with any non-zero exit code.
+fn classify_exit_status(status: ExitStatus, runner: Runner) -> MutantResult {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return MutantResult::Error {
+                signal: Some(signal),
+                code: None,
+            };
+        }
+    }
+
+    match (status.code(), runner) {
+        (Some(0), _) => MutantResult::Missed,
+        (_, Runner::Custom) | (Some(1), _) => MutantResult::Caught,
+        (Some(PYTEST_NO_TESTS), _) => MutantResult::NoTests,
+        (code, _) => MutantResult::Error { signal: None, code },
+    }
+}
+
+/// Build the command that runs the test suite depending on the configured runner.
+/// The pytest runner runs only the `tests` with these node ids if given.
+fn test_command(
+    config: &RunConfig,
+    tests: Option<&BTreeSet<String>>,
+) -> Result<Command, Box<dyn Error + Send + Sync>> {
+    let command = match config.runner {
         Runner::Pytest => {
+            let mut command = Command::new(python(config));
+            command.arg("-B").arg("-m").arg("pytest");
+            match tests {
+                Some(tests) => command.args(tests),
+                None => command.arg(normalized_tests_argument(config)),
+            };
+            command.arg("-x");
+            if config.pytest_cache_clear {
+                command.arg("--cache-clear");
+            }
+            // the flag and its value are separate arguments
+            if let Some(workers) = config.pytest_workers {
+                command.arg("-n").arg(workers.to_string());
+                if let Some(dist) = &config.pytest_dist {
+                    command.arg("--dist").arg(dist);
+                }
+            }
+            command
+        }
+        Runner::Unittest => {
+            let mut command = Command::new(python(config));
+            command.arg("-B").arg("-m").arg("unittest");
+            let verbosity = match config.output_level {
+                OutputLevel::Process => "-v",
+                _ => "-q",
+            };
+            match is_unittest_name(&config.tests) {
+                true => command.arg(verbosity).arg("-f").arg(&config.tests),
+                false => command
+                    .arg("discover")
+                    .arg("-s")
+                    .arg(normalized_tests_argument(config))
+                    .arg(verbosity)
+                    .arg("-f"),
+            };
             command
-                .arg("-B")
-                .arg("-m")
-                .arg("pytest")
-                .arg(tests_glob)
-                .arg("-x");
         }
         Runner::Tox => {
-            if let Some(env) = environment {
+            let mut command = Command::new(runner_binary(config, "tox"));
+            if let Some(env) = &config.environment {
                 command.arg(format!("-e {env}"));
             };
+            command
+        }
+        Runner::Nox => {
+            let mut command = Command::new(runner_binary(config, "nox"));
+            if let Some(environment) = &config.environment {
+                command.arg("-s").args(nox_sessions(environment));
+            }
+            command
+        }
+        Runner::Custom => {
+            let (program, args) = split_test_command(config.test_command.as_deref())?;
+            let mut command = Command::new(program);
+            command.args(args);
+            command
         }
     };
+    let mut command = command;
+    if let Some(runner_args) = &config.runner_args {
+        let args = shlex::split(runner_args).ok_or(InvalidRunnerArgs {
+            args: runner_args.clone(),
+        })?;
+        // tox and nox pass the positional arguments after `--` on to the sessions
+        if matches!(config.runner, Runner::Tox | Runner::Nox) && !args.is_empty() {
+            command.arg("--");
+        }
+        command.args(args);
+    }
+    Ok(command)
+}
 
-    match output_level {
-        OutputLevel::Process => (),
-        _ => {
-            command.stdout(Stdio::null()).stderr(Stdio::null());
+/// The python interpreter to run the tests with, see `RunConfig::python`.
+pub(crate) fn python(config: &RunConfig) -> PathBuf {
+    configured_program(config.python.as_deref(), "python")
+}
+
+/// The tox or nox executable (`default`), see `RunConfig::runner_binary`.
+fn runner_binary(config: &RunConfig, default: &str) -> PathBuf {
+    configured_program(config.runner_binary.as_deref(), default)
+}
+
+/// The configured `program` as found by `find_program`, since a relative path
+/// would be resolved in the working copy, or `default` from the PATH.
+fn configured_program(program: Option<&Path>, default: &str) -> PathBuf {
+    match program {
+        Some(program) => find_program(program).unwrap_or_else(|| program.to_path_buf()),
+        None => PathBuf::from(default),
+    }
+}
+
+/// Find the executable `program`: a bare name on the PATH, and any other path
+/// relative to the current directory.
+pub fn find_program(program: &Path) -> Option<PathBuf> {
+    let is_executable = |path: &Path| {
+        let Ok(metadata) = path.metadata() else {
+            return false;
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
         }
+        #[cfg(not(unix))]
+        metadata.is_file()
     };
 
-    let status = command.current_dir(&dir).status()?;
+    if program.components().count() > 1 || program.is_absolute() {
+        let program = std::path::absolute(program).ok()?;
+        return is_executable(&program).then_some(program);
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
 
-    dir.close()?;
+/// Check that the configured python interpreter and runner binary exist and are
+/// executable, instead of failing the test run of every mutant.
+pub fn validate_programs(config: &RunConfig) -> Result<(), PymuteError> {
+    let programs = [
+        ("--python", config.python.as_deref()),
+        ("--runner-binary", config.runner_binary.as_deref()),
+    ];
+    for (option, program) in programs {
+        if let Some(program) = program {
+            if find_program(program).is_none() {
+                return Err(PymuteError::other(ProgramNotFound {
+                    option,
+                    program: program.to_path_buf(),
+                }));
+            }
+        }
+    }
+    Ok(())
+}
 
-    if status.success() {
-        Ok(MutantResult::Missed)
-    } else {
-        Ok(MutantResult::Caught)
+/// The nox sessions of the comma-separated `environment`, e.g. `lint, tests`.
+fn nox_sessions(environment: &str) -> impl Iterator<Item = &str> {
+    environment
+        .split(',')
+        .map(str::trim)
+        .filter(|session| !session.is_empty())
+}
+
+/// Whether unittest runs `tests` by name rather than discovering the tests in a
+/// start directory, i.e. it is a dotted name such as `tests.test_calc.TestAdd`
+/// or a test file.
+fn is_unittest_name(tests: &str) -> bool {
+    if tests.ends_with(".py") {
+        return true;
     }
+    let path_like = tests.contains(['/', '\\']) || tests.starts_with('.');
+    !path_like && tests.contains('.')
+}
+
+/// The test command run for each mutant, with the program first.
+pub fn test_command_line(config: &RunConfig) -> Result<Vec<String>, PymuteError> {
+    let command = test_command(config, None)?;
+    Ok(std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect())
 }
 
-enum MutantResult {
+/// Split a custom test command into program and arguments using shell-style
+/// quoting rules.
+pub fn split_test_command(
+    test_command: Option<&str>,
+) -> Result<(String, Vec<String>), Box<dyn Error + Send + Sync>> {
+    let test_command = test_command.ok_or(InvalidTestCommand {
+        command: String::new(),
+    })?;
+    let mut words = shlex::split(test_command)
+        .filter(|words| !words.is_empty())
+        .ok_or(InvalidTestCommand {
+            command: test_command.into(),
+        })?;
+    let program = words.remove(0);
+    Ok((program, words))
+}
+
+/// A mutant together with the result of running the test suite against it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutantOutcome {
+    /// The mutant that was inserted.
+    pub mutant: Mutant,
+    /// The result of running the test suite.
+    pub result: MutantResult,
+    /// How long the test suite ran against the mutant. Untested mutants and
+    /// results from caches written before durations were recorded have none.
+    #[serde(default)]
+    pub duration: Option<Duration>,
+    /// Why pymute failed to run the test suite against the mutant (e.g. the
+    /// working copy could not be created), in which case the result is an error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The result of running the test suite against a single mutant.
+///
+/// Serialized as a plain string (e.g. `caught` or `error:9`) so that it fits in a
+/// single column of the cache.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum MutantResult {
+    /// The test suite failed, i.e. the mutant was killed.
     Caught,
+    /// The test suite passed, i.e. the mutant survived.
     Missed,
+    /// The test suite was not run because no tests are associated with the file
+    /// of the mutant (see `--skip-untested-files`).
+    Untested,
+    /// The test suite was not run because no test executes the line of the mutant
+    /// (see `--coverage-guided`).
+    Uncovered,
+    /// The test runner collected no tests, so the mutant was not tested.
+    NoTests,
+    /// The mutated file is not valid python, so the test suite was not run (see
+    /// `--skip-invalid`). Invalid mutants are excluded from the mutation score.
+    Invalid,
+    /// The test run did not finish normally, e.g. because it was killed by the
+    /// OS or the tests could not be collected. Errors are excluded from the
+    /// mutation score.
+    Error {
+        /// The signal that terminated the test run (Unix only).
+        signal: Option<i32>,
+        /// The exit code of pytest or tox if the tests could not be run, e.g. 2 for
+        /// an error while collecting them.
+        code: Option<i32>,
+    },
+}
+
+impl fmt::Display for MutantResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MutantResult::Caught => write!(f, "caught"),
+            MutantResult::Missed => write!(f, "missed"),
+            MutantResult::Untested => write!(f, "untested"),
+            MutantResult::Uncovered => write!(f, "uncovered"),
+            MutantResult::NoTests => write!(f, "no-tests"),
+            MutantResult::Invalid => write!(f, "invalid"),
+            MutantResult::Error {
+                signal: Some(signal),
+                ..
+            } => write!(f, "error:{signal}"),
+            MutantResult::Error {
+                code: Some(code), ..
+            } => write!(f, "error:exit:{code}"),
+            MutantResult::Error { .. } => write!(f, "error"),
+        }
+    }
+}
+
+impl From<MutantResult> for String {
+    fn from(result: MutantResult) -> Self {
+        result.to_string()
+    }
+}
+
+impl TryFrom<String> for MutantResult {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, String> {
+        match value.as_str() {
+            "caught" => Ok(MutantResult::Caught),
+            "missed" => Ok(MutantResult::Missed),
+            "untested" => Ok(MutantResult::Untested),
+            "uncovered" => Ok(MutantResult::Uncovered),
+            "no-tests" => Ok(MutantResult::NoTests),
+            "invalid" => Ok(MutantResult::Invalid),
+            "error" => Ok(MutantResult::Error {
+                signal: None,
+                code: None,
+            }),
+            _ => match value.strip_prefix("error:exit:") {
+                Some(code) => code.parse().ok().map(|code| MutantResult::Error {
+                    signal: None,
+                    code: Some(code),
+                }),
+                None => value
+                    .strip_prefix("error:")
+                    .and_then(|signal| signal.parse().ok())
+                    .map(|signal| MutantResult::Error {
+                        signal: Some(signal),
+                        code: None,
+                    }),
+            }
+            .ok_or(format!("Invalid mutant result: {value}")),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct InvalidTestCommand {
+    command: String,
+}
+
+impl Error for InvalidTestCommand {}
+impl fmt::Display for InvalidTestCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.command.is_empty() {
+            true => write!(
+                f,
+                "The custom runner requires a test command (--test-command)!"
+            ),
+            false => write!(f, "Invalid test command: {}", self.command),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct InvalidRunnerArgs {
+    args: String,
+}
+
+impl Error for InvalidRunnerArgs {}
+impl fmt::Display for InvalidRunnerArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid runner arguments: {}", self.args)
+    }
+}
+
+#[derive(Debug)]
+struct ProgramNotFound {
+    option: &'static str,
+    program: PathBuf,
+}
+
+impl Error for ProgramNotFound {}
+impl fmt::Display for ProgramNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The program `{}` given with {} does not exist or is not executable!",
+            self.program.display(),
+            self.option
+        )
+    }
+}
+
+#[derive(Debug)]
+struct TestsNotFound {
+    tests: String,
+    reason: String,
+}
+
+impl Error for TestsNotFound {}
+impl fmt::Display for TestsNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The tests `{}` would not be found in the working copies of the project: {}!",
+            self.tests, self.reason
+        )
+    }
+}
+
+#[derive(Debug)]
+struct DirtyWorkingTree {
+    root: PathBuf,
+    changes: Vec<String>,
+}
+
+impl Error for DirtyWorkingTree {}
+impl fmt::Display for DirtyWorkingTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Refusing to insert mutants into {} in place, its git working tree has uncommitted changes:",
+            self.root.display()
+        )?;
+        for change in &self.changes {
+            write!(f, "\n  {change}")?;
+        }
+        write!(f, "\nCommit or stash them first, or pass `--allow-dirty`")
+    }
+}
+
+#[derive(Debug)]
+struct WorkspaceIsRoot {
+    workspace: PathBuf,
+}
+
+impl Error for WorkspaceIsRoot {}
+impl fmt::Display for WorkspaceIsRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The workspace {} is the root of the project itself. Give a copy of the project with `--workspace`, the original files are never mutated!",
+            self.workspace.display()
+        )
+    }
 }
 
 #[derive(Debug)]
-struct KeyboardInterrupt {}
+struct PreparedFileMismatch {
+    file: PathBuf,
+    prepared: PathBuf,
+    reason: &'static str,
+    /// Whether the file was looked for in a workspace instead of a prepared root.
+    workspace: bool,
+}
 
-impl Error for KeyboardInterrupt {}
-impl fmt::Display for KeyboardInterrupt {
+impl Error for PreparedFileMismatch {}
+impl fmt::Display for PreparedFileMismatch {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Program interrupted by user!")
+        match self.workspace {
+            true => write!(
+                f,
+                "Cannot insert the mutants of {} into the workspace: {} {}. Is the workspace a copy of the current sources?",
+                self.file.display(),
+                self.prepared.display(),
+                self.reason
+            ),
+            false => write!(
+                f,
+                "Cannot insert the mutants of {} into the prepared root: {} {}. Is the prepared root built from the current sources, and is `--prepared-src-prefix` set correctly?",
+                self.file.display(),
+                self.prepared.display(),
+                self.reason
+            ),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::mutants::{self, MutationType};
-    use crate::runner;
+    use crate::error::PymuteError;
+    use crate::events::{Dispatch, Event, Observer};
+    use crate::mutants::{self, Mutant, MutationType};
+    use crate::runner::OutputLevel;
+    use crate::runner::{
+        self, copy_project, estimate_remaining, find_program, is_unittest_name,
+        normalized_tests_argument, split_test_command, test_command, test_command_line,
+        validate_programs, validate_tests_path, MutantResult, PytestWorkers, Runner, WorkdirPool,
+    };
+    use crate::subprojects::{Subproject, SubprojectSettings, Subprojects};
+    use crate::RunConfig;
     use std::{
+        collections::{BTreeMap, BTreeSet},
         fs::{self, File},
         io::Write,
-        path::PathBuf,
+        path::Path,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
     };
     use tempfile::tempdir;
 
+    #[test]
+    fn test_estimate_remaining() {
+        let secs = Duration::from_secs;
+        assert_eq!(estimate_remaining(secs(0), 0, 10, 4), None);
+        // 3 mutants took 30s, so 10s each: 8 more on 4 threads take 20s
+        assert_eq!(estimate_remaining(secs(30), 3, 8, 4), Some(secs(20)));
+        assert_eq!(estimate_remaining(secs(30), 3, 8, 1), Some(secs(80)));
+        assert_eq!(estimate_remaining(secs(30), 3, 0, 4), Some(secs(0)));
+        assert_eq!(estimate_remaining(secs(30), 3, 8, 0), Some(secs(80)));
+    }
+
     #[test]
     fn test_pytest_mutants() {
         let temp_dir = tempdir().unwrap();
@@ -361,15 +1887,1069 @@ print(res) # print the result +
 
         assert_eq!(mutants_vec.len(), 7);
 
-        runner::run_mutants(
-            &PathBuf::from(base_path),
-            &mutants_vec,
-            &runner::Runner::Pytest,
-            &".",
-            &None,
-            &runner::OutputLevel::Missed,
+        let config = RunConfig::new(base_path);
+        runner::run_mutants(&config, &mutants_vec).expect("run_mutants failed!");
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_split_test_command() {
+        let (program, args) = split_test_command(Some("make test")).unwrap();
+        assert_eq!(program, "make");
+        assert_eq!(args, vec!["test"]);
+
+        let (program, args) =
+            split_test_command(Some(r#"python -c "import sys; sys.exit(1)" 'a b'"#)).unwrap();
+        assert_eq!(program, "python");
+        assert_eq!(args, vec!["-c", "import sys; sys.exit(1)", "a b"]);
+
+        assert!(split_test_command(None).is_err());
+        assert!(split_test_command(Some("")).is_err());
+        assert!(split_test_command(Some("make \"unterminated")).is_err());
+    }
+
+    #[test]
+    fn test_pytest_workers() {
+        assert_eq!("auto".parse(), Ok(PytestWorkers::Auto));
+        assert_eq!("4".parse(), Ok(PytestWorkers::Count(4)));
+        assert!("0".parse::<PytestWorkers>().is_err());
+        assert!("many".parse::<PytestWorkers>().is_err());
+
+        let config = RunConfig::new("project");
+        let command = test_command_line(&config).unwrap();
+        assert!(!command.contains(&"-n".to_string()), "{command:?}");
+
+        let config = config.pytest_workers(PytestWorkers::Count(4));
+        let command = test_command_line(&config).unwrap();
+        assert_eq!(command[command.len() - 2..], ["-n", "4"]);
+        let command = test_command_line(&config.clone().pytest_dist("loadfile")).unwrap();
+        assert_eq!(
+            command[command.len() - 4..],
+            ["-n", "4", "--dist", "loadfile"]
+        );
+        let command =
+            test_command_line(&config.clone().pytest_workers(PytestWorkers::Auto)).unwrap();
+        assert_eq!(command[command.len() - 2..], ["-n", "auto"]);
+
+        // other runners are not passed the workers
+        let command = test_command_line(&config.clone().runner(Runner::Tox)).unwrap();
+        assert_eq!(command, ["tox"]);
+    }
+
+    #[test]
+    fn test_pytest_cache_clear() {
+        let config = RunConfig::new("project");
+        let command = test_command_line(&config).unwrap();
+        assert!(
+            !command.contains(&"--cache-clear".to_string()),
+            "{command:?}"
+        );
+
+        let config = config.pytest_cache_clear(true);
+        let command = test_command_line(&config).unwrap();
+        assert_eq!(command[command.len() - 2..], ["-x", "--cache-clear"]);
+        let command =
+            test_command_line(&config.clone().pytest_workers(PytestWorkers::Count(2))).unwrap();
+        assert_eq!(
+            command[command.len() - 4..],
+            ["-x", "--cache-clear", "-n", "2"]
+        );
+
+        // other runners do not use the pytest cache
+        let command = test_command_line(&config.clone().runner(Runner::Tox)).unwrap();
+        assert_eq!(command, ["tox"]);
+        let command = test_command_line(&config.clone().runner(Runner::Unittest)).unwrap();
+        assert!(
+            !command.contains(&"--cache-clear".to_string()),
+            "{command:?}"
+        );
+    }
+
+    #[test]
+    fn test_unittest_command() {
+        let config = RunConfig::new("project").runner(Runner::Unittest);
+        let command = test_command_line(&config).unwrap();
+        assert_eq!(
+            command,
+            ["python", "-B", "-m", "unittest", "discover", "-s", ".", "-q", "-f"]
+        );
+
+        let config = config
+            .tests("tests/unit")
+            .output_level(OutputLevel::Process);
+        let command = test_command_line(&config).unwrap();
+        assert_eq!(command[4..], ["discover", "-s", "tests/unit", "-v", "-f"]);
+
+        for tests in ["tests.test_calc.TestAdd", "tests/test_calc.py"] {
+            let command = test_command_line(&config.clone().tests(tests)).unwrap();
+            assert_eq!(command[4..], ["-v", "-f", tests]);
+        }
+        assert!(!is_unittest_name("tests"));
+        assert!(!is_unittest_name("./tests"));
+    }
+
+    #[test]
+    fn test_nox_command() {
+        let config = RunConfig::new("project").runner(Runner::Nox);
+        assert_eq!(test_command_line(&config).unwrap(), ["nox"]);
+
+        let config = config.environment("tests");
+        assert_eq!(test_command_line(&config).unwrap(), ["nox", "-s", "tests"]);
+
+        let config = config.environment("lint, tests").runner_args("-x");
+        assert_eq!(
+            test_command_line(&config).unwrap(),
+            ["nox", "-s", "lint", "tests", "--", "-x"]
+        );
+    }
+
+    #[test]
+    fn test_configured_programs() {
+        let temp_dir = tempdir().unwrap();
+        let python = temp_dir.path().join("python3.12");
+        fs::write(&python, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&python, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let config = RunConfig::new("project").python(&python);
+        assert!(validate_programs(&config).is_ok());
+        let command = test_command(&config, None).unwrap();
+        assert_eq!(command.get_program(), python.as_os_str());
+        let command = test_command(&config.clone().runner(Runner::Unittest), None).unwrap();
+        assert_eq!(command.get_program(), python.as_os_str());
+
+        let config = config.runner(Runner::Tox).runner_binary(&python);
+        let command = test_command(&config, None).unwrap();
+        assert_eq!(command.get_program(), python.as_os_str());
+
+        let missing = temp_dir.path().join("venv").join("bin").join("tox");
+        let error = validate_programs(&config.clone().runner_binary(&missing)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "The program `{}` given with --runner-binary does not exist or is not executable!",
+                missing.display()
+            )
+        );
+        #[cfg(unix)]
+        {
+            let not_executable = temp_dir.path().join("python");
+            fs::write(&not_executable, "").unwrap();
+            assert!(validate_programs(&config.clone().python(not_executable)).is_err());
+        }
+        // names are looked up on the PATH
+        assert!(find_program(Path::new("sh")).is_some());
+        assert!(find_program(Path::new("pymute-no-such-program")).is_none());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_env() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let script = base_path.join("calc").join("ops.py");
+        fs::create_dir(base_path.join("calc")).unwrap();
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutant = Mutant::new(script, 2, " + ", " - ", 12, "    return a + b");
+        // the stub writes its environment outside the working copy
+        let dump = base_path.join("env.json");
+        fs::write(
+            base_path.join("dump_env.py"),
+            format!(
+                "import json, os\nenv = dict(os.environ)\nenv['IN_WORKDIR'] = str(os.path.samefile(os.getcwd(), env['PYMUTE_WORKDIR']))\njson.dump(env, open({:?}, 'w'))\n",
+                dump.display().to_string()
+            ),
+        )
+        .unwrap();
+
+        let config = RunConfig::new(base_path)
+            .runner(Runner::Custom)
+            .test_command("python dump_env.py")
+            .env("DJANGO_SETTINGS_MODULE", "app.settings")
+            .output_level(OutputLevel::Quiet);
+        runner::run_mutants(&config, std::slice::from_ref(&mutant)).unwrap();
+
+        let env: BTreeMap<String, String> =
+            serde_json::from_str(&fs::read_to_string(&dump).unwrap()).unwrap();
+        assert_eq!(env["DJANGO_SETTINGS_MODULE"], "app.settings");
+        assert_eq!(env["PYMUTE_MUTANT_ID"], mutant.id(base_path));
+        assert_eq!(env["PYMUTE_MUTANT_FILE"], "calc/ops.py");
+        assert_eq!(env["PYMUTE_MUTANT_LINE"], "2");
+        assert_eq!(env["IN_WORKDIR"], "True");
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_log_dir() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let script = base_path.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutant = Mutant::new(script, 2, " + ", " - ", 12, "    return a + b");
+        let log_dir = base_path.join("logs");
+
+        let config = RunConfig::new(base_path)
+            .runner(Runner::Custom)
+            .test_command(
+                r#"python -c "import sys; print('1 passed'); print('warning', file=sys.stderr)""#,
+            )
+            .log_dir(&log_dir)
+            .output_level(OutputLevel::Quiet);
+        runner::run_mutants(&config, std::slice::from_ref(&mutant)).unwrap();
+
+        let log = runner::mutant_log_file(&config, &mutant).unwrap();
+        assert_eq!(log, log_dir.join(format!("{}.log", mutant.id(base_path))));
+        let output = fs::read_to_string(&log).unwrap();
+        assert!(
+            output.contains("1 passed") && output.contains("warning"),
+            "{output}"
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_keep_missed_dirs() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        let script = root.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutant = Mutant::new(script.clone(), 2, " + ", " - ", 12, "    return a + b");
+        let keep_dir = temp_dir.path().join("kept");
+
+        let config = RunConfig::new(&root)
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import sys; sys.exit(1)""#)
+            .keep_missed_dirs(&keep_dir)
+            .output_level(OutputLevel::Quiet);
+        let kept = runner::mutant_kept_dir(&config, &mutant, MutantResult::Missed).unwrap();
+        assert_eq!(kept, keep_dir.join(mutant.id(&root)));
+
+        // caught mutants are cleaned up
+        let outcomes = runner::run_mutants(&config, std::slice::from_ref(&mutant)).unwrap();
+        assert_eq!(outcomes[0].result, MutantResult::Caught);
+        assert!(!kept.exists());
+
+        let config = config.test_command(r#"python -c "import sys; sys.exit(0)""#);
+        let outcomes = runner::run_mutants(&config, std::slice::from_ref(&mutant)).unwrap();
+        assert_eq!(outcomes[0].result, MutantResult::Missed);
+        assert_eq!(
+            fs::read_to_string(kept.join("script.py")).unwrap(),
+            "def add(a, b):\n    return a - b\n"
+        );
+        fs::remove_dir_all(&kept).unwrap();
+
+        let config = config
+            .test_command(r#"python -c "import sys; sys.exit(1)""#)
+            .keep_all_dirs(true);
+        runner::run_mutants(&config, std::slice::from_ref(&mutant)).unwrap();
+        assert_eq!(
+            fs::read_to_string(kept.join("script.py")).unwrap(),
+            "def add(a, b):\n    return a - b\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&script).unwrap(),
+            "def add(a, b):\n    return a + b\n"
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_command_with_tests() {
+        let config = RunConfig::new("project").tests("tests");
+        let tests: BTreeSet<String> = [
+            "tests/test_calc.py::test_add",
+            "tests/test_calc.py::test_sum[1-2]",
+        ]
+        .map(String::from)
+        .into();
+        let command = test_command(&config, Some(&tests)).unwrap();
+        let args: Vec<_> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "-B",
+                "-m",
+                "pytest",
+                "tests/test_calc.py::test_add",
+                "tests/test_calc.py::test_sum[1-2]",
+                "-x"
+            ]
+        );
+        assert_eq!(test_command_line(&config).unwrap()[4], "tests");
+    }
+
+    #[test]
+    fn test_runner_args() {
+        let config = RunConfig::new("project").runner_args(r#"-k "a and b" -p no:cacheprovider"#);
+        let command = test_command_line(&config).unwrap();
+        assert_eq!(
+            command[command.len() - 4..],
+            ["-k", "a and b", "-p", "no:cacheprovider"]
+        );
+
+        let command =
+            test_command_line(&config.clone().runner(Runner::Tox).environment("py311")).unwrap();
+        assert_eq!(
+            command,
+            [
+                "tox",
+                "-e py311",
+                "--",
+                "-k",
+                "a and b",
+                "-p",
+                "no:cacheprovider"
+            ]
+        );
+
+        let command = test_command_line(
+            &config
+                .clone()
+                .runner(Runner::Custom)
+                .test_command("make test")
+                .runner_args("ARGS='-x -q'"),
+        )
+        .unwrap();
+        assert_eq!(command, ["make", "test", "ARGS=-x -q"]);
+
+        assert!(test_command_line(&config.runner_args("-k 'a and b")).is_err());
+    }
+
+    #[test]
+    fn test_custom_runner() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let script = base_path.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutants_vec = vec![Mutant::new(script, 2, " + ", " - ", 12, "    return a + b")];
+
+        let config = RunConfig::new(base_path).runner(Runner::Custom);
+        assert!(runner::run_mutants(&config, &mutants_vec).is_err());
+
+        let config = config.test_command(r#"python -c "import sys; sys.exit(1)""#);
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(outcomes[0].result, MutantResult::Caught);
+
+        let config = config.test_command(r#"python -c "import sys; sys.exit(0)""#);
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(outcomes[0].result, MutantResult::Missed);
+
+        temp_dir.close().unwrap();
+    }
+
+    /// Stops the run after `limit` mutants finished.
+    struct StopAfter {
+        limit: usize,
+        finished: AtomicUsize,
+    }
+
+    impl Observer for StopAfter {
+        fn notify(&self, event: &Event) {
+            if let Event::MutantFinished { .. } = event {
+                self.finished.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        fn dispatch(&self) -> Dispatch {
+            match self.finished.load(Ordering::SeqCst) >= self.limit {
+                true => Dispatch::Stop,
+                false => Dispatch::Continue,
+            }
+        }
+    }
+
+    #[test]
+    fn test_observer_stops_dispatching() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let script = base_path.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutants_vec: Vec<Mutant> = (0..4)
+            .map(|_| Mutant::new(script.clone(), 2, " + ", " - ", 12, "    return a + b"))
+            .collect();
+
+        let observer = Arc::new(StopAfter {
+            limit: 2,
+            finished: AtomicUsize::new(0),
+        });
+        let config = RunConfig::new(base_path)
+            .runner(Runner::Custom)
+            .test_command(r#"python -c "import sys; sys.exit(1)""#)
+            .output_level(OutputLevel::Quiet)
+            .observer(observer.clone());
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(observer.finished.load(Ordering::SeqCst), 2);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_copy_project_excludes() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        for dir in [
+            ".git/objects",
+            ".venv/lib",
+            "pkg/__pycache__",
+            "pkg/sub",
+            "logs",
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            ".git/objects/abc",
+            ".venv/lib/site.py",
+            "pkg/__pycache__/module.cpython-311.pyc",
+            "pkg/sub/module.py",
+            "logs/run.log",
+            "setup.py",
+        ] {
+            fs::write(root.join(file), "x = 1 + 2\n").unwrap();
+        }
+
+        let invalid = RunConfig::new(&root).copy_exclude(&["logs/[.log"]);
+        assert!(matches!(
+            runner::copy_excludes(&invalid),
+            Err(PymuteError::InvalidGlob { .. })
+        ));
+        let config = RunConfig::new(&root).copy_exclude(&["logs/*.log"]);
+        let excludes = runner::copy_excludes(&config).unwrap();
+        let dest = temp_dir.path().join("copy");
+        copy_project(&root, &dest, &excludes).unwrap();
+
+        assert!(!dest.join(".git").exists());
+        assert!(!dest.join(".venv").exists());
+        assert!(!dest.join("pkg/__pycache__").exists());
+        assert!(!dest.join("logs/run.log").exists());
+        assert!(dest.join("logs").is_dir());
+        assert!(dest.join("setup.py").is_file());
+
+        // the mutant still lands at the same relative location
+        let mutant = Mutant::new(
+            root.join("pkg/sub/module.py"),
+            1,
+            " + ",
+            " - ",
+            5,
+            "x = 1 + 2",
+        );
+        mutant.insert_in_new_root(&root, &dest).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("pkg/sub/module.py")).unwrap(),
+            "x = 1 - 2\n"
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_copy_project_excludes_nested_repositories() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        for dir in ["vendor/lib/.git/objects", "vendor/other", "pkg"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        // the root is a worktree and `vendor/other` a submodule, both with a
+        // `.git` file pointing to the actual repository
+        fs::write(
+            root.join(".git"),
+            "gitdir: /repos/main/.git/worktrees/project\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("vendor/other/.git"),
+            "gitdir: ../../.git/modules/other\n",
+        )
+        .unwrap();
+        fs::write(root.join("vendor/lib/.git/objects/abc"), "blob").unwrap();
+        for file in [
+            "vendor/lib/lib.py",
+            "vendor/other/other.py",
+            "pkg/module.py",
+        ] {
+            fs::write(root.join(file), "x = 1 + 2\n").unwrap();
+        }
+
+        let excludes = runner::copy_excludes(&RunConfig::new(&root)).unwrap();
+        let dest = temp_dir.path().join("copy");
+        copy_project(&root, &dest, &excludes).unwrap();
+
+        assert!(!dest.join(".git").exists());
+        assert!(!dest.join("vendor/lib/.git").exists());
+        assert!(!dest.join("vendor/other/.git").exists());
+        for file in [
+            "vendor/lib/lib.py",
+            "vendor/other/other.py",
+            "pkg/module.py",
+        ] {
+            assert!(dest.join(file).is_file(), "{file}");
+        }
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_validate_tests_path() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("tests/unit")).unwrap();
+        fs::create_dir_all(root.join("build/tests")).unwrap();
+        fs::write(
+            root.join("tests/unit/test_x.py"),
+            "def test_x():\n    pass\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("build/tests/test_y.py"),
+            "def test_y():\n    pass\n",
+        )
+        .unwrap();
+
+        let validate = |tests: &str| {
+            let config = RunConfig::new(root).tests(tests).copy_exclude(&["build"]);
+            validate_tests_path(&config, &runner::copy_excludes(&config).unwrap())
+        };
+
+        // node ids are validated by their file
+        assert!(validate("tests/unit/test_x.py::TestFoo::test_bar").is_ok());
+        assert!(validate("tests/unit").is_ok());
+        assert!(validate(".").is_ok());
+
+        let missing = validate("tests/integration").unwrap_err().to_string();
+        assert!(missing.contains("does not exist"), "{missing}");
+        let missing = validate("tests/unit/test_z.py::test_z")
+            .unwrap_err()
+            .to_string();
+        assert!(missing.contains("does not exist"), "{missing}");
+
+        let excluded = validate("build/tests/test_y.py").unwrap_err().to_string();
+        assert!(excluded.contains("--copy-exclude"), "{excluded}");
+
+        // other runners do not use the tests path
+        let config = RunConfig::new(root)
+            .tests("tests/integration")
+            .runner(Runner::Tox);
+        assert!(validate_tests_path(&config, &[]).is_ok());
+
+        // absolute paths inside the root point into the working copies
+        let absolute = root.join("tests").join("unit").join("test_x.py");
+        let config = RunConfig::new(root).tests(format!("{}::test_x", absolute.display()));
+        assert_eq!(
+            normalized_tests_argument(&config),
+            "tests/unit/test_x.py::test_x"
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_workdir_pool_restores_mutated_file() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        let script = root.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n\nx = 1 * 2\n").unwrap();
+
+        let first = Mutant::new(script.clone(), 2, " + ", " - ", 12, "    return a + b");
+        let second = Mutant::new(script.clone(), 4, " * ", " / ", 5, "x = 1 * 2");
+
+        let pool = WorkdirPool::new(&root, 1, Vec::new()).unwrap();
+        let read_script =
+            |dir: &std::path::Path| fs::read_to_string(dir.join("script.py")).unwrap();
+
+        let (first_dir, content) = pool
+            .with_mutant(&first, |dir| (dir.to_path_buf(), read_script(dir)))
+            .unwrap();
+        assert_eq!(content, "def add(a, b):\n    return a - b\n\nx = 1 * 2\n");
+
+        let (second_dir, content) = pool
+            .with_mutant(&second, |dir| (dir.to_path_buf(), read_script(dir)))
+            .unwrap();
+        assert_eq!(content, "def add(a, b):\n    return a + b\n\nx = 1 / 2\n");
+
+        // the working copy is reused and the original is untouched
+        assert_eq!(first_dir, second_dir);
+        assert_eq!(
+            fs::read_to_string(&script).unwrap(),
+            "def add(a, b):\n    return a + b\n\nx = 1 * 2\n"
+        );
+
+        pool.close().unwrap();
+        assert!(!first_dir.exists());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_workdir_pool_cleans_up_failed_runs() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        let script = root.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutant = Mutant::new(script, 2, " + ", " - ", 12, "    return a + b");
+        let parent = temp_dir.path().join("tmp");
+        fs::create_dir(&parent).unwrap();
+        let count = || fs::read_dir(&parent).unwrap().count();
+
+        // the test command can not be spawned
+        let pool = WorkdirPool::new_in(&root, 1, Vec::new(), &parent).unwrap();
+        let spawned = pool
+            .with_mutant(&mutant, |dir| {
+                std::process::Command::new("pymute-does-not-exist")
+                    .current_dir(dir)
+                    .spawn()
+            })
+            .unwrap();
+        assert!(spawned.is_err());
+        assert_eq!(count(), 1);
+        drop(pool);
+        assert_eq!(count(), 0);
+
+        // a worker panics while its mutant is inserted
+        let pool = WorkdirPool::new_in(&root, 1, Vec::new(), &parent).unwrap();
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.with_mutant(&mutant, |_| panic!("test run panicked"))
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(count(), 1);
+        drop(pool);
+        assert_eq!(count(), 0);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_workdir_pool_prepared_root() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        let prepared = temp_dir.path().join("image");
+        let prefix = Path::new("lib/python3.11/site-packages");
+        let original = "def add(a, b):\n    return a + b\n";
+        for dir in [root.join("pkg"), prepared.join(prefix).join("pkg")] {
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("calc.py"), original).unwrap();
+        }
+        fs::write(prepared.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+
+        let script = root.join("pkg/calc.py");
+        let mutant = Mutant::new(script.clone(), 2, " + ", " - ", 12, "    return a + b");
+        let pool = WorkdirPool::new(&root, 1, Vec::new())
+            .unwrap()
+            .prepared(&prepared, prefix)
+            .unwrap();
+        pool.validate(std::slice::from_ref(&mutant)).unwrap();
+
+        let (mutated, prepared_copy, unprefixed) = pool
+            .with_mutant(&mutant, |dir| {
+                (
+                    fs::read_to_string(dir.join(prefix).join("pkg/calc.py")).unwrap(),
+                    dir.join("pyvenv.cfg").exists(),
+                    dir.join("pkg").exists(),
+                )
+            })
+            .unwrap();
+        assert_eq!(mutated, "def add(a, b):\n    return a - b\n");
+        assert!(prepared_copy);
+        assert!(!unprefixed);
+        assert_eq!(fs::read_to_string(&script).unwrap(), original);
+        pool.close().unwrap();
+
+        // the prepared tree has to contain the files as found in the root
+        let pool = WorkdirPool::new(&root, 1, Vec::new())
+            .unwrap()
+            .prepared(&prepared, Path::new("wrong/prefix"))
+            .unwrap();
+        let error = pool.validate(std::slice::from_ref(&mutant)).unwrap_err();
+        assert!(error.to_string().contains("does not exist"), "{error}");
+        pool.close().unwrap();
+
+        fs::write(
+            prepared.join(prefix).join("pkg/calc.py"),
+            "def add(a, b):\n    return b + a\n",
+        )
+        .unwrap();
+        let pool = WorkdirPool::new(&root, 1, Vec::new())
+            .unwrap()
+            .prepared(&prepared, prefix)
+            .unwrap();
+        let error = pool.validate(&[mutant]).unwrap_err();
+        assert!(
+            error.to_string().contains("differs from the original"),
+            "{error}"
+        );
+        pool.close().unwrap();
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_workspaces() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("project");
+        let workspace = temp_dir.path().join("workspace");
+        let original = "def add(a, b):\n    return a + b\n\nx = 1 * 2\n";
+        for dir in [&root, &workspace] {
+            fs::create_dir(dir).unwrap();
+            fs::write(dir.join("script.py"), original).unwrap();
+        }
+        let script = root.join("script.py");
+        let mutants_vec = vec![
+            Mutant::new(script.clone(), 2, " + ", " - ", 12, "    return a + b"),
+            Mutant::new(script.clone(), 4, " * ", " / ", 5, "x = 1 * 2"),
+        ];
+
+        // each test run appends the directory it runs in and the mutated file
+        let log = temp_dir.path().join("runs.log");
+        let command = format!(
+            "python -c \"import os; open('{}', 'a').write(os.getcwd() + '\\n' + open('script.py').read())\"",
+            log.display()
+        );
+        let config = RunConfig::new(&root)
+            .runner(Runner::Custom)
+            .test_command(&command)
+            .num_threads(4)
+            .workspace(&workspace)
+            .output_level(OutputLevel::Quiet);
+        assert_eq!(runner::worker_threads(&config), 1);
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(outcomes.len(), 2);
+
+        // every mutant ran alone in the workspace
+        let workspace_path = workspace.canonicalize().unwrap().display().to_string();
+        let expected = format!(
+            "{workspace_path}\ndef add(a, b):\n    return a - b\n\nx = 1 * 2\n\
+             {workspace_path}\ndef add(a, b):\n    return a + b\n\nx = 1 / 2\n"
+        );
+        assert_eq!(fs::read_to_string(&log).unwrap(), expected);
+        assert_eq!(fs::read_to_string(&script).unwrap(), original);
+        assert_eq!(
+            fs::read_to_string(workspace.join("script.py")).unwrap(),
+            original
+        );
+
+        // a workspace is restored when the pool is dropped
+        let pool = WorkdirPool::new(&root, 1, Vec::new())
+            .unwrap()
+            .workspaces(std::slice::from_ref(&workspace))
+            .unwrap();
+        let mutated = pool
+            .with_mutant(&mutants_vec[0], |dir| {
+                fs::read_to_string(dir.join("script.py")).unwrap()
+            })
+            .unwrap();
+        assert_ne!(mutated, original);
+        drop(pool);
+        assert!(workspace.is_dir());
+        assert_eq!(
+            fs::read_to_string(workspace.join("script.py")).unwrap(),
+            original
+        );
+
+        // the root is never mutated in place
+        let error =
+            runner::run_mutants(&config.clone().workspace(&root), &mutants_vec).unwrap_err();
+        assert!(error.to_string().contains("is the root"), "{error}");
+        assert_eq!(fs::read_to_string(&script).unwrap(), original);
+
+        // the workspace has to contain the files as found in the root
+        fs::write(workspace.join("script.py"), "x = 2\n").unwrap();
+        let error = runner::run_mutants(&config, &mutants_vec).unwrap_err();
+        assert!(
+            error.to_string().contains("differs from the original"),
+            "{error}"
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_in_place() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let original = "def add(a, b):\n    return a + b\n\nx = 1 * 2\n";
+        let script = root.join("script.py");
+        fs::write(&script, original).unwrap();
+        let mutants_vec = vec![
+            Mutant::new(script.clone(), 2, " + ", " - ", 12, "    return a + b"),
+            Mutant::new(script.clone(), 4, " * ", " / ", 5, "x = 1 * 2"),
+        ];
+
+        // each test run sees only its own mutant in the root
+        let log = root.join("runs.log");
+        let command = format!(
+            "python -c \"open('{}', 'a').write(open('script.py').read())\"",
+            log.display()
+        );
+        let config = RunConfig::new(root)
+            .runner(Runner::Custom)
+            .test_command(&command)
+            .in_place(true)
+            .allow_dirty(true)
+            .output_level(OutputLevel::Quiet);
+        assert_eq!(runner::worker_threads(&config.clone().num_threads(4)), 1);
+        runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "def add(a, b):\n    return a - b\n\nx = 1 * 2\n\
+             def add(a, b):\n    return a + b\n\nx = 1 / 2\n"
+        );
+        assert_eq!(fs::read_to_string(&script).unwrap(), original);
+
+        // a file changed while the tests ran, e.g. by a formatter, stops the run
+        // loudly and is left for inspection, with its original content next to it
+        let config = config.test_command(r#"python -c "open('script.py', 'a').write('y = 3\n')""#);
+        let error = runner::run_mutants(&config, &mutants_vec).unwrap_err();
+        assert!(
+            matches!(error, PymuteError::NotRestored { line_number: 2, .. }),
+            "{error}"
+        );
+        assert!(error.to_string().contains("left mutated"), "{error}");
+        assert_eq!(
+            fs::read_to_string(&script).unwrap(),
+            "def add(a, b):\n    return a - b\n\nx = 1 * 2\ny = 3\n"
+        );
+        let original_copy = root.join("script.py.pymute-original");
+        assert_eq!(fs::read_to_string(&original_copy).unwrap(), original);
+        fs::rename(&original_copy, &script).unwrap();
+
+        // without a clean git working tree, the project could not be restored
+        let config = config.allow_dirty(false);
+        let error = runner::run_mutants(&config, &mutants_vec).unwrap_err();
+        assert!(
+            error.to_string().contains("not in a git repository"),
+            "{error}"
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_pytest_exit_codes() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let script = base_path.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutants_vec = vec![Mutant::new(script, 2, " + ", " - ", 12, "    return a + b")];
+        // `python -m pytest` runs this stub in place of pytest
+        let stub = |code: i32| {
+            fs::write(
+                base_path.join("pytest.py"),
+                format!("import sys\nsys.exit({code})\n"),
+            )
+            .unwrap();
+        };
+
+        let config = RunConfig::new(base_path).output_level(runner::OutputLevel::Quiet);
+        let error = |code| MutantResult::Error {
+            signal: None,
+            code: Some(code),
+        };
+        for (code, expected) in [
+            (0, MutantResult::Missed),
+            (1, MutantResult::Caught),
+            (2, error(2)),
+            (4, error(4)),
+            (5, MutantResult::NoTests),
+        ] {
+            stub(code);
+            let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+            assert_eq!(outcomes[0].result, expected, "exit code {code}");
+        }
+
+        // any failure of a custom test command catches the mutant
+        let config = config
+            .runner(Runner::Custom)
+            .test_command("python -c 'import sys; sys.exit(2)'");
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(outcomes[0].result, MutantResult::Caught);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_mutant_run_errors() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let script = base_path.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        // the file of the first mutant is gone by the time it is run
+        let mutants_vec = vec![
+            Mutant::new(
+                base_path.join("gone.py"),
+                2,
+                " + ",
+                " - ",
+                12,
+                "    return a + b",
+            ),
+            Mutant::new(script, 2, " + ", " - ", 12, "    return a + b"),
+        ];
+
+        let config = RunConfig::new(base_path)
+            .runner(Runner::Custom)
+            .test_command("python -c 'import sys; sys.exit(1)'")
+            .output_level(OutputLevel::Quiet);
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        let failed = MutantResult::Error {
+            signal: None,
+            code: None,
+        };
+        assert_eq!(outcomes[0].result, failed);
+        assert!(outcomes[0].error.is_some());
+        assert_eq!(outcomes[1].result, MutantResult::Caught);
+        assert_eq!(outcomes[1].error, None);
+
+        // the run stops once more mutants than allowed could not be run
+        let error = runner::run_mutants(&config.clone().max_errors(0), &mutants_vec).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                PymuteError::TooManyErrors {
+                    errors: 1,
+                    max_errors: 0
+                }
+            ),
+            "{error}"
+        );
+        assert!(runner::run_mutants(&config.clone().max_errors(1), &mutants_vec).is_ok());
+
+        // the test command of a subproject can not be spawned
+        let subprojects = Subprojects(vec![Subproject {
+            dir: base_path.to_path_buf(),
+            name: "sub".to_string(),
+            settings: SubprojectSettings {
+                test_command: Some("pymute-does-not-exist".to_string()),
+                ..Default::default()
+            },
+        }]);
+        let outcomes =
+            runner::run_mutants_in_subprojects(&config, &mutants_vec[1..], &subprojects).unwrap();
+        assert_eq!(outcomes[0].result, failed);
+        let error = outcomes[0].error.as_deref().unwrap();
+        assert!(error.contains("pymute-does-not-exist"), "{error}");
+
+        // an unreadable file fails the copy of the project, unless running as root
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let secret = base_path.join("secret.txt");
+            fs::write(&secret, "").unwrap();
+            fs::set_permissions(&secret, fs::Permissions::from_mode(0o000)).unwrap();
+            if fs::read(&secret).is_err() {
+                let outcomes = runner::run_mutants(&config, &mutants_vec[1..]).unwrap();
+                assert_eq!(outcomes[0].result, failed);
+                assert!(outcomes[0].error.is_some());
+            }
+            fs::set_permissions(&secret, fs::Permissions::from_mode(0o644)).unwrap();
+        }
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_skip_invalid() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let script = base_path.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutants_vec = vec![Mutant::new(script, 1, ":", "", 13, "def add(a, b):")];
+        // the stub leaves a marker outside the working copy whenever it runs
+        let marker = base_path.join("ran");
+        fs::write(
+            base_path.join("pytest.py"),
+            format!("open({:?}, 'w').close()\n", marker.display().to_string()),
         )
-        .expect("run_mutants failed!");
+        .unwrap();
+
+        let config = RunConfig::new(base_path).output_level(runner::OutputLevel::Quiet);
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(outcomes[0].result, MutantResult::Invalid);
+        assert!(!marker.exists());
+
+        let config = config.skip_invalid(false);
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(outcomes[0].result, MutantResult::Missed);
+        assert!(marker.exists());
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_mutant_result_strings() {
+        for result in [
+            MutantResult::Caught,
+            MutantResult::NoTests,
+            MutantResult::Invalid,
+            MutantResult::Error {
+                signal: None,
+                code: None,
+            },
+            MutantResult::Error {
+                signal: Some(9),
+                code: None,
+            },
+            MutantResult::Error {
+                signal: None,
+                code: Some(2),
+            },
+        ] {
+            assert_eq!(MutantResult::try_from(result.to_string()), Ok(result));
+        }
+        assert_eq!(
+            MutantResult::Error {
+                signal: None,
+                code: Some(2)
+            }
+            .to_string(),
+            "error:exit:2"
+        );
+        assert!(MutantResult::try_from("error:exit:x".to_string()).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_custom_runner_killed_by_signal() {
+        let temp_dir = tempdir().unwrap();
+        let base_path = temp_dir.path();
+        let script = base_path.join("script.py");
+        fs::write(&script, "def add(a, b):\n    return a + b\n").unwrap();
+        let mutants_vec = vec![Mutant::new(script, 2, " + ", " - ", 12, "    return a + b")];
+        let kill = |signal: &str| {
+            format!(
+                "python -c \"import os, signal; signal.signal(signal.{signal}, signal.SIG_DFL); \
+                 os.kill(os.getpid(), signal.{signal})\""
+            )
+        };
+
+        let config = RunConfig::new(base_path)
+            .runner(Runner::Custom)
+            .output_level(runner::OutputLevel::Quiet)
+            .test_command(kill("SIGTERM"));
+        let outcomes = runner::run_mutants(&config, &mutants_vec).unwrap();
+        assert_eq!(
+            outcomes[0].result,
+            MutantResult::Error {
+                signal: Some(15),
+                code: None
+            }
+        );
+
+        // an interrupted test run cancels the whole run
+        let config = config.test_command(kill("SIGINT"));
+        assert!(runner::run_mutants(&config, &mutants_vec).is_err());
 
         temp_dir.close().unwrap();
     }
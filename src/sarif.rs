@@ -0,0 +1,338 @@
+//! SARIF output of surviving mutants, e.g. for GitHub code scanning.
+//!
+//! SARIF 2.1.0 is the format code scanning tools use to report findings. Each
+//! surviving mutant is a result whose rule is its mutation type, so that a pull
+//! request is annotated at the lines where a mutant survived:
+//!
+//! ```json
+//! {
+//!   "ruleId": "math-ops",
+//!   "level": "warning",
+//!   "message": {"text": "Mutant survived: ` + ` → ` - `"},
+//!   "locations": [{"physicalLocation": {
+//!     "artifactLocation": {"uri": "src/calc.py", "uriBaseId": "%SRCROOT%"},
+//!     "region": {"startLine": 2, "startColumn": 14}
+//!   }}],
+//!   "partialFingerprints": {"pymuteMutantId/v1": "3fa2c81b09de"}
+//! }
+//! ```
+//!
+//! Only the subset of the specification pymute needs is modelled here. Caught
+//! mutants are left out, unless they are included as notes. Survivors accepted
+//! with a verdict (see `verdicts`) are suppressed with the note of their verdict
+//! as justification, so that code scanning does not annotate them.
+
+use crate::mutants::Mutant;
+use crate::runner::MutantResult;
+use crate::verdicts::Verdicts;
+use crate::RunSummary;
+
+use serde::Serialize;
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Component, Path},
+};
+
+/// Version of the SARIF specification of the log.
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// JSON schema of the SARIF specification of the log.
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rule of mutants without a mutation type, see `Mutant::mutation_type`.
+const UNKNOWN_RULE: &str = "mutant";
+
+#[derive(Debug, Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<SarifRun>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifRun {
+    tool: Tool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: String,
+    short_description: Message,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    partial_fingerprints: BTreeMap<&'static str, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suppressions: Vec<Suppression>,
+}
+
+#[derive(Debug, Serialize)]
+struct Suppression {
+    kind: &'static str,
+    justification: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    uri_base_id: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+}
+
+/// Write the surviving mutants of a run of the project at `root` as a SARIF log
+/// to `path`. Caught mutants are included at the `note` level if
+/// `include_caught` is set, and survivors accepted in `verdicts` are suppressed.
+pub fn write_sarif(
+    path: &Path,
+    summary: &RunSummary,
+    root: &Path,
+    verdicts: &Verdicts,
+    include_caught: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut rules = BTreeMap::new();
+    let mut results = Vec::new();
+    for outcome in &summary.outcomes {
+        let (level, verb) = match outcome.result {
+            MutantResult::Missed => ("warning", "survived"),
+            MutantResult::Caught if include_caught => ("note", "caught"),
+            _ => continue,
+        };
+        let mutant = &outcome.mutant;
+        let id = mutant.id(root);
+        let suppressions = match (outcome.result, verdicts.get(&id)) {
+            (MutantResult::Missed, Some(verdict)) => vec![Suppression {
+                kind: "external",
+                justification: verdict.note.clone(),
+            }],
+            _ => Vec::new(),
+        };
+        let rule = rule_id(mutant);
+        rules.entry(rule.clone()).or_insert_with(|| Rule {
+            id: rule.clone(),
+            short_description: Message {
+                text: format!("Mutants of type {rule}"),
+            },
+        });
+        results.push(SarifResult {
+            rule_id: rule,
+            level,
+            message: Message {
+                text: format!("Mutant {verb}: `{}` → `{}`", mutant.before, mutant.after),
+            },
+            locations: vec![Location {
+                physical_location: PhysicalLocation {
+                    artifact_location: ArtifactLocation {
+                        uri: relative_uri(&mutant.file_path, root),
+                        uri_base_id: "%SRCROOT%",
+                    },
+                    region: Region {
+                        start_line: mutant.line_number,
+                        start_column: start_column(mutant),
+                    },
+                },
+            }],
+            partial_fingerprints: [("pymuteMutantId/v1", id)].into(),
+            suppressions,
+        });
+    }
+
+    let log = SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![SarifRun {
+            tool: Tool {
+                driver: Driver {
+                    name: "pymute",
+                    version: env!("CARGO_PKG_VERSION"),
+                    information_uri: env!("CARGO_PKG_REPOSITORY"),
+                    rules: rules.into_values().collect(),
+                },
+            },
+            results,
+        }],
+    };
+    fs::write(path, serde_json::to_string_pretty(&log)?)?;
+    Ok(())
+}
+
+/// The rule of `mutant`: the name of its mutation type.
+fn rule_id(mutant: &Mutant) -> String {
+    mutant
+        .mutation_type
+        .map_or(UNKNOWN_RULE.to_string(), |mutation_type| {
+            mutation_type.to_string()
+        })
+}
+
+/// The path of `file` relative to `root` with `/` as separator, as SARIF expects
+/// for URIs. Files outside of the root keep their absolute path.
+fn relative_uri(file: &Path, root: &Path) -> String {
+    let components: Vec<_> = file
+        .strip_prefix(root)
+        .unwrap_or(file)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect();
+    components.join("/")
+}
+
+/// The column (starting at 1) of the replaced token of `mutant`, without the
+/// whitespace `before` may start with.
+fn start_column(mutant: &Mutant) -> usize {
+    let whitespace = mutant.before.len() - mutant.before.trim_start().len();
+    mutant.column + whitespace + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutants::{Mutant, MutationType};
+    use crate::runner::{MutantOutcome, MutantResult};
+    use crate::sarif::write_sarif;
+    use crate::verdicts::{Verdict, VerdictKind, Verdicts};
+    use crate::RunSummary;
+    use serde_json::json;
+    use std::{path::Path, time::Duration};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_sarif() {
+        let temp_dir = tempdir().unwrap();
+        let root = Path::new("/projects/project");
+        let mut survived = Mutant::new(
+            root.join("src/calc.py"),
+            2,
+            " + ",
+            " - ",
+            12,
+            "    return a + b",
+        );
+        survived.mutation_type = Some(MutationType::MathOps);
+        let caught = Mutant::new(
+            root.join("src/calc.py"),
+            5,
+            " < ",
+            " > ",
+            8,
+            "    if a < b:",
+        );
+        let summary = RunSummary::new(
+            vec![
+                MutantOutcome {
+                    mutant: survived.clone(),
+                    result: MutantResult::Missed,
+                    duration: None,
+                    error: None,
+                },
+                MutantOutcome {
+                    mutant: caught,
+                    result: MutantResult::Caught,
+                    duration: None,
+                    error: None,
+                },
+            ],
+            Duration::from_secs(1),
+        );
+
+        let path = temp_dir.path().join("pymute.sarif");
+        write_sarif(&path, &summary, root, &Verdicts::new(), false).unwrap();
+        let log: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "pymute");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "math-ops");
+        assert_eq!(
+            run["results"],
+            json!([{
+                "ruleId": "math-ops",
+                "level": "warning",
+                "message": {"text": "Mutant survived: ` + ` → ` - `"},
+                "locations": [{"physicalLocation": {
+                    "artifactLocation": {"uri": "src/calc.py", "uriBaseId": "%SRCROOT%"},
+                    "region": {"startLine": 2, "startColumn": 14}
+                }}],
+                "partialFingerprints": {"pymuteMutantId/v1": survived.id(root)}
+            }])
+        );
+
+        // caught mutants are notes if they are included
+        write_sarif(&path, &summary, root, &Verdicts::new(), true).unwrap();
+        let log: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1]["level"], "note");
+        assert_eq!(results[1]["ruleId"], "mutant");
+
+        // accepted survivors are suppressed with the note of their verdict
+        let verdict = Verdict {
+            id: survived.id(root),
+            verdict: VerdictKind::Accepted,
+            note: "only changes a log message".into(),
+        };
+        let verdicts: Verdicts = [(verdict.id.clone(), verdict)].into_iter().collect();
+        write_sarif(&path, &summary, root, &verdicts, false).unwrap();
+        let log: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            log["runs"][0]["results"][0]["suppressions"],
+            json!([{"kind": "external", "justification": "only changes a log message"}])
+        );
+
+        temp_dir.close().unwrap();
+    }
+}
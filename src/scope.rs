@@ -0,0 +1,254 @@
+//! Nesting depth and enclosing functions of the lines of python files.
+//!
+//! Deeply nested code and long functions produce many mutants of little value, so
+//! runs can be focused on the rest (see `RunConfig::max_nesting` and
+//! `RunConfig::max_function_lines`). The scopes are derived from the indentation of
+//! the logical lines: a line at the top level of a module has depth 0, the body
+//! of a function depth 1 and the body of an `if` in that function depth 2. Lines
+//! continuing a statement (in brackets, after a backslash or in a multi-line
+//! string) share the scope of the statement's first line.
+//!
+//! ```
+//! use pymute::scope::line_scopes;
+//!
+//! let scopes = line_scopes("def f(x):\n    if x:\n        return 1\n    return 0\n");
+//! assert_eq!(scopes[2].depth, 2);
+//! assert_eq!(scopes[2].function_lines, Some(4));
+//! assert_eq!(scopes[0].depth, 0);
+//! ```
+
+use crate::mutants::{mask_strings, Mutant};
+
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+
+/// Scope of a single line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineScope {
+    /// Number of blocks the line is nested in.
+    pub depth: usize,
+    /// Length in lines of the innermost function the line is part of (from its
+    /// `def` to its last line of code), if any.
+    pub function_lines: Option<usize>,
+}
+
+/// A block whose header was seen but whose body has not ended yet.
+struct Block {
+    /// Indentation of the header.
+    indent: usize,
+    /// Index into the functions if the block is a function.
+    function: Option<usize>,
+}
+
+/// Find the scope of each line of `source`.
+///
+/// A `def` line itself is part of its function, but not nested in it.
+pub fn line_scopes(source: &str) -> Vec<LineScope> {
+    let mut blocks: Vec<Block> = Vec::new();
+    // first and last line of each function
+    let mut functions: Vec<(usize, usize)> = Vec::new();
+    // for each line its depth and innermost function
+    let mut lines: Vec<(usize, Option<usize>)> = Vec::new();
+
+    let mut open_string = None;
+    // the first line, indentation and scope of the statement that is continued
+    let mut statement: Option<(usize, usize, (usize, Option<usize>))> = None;
+    let mut brackets: usize = 0;
+
+    for (index, line) in source.lines().enumerate() {
+        let continued = statement.is_some();
+        let code = mask_strings(line, &mut open_string);
+        let text = code.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+
+        let (start, indent, scope) = match statement {
+            Some(statement) => statement,
+            None if text.is_empty() => {
+                lines.push((blocks.len(), blocks.iter().rev().find_map(|b| b.function)));
+                continue;
+            }
+            None => {
+                let indent = line.len() - line.trim_start().len();
+                while blocks.last().is_some_and(|block| block.indent >= indent) {
+                    blocks.pop();
+                }
+                let function = blocks.iter().rev().find_map(|block| block.function);
+                (index, indent, (blocks.len(), function))
+            }
+        };
+        lines.push(scope);
+        if !text.is_empty() || continued {
+            for block in &blocks {
+                if let Some(function) = block.function {
+                    functions[function].1 = index;
+                }
+            }
+        }
+
+        for byte in code.bytes() {
+            match byte {
+                b'(' | b'[' | b'{' => brackets += 1,
+                b')' | b']' | b'}' => brackets = brackets.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if brackets > 0 || open_string.is_some() || code.trim_end().ends_with('\\') {
+            statement = Some((start, indent, scope));
+            continue;
+        }
+        statement = None;
+
+        // a header of a block, unless the body is on the same line
+        if code.trim_end().ends_with(':') {
+            let header = source.lines().nth(start).unwrap_or_default().trim_start();
+            let function = match header.starts_with("def ") || header.starts_with("async def ") {
+                true => {
+                    functions.push((start, index));
+                    // the header is part of the function, but not nested in it
+                    for line in &mut lines[start..=index] {
+                        line.1 = Some(functions.len() - 1);
+                    }
+                    Some(functions.len() - 1)
+                }
+                false => None,
+            };
+            blocks.push(Block { indent, function });
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|(depth, function)| LineScope {
+            depth,
+            function_lines: function.map(|function| {
+                let (first, last) = functions[function];
+                last - first + 1
+            }),
+        })
+        .collect()
+}
+
+/// Remove the mutants on lines nested deeper than `max_nesting` or in functions
+/// longer than `max_function_lines`, see `line_scopes`.
+///
+/// Returns the remaining mutants and the number of removed mutants.
+pub fn filter_mutants(
+    mutants: Vec<Mutant>,
+    max_nesting: Option<usize>,
+    max_function_lines: Option<usize>,
+) -> Result<(Vec<Mutant>, usize), Box<dyn Error + Send + Sync>> {
+    if max_nesting.is_none() && max_function_lines.is_none() {
+        return Ok((mutants, 0));
+    }
+
+    let mut scopes: HashMap<PathBuf, Vec<LineScope>> = HashMap::new();
+    let mut kept = Vec::with_capacity(mutants.len());
+    let mut removed = 0;
+    for mutant in mutants {
+        if !scopes.contains_key(&mutant.file_path) {
+            let source = fs::read_to_string(&mutant.file_path)?;
+            scopes.insert(mutant.file_path.clone(), line_scopes(&source));
+        }
+        let scope = scopes[&mutant.file_path]
+            .get(mutant.line_number - 1)
+            .copied()
+            .unwrap_or_default();
+        let too_deep = max_nesting.is_some_and(|max| scope.depth > max);
+        let too_long = max_function_lines
+            .zip(scope.function_lines)
+            .is_some_and(|(max, lines)| lines > max);
+        match too_deep || too_long {
+            true => removed += 1,
+            false => kept.push(mutant),
+        }
+    }
+    Ok((kept, removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutants::{find_mutants_in_files, MutationType};
+    use crate::scope::{filter_mutants, line_scopes, LineScope};
+    use std::{fs, path::PathBuf};
+    use tempfile::tempdir;
+
+    const SOURCE: &str = "LIMIT = 1 + 1
+
+
+def check(values):
+    total = 0
+    for value in values:
+        if value > LIMIT:
+            if value > 2 * LIMIT:
+                total = total + value
+    message = (
+        1 + 2
+    )
+    return total
+
+
+class Checker:
+    def short(self, x):
+        return x + 1
+";
+
+    #[test]
+    fn test_line_scopes() {
+        let scopes = line_scopes(SOURCE);
+        let depths: Vec<usize> = scopes.iter().map(|scope| scope.depth).collect();
+        assert_eq!(
+            depths,
+            vec![0, 0, 0, 0, 1, 1, 2, 3, 4, 1, 1, 1, 1, 1, 1, 0, 1, 2]
+        );
+        assert_eq!(scopes[0].function_lines, None);
+        // `check` spans from its `def` to its `return`
+        assert_eq!(scopes[3].function_lines, Some(10));
+        assert_eq!(scopes[8].function_lines, Some(10));
+        // lines continuing a statement share its scope
+        assert_eq!(
+            scopes[10],
+            LineScope {
+                depth: 1,
+                function_lines: Some(10)
+            }
+        );
+        assert_eq!(scopes[15].function_lines, None);
+        assert_eq!(scopes[17].function_lines, Some(2));
+
+        // multi-line headers and strings
+        let scopes = line_scopes("def f(\n    a,\n):\n    s = \"\"\"\nx\n\"\"\"\n    return a\n");
+        let depths: Vec<usize> = scopes.iter().map(|scope| scope.depth).collect();
+        assert_eq!(depths, vec![0, 0, 0, 1, 1, 1, 1]);
+        assert_eq!(scopes[6].function_lines, Some(7));
+    }
+
+    #[test]
+    fn test_filter_mutants() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("check.py");
+        fs::write(&file, SOURCE).unwrap();
+        let mutants = find_mutants_in_files(&[file], &[MutationType::MathOps]).unwrap();
+        let lines = |mutants: &[crate::mutants::Mutant]| -> Vec<usize> {
+            mutants.iter().map(|mutant| mutant.line_number).collect()
+        };
+        assert_eq!(lines(&mutants), vec![1, 8, 9, 11, 18]);
+
+        let filter = |max_nesting, max_function_lines| {
+            let (kept, removed) =
+                filter_mutants(mutants.clone(), max_nesting, max_function_lines).unwrap();
+            assert_eq!(kept.len() + removed, mutants.len());
+            lines(&kept)
+        };
+        assert_eq!(filter(None, None), vec![1, 8, 9, 11, 18]);
+        assert_eq!(filter(Some(3), None), vec![1, 8, 11, 18]);
+        assert_eq!(filter(Some(2), None), vec![1, 11, 18]);
+        assert_eq!(filter(Some(0), None), vec![1]);
+        assert_eq!(filter(None, Some(10)), vec![1, 8, 9, 11, 18]);
+        assert_eq!(filter(None, Some(9)), vec![1, 18]);
+        assert_eq!(filter(Some(1), Some(1)), vec![1]);
+
+        assert!(filter_mutants(mutants, Some(1), None).is_ok());
+        let missing =
+            crate::mutants::Mutant::new(PathBuf::from("missing.py"), 1, " + ", " - ", 0, "1 + 1");
+        assert!(filter_mutants(vec![missing], Some(1), None).is_err());
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+//! Heuristic mapping from source files to the tests that plausibly exercise them.
+//!
+//! Test files are the files named `test_*.py` or `*_test.py` under the tests path.
+//! A test file is considered a candidate for a source file `pkg/module.py` if
+//!
+//! - it is named `test_module.py` or `module_test.py`, or
+//! - one of its `import` or `from` lines mentions `module` as a whole word.
+//!
+//! For `__init__.py` files the name of the package directory is used instead. The
+//! mapping is used before a run to report (and optionally skip) mutants in files
+//! without any associated tests, as these will almost certainly survive.
+
+use crate::mutants::Mutant;
+use crate::runner::split_tests_argument;
+
+use glob::{glob, Pattern};
+use regex::Regex;
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Test files of a project together with the modules they import.
+#[derive(Debug, Clone, Default)]
+pub struct TestMapping {
+    tests: Vec<TestFile>,
+}
+
+#[derive(Debug, Clone)]
+struct TestFile {
+    path: PathBuf,
+    import_lines: Vec<String>,
+}
+
+impl TestMapping {
+    /// Collect the test files under `tests` (relative to `root`).
+    ///
+    /// `tests` may point to a directory or a single test file, optionally as a
+    /// pytest node id (e.g. `tests/test_x.py::TestFoo`).
+    pub fn new(root: &Path, tests: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let (tests, _) = split_tests_argument(tests);
+        let tests_path = root.join(tests);
+        let paths: Vec<PathBuf> = if tests_path.is_file() {
+            vec![tests_path]
+        } else {
+            let base = Pattern::escape(tests_path.to_str().ok_or("Invalid tests path")?);
+            glob(&format!("{base}/**/*.py"))?
+                .filter_map(Result::ok)
+                .filter(|path| is_test_file(path))
+                .collect()
+        };
+
+        let mut mapping = TestMapping::default();
+        for path in paths {
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let import_lines = content
+                .lines()
+                .map(str::trim)
+                .filter(|line| line.starts_with("import ") || line.starts_with("from "))
+                .map(String::from)
+                .collect();
+            mapping.tests.push(TestFile { path, import_lines });
+        }
+        Ok(mapping)
+    }
+
+    /// Test files that plausibly exercise the source file at `source`.
+    pub fn candidate_tests(&self, source: &Path) -> Vec<&Path> {
+        let Some(module) = module_name(source) else {
+            return Vec::new();
+        };
+        let Ok(word) = Regex::new(&format!(r"\b{}\b", regex::escape(module))) else {
+            return Vec::new();
+        };
+        let by_name = [format!("test_{module}.py"), format!("{module}_test.py")];
+
+        self.tests
+            .iter()
+            .filter(|test| {
+                let named = test
+                    .path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| by_name.iter().any(|n| n == name));
+                named || test.import_lines.iter().any(|line| word.is_match(line))
+            })
+            .map(|test| test.path.as_path())
+            .collect()
+    }
+
+    /// Files containing any of `mutants` that have no candidate tests, together
+    /// with the number of mutants in each of them.
+    pub fn untested_files(&self, mutants: &[Mutant]) -> BTreeMap<PathBuf, usize> {
+        let mut untested = BTreeMap::new();
+        for mutant in mutants {
+            if self.candidate_tests(&mutant.file_path).is_empty() {
+                *untested.entry(mutant.file_path.clone()).or_insert(0) += 1;
+            }
+        }
+        untested
+    }
+}
+
+/// Whether `path` follows the pytest naming convention for test files.
+fn is_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("test_") || name.ends_with("_test.py"))
+}
+
+/// Name under which the python file at `path` is imported.
+fn module_name(path: &Path) -> Option<&str> {
+    let stem = path.file_stem()?.to_str()?;
+    if stem == "__init__" {
+        return path.parent()?.file_name()?.to_str();
+    }
+    Some(stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutants::Mutant;
+    use crate::selection::TestMapping;
+    use std::{fs, path::Path};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_mapping_fixture_layout() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        for dir in ["pkg/sub", "tests"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "pkg/calc.py",
+            "pkg/io.py",
+            "pkg/orphan.py",
+            "pkg/sub/__init__.py",
+        ] {
+            fs::write(root.join(file), "x = 1 + 2\n").unwrap();
+        }
+        fs::write(
+            root.join("tests/test_calc.py"),
+            "def test_add():\n    pass\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("tests/test_misc.py"),
+            "from pkg.io import read\nimport pkg.sub\n# orphan is only mentioned here\n",
+        )
+        .unwrap();
+
+        let mapping = TestMapping::new(root, "tests").unwrap();
+        assert_eq!(
+            mapping.candidate_tests(&root.join("pkg/calc.py")),
+            vec![root.join("tests/test_calc.py")]
+        );
+        assert_eq!(mapping.candidate_tests(&root.join("pkg/io.py")).len(), 1);
+        assert_eq!(
+            mapping
+                .candidate_tests(&root.join("pkg/sub/__init__.py"))
+                .len(),
+            1
+        );
+        assert!(mapping
+            .candidate_tests(&root.join("pkg/orphan.py"))
+            .is_empty());
+
+        let mutant = |file: &str| Mutant::new(root.join(file), 1, " + ", " - ", 5, "x = 1 + 2");
+        let untested = mapping.untested_files(&[
+            mutant("pkg/calc.py"),
+            mutant("pkg/orphan.py"),
+            mutant("pkg/orphan.py"),
+        ]);
+        assert_eq!(untested.len(), 1);
+        assert_eq!(untested[&root.join("pkg/orphan.py")], 2);
+
+        // pointing at a single test file only considers that file
+        let mapping = TestMapping::new(root, "tests/test_calc.py").unwrap();
+        assert!(mapping.candidate_tests(Path::new("pkg/io.py")).is_empty());
+
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+//! Splitting the mutants of a run across parallel jobs, e.g. CI machines.
+//!
+//! Shard `i/n` runs every `n`-th mutant, starting with the `i`-th, of the mutants
+//! sorted by file, line, column and replacement. As long as every job finds the
+//! same mutants (the same files, options and seed), the shards of a run are
+//! disjoint and together hold exactly all of its mutants. Sampling with
+//! `RunConfig::max_mutants` happens before sharding, so that the shards split the
+//! same sample.
+//!
+//! ```
+//! use pymute::shard::Shard;
+//!
+//! let shard: Shard = "2/4".parse().unwrap();
+//! assert_eq!(shard, Shard { index: 2, total: 4 });
+//! assert!("5/4".parse::<Shard>().is_err());
+//! ```
+
+use crate::mutants::Mutant;
+
+use serde::{Deserialize, Serialize};
+
+use std::{fmt, str::FromStr};
+
+/// One of `total` shards of the mutants of a run, `index` starts at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shard {
+    /// Number of the shard, from 1 to `total`.
+    pub index: usize,
+    /// Number of shards.
+    pub total: usize,
+}
+
+impl Shard {
+    /// Keep only the mutants of this shard.
+    pub fn filter_mutants(&self, mut mutants: Vec<Mutant>) -> Vec<Mutant> {
+        mutants.sort_by(|a, b| {
+            (&a.file_path, a.line_number, a.column, &a.before, &a.after).cmp(&(
+                &b.file_path,
+                b.line_number,
+                b.column,
+                &b.before,
+                &b.after,
+            ))
+        });
+        mutants
+            .into_iter()
+            .enumerate()
+            .filter(|(position, _)| position % self.total == self.index - 1)
+            .map(|(_, mutant)| mutant)
+            .collect()
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("invalid shard `{value}`, expected <index>/<total> with 1 ≤ index ≤ total");
+        let (index, total) = value.split_once('/').ok_or_else(invalid)?;
+        let index: usize = index.trim().parse().map_err(|_| invalid())?;
+        let total: usize = total.trim().parse().map_err(|_| invalid())?;
+        if index == 0 || index > total {
+            return Err(invalid());
+        }
+        Ok(Shard { index, total })
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutants::{find_mutants_in_files, MutationType};
+    use crate::shard::Shard;
+    use std::{collections::HashSet, fs};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_shards_partition_mutants() {
+        let temp_dir = tempdir().unwrap();
+        let files = vec![
+            temp_dir.path().join("calc.py"),
+            temp_dir.path().join("ops.py"),
+        ];
+        fs::write(
+            &files[0],
+            "def add(a, b):\n    c = a + b\n    d = c - 1\n    return d * 2\n",
+        )
+        .unwrap();
+        fs::write(
+            &files[1],
+            "def lt(a, b):\n    c = a < b\n    d = a > b\n    return c and d\n",
+        )
+        .unwrap();
+        let mutants = find_mutants_in_files(
+            &files,
+            &[
+                MutationType::MathOps,
+                MutationType::CompOps,
+                MutationType::Numbers,
+            ],
+        )
+        .unwrap();
+        assert!(mutants.len() > 3);
+
+        let key = |mutant: &crate::mutants::Mutant| format!("{mutant:?}");
+        let mut seen = HashSet::new();
+        for index in 1..=3 {
+            let shard = Shard { index, total: 3 }.filter_mutants(mutants.clone());
+            assert!(!shard.is_empty());
+            for mutant in &shard {
+                // shards are disjoint
+                assert!(seen.insert(key(mutant)), "{mutant:?}");
+            }
+        }
+        // and together cover all mutants
+        let all: HashSet<String> = mutants.iter().map(key).collect();
+        assert_eq!(seen, all);
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_parse_shard() {
+        assert_eq!("1/1".parse(), Ok(Shard { index: 1, total: 1 }));
+        assert_eq!("3/4".parse::<Shard>().unwrap().to_string(), "3/4");
+        for invalid in ["0/4", "5/4", "2", "a/4", "2/0"] {
+            assert!(invalid.parse::<Shard>().is_err(), "{invalid}");
+        }
+    }
+}
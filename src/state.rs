@@ -0,0 +1,295 @@
+//! On-disk layout of all files that pymute produces for a project.
+//!
+//! Everything pymute keeps between runs lives in a single state directory, by
+//! default `.pymute/` under the project root:
+//!
+//! ```text
+//! .pymute/
+//! ├── .gitignore      ignores the whole directory
+//! ├── cache.csv       results of previous runs
+//! ├── history.jsonl   one line per run
+//! ├── baseline.csv    results of the baseline run
+//! ├── verdicts.csv    user verdicts on surviving mutants
+//! ├── lock            prevents concurrent runs on the same project
+//! ├── logs/           output of the test runs
+//! ├── workdirs/       working copies of the project
+//! └── reports/        generated reports
+//! ```
+//!
+//! The state directory can be relocated with `--state-dir`. All modules get their
+//! paths from `StateDir` rather than building them themselves.
+
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Name of the default state directory under the project root.
+pub const DEFAULT_STATE_DIR: &str = ".pymute";
+/// Name of the cache file that older versions wrote to the project root.
+pub const LEGACY_CACHE_FILE: &str = ".pymute_cache.csv";
+
+/// The directory holding all files pymute produces for a project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDir {
+    path: PathBuf,
+}
+
+impl StateDir {
+    /// Create the state directory layout for the project at `root`.
+    ///
+    /// If `state_dir` is given it is used instead of the default `.pymute/`.
+    /// A relative `state_dir` is interpreted relative to `root`.
+    pub fn new(root: &Path, state_dir: Option<&Path>) -> Self {
+        let path = match state_dir {
+            Some(dir) => root.join(dir),
+            None => root.join(DEFAULT_STATE_DIR),
+        };
+        StateDir { path }
+    }
+
+    /// Path to the state directory itself.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path to the cache of mutant results.
+    pub fn cache_file(&self) -> PathBuf {
+        self.path.join("cache.csv")
+    }
+
+    /// Path to the run history.
+    pub fn history_file(&self) -> PathBuf {
+        self.path.join("history.jsonl")
+    }
+
+    /// Path to the results of the baseline run.
+    pub fn baseline_file(&self) -> PathBuf {
+        self.path.join("baseline.csv")
+    }
+
+    /// Path to the user verdicts on surviving mutants.
+    pub fn verdicts_file(&self) -> PathBuf {
+        self.path.join("verdicts.csv")
+    }
+
+    /// Path to the coverage data of the tests, see `coverage::CoverageMap::collect`.
+    pub fn coverage_data_file(&self) -> PathBuf {
+        self.path.join("coverage.sqlite")
+    }
+
+    /// Path to the coverage JSON report of the tests.
+    pub fn coverage_file(&self) -> PathBuf {
+        self.path.join("coverage.json")
+    }
+
+    /// Path to the lock file.
+    pub fn lock_file(&self) -> PathBuf {
+        self.path.join("lock")
+    }
+
+    /// Directory for logs of the test runs.
+    pub fn logs_dir(&self) -> PathBuf {
+        self.path.join("logs")
+    }
+
+    /// Directory for working copies of the project.
+    pub fn workdirs_dir(&self) -> PathBuf {
+        self.path.join("workdirs")
+    }
+
+    /// Directory for generated reports.
+    pub fn reports_dir(&self) -> PathBuf {
+        self.path.join("reports")
+    }
+
+    /// Create the state directory if it does not exist yet.
+    ///
+    /// A `.gitignore` is placed inside so the directory is never committed by
+    /// accident.
+    pub fn create(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.path)?;
+        let gitignore = self.path.join(".gitignore");
+        if !gitignore.exists() {
+            fs::write(gitignore, "# Automatically created by pymute.\n*\n")?;
+        }
+        Ok(())
+    }
+
+    /// Move a cache file written by older versions of pymute to the project root
+    /// into the state directory.
+    ///
+    /// The legacy file is only moved if the state directory holds no cache yet.
+    /// Returns whether a file was migrated.
+    pub fn migrate_legacy_cache(&self, root: &Path) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let legacy = root.join(LEGACY_CACHE_FILE);
+        if !legacy.is_file() || self.cache_file().exists() {
+            return Ok(false);
+        }
+
+        self.create()?;
+        if fs::rename(&legacy, self.cache_file()).is_err() {
+            // renaming fails across file systems, so fall back to copying
+            fs::copy(&legacy, self.cache_file())?;
+            fs::remove_file(&legacy)?;
+        }
+        Ok(true)
+    }
+
+    /// Take the lock on the state directory for the duration of a run.
+    ///
+    /// Only one run at a time may use a state directory, while runs on different
+    /// projects (i.e. different state directories) can happen concurrently. The lock
+    /// is released when the returned guard is dropped.
+    pub fn lock(&self) -> Result<StateLock, Box<dyn Error + Send + Sync>> {
+        self.create()?;
+        let path = self.lock_file();
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(Box::new(StateDirLocked { path }))
+            }
+            Err(err) => return Err(Box::new(err)),
+        };
+        writeln!(file, "{}", process::id())?;
+        Ok(StateLock { path })
+    }
+}
+
+/// Guard holding the lock on a state directory, see `StateDir::lock`.
+#[derive(Debug)]
+pub struct StateLock {
+    path: PathBuf,
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Debug)]
+struct StateDirLocked {
+    path: PathBuf,
+}
+
+impl Error for StateDirLocked {}
+impl fmt::Display for StateDirLocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Another pymute run is using this project! If that is not the case, remove {}",
+            self.path.display()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{StateDir, LEGACY_CACHE_FILE};
+    use std::{
+        fs::{self, read_to_string},
+        path::Path,
+    };
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_state_dir_layout() {
+        let root = Path::new("/projects/project");
+        let state = StateDir::new(root, None);
+
+        assert_eq!(state.path(), Path::new("/projects/project/.pymute"));
+        for path in [
+            state.cache_file(),
+            state.history_file(),
+            state.baseline_file(),
+            state.verdicts_file(),
+            state.lock_file(),
+            state.logs_dir(),
+            state.workdirs_dir(),
+            state.reports_dir(),
+        ] {
+            assert!(path.starts_with(state.path()), "{path:?} outside state dir");
+        }
+    }
+
+    #[test]
+    fn test_state_dir_relocation() {
+        let root = Path::new("/projects/project");
+
+        let state = StateDir::new(root, Some(Path::new("/var/cache/pymute")));
+        assert_eq!(state.cache_file(), Path::new("/var/cache/pymute/cache.csv"));
+
+        let state = StateDir::new(root, Some(Path::new("build/pymute")));
+        assert_eq!(
+            state.lock_file(),
+            Path::new("/projects/project/build/pymute/lock")
+        );
+    }
+
+    #[test]
+    fn test_state_dir_create() {
+        let temp_dir = tempdir().unwrap();
+        let state = StateDir::new(temp_dir.path(), None);
+
+        state.create().unwrap();
+        assert!(state.path().is_dir());
+        assert!(read_to_string(state.path().join(".gitignore"))
+            .unwrap()
+            .contains('*'));
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_migrate_legacy_cache() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let legacy = root.join(LEGACY_CACHE_FILE);
+        fs::write(&legacy, "legacy content").unwrap();
+
+        let state = StateDir::new(root, None);
+        assert!(state.migrate_legacy_cache(root).unwrap());
+        assert!(!legacy.exists());
+        assert_eq!(
+            read_to_string(state.cache_file()).unwrap(),
+            "legacy content"
+        );
+
+        // an existing cache in the state dir is never overwritten
+        fs::write(&legacy, "newer legacy content").unwrap();
+        assert!(!state.migrate_legacy_cache(root).unwrap());
+        assert!(legacy.exists());
+        assert_eq!(
+            read_to_string(state.cache_file()).unwrap(),
+            "legacy content"
+        );
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_state_dir_lock() {
+        let temp_dir = tempdir().unwrap();
+        let state = StateDir::new(temp_dir.path(), None);
+
+        let lock = state.lock().unwrap();
+        assert!(state.lock_file().is_file());
+        assert!(state.lock().is_err());
+
+        // a different project can be locked at the same time
+        let other = StateDir::new(temp_dir.path(), Some(Path::new("other")));
+        let other_lock = other.lock().unwrap();
+
+        drop(lock);
+        assert!(!state.lock_file().exists());
+        drop(state.lock().unwrap());
+        drop(other_lock);
+
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,313 @@
+//! Settings for the subprojects of a monorepo, from `pymute.toml` files.
+//!
+//! A directory below the root containing a `SUBPROJECT_FILE` is a subproject. Its
+//! settings override the configuration of the run for all files under the
+//! directory: their mutants are found with its mutation types, its skip patterns
+//! exclude files, its test command is run in the subproject's directory of the
+//! working copy, and its mutants are scored against its minimum score. Files that
+//! are part of several nested subprojects belong to the innermost one.
+//!
+//! ```toml
+//! name = "billing"
+//! test-command = "python -m pytest tests -x"
+//! min-score = 90.0
+//! mutation-types = ["math-ops", "comp-ops"]
+//! skip = ["migrations/**"]
+//! ```
+
+use crate::mutants::MutationType;
+use crate::summary::RunSummary;
+
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// Name of the file marking the directory of a subproject.
+pub const SUBPROJECT_FILE: &str = "pymute.toml";
+
+/// Settings of a subproject, as read from its `SUBPROJECT_FILE`. Settings that
+/// are not given are taken from the configuration of the run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SubprojectSettings {
+    /// Name of the subproject in the summary. Defaults to its directory relative
+    /// to the root.
+    pub name: Option<String>,
+    /// Test command run in the subproject's directory, split using shell-style
+    /// quoting.
+    pub test_command: Option<String>,
+    /// Minimum mutation score (in percent) of the subproject.
+    pub min_score: Option<f64>,
+    /// Mutation types to look for in the subproject.
+    pub mutation_types: Option<Vec<MutationType>>,
+    /// Glob patterns of files (relative to the subproject's directory) not to
+    /// mutate.
+    pub skip: Vec<String>,
+}
+
+/// A subproject of the project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subproject {
+    /// Directory of the subproject.
+    pub dir: PathBuf,
+    /// Name of the subproject.
+    pub name: String,
+    /// Settings of the subproject.
+    pub settings: SubprojectSettings,
+}
+
+impl Subproject {
+    /// Read the subproject in `dir` of the project at `root`.
+    fn read(dir: &Path, root: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let path = dir.join(SUBPROJECT_FILE);
+        let settings: SubprojectSettings = fs::read_to_string(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|content| toml::from_str(&content).map_err(|error| error.to_string()))
+            .map_err(|reason| InvalidSubprojectFile { path, reason })?;
+        let name = match &settings.name {
+            Some(name) => name.clone(),
+            None => match dir.strip_prefix(root) {
+                Ok(relative) => relative.display().to_string(),
+                Err(_) => dir.display().to_string(),
+            },
+        };
+        Ok(Subproject {
+            dir: dir.to_path_buf(),
+            name,
+            settings,
+        })
+    }
+
+    /// Whether `file` matches one of the skip patterns of the subproject.
+    fn skips(&self, file: &Path) -> bool {
+        let relative = file.strip_prefix(&self.dir).unwrap_or(file);
+        self.settings.skip.iter().any(|pattern| {
+            Pattern::new(pattern).is_ok_and(|pattern| pattern.matches_path(relative))
+        })
+    }
+}
+
+/// The subprojects of a project.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Subprojects(pub Vec<Subproject>);
+
+impl Subprojects {
+    /// Find the subprojects of `files` in the project at `root`.
+    ///
+    /// For each file, the directories from the file's up to (but excluding) the root
+    /// are searched for a `SUBPROJECT_FILE`, and the first one found is the file's subproject.
+    /// Every directory is only searched once.
+    pub fn discover(root: &Path, files: &[PathBuf]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut has_file: HashMap<PathBuf, bool> = HashMap::new();
+        let mut subprojects = Vec::new();
+        for file in files {
+            let mut dir = file.parent();
+            // a `SUBPROJECT_FILE` in the root is the `config_file::CONFIG_FILE`
+            while let Some(current) = dir.filter(|dir| dir.starts_with(root) && *dir != root) {
+                if let Some(&found) = has_file.get(current) {
+                    if found {
+                        break;
+                    }
+                } else {
+                    let found = current.join(SUBPROJECT_FILE).is_file();
+                    has_file.insert(current.to_path_buf(), found);
+                    if found {
+                        subprojects.push(Subproject::read(current, root)?);
+                        break;
+                    }
+                }
+                dir = current.parent();
+            }
+        }
+        Ok(Subprojects(subprojects))
+    }
+
+    /// The subproject `file` belongs to, i.e. the innermost one containing it.
+    pub fn find(&self, file: &Path) -> Option<&Subproject> {
+        self.0
+            .iter()
+            .filter(|subproject| file.starts_with(&subproject.dir))
+            .max_by_key(|subproject| subproject.dir.components().count())
+    }
+
+    /// Whether `file` is skipped by its subproject.
+    pub fn skips(&self, file: &Path) -> bool {
+        self.find(file)
+            .is_some_and(|subproject| subproject.skips(file))
+    }
+
+    /// The mutation types for `file`: those of its subproject, or `default`.
+    pub fn mutation_types<'a>(
+        &'a self,
+        file: &Path,
+        default: &'a [MutationType],
+    ) -> &'a [MutationType] {
+        self.find(file)
+            .and_then(|subproject| subproject.settings.mutation_types.as_deref())
+            .unwrap_or(default)
+    }
+}
+
+/// Results of the mutants of a subproject.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubprojectSummary {
+    /// Name of the subproject.
+    pub name: String,
+    /// Mutation score of the subproject, see `RunSummary::score`.
+    pub score: Option<f64>,
+    /// Minimum mutation score of the subproject.
+    pub min_score: Option<f64>,
+}
+
+impl SubprojectSummary {
+    /// Summarize the results of a subproject, given as the summary of a run of its
+    /// mutants only.
+    pub fn new(subproject: &Subproject, summary: &RunSummary) -> Self {
+        SubprojectSummary {
+            name: subproject.name.clone(),
+            score: summary.score(),
+            min_score: subproject.settings.min_score,
+        }
+    }
+
+    /// Whether the subproject reaches its minimum score. Subprojects without a
+    /// minimum score or without mutants always pass.
+    pub fn passed(&self) -> bool {
+        match (self.score, self.min_score) {
+            (Some(score), Some(min_score)) => score >= min_score,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for SubprojectSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(score) = self.score else {
+            return write!(f, "{}: no mutants", self.name);
+        };
+        match (self.min_score, self.passed()) {
+            (Some(min_score), true) => {
+                write!(f, "{}: {score:.1}% ≥ {min_score:.1}% ok", self.name)
+            }
+            (Some(min_score), false) => {
+                write!(f, "{}: {score:.1}% < {min_score:.1}% FAIL", self.name)
+            }
+            (None, _) => write!(f, "{}: {score:.1}%", self.name),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct InvalidSubprojectFile {
+    path: PathBuf,
+    reason: String,
+}
+
+impl Error for InvalidSubprojectFile {}
+impl fmt::Display for InvalidSubprojectFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not read the subproject settings in {}: {}",
+            self.path.display(),
+            self.reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutants::MutationType;
+    use crate::subprojects::{SubprojectSummary, Subprojects};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_discover_subprojects() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        for dir in ["billing/core", "billing/migrations", "checkout", "tools"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(
+            root.join("billing/pymute.toml"),
+            "test-command = \"pytest\"\nmin-score = 90\nskip = [\"migrations/**\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("checkout/pymute.toml"),
+            "name = \"shop\"\nmutation-types = [\"comp-ops\"]\n",
+        )
+        .unwrap();
+        let files = vec![
+            root.join("billing/core/invoice.py"),
+            root.join("billing/migrations/0001.py"),
+            root.join("checkout/cart.py"),
+            root.join("tools/lint.py"),
+        ];
+
+        let subprojects = Subprojects::discover(root, &files).unwrap();
+        assert_eq!(subprojects.0.len(), 2);
+        let billing = subprojects.find(&files[0]).unwrap();
+        assert_eq!(billing.name, "billing");
+        assert_eq!(billing.settings.test_command.as_deref(), Some("pytest"));
+        assert_eq!(billing.settings.min_score, Some(90.0));
+        assert_eq!(subprojects.find(&files[2]).unwrap().name, "shop");
+        assert_eq!(subprojects.find(&files[3]), None);
+
+        assert!(!subprojects.skips(&files[0]));
+        assert!(subprojects.skips(&files[1]));
+        assert!(!subprojects.skips(&files[3]));
+
+        let default = [MutationType::MathOps];
+        assert_eq!(subprojects.mutation_types(&files[0], &default), default);
+        assert_eq!(
+            subprojects.mutation_types(&files[2], &default),
+            [MutationType::CompOps]
+        );
+
+        // the file in the root configures the whole project instead
+        fs::write(
+            root.join("pymute.toml"),
+            "[profile.quick]\nmax_mutants = 5\n",
+        )
+        .unwrap();
+        let subprojects = Subprojects::discover(root, &files).unwrap();
+        assert_eq!(subprojects.0.len(), 2);
+        assert_eq!(subprojects.find(&files[3]), None);
+
+        // unknown settings are an error
+        fs::write(root.join("tools/pymute.toml"), "min_score = 90\n").unwrap();
+        let error = Subprojects::discover(root, &files).unwrap_err();
+        assert!(error.to_string().contains("tools"), "{error}");
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_subproject_summary() {
+        let summary = |score, min_score| SubprojectSummary {
+            name: "billing".into(),
+            score,
+            min_score,
+        };
+        assert_eq!(
+            summary(Some(91.0), Some(90.0)).to_string(),
+            "billing: 91.0% ≥ 90.0% ok"
+        );
+        assert!(!summary(Some(74.0), Some(85.0)).passed());
+        assert_eq!(
+            summary(Some(74.0), Some(85.0)).to_string(),
+            "billing: 74.0% < 85.0% FAIL"
+        );
+        assert!(summary(Some(74.0), None).passed());
+        assert!(summary(None, Some(85.0)).passed());
+        assert_eq!(summary(None, Some(85.0)).to_string(), "billing: no mutants");
+    }
+}
@@ -0,0 +1,744 @@
+//! Aggregate numbers describing a single pymute run.
+//!
+//! The structs in this module only ever hold counts, durations and configuration
+//! values, never file paths or source code. This makes them suitable to be written
+//! to a summary file (see `--summary-file`) that can be committed to a repository
+//! or uploaded as a CI artifact, and then aggregated across many projects.
+//!
+//! ## Schema Stability
+//!
+//! The summary file is a JSON object carrying a `schema_version` field. Within a
+//! given schema version, fields are never removed, renamed or changed in meaning;
+//! new fields may be added, so consumers should ignore keys they do not know.
+//! Any incompatible change bumps `schema_version`.
+//!
+//! ## Example
+//!
+//! ```
+//! use pymute::mutants::MutationType;
+//! use pymute::summary::{RunMetadata, RunSummary};
+//! use std::time::Duration;
+//!
+//! let summary = RunSummary::new(vec![], Duration::from_secs(3));
+//! assert_eq!(summary.score(), None);
+//!
+//! let metadata = RunMetadata::new(&[MutationType::MathOps], 10);
+//! let dir = tempfile::tempdir().unwrap();
+//! pymute::summary::write_summary_file(&dir.path().join("summary.json"), &summary, &metadata)
+//!     .unwrap();
+//! ```
+
+use crate::equivalence::EquivalenceRule;
+use crate::humanize::{format_duration, format_rate};
+use crate::mutants::MutationType;
+use crate::runner::{MutantOutcome, MutantResult};
+use crate::subprojects::SubprojectSummary;
+use crate::verdicts::Verdicts;
+
+use serde::Serialize;
+
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Version of the summary file schema. See the module documentation.
+pub const SUMMARY_SCHEMA_VERSION: u32 = 1;
+
+/// A phase of a run whose duration is measured, see `RunSummary::phases`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Finding the files and mutants, sampling them and selecting the tests.
+    Discovery,
+    /// Running the test suite against the mutants.
+    Execution,
+    /// Combining the results, applying the verdicts and writing the cache.
+    Report,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Phase::Discovery => "discovery",
+            Phase::Execution => "execution",
+            Phase::Report => "report",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Results of a run.
+#[derive(Debug, Clone, Default)]
+pub struct RunSummary {
+    /// Number of mutants for which the test suite was run.
+    pub total: usize,
+    /// Number of mutants killed by the test suite.
+    pub caught: usize,
+    /// Number of mutants that survived the test suite, without accepted survivors.
+    pub missed: usize,
+    /// Number of mutants that survived the test suite but were accepted by the
+    /// user, see `verdicts`. They are excluded from the mutation score.
+    pub accepted: usize,
+    /// Ids of mutants with a verdict that are caught now, so their verdict can be
+    /// removed.
+    pub stale_verdicts: Vec<String>,
+    /// Number of mutants that were skipped because their file has no associated
+    /// tests.
+    pub untested: usize,
+    /// Number of mutants that were skipped because no test executes their line,
+    /// see `coverage`.
+    pub uncovered: usize,
+    /// Whether uncovered mutants count as survivors in the mutation score. They
+    /// are excluded by default.
+    pub include_uncovered: bool,
+    /// Number of likely equivalent mutants excluded from the mutation score, see
+    /// `exclude_likely_equivalent`.
+    pub likely_equivalent: usize,
+    /// Number of mutants flagged as likely equivalent by each rule, whether or not
+    /// they are excluded from the score.
+    pub equivalence_rules: BTreeMap<EquivalenceRule, usize>,
+    /// Caught and missed mutants of each mutation type, before excluding likely
+    /// equivalent or accepted mutants. Mutants without a mutation type (see
+    /// `Mutant::mutation_type`) are not counted.
+    pub mutation_types: BTreeMap<MutationType, MutationTypeCounts>,
+    /// Number of mutants whose test run did not finish normally, e.g. because it
+    /// was killed by a signal or the tests could not be collected.
+    pub errors: usize,
+    /// Number of mutants for which the test runner collected no tests.
+    pub no_tests: usize,
+    /// Number of mutants that are not valid python, see `MutantResult::Invalid`.
+    pub invalid: usize,
+    /// Files without any associated tests, see `selection::TestMapping`.
+    pub untested_files: Vec<PathBuf>,
+    /// Files excluded from the run because of syntax errors, together with the
+    /// error message, see `syntax::check_syntax`.
+    pub invalid_files: BTreeMap<PathBuf, String>,
+    /// Wall-clock time of the run.
+    pub duration: Duration,
+    /// Wall-clock time of each phase of the run, in the order they ran. Together
+    /// they make up about the duration of the run.
+    pub phases: Vec<(Phase, Duration)>,
+    /// Each mutant together with its result.
+    pub outcomes: Vec<MutantOutcome>,
+    /// Number of outcomes taken from the cache of previous runs instead of running
+    /// the mutant, see `cache::cached_outcomes`.
+    pub cached: usize,
+    /// Number of malformed rows of the cache that were skipped, see
+    /// `cache::read_csv_cache_lenient`.
+    pub invalid_cache_rows: usize,
+    /// Results of each subproject, see `subprojects`.
+    pub subprojects: Vec<SubprojectSummary>,
+}
+
+impl RunSummary {
+    /// Count the outcomes of a run.
+    pub fn new(outcomes: Vec<MutantOutcome>, duration: Duration) -> Self {
+        let mut summary = RunSummary {
+            duration,
+            ..Default::default()
+        };
+        for outcome in &outcomes {
+            match outcome.result {
+                MutantResult::Caught => summary.caught += 1,
+                MutantResult::Missed => summary.missed += 1,
+                MutantResult::Untested => summary.untested += 1,
+                MutantResult::Uncovered => summary.uncovered += 1,
+                MutantResult::NoTests => summary.no_tests += 1,
+                MutantResult::Invalid => summary.invalid += 1,
+                MutantResult::Error { .. } => summary.errors += 1,
+            }
+            if let Some(rule) = outcome.mutant.likely_equivalent {
+                *summary.equivalence_rules.entry(rule).or_default() += 1;
+            }
+            if let Some(mutation_type) = outcome.mutant.mutation_type {
+                let caught = match outcome.result {
+                    MutantResult::Caught => true,
+                    MutantResult::Missed => false,
+                    _ => continue,
+                };
+                let counts = summary.mutation_types.entry(mutation_type).or_default();
+                match caught {
+                    true => counts.caught += 1,
+                    false => counts.missed += 1,
+                }
+            }
+        }
+        summary.total = summary.caught + summary.missed;
+        summary.outcomes = outcomes;
+        summary
+    }
+
+    /// Move the likely equivalent mutants out of the caught, missed, untested and
+    /// uncovered mutants, so that they are excluded from the mutation score.
+    pub fn exclude_likely_equivalent(&mut self) {
+        for outcome in &self.outcomes {
+            if outcome.mutant.likely_equivalent.is_none() {
+                continue;
+            }
+            let count = match outcome.result {
+                MutantResult::Caught => &mut self.caught,
+                MutantResult::Missed => &mut self.missed,
+                MutantResult::Untested => &mut self.untested,
+                MutantResult::Uncovered => &mut self.uncovered,
+                MutantResult::Error { .. } | MutantResult::NoTests | MutantResult::Invalid => {
+                    continue
+                }
+            };
+            *count -= 1;
+            self.likely_equivalent += 1;
+        }
+    }
+
+    /// Move survivors accepted in `verdicts` to their own bucket and collect the
+    /// verdicts on mutants that are caught now.
+    ///
+    /// Mutant ids are derived relative to `root`.
+    pub fn apply_verdicts(&mut self, verdicts: &Verdicts, root: &Path) {
+        for outcome in &self.outcomes {
+            let id = outcome.mutant.id(root);
+            // excluded likely equivalent survivors are no longer counted as missed
+            let excluded = self.likely_equivalent > 0 && outcome.mutant.likely_equivalent.is_some();
+            match (outcome.result, verdicts.get(&id)) {
+                (MutantResult::Missed, Some(_)) if excluded => {}
+                (MutantResult::Missed, Some(_)) => {
+                    self.missed -= 1;
+                    self.accepted += 1;
+                }
+                (MutantResult::Caught, Some(_)) => self.stale_verdicts.push(id),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether all subprojects reach their minimum score.
+    pub fn passed(&self) -> bool {
+        self.subprojects.iter().all(SubprojectSummary::passed)
+    }
+
+    /// Check the results against the thresholds of a run: the minimum score
+    /// `fail_under` (in percent), no survivors if `fail_on_missed`, and the minimum
+    /// scores of the subprojects (see `passed`).
+    ///
+    /// A run without mutants has no score and passes `fail_under`. Accepted
+    /// survivors do not fail `fail_on_missed`.
+    pub fn check(
+        &self,
+        fail_under: Option<f64>,
+        fail_on_missed: bool,
+    ) -> Result<(), ThresholdFailure> {
+        if let (Some(score), Some(fail_under)) = (self.score(), fail_under) {
+            if score < fail_under {
+                return Err(ThresholdFailure::ScoreBelow { score, fail_under });
+            }
+        }
+        if fail_on_missed && self.missed > 0 {
+            return Err(ThresholdFailure::Missed {
+                missed: self.missed,
+            });
+        }
+        if !self.passed() {
+            return Err(ThresholdFailure::Subprojects);
+        }
+        Ok(())
+    }
+
+    /// Mutation score as the percentage of caught mutants.
+    ///
+    /// Untested mutants (and uncovered ones if `include_uncovered`) count as
+    /// survivors, and errors, accepted survivors and
+    /// excluded likely equivalent mutants are excluded. Returns None if
+    /// there are no mutants.
+    pub fn score(&self) -> Option<f64> {
+        let uncovered = match self.include_uncovered {
+            true => self.uncovered,
+            false => 0,
+        };
+        let denominator = self.caught + self.missed + self.untested + uncovered;
+        if denominator == 0 {
+            return None;
+        }
+        Some(100.0 * self.caught as f64 / denominator as f64)
+    }
+
+    /// The `n` outcomes whose test runs took longest, slowest first. Outcomes
+    /// without a duration are left out.
+    pub fn slowest(&self, n: usize) -> Vec<&MutantOutcome> {
+        let mut outcomes: Vec<_> = self
+            .outcomes
+            .iter()
+            .filter(|outcome| outcome.duration.is_some())
+            .collect();
+        outcomes.sort_by_key(|outcome| Reverse(outcome.duration));
+        outcomes.truncate(n);
+        outcomes
+    }
+}
+
+/// Caught and missed mutants of a mutation type, see `RunSummary::mutation_types`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct MutationTypeCounts {
+    /// Number of mutants killed by the test suite.
+    pub caught: usize,
+    /// Number of mutants that survived the test suite.
+    pub missed: usize,
+}
+
+impl fmt::Display for MutationTypeCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} caught", self.caught, self.caught + self.missed)
+    }
+}
+
+/// A threshold a run failed, see `RunSummary::check`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThresholdFailure {
+    /// The mutation score is below the minimum score.
+    ScoreBelow { score: f64, fail_under: f64 },
+    /// Mutants survived although none may.
+    Missed { missed: usize },
+    /// Subprojects are below their minimum score.
+    Subprojects,
+}
+
+impl Error for ThresholdFailure {}
+impl fmt::Display for ThresholdFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdFailure::ScoreBelow { score, fail_under } => write!(
+                f,
+                "mutation score {score:.1}% is below the minimum of {fail_under:.1}%"
+            ),
+            ThresholdFailure::Missed { missed } => {
+                write!(f, "{missed} mutants survived, but none may")
+            }
+            ThresholdFailure::Subprojects => {
+                write!(f, "subprojects are below their minimum score")
+            }
+        }
+    }
+}
+
+/// Information about how a run was configured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunMetadata {
+    /// Version of pymute that produced the run.
+    pub version: String,
+    /// Mutation types that were configured for the run.
+    pub mutation_types: Vec<MutationType>,
+    /// Number of mutants discovered before any sampling.
+    pub population: usize,
+}
+
+impl RunMetadata {
+    /// Create metadata for a run of the current pymute version.
+    pub fn new(mutation_types: &[MutationType], population: usize) -> Self {
+        RunMetadata {
+            version: env!("CARGO_PKG_VERSION").into(),
+            mutation_types: mutation_types.to_vec(),
+            population,
+        }
+    }
+}
+
+/// On-disk representation of the summary file.
+#[derive(Serialize)]
+struct SummaryFile<'a> {
+    schema_version: u32,
+    pymute_version: &'a str,
+    mutation_types: &'a [MutationType],
+    population: usize,
+    total: usize,
+    caught: usize,
+    missed: usize,
+    accepted: usize,
+    untested: usize,
+    uncovered: usize,
+    /// Number of results taken from the cache of previous runs.
+    cached: usize,
+    likely_equivalent: usize,
+    /// Number of mutants flagged as likely equivalent by each rule.
+    likely_equivalent_rules: &'a BTreeMap<EquivalenceRule, usize>,
+    untested_files: usize,
+    invalid_files: usize,
+    errors: usize,
+    no_tests: usize,
+    invalid_mutants: usize,
+    /// Number of subprojects below their minimum score.
+    failed_subprojects: usize,
+    score: Option<f64>,
+    duration_ms: u128,
+    /// `duration_ms` formatted for humans, e.g. `1h 12m 5s`.
+    duration: String,
+    /// Mutants run per minute, formatted for humans.
+    rate: String,
+    /// Duration of each phase of the run in milliseconds.
+    phases_ms: BTreeMap<Phase, u128>,
+}
+
+/// Write the aggregate numbers of a run as JSON to `path`.
+///
+/// The file only contains aggregate numbers and configuration values, never any
+/// file paths or code.
+pub fn write_summary_file(
+    path: &Path,
+    summary: &RunSummary,
+    metadata: &RunMetadata,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let file = SummaryFile {
+        schema_version: SUMMARY_SCHEMA_VERSION,
+        pymute_version: &metadata.version,
+        mutation_types: &metadata.mutation_types,
+        population: metadata.population,
+        total: summary.total,
+        caught: summary.caught,
+        missed: summary.missed,
+        accepted: summary.accepted,
+        untested: summary.untested,
+        uncovered: summary.uncovered,
+        cached: summary.cached,
+        likely_equivalent: summary.likely_equivalent,
+        likely_equivalent_rules: &summary.equivalence_rules,
+        untested_files: summary.untested_files.len(),
+        invalid_files: summary.invalid_files.len(),
+        errors: summary.errors,
+        no_tests: summary.no_tests,
+        invalid_mutants: summary.invalid,
+        failed_subprojects: summary
+            .subprojects
+            .iter()
+            .filter(|subproject| !subproject.passed())
+            .count(),
+        score: summary.score(),
+        duration_ms: summary.duration.as_millis(),
+        duration: format_duration(summary.duration),
+        rate: format_rate(summary.total, summary.duration),
+        phases_ms: summary
+            .phases
+            .iter()
+            .map(|(phase, duration)| (*phase, duration.as_millis()))
+            .collect(),
+    };
+
+    fs::write(path, serde_json::to_string_pretty(&file)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::equivalence::EquivalenceRule;
+    use crate::mutants::{Mutant, MutationType};
+    use crate::runner::{MutantOutcome, MutantResult};
+    use crate::summary::{
+        write_summary_file, MutationTypeCounts, Phase, RunMetadata, RunSummary, ThresholdFailure,
+    };
+    use crate::verdicts::{Verdict, VerdictKind, Verdicts};
+    use std::{
+        fs::read_to_string,
+        path::{Path, PathBuf},
+        time::Duration,
+    };
+    use tempfile::tempdir;
+
+    fn outcome(result: MutantResult) -> MutantOutcome {
+        let mutant = Mutant::new(PathBuf::from("script.py"), 1, " + ", " - ", 1, "a + b");
+        MutantOutcome {
+            mutant,
+            result,
+            duration: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_run_summary_new() {
+        let summary = RunSummary::new(
+            vec![
+                outcome(MutantResult::Caught),
+                outcome(MutantResult::Caught),
+                outcome(MutantResult::Caught),
+                outcome(MutantResult::Missed),
+            ],
+            Duration::from_secs(1),
+        );
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.caught, 3);
+        assert_eq!(summary.missed, 1);
+        assert_eq!(summary.outcomes.len(), 4);
+        assert_eq!(summary.score(), Some(75.0));
+
+        let summary = RunSummary::new(
+            vec![
+                outcome(MutantResult::Caught),
+                outcome(MutantResult::Untested),
+                outcome(MutantResult::Uncovered),
+                outcome(MutantResult::Error {
+                    signal: Some(9),
+                    code: None,
+                }),
+            ],
+            Duration::from_secs(1),
+        );
+        assert_eq!(summary.total, 1);
+        assert_eq!(summary.untested, 1);
+        assert_eq!(summary.uncovered, 1);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.score(), Some(50.0));
+        let summary = RunSummary {
+            include_uncovered: true,
+            ..summary
+        };
+        assert!((summary.score().unwrap() - 100.0 / 3.0).abs() < 1e-9);
+
+        assert_eq!(RunSummary::default().score(), None);
+    }
+
+    #[test]
+    fn test_run_summary_mutation_types() {
+        let typed = |mutation_type, result| {
+            let mut outcome = outcome(result);
+            outcome.mutant.mutation_type = Some(mutation_type);
+            outcome
+        };
+        let summary = RunSummary::new(
+            vec![
+                typed(MutationType::CompOps, MutantResult::Caught),
+                typed(MutationType::CompOps, MutantResult::Missed),
+                typed(
+                    MutationType::CompOps,
+                    MutantResult::Error {
+                        signal: Some(9),
+                        code: None,
+                    },
+                ),
+                typed(MutationType::MathOps, MutantResult::Caught),
+                outcome(MutantResult::Missed),
+            ],
+            Duration::from_secs(1),
+        );
+        let comp_ops = summary.mutation_types[&MutationType::CompOps];
+        assert_eq!(
+            comp_ops,
+            MutationTypeCounts {
+                caught: 1,
+                missed: 1
+            }
+        );
+        assert_eq!(comp_ops.to_string(), "1/2 caught");
+        assert_eq!(
+            summary.mutation_types[&MutationType::MathOps].to_string(),
+            "1/1 caught"
+        );
+        assert_eq!(summary.mutation_types.len(), 2);
+    }
+
+    #[test]
+    fn test_slowest_outcomes() {
+        let timed = |millis| {
+            let mut outcome = outcome(MutantResult::Caught);
+            outcome.duration = Some(Duration::from_millis(millis));
+            outcome
+        };
+        let summary = RunSummary::new(
+            vec![
+                timed(20),
+                outcome(MutantResult::Missed),
+                timed(300),
+                timed(5),
+            ],
+            Duration::from_secs(1),
+        );
+        let durations = |n| -> Vec<_> {
+            summary
+                .slowest(n)
+                .iter()
+                .map(|outcome| outcome.duration.unwrap().as_millis())
+                .collect()
+        };
+        assert_eq!(durations(2), [300, 20]);
+        assert_eq!(durations(10), [300, 20, 5]);
+    }
+
+    #[test]
+    fn test_check_thresholds() {
+        let summary = RunSummary::new(
+            vec![
+                outcome(MutantResult::Caught),
+                outcome(MutantResult::Caught),
+                outcome(MutantResult::Caught),
+                outcome(MutantResult::Missed),
+            ],
+            Duration::from_secs(1),
+        );
+        assert_eq!(summary.check(None, false), Ok(()));
+        assert_eq!(summary.check(Some(75.0), false), Ok(()));
+        let failure = summary.check(Some(80.0), false).unwrap_err();
+        assert_eq!(
+            failure,
+            ThresholdFailure::ScoreBelow {
+                score: 75.0,
+                fail_under: 80.0
+            }
+        );
+        assert_eq!(
+            failure.to_string(),
+            "mutation score 75.0% is below the minimum of 80.0%"
+        );
+        assert_eq!(
+            summary.check(None, true),
+            Err(ThresholdFailure::Missed { missed: 1 })
+        );
+
+        // without mutants there is no score to check
+        assert_eq!(RunSummary::default().check(Some(100.0), true), Ok(()));
+    }
+
+    #[test]
+    fn test_apply_verdicts() {
+        let mutant =
+            |after: &str| Mutant::new(PathBuf::from("script.py"), 1, " + ", after, 1, "a + b");
+        let outcomes = vec![
+            MutantOutcome {
+                mutant: mutant(" - "),
+                result: MutantResult::Missed,
+                duration: None,
+                error: None,
+            },
+            MutantOutcome {
+                mutant: mutant(" * "),
+                result: MutantResult::Missed,
+                duration: None,
+                error: None,
+            },
+            MutantOutcome {
+                mutant: mutant(" / "),
+                result: MutantResult::Caught,
+                duration: None,
+                error: None,
+            },
+            MutantOutcome {
+                mutant: mutant(" // "),
+                result: MutantResult::Caught,
+                duration: None,
+                error: None,
+            },
+        ];
+        let root = Path::new("");
+        let verdicts: Verdicts = [&outcomes[0], &outcomes[2]]
+            .iter()
+            .map(|outcome| {
+                let id = outcome.mutant.id(root);
+                let verdict = Verdict {
+                    id: id.clone(),
+                    verdict: VerdictKind::Accepted,
+                    note: String::new(),
+                };
+                (id, verdict)
+            })
+            .collect();
+
+        let mut summary = RunSummary::new(outcomes, Duration::from_secs(1));
+        assert_eq!(summary.score(), Some(50.0));
+        summary.apply_verdicts(&verdicts, root);
+
+        // the accepted survivor no longer counts against the score
+        assert_eq!((summary.total, summary.missed, summary.accepted), (4, 1, 1));
+        assert_eq!(summary.score(), Some(200.0 / 3.0));
+        // the verdict on the caught mutant is stale
+        assert_eq!(
+            summary.stale_verdicts,
+            vec![summary.outcomes[2].mutant.id(root)]
+        );
+    }
+
+    #[test]
+    fn test_exclude_likely_equivalent() {
+        let flagged = |result| {
+            let mut outcome = outcome(result);
+            outcome.mutant.after = " * ".into();
+            outcome.mutant.likely_equivalent = Some(EquivalenceRule::AddZero);
+            outcome
+        };
+        let outcomes = vec![
+            outcome(MutantResult::Caught),
+            outcome(MutantResult::Missed),
+            flagged(MutantResult::Caught),
+            flagged(MutantResult::Missed),
+            flagged(MutantResult::Missed),
+        ];
+        let mut summary = RunSummary::new(outcomes, Duration::from_secs(1));
+        assert_eq!(summary.score(), Some(40.0));
+        assert_eq!(
+            summary.equivalence_rules,
+            [(EquivalenceRule::AddZero, 3)].into()
+        );
+
+        summary.exclude_likely_equivalent();
+        assert_eq!(
+            (summary.caught, summary.missed, summary.likely_equivalent),
+            (1, 1, 3)
+        );
+        assert_eq!(summary.score(), Some(50.0));
+
+        // accepting an excluded survivor does not count it twice
+        let id = summary.outcomes[3].mutant.id(Path::new(""));
+        let verdict = Verdict {
+            id: id.clone(),
+            verdict: VerdictKind::Accepted,
+            note: String::new(),
+        };
+        summary.apply_verdicts(&[(id, verdict)].into_iter().collect(), Path::new(""));
+        assert_eq!((summary.missed, summary.accepted), (1, 0));
+    }
+
+    #[test]
+    fn test_write_summary_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("summary.json");
+
+        let mut summary = RunSummary::new(
+            vec![outcome(MutantResult::Caught), outcome(MutantResult::Missed)],
+            Duration::from_millis(1500),
+        );
+        summary.phases = vec![
+            (Phase::Discovery, Duration::from_millis(200)),
+            (Phase::Execution, Duration::from_millis(1250)),
+            (Phase::Report, Duration::from_millis(50)),
+        ];
+        let metadata = RunMetadata::new(&[MutationType::MathOps, MutationType::CompOps], 12);
+        write_summary_file(&path, &summary, &metadata).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["pymute_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["mutation_types"][0], "math-ops");
+        assert_eq!(json["mutation_types"][1], "comp-ops");
+        assert_eq!(json["population"], 12);
+        assert_eq!(json["total"], 2);
+        assert_eq!(json["caught"], 1);
+        assert_eq!(json["missed"], 1);
+        assert_eq!(json["untested"], 0);
+        assert_eq!(json["uncovered"], 0);
+        assert_eq!(json["likely_equivalent"], 0);
+        assert_eq!(json["likely_equivalent_rules"], serde_json::json!({}));
+        assert_eq!(json["untested_files"], 0);
+        assert_eq!(json["invalid_files"], 0);
+        assert_eq!(json["errors"], 0);
+        assert_eq!(json["failed_subprojects"], 0);
+        assert_eq!(json["score"], 50.0);
+        assert_eq!(json["duration_ms"], 1500);
+        assert_eq!(json["duration"], "1s");
+        assert_eq!(json["rate"], "80.0 mutants/min");
+        assert_eq!(
+            json["phases_ms"],
+            serde_json::json!({"discovery": 200, "execution": 1250, "report": 50})
+        );
+
+        temp_dir.close().unwrap();
+    }
+}
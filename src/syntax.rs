@@ -0,0 +1,115 @@
+//! Check python files for syntax errors before looking for mutants in them.
+//!
+//! Mutants in a file that does not compile (e.g. work in progress or python 2
+//! leftovers) can only fail at test collection, so such files are excluded from
+//! discovery. The files are compiled by python itself, in batches of
+//! `SYNTAX_CHECK_BATCH_SIZE` files per interpreter, the same way `py_compile` does
+//! but without writing any bytecode into the project.
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Number of files compiled by a single python process, to keep the command line
+/// well below the limits of all platforms.
+pub const SYNTAX_CHECK_BATCH_SIZE: usize = 256;
+
+/// Compiles the files given as arguments and prints one JSON line of
+/// `[index, message]` per file that does not compile.
+const CHECK_SCRIPT: &str = r#"
+import json, sys
+for index, path in enumerate(sys.argv[1:]):
+    try:
+        with open(path, "rb") as f:
+            compile(f.read(), path, "exec", dont_inherit=True)
+    except SyntaxError as e:
+        print(json.dumps([index, f"{e.msg} (line {e.lineno})"]))
+    except (OSError, ValueError) as e:
+        print(json.dumps([index, str(e)]))
+"#;
+
+/// Find the files that are not valid python using the interpreter `python`.
+///
+/// Returns each invalid file together with its error message.
+pub fn check_syntax(
+    python: &Path,
+    files: &[PathBuf],
+) -> Result<BTreeMap<PathBuf, String>, Box<dyn Error + Send + Sync>> {
+    let mut invalid = BTreeMap::new();
+    for batch in files.chunks(SYNTAX_CHECK_BATCH_SIZE) {
+        let output = Command::new(python)
+            .arg("-c")
+            .arg(CHECK_SCRIPT)
+            .args(batch)
+            .output()
+            .map_err(|error| SyntaxCheckFailed {
+                python: python.display().to_string(),
+                reason: error.to_string(),
+            })?;
+        if !output.status.success() {
+            return Err(Box::new(SyntaxCheckFailed {
+                python: python.display().to_string(),
+                reason: String::from_utf8_lossy(&output.stderr).trim().into(),
+            }));
+        }
+
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let (index, message): (usize, String) = serde_json::from_str(line)?;
+            if let Some(path) = batch.get(index) {
+                invalid.insert(path.clone(), message);
+            }
+        }
+    }
+    Ok(invalid)
+}
+
+#[derive(Debug)]
+struct SyntaxCheckFailed {
+    python: String,
+    reason: String,
+}
+
+impl Error for SyntaxCheckFailed {}
+impl fmt::Display for SyntaxCheckFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not check the syntax of the python files with `{}` ({}). Use `--check-syntax false` to skip the check.",
+            self.python, self.reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::check_syntax;
+    use std::{fs, path::Path};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_check_syntax() {
+        let temp_dir = tempdir().unwrap();
+        let valid = temp_dir.path().join("valid.py");
+        let broken = temp_dir.path().join("broken.py");
+        fs::write(&valid, "def add(a, b):\n    return a + b\n").unwrap();
+        fs::write(&broken, "def add(a, b):\n    print \"a + b\"\n").unwrap();
+
+        let invalid = check_syntax(Path::new("python"), &[valid.clone(), broken.clone()]).unwrap();
+        assert_eq!(invalid.len(), 1);
+        assert!(
+            invalid[&broken].contains("(line 2)"),
+            "{}",
+            invalid[&broken]
+        );
+        // no bytecode is written into the project
+        assert!(!temp_dir.path().join("__pycache__").exists());
+
+        assert!(check_syntax(Path::new("does-not-exist-python"), &[valid]).is_err());
+
+        temp_dir.close().unwrap();
+    }
+}
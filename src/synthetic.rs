@@ -0,0 +1,184 @@
+//! Deterministic synthetic python projects for tests and benchmarks.
+//!
+//! A synthetic project consists of `files` python modules with `lines` lines each.
+//! Every line is an assignment, and `operator_density` controls the fraction of
+//! lines that contain an operator and thus a mutant. The generated content only
+//! depends on the parameters and the seed, so timings are comparable across runs.
+//!
+//! ## Example
+//!
+//! ```
+//! use pymute::synthetic::SyntheticProject;
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! SyntheticProject::new(10, 50)
+//!     .operator_density(0.5)
+//!     .write(dir.path())
+//!     .unwrap();
+//! ```
+
+use crate::mutants::Mutant;
+use crate::runner::{MutantOutcome, MutantResult};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Operators used in the generated lines, all of which are mutated by the
+/// default mutation types.
+const OPERATORS: [&str; 7] = ["+", "-", "*", "/", "==", "<", ">"];
+
+/// A test command that succeeds instantly, for measuring the overhead of
+/// pymute itself with the custom runner.
+#[cfg(unix)]
+pub const INSTANT_TEST_COMMAND: &str = "true";
+/// A test command that succeeds instantly, for measuring the overhead of
+/// pymute itself with the custom runner.
+#[cfg(windows)]
+pub const INSTANT_TEST_COMMAND: &str = "cmd /C exit 0";
+
+/// Parameters of a synthetic python project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticProject {
+    /// Number of python modules.
+    pub files: usize,
+    /// Number of lines per module.
+    pub lines: usize,
+    /// Fraction of lines that contain an operator.
+    pub operator_density: f64,
+    /// Seed for the random number generator.
+    pub seed: u64,
+}
+
+impl SyntheticProject {
+    /// Create a project of `files` modules with `lines` lines each, where every
+    /// line contains an operator.
+    pub fn new(files: usize, lines: usize) -> Self {
+        SyntheticProject {
+            files,
+            lines,
+            operator_density: 1.0,
+            seed: 0,
+        }
+    }
+
+    /// Set the fraction of lines that contain an operator.
+    pub fn operator_density(mut self, operator_density: f64) -> Self {
+        self.operator_density = operator_density.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the seed for the random number generator.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Write the project to `root`, with the modules in `root/package/`.
+    ///
+    /// Returns the paths to all modules.
+    pub fn write(&self, root: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let package = root.join("package");
+        fs::create_dir_all(&package)?;
+
+        let mut paths = Vec::with_capacity(self.files);
+        for file in 0..self.files {
+            let mut content = String::from("def compute(a, b):\n");
+            for line in 0..self.lines {
+                if rng.gen_bool(self.operator_density) {
+                    let operator = OPERATORS[rng.gen_range(0..OPERATORS.len())];
+                    content.push_str(&format!("    x{line} = a {operator} b\n"));
+                } else {
+                    content.push_str(&format!("    x{line} = a\n"));
+                }
+            }
+            content.push_str("    return a\n");
+
+            let path = package.join(format!("module_{file}.py"));
+            fs::write(&path, content)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// Create `n` outcomes of synthetic mutants, e.g. for benchmarking the cache.
+pub fn outcomes(n: usize) -> Vec<MutantOutcome> {
+    (0..n)
+        .map(|i| MutantOutcome {
+            mutant: Mutant::new(
+                PathBuf::from(format!("package/module_{}.py", i / 100)),
+                i % 100 + 2,
+                " + ",
+                " - ",
+                9,
+                "    x = a + b",
+            ),
+            result: match i % 3 {
+                0 => MutantResult::Missed,
+                _ => MutantResult::Caught,
+            },
+            duration: None,
+            error: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::DEFAULT_MUTATION_TYPES;
+    use crate::mutants::find_mutants;
+    use crate::synthetic::SyntheticProject;
+    use std::{fs, time::Instant};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_synthetic_project_is_deterministic() {
+        let temp_dir = tempdir().unwrap();
+        let project = SyntheticProject::new(3, 20).operator_density(0.5).seed(1);
+
+        let first = temp_dir.path().join("first");
+        let second = temp_dir.path().join("second");
+        let paths = project.write(&first).unwrap();
+        project.write(&second).unwrap();
+
+        assert_eq!(paths.len(), 3);
+        for path in &paths {
+            let relative = path.strip_prefix(&first).unwrap();
+            assert_eq!(
+                fs::read_to_string(path).unwrap(),
+                fs::read_to_string(second.join(relative)).unwrap()
+            );
+        }
+
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn test_discovery_smoke() {
+        let temp_dir = tempdir().unwrap();
+        SyntheticProject::new(1000, 50)
+            .operator_density(0.5)
+            .write(temp_dir.path())
+            .unwrap();
+
+        let glob = format!("{}/**/*.py", temp_dir.path().to_str().unwrap());
+        let start = Instant::now();
+        let mutants = find_mutants(&glob, &DEFAULT_MUTATION_TYPES).unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(mutants.len() > 10_000);
+        // very generous bound, this only catches order-of-magnitude regressions
+        assert!(
+            elapsed.as_secs() < 60,
+            "Discovery of 1000 files took {elapsed:?}"
+        );
+
+        temp_dir.close().unwrap();
+    }
+}
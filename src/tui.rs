@@ -0,0 +1,518 @@
+//! A full-screen terminal dashboard for following a run (`--tui`).
+//!
+//! The dashboard is built on the events of the run only (see `events`): a
+//! `Dashboard` is an `Observer` that folds the events into a `DashboardState`,
+//! which is drawn by a separate thread. Keybindings pause the dispatching of new
+//! mutants, toggle the verbosity of the list of recent mutants or stop the run at
+//! the next survivor (fail fast):
+//!
+//! - `p`: pause or resume
+//! - `v`: show all recent mutants instead of only the survivors
+//! - `f`: stop at the next survivor
+//! - `q` or `Ctrl-C`: stop the run
+//!
+//! The state can be driven without a terminal, e.g.
+//!
+//! ```
+//! use pymute::events::{Dispatch, Event};
+//! use pymute::tui::DashboardState;
+//!
+//! let mut state = DashboardState::default();
+//! state.apply(&Event::RunStarted { mutants: 10 });
+//! state.handle_key('p');
+//! assert_eq!(state.dispatch(), Dispatch::Pause);
+//! ```
+
+use crate::events::{Dispatch, Event, Observer};
+use crate::runner::MutantResult;
+
+use ratatui::{
+    crossterm::{
+        event::{self, KeyCode, KeyEventKind, KeyModifiers},
+        terminal,
+    },
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Gauge, List, ListItem, Paragraph},
+    Frame,
+};
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    io::{self, IsTerminal},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// Smallest terminal (columns, rows) the dashboard is drawn in.
+pub const MIN_TERMINAL_SIZE: (u16, u16) = (60, 16);
+
+/// Number of recent mutants kept for the list of recent mutants.
+const RECENT_MUTANTS: usize = 50;
+
+/// Number of results per file shown in its sparkline.
+const SPARKLINE_LENGTH: usize = 20;
+
+/// Interval in which the dashboard is redrawn and checks for key presses.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A mutant that finished, as shown in the list of recent mutants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinishedMutant {
+    pub file_path: PathBuf,
+    pub line_number: usize,
+    pub before: String,
+    pub after: String,
+    pub result: MutantResult,
+}
+
+/// Results of the mutants in a single file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileResults {
+    pub caught: usize,
+    pub missed: usize,
+    /// Whether each of the last `SPARKLINE_LENGTH` mutants was caught.
+    pub history: VecDeque<bool>,
+}
+
+impl FileResults {
+    /// Percentage of caught mutants, if any were caught or missed.
+    pub fn score(&self) -> Option<f64> {
+        match self.caught + self.missed {
+            0 => None,
+            total => Some(100.0 * self.caught as f64 / total as f64),
+        }
+    }
+
+    /// The history as a sparkline, e.g. `█▁██`.
+    pub fn sparkline(&self) -> String {
+        self.history
+            .iter()
+            .map(|&caught| if caught { '█' } else { '▁' })
+            .collect()
+    }
+}
+
+/// Everything the dashboard shows, updated from the events of the run and the
+/// keys pressed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DashboardState {
+    /// Number of mutants to run.
+    pub total: usize,
+    /// Number of mutants that finished.
+    pub finished: usize,
+    pub caught: usize,
+    pub missed: usize,
+    pub errors: usize,
+    /// The most recent mutants first: only survivors unless `verbose`.
+    pub recent: VecDeque<FinishedMutant>,
+    pub files: BTreeMap<PathBuf, FileResults>,
+    /// Whether the dispatching of new mutants is paused.
+    pub paused: bool,
+    /// Whether caught mutants are listed among the recent mutants too.
+    pub verbose: bool,
+    /// Whether the run stops at the next survivor.
+    pub fail_fast: bool,
+    /// Whether no more mutants are started.
+    pub stopped: bool,
+    /// Whether the run finished.
+    pub done: bool,
+}
+
+impl DashboardState {
+    /// Update the state with `event`.
+    pub fn apply(&mut self, event: &Event) {
+        match event {
+            Event::RunStarted { mutants } => self.total = *mutants,
+            Event::MutantStarted { .. } => {}
+            Event::MutantFinished {
+                file_path,
+                line_number,
+                before,
+                after,
+                result,
+                ..
+            } => {
+                self.finished += 1;
+                let file = self.files.entry(file_path.clone()).or_default();
+                match result {
+                    MutantResult::Caught => {
+                        self.caught += 1;
+                        file.caught += 1;
+                        file.history.push_back(true);
+                    }
+                    MutantResult::Missed => {
+                        self.missed += 1;
+                        file.missed += 1;
+                        file.history.push_back(false);
+                        if self.fail_fast {
+                            self.stopped = true;
+                        }
+                    }
+                    MutantResult::Error { .. } | MutantResult::NoTests => self.errors += 1,
+                    MutantResult::Untested | MutantResult::Uncovered | MutantResult::Invalid => {}
+                }
+                if file.history.len() > SPARKLINE_LENGTH {
+                    file.history.pop_front();
+                }
+
+                if self.verbose || *result == MutantResult::Missed {
+                    self.recent.push_front(FinishedMutant {
+                        file_path: file_path.clone(),
+                        line_number: *line_number,
+                        before: before.clone(),
+                        after: after.clone(),
+                        result: *result,
+                    });
+                    self.recent.truncate(RECENT_MUTANTS);
+                }
+            }
+            Event::RunFinished { .. } => self.done = true,
+        }
+    }
+
+    /// Handle a key press, see the module documentation for the keybindings.
+    pub fn handle_key(&mut self, key: char) {
+        match key {
+            'p' => self.paused = !self.paused,
+            'v' => self.verbose = !self.verbose,
+            'f' => self.fail_fast = !self.fail_fast,
+            'q' => self.stopped = true,
+            _ => {}
+        }
+    }
+
+    /// Whether new mutants should be started.
+    pub fn dispatch(&self) -> Dispatch {
+        match (self.stopped, self.paused) {
+            (true, _) => Dispatch::Stop,
+            (false, true) => Dispatch::Pause,
+            (false, false) => Dispatch::Continue,
+        }
+    }
+
+    /// Draw the dashboard into `frame`.
+    pub fn render(&self, frame: &mut Frame) {
+        let [progress, counters, body, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let ratio = match self.total {
+            0 => 0.0,
+            total => (self.finished as f64 / total as f64).min(1.0),
+        };
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title(" pymute "))
+                .gauge_style(Style::default().fg(Color::Cyan))
+                .ratio(ratio)
+                .label(format!("{}/{}", self.finished, self.total)),
+            progress,
+        );
+
+        let mut status = vec![
+            Span::from(format!(" {} caught", self.caught)).green(),
+            Span::from(format!("  {} missed", self.missed)).red(),
+            Span::from(format!("  {} errors", self.errors)).magenta(),
+        ];
+        for (flag, name) in [
+            (self.paused, "paused"),
+            (self.verbose, "verbose"),
+            (self.fail_fast, "fail-fast"),
+            (self.stopped, "stopped"),
+        ] {
+            if flag {
+                status.push(Span::from(format!("  [{name}]")).yellow());
+            }
+        }
+        frame.render_widget(Paragraph::new(Line::from(status)), counters);
+
+        let [recent, files] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(body);
+        let title = match self.verbose {
+            true => " Recent mutants ",
+            false => " Recent survivors ",
+        };
+        let items: Vec<ListItem> = self
+            .recent
+            .iter()
+            .map(|mutant| {
+                ListItem::new(vec![
+                    Line::from(format!(
+                        "{}:{} [{}]",
+                        mutant.file_path.display(),
+                        mutant.line_number,
+                        mutant.result
+                    )),
+                    Line::from(format!("- {}", mutant.before.trim())).red(),
+                    Line::from(format!("+ {}", mutant.after.trim())).green(),
+                ])
+            })
+            .collect();
+        frame.render_widget(
+            List::new(items).block(Block::bordered().title(title)),
+            recent,
+        );
+
+        let lines: Vec<Line> = self
+            .files
+            .iter()
+            .map(|(path, results)| {
+                let score = match results.score() {
+                    Some(score) => format!("{score:5.1}%"),
+                    None => "  n/a ".into(),
+                };
+                Line::from(vec![
+                    Span::from(score).yellow(),
+                    Span::from(format!(
+                        " {:<width$} ",
+                        results.sparkline(),
+                        width = SPARKLINE_LENGTH
+                    )),
+                    Span::from(path.display().to_string()),
+                ])
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Files ")),
+            files,
+        );
+
+        frame.render_widget(
+            Paragraph::new(" p pause  v verbose  f fail fast  q quit").dark_gray(),
+            help,
+        );
+    }
+}
+
+/// An observer showing the run in a full-screen dashboard.
+///
+/// Create it with `Dashboard::start` and call `Dashboard::finish` after the run to
+/// restore the terminal.
+pub struct Dashboard {
+    state: Arc<Mutex<DashboardState>>,
+    finished: Arc<AtomicBool>,
+    drawing: Mutex<Option<JoinHandle<io::Result<()>>>>,
+}
+
+impl Dashboard {
+    /// Take over the terminal and start drawing the dashboard.
+    ///
+    /// Returns None if stdout is not a terminal or the terminal is smaller than
+    /// `MIN_TERMINAL_SIZE`, in which case the normal output should be used. The
+    /// terminal is restored if the program panics.
+    pub fn start() -> Result<Option<Arc<Self>>, Box<dyn Error + Send + Sync>> {
+        if !io::stdout().is_terminal() {
+            return Ok(None);
+        }
+        let (columns, rows) = terminal::size()?;
+        if columns < MIN_TERMINAL_SIZE.0 || rows < MIN_TERMINAL_SIZE.1 {
+            return Ok(None);
+        }
+
+        let state = Arc::new(Mutex::new(DashboardState::default()));
+        let finished = Arc::new(AtomicBool::new(false));
+        // also installs a panic hook restoring the terminal
+        let mut terminal = ratatui::init();
+
+        let drawing = {
+            let state = Arc::clone(&state);
+            let finished = Arc::clone(&finished);
+            thread::spawn(move || -> io::Result<()> {
+                while !finished.load(Ordering::SeqCst) {
+                    let snapshot = lock(&state).clone();
+                    terminal.draw(|frame| snapshot.render(frame))?;
+
+                    if !event::poll(REFRESH_INTERVAL)? {
+                        continue;
+                    }
+                    if let event::Event::Key(key) = event::read()? {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        // the terminal is in raw mode, so Ctrl-C arrives as a key
+                        let key = match key.code {
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                'q'
+                            }
+                            KeyCode::Char(key) => key,
+                            KeyCode::Esc => 'q',
+                            _ => continue,
+                        };
+                        lock(&state).handle_key(key);
+                    }
+                }
+                Ok(())
+            })
+        };
+
+        Ok(Some(Arc::new(Dashboard {
+            state,
+            finished,
+            drawing: Mutex::new(Some(drawing)),
+        })))
+    }
+
+    /// Stop drawing the dashboard and restore the terminal.
+    pub fn finish(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.finished.store(true, Ordering::SeqCst);
+        let drawing = lock(&self.drawing).take();
+        let result = drawing.map(|drawing| drawing.join());
+        ratatui::restore();
+        match result {
+            Some(Ok(result)) => Ok(result?),
+            Some(Err(_)) => Err("The dashboard crashed".into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Observer for Dashboard {
+    fn notify(&self, event: &Event) {
+        lock(&self.state).apply(event);
+    }
+
+    fn dispatch(&self) -> Dispatch {
+        lock(&self.state).dispatch()
+    }
+}
+
+/// Lock `mutex`, even if a thread panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::{Dispatch, Event};
+    use crate::mutants::Mutant;
+    use crate::runner::{MutantOutcome, MutantResult};
+    use crate::tui::DashboardState;
+    use ratatui::{backend::TestBackend, Terminal};
+    use std::{path::PathBuf, time::Duration};
+
+    fn finished(file: &str, line_number: usize, result: MutantResult) -> Event {
+        let outcome = MutantOutcome {
+            mutant: Mutant::new(
+                PathBuf::from(file),
+                line_number,
+                " + ",
+                " - ",
+                12,
+                "    return a + b",
+            ),
+            result,
+            duration: None,
+            error: None,
+        };
+        Event::mutant_finished(&outcome, Duration::from_millis(10))
+    }
+
+    fn rendered(state: &DashboardState) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|frame| state.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_dashboard_state_from_events() {
+        let mut state = DashboardState::default();
+        for event in [
+            Event::RunStarted { mutants: 4 },
+            finished("pkg/calc.py", 2, MutantResult::Caught),
+            finished("pkg/calc.py", 5, MutantResult::Missed),
+            finished(
+                "pkg/io.py",
+                7,
+                MutantResult::Error {
+                    signal: Some(9),
+                    code: None,
+                },
+            ),
+        ] {
+            state.apply(&event);
+        }
+
+        assert_eq!(
+            (
+                state.total,
+                state.finished,
+                state.caught,
+                state.missed,
+                state.errors
+            ),
+            (4, 3, 1, 1, 1)
+        );
+        // only survivors are listed unless verbose
+        assert_eq!(state.recent.len(), 1);
+        assert_eq!(state.recent[0].line_number, 5);
+        let calc = &state.files[&PathBuf::from("pkg/calc.py")];
+        assert_eq!(
+            (calc.score(), calc.sparkline().as_str()),
+            (Some(50.0), "█▁")
+        );
+        assert_eq!(state.files[&PathBuf::from("pkg/io.py")].score(), None);
+
+        let screen = rendered(&state);
+        assert!(screen.contains("3/4"), "{screen}");
+        assert!(screen.contains("1 caught  1 missed  1 errors"), "{screen}");
+        assert!(screen.contains("Recent survivors"), "{screen}");
+        assert!(screen.contains("pkg/calc.py:5 [missed]"), "{screen}");
+        assert!(screen.contains("- +"), "{screen}");
+        assert!(screen.contains(" 50.0% █▁"), "{screen}");
+
+        state.handle_key('v');
+        state.apply(&finished("pkg/calc.py", 9, MutantResult::Caught));
+        assert_eq!(state.recent[0].line_number, 9);
+        let screen = rendered(&state);
+        assert!(screen.contains("Recent mutants"), "{screen}");
+        assert!(screen.contains("[verbose]"), "{screen}");
+    }
+
+    #[test]
+    fn test_dashboard_keybindings() {
+        let mut state = DashboardState::default();
+        assert_eq!(state.dispatch(), Dispatch::Continue);
+
+        state.handle_key('p');
+        assert_eq!(state.dispatch(), Dispatch::Pause);
+        state.handle_key('p');
+        assert_eq!(state.dispatch(), Dispatch::Continue);
+
+        // fail fast stops at the next survivor, not at caught mutants
+        state.handle_key('f');
+        state.apply(&finished("pkg/calc.py", 2, MutantResult::Caught));
+        assert_eq!(state.dispatch(), Dispatch::Continue);
+        state.apply(&finished("pkg/calc.py", 5, MutantResult::Missed));
+        assert_eq!(state.dispatch(), Dispatch::Stop);
+
+        let mut state = DashboardState::default();
+        state.handle_key('q');
+        assert_eq!(state.dispatch(), Dispatch::Stop);
+        state.apply(&Event::RunFinished {
+            caught: 0,
+            missed: 0,
+            errors: 0,
+        });
+        assert!(state.done);
+    }
+}
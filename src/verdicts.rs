@@ -0,0 +1,126 @@
+//! User verdicts on surviving mutants.
+//!
+//! Some surviving mutants are accepted risks, e.g. in logging details. Instead of
+//! excluding their lines with a pragma comment, a verdict can be recorded for
+//! them without touching the source (see `pymute mark`). Verdicts are kept in
+//! `verdicts.csv` in the state directory, keyed by the stable mutant id (see
+//! `Mutant::id`). Accepted survivors are reported in their own bucket and do not
+//! count against the mutation score.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, error::Error, fs, path::Path};
+
+/// The verdict of a user on a surviving mutant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerdictKind {
+    /// The mutant is an intended survivor, e.g. an accepted risk.
+    Accepted,
+}
+
+/// A single row of the verdicts file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verdict {
+    /// Stable id of the mutant, see `Mutant::id`.
+    pub id: String,
+    /// The verdict on the mutant.
+    pub verdict: VerdictKind,
+    /// Why the verdict was given.
+    pub note: String,
+}
+
+/// All verdicts by mutant id.
+pub type Verdicts = BTreeMap<String, Verdict>;
+
+/// Read the verdicts file at `path`. A missing file holds no verdicts.
+pub fn read_verdicts(path: &Path) -> Result<Verdicts, Box<dyn Error + Send + Sync>> {
+    if !path.exists() {
+        return Ok(Verdicts::new());
+    }
+    let mut reader = csv::Reader::from_path(path)?;
+    let mut verdicts = Verdicts::new();
+    for verdict in reader.deserialize() {
+        let verdict: Verdict = verdict?;
+        verdicts.insert(verdict.id.clone(), verdict);
+    }
+    Ok(verdicts)
+}
+
+/// Write all `verdicts` to the verdicts file at `path`.
+pub fn write_verdicts(
+    path: &Path,
+    verdicts: &Verdicts,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = csv::Writer::from_path(path)?;
+    for verdict in verdicts.values() {
+        writer.serialize(verdict)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Record `verdict` in the verdicts file at `path`, replacing an earlier verdict
+/// on the same mutant.
+pub fn mark(path: &Path, verdict: Verdict) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut verdicts = read_verdicts(path)?;
+    verdicts.insert(verdict.id.clone(), verdict);
+    write_verdicts(path, &verdicts)
+}
+
+/// Remove the verdict on the mutant `id` from the verdicts file at `path`.
+///
+/// Returns whether there was a verdict to remove.
+pub fn unmark(path: &Path, id: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let mut verdicts = read_verdicts(path)?;
+    if verdicts.remove(id).is_none() {
+        return Ok(false);
+    }
+    write_verdicts(path, &verdicts)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::verdicts::{mark, read_verdicts, unmark, Verdict, VerdictKind};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_mark_unmark() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(".pymute/verdicts.csv");
+        assert!(read_verdicts(&path).unwrap().is_empty());
+
+        let verdict = |id: &str, note: &str| Verdict {
+            id: id.into(),
+            verdict: VerdictKind::Accepted,
+            note: note.into(),
+        };
+        mark(&path, verdict("0123456789ab", "only logging")).unwrap();
+        mark(
+            &path,
+            verdict("ba9876543210", "performance counter, \"best effort\""),
+        )
+        .unwrap();
+        mark(&path, verdict("0123456789ab", "log message only")).unwrap();
+
+        let verdicts = read_verdicts(&path).unwrap();
+        assert_eq!(verdicts.len(), 2);
+        assert_eq!(verdicts["0123456789ab"].note, "log message only");
+        assert_eq!(
+            verdicts["ba9876543210"],
+            verdict("ba9876543210", "performance counter, \"best effort\"")
+        );
+
+        assert!(unmark(&path, "0123456789ab").unwrap());
+        assert!(!unmark(&path, "0123456789ab").unwrap());
+        let verdicts = read_verdicts(&path).unwrap();
+        assert_eq!(verdicts.keys().collect::<Vec<_>>(), vec!["ba9876543210"]);
+
+        temp_dir.close().unwrap();
+    }
+}
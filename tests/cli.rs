@@ -31,3 +31,496 @@ print(res) # print the result *
     temp_dir.close().unwrap();
     Ok(())
 }
+
+#[test]
+fn test_pymute_profiles_command() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("pymute")?;
+    cmd.arg("profiles");
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("quick"))
+        .stdout(predicates::str::contains("--max-mutants 100"))
+        .stdout(predicates::str::contains("thorough"))
+        .stdout(predicates::str::contains("--diff-base HEAD"))
+        .stdout(predicates::str::contains("--output-level quiet"))
+        .stdout(predicates::str::contains("--fail-under 80"));
+    Ok(())
+}
+
+#[test]
+fn test_pymute_mark_command() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path().to_str().unwrap();
+
+    let mut cmd = Command::cargo_bin("pymute")?;
+    cmd.args([
+        "mark",
+        "0123456789ab",
+        "--note",
+        "only logging",
+        "--root",
+        root,
+    ]);
+    cmd.assert().success().stdout(predicates::str::contains(
+        "Marked mutant 0123456789ab as accepted",
+    ));
+    let verdicts = std::fs::read_to_string(temp_dir.path().join(".pymute/verdicts.csv"))?;
+    assert!(verdicts.contains("0123456789ab,accepted,only logging"));
+
+    let mut cmd = Command::cargo_bin("pymute")?;
+    cmd.args(["unmark", "0123456789ab", "--root", root]);
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("Removed the verdict"));
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_mutant_id() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path().join("project");
+    std::fs::create_dir(&root)?;
+    std::fs::write(
+        root.join("calc.py"),
+        "def add(a, b):\n    return a + b\n\ndef mul(a, b):\n    return a * b\n",
+    )?;
+    let runs = temp_dir.path().join("runs.log");
+    let test_command = format!(
+        r#"python -c "open('{}', 'a').write('run ')""#,
+        runs.to_str().unwrap()
+    );
+
+    let output = Command::cargo_bin("pymute")?
+        .env("NO_COLOR", "1")
+        .args(["list", root.to_str().unwrap()])
+        .output()?;
+    let listed = String::from_utf8(output.stdout)?;
+    let ids: Vec<&str> = listed
+        .lines()
+        .filter_map(|line| Some(line.strip_suffix(')')?.rsplit_once("(id ")?.1))
+        .collect();
+    assert_eq!(ids.len(), 2, "{listed}");
+    let id = ids[0];
+
+    Command::cargo_bin("pymute")?
+        .args(["run", root.to_str().unwrap(), "--runner", "custom"])
+        .args(["--test-command", &test_command, "--mutant-id", id])
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(&runs)?, "run ");
+
+    // unknown ids are reported with similar ids
+    let typo = format!("{}x", &id[..id.len() - 1]);
+    Command::cargo_bin("pymute")?
+        .args(["run", root.to_str().unwrap(), "--mutant-id", &typo])
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(format!("did you mean {id}?")));
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_color() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n")?;
+    let list = |args: &[&str], no_color: Option<&str>| {
+        let mut command = Command::cargo_bin("pymute").unwrap();
+        command.env_remove("NO_COLOR");
+        if let Some(no_color) = no_color {
+            command.env("NO_COLOR", no_color);
+        }
+        let output = command
+            .args(["list", root.to_str().unwrap()])
+            .args(args)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(list(&["--color", "always"], None).contains('\x1b'));
+    assert!(!list(&["--color", "never"], None).contains('\x1b'));
+    assert!(!list(&[], Some("1")).contains('\x1b'));
+    assert!(!list(&["--color", "auto"], None).contains('\x1b'));
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_report_and_clean() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(
+        root.join("calc.py"),
+        "def add(a, b):\n    return a + b\n\ndef mul(a, b):\n    return a * b\n",
+    )?;
+    let root = root.to_str().unwrap();
+
+    Command::cargo_bin("pymute")?
+        .args(["report", root])
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains("There are no results"));
+
+    // the test command fails for every mutant, so all of them are caught
+    Command::cargo_bin("pymute")?
+        .args(["run", root, "--runner", "custom"])
+        .args(["--test-command", "python -c 'import sys; sys.exit(1)'"])
+        .assert()
+        .success();
+    Command::cargo_bin("pymute")?
+        .env("NO_COLOR", "1")
+        .args(["report", root])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "2 mutants in the cache: 2 caught, 0 missed, 0 accepted, mutation score 100.0%",
+        ));
+    let html = temp_dir.path().join("html");
+    Command::cargo_bin("pymute")?
+        .args(["report", root, "--report-html", html.to_str().unwrap()])
+        .assert()
+        .success();
+    assert!(html.join("files/calc.py.html").is_file());
+    assert!(std::fs::read_to_string(html.join("index.html"))?.contains("files/calc.py.html"));
+
+    Command::cargo_bin("pymute")?
+        .args(["clean", root])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("cache.csv"));
+    assert!(!temp_dir.path().join(".pymute/cache.csv").exists());
+    Command::cargo_bin("pymute")?
+        .args(["clean", root])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Nothing to clean."));
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_fail_under() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(
+        root.join("calc.py"),
+        "def add(a, b):\n    return a + b\n\ndef mul(a, b):\n    return a * b\n",
+    )?;
+    let root = root.to_str().unwrap();
+    let run = |test_command: &str, thresholds: &[&str]| {
+        let mut command = Command::cargo_bin("pymute").unwrap();
+        command
+            .env("NO_COLOR", "1")
+            .args(["run", root, "--runner", "custom", "--no-cache"])
+            .args(["--test-command", test_command])
+            .args(thresholds);
+        command.assert()
+    };
+
+    // the test command passes for every mutant, so all of them survive
+    let survive = "python -c 'pass'";
+    run(survive, &[]).success();
+    run(survive, &["--fail-under", "50"])
+        .code(2)
+        .stdout(predicates::str::contains(
+            "mutation score 0.0% is below the minimum of 50.0%",
+        ));
+    run(survive, &["--fail-on-missed"])
+        .code(2)
+        .stdout(predicates::str::contains("2 mutants survived"));
+
+    let catch = "python -c 'import sys; sys.exit(1)'";
+    run(catch, &["--fail-under", "100", "--fail-on-missed"]).success();
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_pymute_interrupted() -> Result<(), Box<dyn std::error::Error>> {
+    use std::time::{Duration, Instant};
+
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n")?;
+    // a test run that would take a minute, writing its pid first
+    let pid_file = root.join("test.pid");
+    let test_command = format!(
+        "python -c \"import os, signal, time; signal.signal(signal.SIGINT, signal.SIG_IGN); \
+         open(r'{}', 'w').write(str(os.getpid())); time.sleep(60)\"",
+        pid_file.display()
+    );
+
+    let mut pymute = Command::cargo_bin("pymute")?
+        .args(["run", root.to_str().unwrap(), "--runner", "custom"])
+        .args(["--test-command", &test_command])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()?;
+    let start = Instant::now();
+    while std::fs::read_to_string(&pid_file)
+        .unwrap_or_default()
+        .is_empty()
+    {
+        assert!(
+            start.elapsed() < Duration::from_secs(30),
+            "test run not started"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let test_pid = std::fs::read_to_string(&pid_file)?;
+
+    // only pymute receives the signal, unlike with Ctrl+C in a terminal
+    let kill = |args: &[&str]| Command::new("kill").args(args).status();
+    assert!(kill(&["-INT", &pymute.id().to_string()])?.success());
+    let status = pymute.wait()?;
+    assert_eq!(status.code(), Some(130));
+    assert!(start.elapsed() < Duration::from_secs(30));
+    assert!(
+        !kill(&["-0", &test_pid])?.success(),
+        "test run still running"
+    );
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_runner_args() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n")?;
+    // passes, i.e. misses every mutant, only if the arguments arrive unchanged
+    std::fs::write(
+        root.join("check_args.py"),
+        "import sys\nsys.exit(sys.argv[1:] != ['-k', 'a and b', '--maxfail', '1'])\n",
+    )?;
+    let root = root.to_str().unwrap();
+
+    let mut command = Command::cargo_bin("pymute")?;
+    command
+        .env("NO_COLOR", "1")
+        .args(["run", root, "--runner", "custom", "--no-cache"])
+        .args(["--modules", "calc.py", "--fail-on-missed"])
+        .args(["--test-command", "python check_args.py"])
+        .args(["--runner-args", "-k 'a and b'", "--", "--maxfail", "1"]);
+    command
+        .assert()
+        .code(2)
+        .stdout(predicates::str::contains("1 mutants survived"));
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_unittest_runner() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n")?;
+    std::fs::create_dir(root.join("tests"))?;
+    std::fs::write(
+        root.join("tests").join("test_calc.py"),
+        "import unittest\n\nfrom calc import add\n\n\nclass TestAdd(unittest.TestCase):\n    def test_add(self):\n        self.assertEqual(add(2, 3), 5)\n",
+    )?;
+    let root = root.to_str().unwrap();
+
+    let mut command = Command::cargo_bin("pymute")?;
+    command
+        .env("NO_COLOR", "1")
+        .args(["run", root, "--runner", "unittest", "--no-cache"])
+        .args([
+            "--modules",
+            "calc.py",
+            "--tests",
+            "tests",
+            "--fail-on-missed",
+        ]);
+    command
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(" 0 missed"));
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pymute_nox_runner() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path().join("project");
+    std::fs::create_dir(&root)?;
+    std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n")?;
+    // the stub runs the tests only if it is asked for the right sessions, and
+    // misses every mutant otherwise
+    let bin = temp_dir.path().join("bin");
+    std::fs::create_dir(&bin)?;
+    let nox = bin.join("nox");
+    std::fs::write(
+        &nox,
+        "#!/bin/sh\n[ \"$*\" = \"-s lint tests\" ] || exit 0\nexec python -c 'from calc import add; assert add(2, 3) == 5'\n",
+    )?;
+    std::fs::set_permissions(&nox, std::fs::Permissions::from_mode(0o755))?;
+    let path = std::env::join_paths(
+        std::iter::once(bin).chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )?;
+
+    let mut command = Command::cargo_bin("pymute")?;
+    command
+        .env("NO_COLOR", "1")
+        .env("PATH", path)
+        .args(["run", root.to_str().unwrap(), "--runner", "nox"])
+        .args(["--environment", "lint,tests", "--no-cache"])
+        .args(["--modules", "calc.py", "--fail-on-missed"]);
+    command
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(" 0 missed"));
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_missing_python() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n")?;
+    let python = root.join("venv").join("bin").join("python");
+
+    let mut command = Command::cargo_bin("pymute")?;
+    command
+        .env("NO_COLOR", "1")
+        .args(["run", root.to_str().unwrap(), "--no-cache"])
+        .args(["--python", python.to_str().unwrap()]);
+    command
+        .assert()
+        .code(1)
+        .stdout(predicates::str::contains(format!(
+            "The program `{}` given with --python does not exist or is not executable!",
+            python.display()
+        )));
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_process_output_blocks() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(
+        root.join("calc.py"),
+        "def add(a, b):\n    return a + b\n\n\ndef sub(a, b):\n    return a - b\n",
+    )?;
+    // the test runs of both mutants print slowly and at the same time
+    std::fs::write(
+        root.join("slow.py"),
+        "import os, time\nfor i in range(5):\n    print('line', os.environ['PYMUTE_MUTANT_ID'], i, flush=True)\n    time.sleep(0.05)\n",
+    )?;
+    let root = root.to_str().unwrap();
+
+    let mut command = Command::cargo_bin("pymute")?;
+    command
+        .env("NO_COLOR", "1")
+        .args(["run", root, "--runner", "custom", "--no-cache"])
+        .args(["--test-command", "python slow.py", "--modules", "calc.py"])
+        .args(["--mutation-types", "math-ops", "--num-threads", "2"])
+        .args(["--output-level", "process"]);
+    let output = command.output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+
+    // every line of a block belongs to the mutant in its header
+    let mut block = None;
+    let mut lines = 0;
+    for line in stdout.lines() {
+        if let Some((_, id)) = line.strip_suffix(')').and_then(|l| l.rsplit_once("(id ")) {
+            block = Some(id.to_string());
+        } else if let Some(rest) = line.strip_prefix("line ") {
+            let id = rest.split(' ').next();
+            assert_eq!(id, block.as_deref(), "{stdout}");
+            lines += 1;
+        }
+    }
+    assert_eq!(lines, 10, "{stdout}");
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_pymute_in_place() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    let original = "def add(a, b):\n    return a + b\n\n\ndef mul(a, b):\n    return a * b\n";
+    std::fs::write(root.join("calc.py"), original)?;
+    std::fs::write(
+        root.join("check.py"),
+        "from calc import add, mul\nassert add(2, 3) == 5 and mul(2, 3) == 6\n",
+    )?;
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=pymute",
+                "-c",
+                "user.email=pymute@example.com",
+            ])
+            .args(args)
+            .current_dir(root)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?}");
+    };
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "init"]);
+    let root_arg = root.to_str().unwrap();
+    let run = || {
+        let mut command = Command::cargo_bin("pymute").unwrap();
+        command
+            .env("NO_COLOR", "1")
+            .args(["run", root_arg, "--runner", "custom", "--no-cache"])
+            .args(["--modules", "calc.py", "--in-place", "--num-threads", "2"])
+            .args(["--test-command", "python -B check.py"]);
+        command
+    };
+
+    run()
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("ignoring --num-threads 2"))
+        .stderr(predicates::str::contains("2 caught, 0 missed"));
+    // the tree is byte-identical afterwards
+    assert_eq!(std::fs::read_to_string(root.join("calc.py"))?, original);
+    let status = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=all"])
+        .current_dir(root)
+        .output()?;
+    assert_eq!(String::from_utf8(status.stdout)?, "");
+
+    // uncommitted changes could not be restored with git
+    std::fs::write(root.join("notes.txt"), "wip\n")?;
+    run()
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains("uncommitted changes"))
+        .stdout(predicates::str::contains("notes.txt"));
+    run().arg("--allow-dirty").assert().success();
+    assert_eq!(std::fs::read_to_string(root.join("calc.py"))?, original);
+
+    temp_dir.close().unwrap();
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Runs embedded in a process of its own, since the test interrupts the process
+//! like Ctrl+C does.
+
+use pymute::runner::{OutputLevel, Runner};
+use pymute::{PymuteError, RunConfig};
+use tempfile::tempdir;
+
+#[test]
+#[cfg(unix)]
+fn test_run_after_interrupted_run() {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    std::fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n").unwrap();
+    let config = RunConfig::new(root)
+        .runner(Runner::Custom)
+        .output_level(OutputLevel::Quiet)
+        .no_cache(true);
+
+    // the test run sends Ctrl+C to the process running pymute
+    let interrupting = config.clone().test_command(
+        r#"python -c "import os, signal, time; os.kill(os.getppid(), signal.SIGINT); time.sleep(10)""#,
+    );
+    let error = interrupting.run().unwrap_err();
+    assert!(matches!(error, PymuteError::Interrupted), "{error}");
+
+    // a later run in the same process is not interrupted
+    let summary = config
+        .test_command(r#"python -c "import sys; sys.exit(1)""#)
+        .run()
+        .unwrap();
+    assert_eq!(summary.caught, summary.total);
+    assert!(summary.total > 0);
+
+    temp_dir.close().unwrap();
+}